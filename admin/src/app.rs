@@ -5,7 +5,7 @@ use rustyindie_common::api::api_service::ApiService;
 use rustyindie_common::api::models::LoginResponse;
 use rustyindie_common::components::page_not_found::PageNotFound;
 use crate::pages::administrator::post::{manage_post::ManagePosts, add_edit_post::AddEditPost};
-use crate::pages::administrator::{mail::Mail, admin::Admin};
+use crate::pages::administrator::{mail::Mail, admin::Admin, users::Users};
 
 
 #[component]
@@ -24,6 +24,7 @@ pub fn App() -> impl IntoView {
                 <Routes>
                     <Route path="/" view=Admin>
                         <Route path="/mail" view=Mail />
+                        <Route path="/users" view=Users />
                         <Route path="/add_edit" view=AddEditPost />
                         <Route path="/add_edit/:post_id" view=AddEditPost />
                         <Route path="/mngpost" view=ManagePosts />
@@ -3,9 +3,11 @@ pub mod pages {
         pub mod post {
             pub mod add_edit_post;
             pub mod manage_post;
+            pub mod post_revisions;
         }
         pub mod admin;
-        pub mod mail;        
+        pub mod mail;
+        pub mod users;
     }
 }
 pub mod app;
@@ -8,6 +8,7 @@ use rustyindie_common::components::modal::Modal;
 const MAIL: &str = "/mail";
 const ADD_EDIT_POST: &str = "/add_edit";
 const MNG_POST: &str = "/mngpost";
+const USERS: &str = "/users";
 
 #[component]
 pub fn Admin() -> impl IntoView {
@@ -42,6 +43,9 @@ pub fn Admin() -> impl IntoView {
                     <li>
                         <a href={MNG_POST} class=("a-selected", move || current_selected_nav() == MNG_POST )>"Manage Posts"</a>
                     </li>
+                    <li>
+                        <a href={USERS} class=("a-selected", move || current_selected_nav() == USERS )>"Users"</a>
+                    </li>
                     <li>
                         <Modal disable_dismiss=true open_state=dialog_open set_open_state=set_dialog_open>
                             <Login />
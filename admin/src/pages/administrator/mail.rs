@@ -1,10 +1,184 @@
 use leptos::*;
+use leptos::logging::log;
+use rustyindie_common::api::api_service::ApiService;
+use rustyindie_common::api::models::{DeleteMail, LoginResponse, Mail as MailItem, Paged, UpdateMailRead};
+use rustyindie_common::utils::date_time::convert_datetime_short_readable;
+
+const MAIL_PAGE_SIZE: i32 = 20;
 
 #[component]
 pub fn Mail() -> impl IntoView {
+    // cursors of the pages before the current one, Newer goes back to the last
+    let (cursors, set_cursors) = create_signal::<Vec<String>>(vec![]);
+    let (selected_mail, set_selected_mail) = create_signal::<Option<MailItem>>(None);
+    let api_service = expect_context::<ReadSignal<ApiService>>();
+    let (login_resp, _) = expect_context::<(ReadSignal<Option<LoginResponse>>, WriteSignal<Option<LoginResponse>>)>();
+
+    let mail = create_resource(move || (login_resp().map(|login| login.access_token), cursors().last().cloned()), move |(token, cursor)| async move {
+        match token {
+            Some(token) => {
+                let result = api_service.get_untracked().get_latest_mail(MAIL_PAGE_SIZE, cursor, token).await;
+                match result {
+                    Ok(data) => data,
+                    Err(e) => {
+                        log!("Failed to get mail data: {}", e);
+                        Paged::default()
+                    }
+                }
+            },
+            None => Paged::default()
+        }
+    });
+
+    let submit_mail_read = create_action(move |update_mail_read: &UpdateMailRead| {
+        let input = update_mail_read.clone();
+        async move {
+            match login_resp.get_untracked() {
+                Some(login_result) => {
+                    let result = api_service.get_untracked().update_mail_read(&input, login_result.access_token).await;
+                    match result {
+                        Ok(_) => {
+                            set_selected_mail.update(|selected| {
+                                if let Some(selected) = selected.as_mut() {
+                                    if selected.id == input.mail_id {
+                                        selected.is_read = input.is_read;
+                                    }
+                                }
+                            });
+                            mail.refetch();
+                        },
+                        Err(e) => log!("update_mail_read failed: {:?}", e)
+                    };
+                },
+                None => log!("update_mail_read failed: user must login first")
+            }
+        }
+    });
+
+    let submit_delete_mail = create_action(move |delete_mail: &DeleteMail| {
+        let input = delete_mail.clone();
+        async move {
+            match login_resp.get_untracked() {
+                Some(login_result) => {
+                    let result = api_service.get_untracked().delete_mail(&input, login_result.access_token).await;
+                    match result {
+                        Ok(_) => {
+                            if selected_mail.get_untracked().map(|selected| selected.id == input.mail_id).unwrap_or_default() {
+                                set_selected_mail(None);
+                            }
+                            mail.refetch();
+                        },
+                        Err(e) => log!("delete_mail failed: {:?}", e)
+                    };
+                },
+                None => log!("delete_mail failed: user must login first")
+            }
+        }
+    });
+
+    // opening a message marks it read
+    let select_mail = move |selected: MailItem| {
+        if !selected.is_read {
+            submit_mail_read.dispatch(UpdateMailRead { mail_id: selected.id, is_read: true });
+        }
+        set_selected_mail(Some(selected));
+    };
+
+    let next_cursor = move || mail().and_then(|page| page.next_cursor);
+    let has_newer = move || !cursors().is_empty();
+    let is_empty = move || mail().map(|page| page.items.is_empty()).unwrap_or_default();
+
     view! {
         <div class="home-content">
-            "mail"
+            <h2>"Mail"</h2>
+            <div class="mail-container">
+                <div class="mail-list-container">
+                    <Transition fallback=move || view! { <p>"Loading ..."</p> }>
+                        <Show when=is_empty fallback=|| ()>
+                            <p>"No mail"</p>
+                        </Show>
+                        <ul class="mail-list">
+                            <For
+                                each=move || mail().map(|page| page.items).unwrap_or_default()
+                                key=|item| (item.id, item.is_read)
+                                children=move |item| {
+                                    let item_id = item.id;
+                                    let clicked_item = item.clone();
+
+                                    view! {
+                                        <li
+                                            class="mail-list-item"
+                                            class=("mail-unread", !item.is_read)
+                                            class=("mail-selected", move || selected_mail().map(|selected| selected.id == item_id).unwrap_or_default())
+                                            on:click=move |_| select_mail(clicked_item.clone())
+                                        >
+                                            <div class="opposites">
+                                                <span class="mail-from">{item.from.clone()}</span>
+                                                <small>{convert_datetime_short_readable(item.created_at)}</small>
+                                            </div>
+                                            <div class="mail-subject">{item.subject.clone()}</div>
+                                        </li>
+                                    }
+                                }
+                            />
+                        </ul>
+                    </Transition>
+                    <div class="opposites mail-paging">
+                        <button
+                            class="secondary-btn small-btn"
+                            prop:disabled=move || !has_newer()
+                            on:click=move |_| set_cursors.update(|cursors| { cursors.pop(); })
+                        >
+                            "Newer"
+                        </button>
+                        <button
+                            class="secondary-btn small-btn"
+                            prop:disabled=move || next_cursor().is_none()
+                            on:click=move |_| {
+                                if let Some(cursor) = next_cursor() {
+                                    set_cursors.update(|cursors| cursors.push(cursor));
+                                }
+                            }
+                        >
+                            "Older"
+                        </button>
+                    </div>
+                </div>
+                <div class="mail-detail">
+                    {move || match selected_mail() {
+                        Some(item) => {
+                            let mail_id = item.id;
+                            let is_read = item.is_read;
+
+                            view! {
+                                <div>
+                                    <h3>{item.subject}</h3>
+                                    <div class="opposites">
+                                        <small><b>{item.from}</b></small>
+                                        <small>{convert_datetime_short_readable(item.created_at)}</small>
+                                    </div>
+                                    <p class="mail-message">{item.message}</p>
+                                    <section class="mail-actions">
+                                        <button
+                                            class="secondary-btn"
+                                            on:click=move |_| submit_mail_read.dispatch(UpdateMailRead { mail_id, is_read: !is_read })
+                                        >
+                                            {if is_read { "Mark Unread" } else { "Mark Read" }}
+                                        </button>
+                                        <button
+                                            class="primary-btn"
+                                            on:click=move |_| submit_delete_mail.dispatch(DeleteMail { mail_id })
+                                        >
+                                            "Delete"
+                                        </button>
+                                    </section>
+                                </div>
+                            }.into_view()
+                        },
+                        None => view! { <p>"Select a message to read it"</p> }.into_view()
+                    }}
+                </div>
+            </div>
         </div>
     }
-}
\ No newline at end of file
+}
@@ -1,8 +1,10 @@
 use leptos::*;
 use leptos::logging::log;
 use leptos_router::{Params, use_params};
-use rustyindie_common::api::models::{LoginResponse, NewPost, UpdatePost};
+use rustyindie_common::api::models::{LoginResponse, NewPost, PostStatus, UpdatePost};
 use rustyindie_common::api::api_service::ApiService;
+use rustyindie_common::utils::date_time::{convert_datetime_to_local_input, convert_local_input_to_datetime};
+use crate::pages::administrator::post::post_revisions::PostRevisions;
 
 #[derive(Params, PartialEq)]
 struct AddEditPostParams {
@@ -24,24 +26,37 @@ pub fn AddEditPost() -> impl IntoView {
         })
     };
     let (title, set_title) = create_signal("".to_string());
+    let (slug, set_slug) = create_signal("".to_string());
     let (content, set_content) = create_signal("".to_string());
+    let (excerpt, set_excerpt) = create_signal("".to_string());
+    let (tags, set_tags) = create_signal("".to_string());
+    let (status, set_status) = create_signal(PostStatus::Draft);
+    let (published_at, set_published_at) = create_signal("".to_string());
+    // bumped on every save, so the revisions are reloaded
+    let (saved_version, set_saved_version) = create_signal(0);
     let api_service = expect_context::<ReadSignal<ApiService>>();
     let (login_resp, _) = expect_context::<(ReadSignal<Option<LoginResponse>>, WriteSignal<Option<LoginResponse>>)>();
 
-    _ = create_resource(post_id, move |id| async move {
-        if let None = login_resp() {
+    let post = create_resource(post_id, move |id| async move {
+        let Some(login_result) = login_resp() else {
             return None;
-        }
+        };
         if let None = id {
             return None;
         }
         
-        let post_res = api_service.get_untracked().get_post(id.unwrap_or_default()).await;
+        // drafts and scheduled posts are only returned to administrators
+        let post_res = api_service.get_untracked().get_managed_post(id.unwrap_or_default(), login_result.access_token).await;
         match post_res {
             Ok(opt_post) => match opt_post {
                 Some(post) => {
                     set_title(post.title.clone());
+                    set_slug(post.slug.clone());
                     set_content(post.message.clone());
+                    set_excerpt(post.excerpt.clone().unwrap_or_default());
+                    set_tags(post.tags.iter().map(|tag| tag.name.clone()).collect::<Vec<String>>().join(", "));
+                    set_status(post.status);
+                    set_published_at(post.published_at.map(convert_datetime_to_local_input).unwrap_or_default());
                     Some(post)
                 },
                 None => None
@@ -79,7 +94,10 @@ pub fn AddEditPost() -> impl IntoView {
                 Some(login_result) => {
                     let result = api_service.get_untracked().update_post(&input, login_result.access_token).await;
                     match result { 
-                        Ok(_resp) => log!("update_post success"),
+                        Ok(_resp) => {
+                            log!("update_post success");
+                            set_saved_version.update(|version| *version += 1);
+                        },
                         Err(e) => log!("update_post failed: {:?}", e)
                     };  
                 },
@@ -96,6 +114,31 @@ pub fn AddEditPost() -> impl IntoView {
         None => true
     };
 
+    // an empty excerpt means previews are made from the start of the content
+    let excerpt_input = move || {
+        let excerpt = excerpt();
+        if excerpt.trim().is_empty() { None } else { Some(excerpt) }
+    };
+    // an empty slug is made from the title for new posts, and left as it is for existing ones
+    let slug_input = move || {
+        let slug = slug();
+        if slug.trim().is_empty() { None } else { Some(slug) }
+    };
+
+    // comma separated, the api drops blank and repeated names
+    let tags_input = move || tags().split(',').map(|tag| tag.trim().to_string()).collect::<Vec<String>>();
+
+    // an empty time publishes now, scheduling needs one. The input only has minutes,
+    // so an unchanged time is sent as it was loaded, keeping the post's exact publish time
+    let published_at_input = move || {
+        let input = published_at();
+        let loaded_published_at = post.get_untracked().flatten().and_then(|post| post.published_at);
+        match loaded_published_at {
+            Some(loaded_published_at) if convert_datetime_to_local_input(loaded_published_at) == input => Some(loaded_published_at),
+            _ => convert_local_input_to_datetime(&input)
+        }
+    };
+
     let submit_btn_label = move || {
         if let Some(_id) = post_id() {
             EDIT
@@ -110,9 +153,26 @@ pub fn AddEditPost() -> impl IntoView {
             <form on:submit=move |ev| {
                 ev.prevent_default();
                 if let None = post_id() {
-                    submit_new_post.dispatch(NewPost { title: title(), message: content(), admin_id: 1 });
+                    submit_new_post.dispatch(NewPost { 
+                        title: title(), 
+                        message: content(), 
+                        excerpt: excerpt_input(), 
+                        slug: slug_input(), 
+                        tags: tags_input(), 
+                        status: status(), 
+                        published_at: published_at_input() 
+                    });
                 } else {
-                    submit_update_post.dispatch(UpdatePost { post_id: post_id().unwrap(), admin_id: 1, title: title(), message: content() });
+                    submit_update_post.dispatch(UpdatePost { 
+                        post_id: post_id().unwrap(), 
+                        title: title(), 
+                        message: content(), 
+                        excerpt: excerpt_input(), 
+                        slug: slug_input(), 
+                        tags: Some(tags_input()), 
+                        status: status(), 
+                        published_at: published_at_input() 
+                    });
                 }
             }>
                 <section class="form-section">
@@ -130,6 +190,21 @@ pub fn AddEditPost() -> impl IntoView {
                         style="width: 100%"
                     />
                 </section>
+                <section class="form-section">
+                    <label for="slug">
+                        "Slug (optional, made from the title when empty)"
+                    </label>
+                    <input 
+                        type="text" 
+                        id="slug"
+                        name="slug"
+                        on:input=move |ev| {
+                            set_slug(event_target_value(&ev));
+                        } 
+                        prop:value=slug
+                        style="width: 100%"
+                    />
+                </section>
                 <section class="form-section" style="height: 600px">
                     <label for="content">
                         "Content"                    
@@ -145,10 +220,82 @@ pub fn AddEditPost() -> impl IntoView {
                         {untrack(move || content())}
                     </textarea>
                 </section>
+                <section class="form-section">
+                    <label for="excerpt">
+                        "Excerpt (optional, shown in previews instead of the start of the content)"
+                    </label>
+                    <textarea
+                        id="excerpt"
+                        name="excerpt"
+                        rows="3"
+                        prop:value=move || excerpt()
+                        on:input=move |ev| {
+                            set_excerpt(event_target_value(&ev));
+                        }
+                        style="width: 100%"
+                    >
+                        {untrack(move || excerpt())}
+                    </textarea>
+                </section>
+                <section class="form-section">
+                    <label for="tags">
+                        "Tags (comma separated)"
+                    </label>
+                    <input 
+                        type="text" 
+                        id="tags"
+                        name="tags"
+                        on:input=move |ev| {
+                            set_tags(event_target_value(&ev));
+                        } 
+                        prop:value=tags
+                        style="width: 100%"
+                    />
+                </section>
+                <section class="form-section">
+                    <label for="status">"Status"</label>
+                    <select
+                        id="status"
+                        name="status"
+                        on:change=move |ev| {
+                            if let Some(selected) = PostStatus::from_str(&event_target_value(&ev)) {
+                                set_status(selected);
+                            }
+                        }
+                    >
+                        {PostStatus::ALL.into_iter().map(|option| view! {
+                            <option value=option.as_str() selected=move || status() == option>{option.as_str()}</option>
+                        }).collect_view()}
+                    </select>
+                </section>
+                <section class="form-section">
+                    <label for="published-at">
+                        "Publish at (required when scheduled, now when empty)"
+                    </label>
+                    <input 
+                        type="datetime-local" 
+                        id="published-at"
+                        name="published-at"
+                        on:input=move |ev| {
+                            set_published_at(event_target_value(&ev));
+                        } 
+                        prop:value=published_at
+                    />
+                </section>
                 <section class="form-section">
                     <button prop:disabled=disable_post_submit type="submit" class="primary-btn" >{submit_btn_label}</button>                    
                 </section>
             </form>
+            {move || post_id().map(|id| view! {
+                <PostRevisions 
+                    post_id=id 
+                    saved_version=saved_version 
+                    on_restored=move |_| {
+                        post.refetch();
+                        set_saved_version.update(|version| *version += 1);
+                    } 
+                />
+            })}
         </div>
     }
 }
\ No newline at end of file
@@ -1,25 +1,92 @@
 use leptos::logging::log;
 use leptos::*;
+use leptos_router::A;
 use rustyindie_common::api::api_service::ApiService;
-use rustyindie_common::components::post::list_post_previews::ListPostPreviews;
+use rustyindie_common::api::models::{LoginResponse, Paged, Post, PostStatus};
+use rustyindie_common::components::paged_list::{use_paged_list, LoadMore};
+use rustyindie_common::utils::date_time::convert_datetime_short_readable;
 
+const POST_PAGE_SIZE: i32 = 20;
+
+/// Lists posts of every status, unlike the web front-end which only gets public ones
 #[component]
 pub fn ManagePosts() -> impl IntoView {
-    let (last_offset, _set_last_offset) = create_signal(0);    
+    let (status_filter, set_status_filter) = create_signal::<Option<PostStatus>>(None);
     let api_service = expect_context::<ReadSignal<ApiService>>();
-  
-    let posts = create_resource(last_offset, move |offset| async move {
-        let result = api_service.get_untracked().get_latest_posts(offset).await;
-        match result {
-            Ok(data) => data,
-            Err(e) => {
-                log!("Failed to get post data: {}", e);
-                vec![]
-            }
+    let (login_resp, _) = expect_context::<(ReadSignal<Option<LoginResponse>>, WriteSignal<Option<LoginResponse>>)>();
+
+    let posts = use_paged_list(move || (login_resp().map(|login| login.access_token), status_filter()), move |(token, status), cursor| async move {
+        match token {
+            Some(token) => {
+                let result = api_service.get_untracked().get_managed_posts(POST_PAGE_SIZE, cursor, status, token).await;
+                match result {
+                    Ok(data) => data,
+                    Err(e) => {
+                        log!("Failed to get post data: {}", e);
+                        Paged::default()
+                    }
+                }
+            },
+            None => Paged::default()
         }
     });
 
     view! {
-        <ListPostPreviews posts=posts editable=true />
+        <div class="home-content">
+            <h2>"Posts"</h2>
+            <section class="form-section">
+                <label for="status-filter">"Status"</label>
+                <select
+                    id="status-filter"
+                    name="status-filter"
+                    on:change=move |ev| {
+                        set_status_filter(PostStatus::from_str(&event_target_value(&ev)));
+                    }
+                >
+                    <option value="" selected=move || status_filter().is_none()>"all"</option>
+                    {PostStatus::ALL.into_iter().map(|option| view! {
+                        <option value=option.as_str() selected=move || status_filter() == Some(option)>{option.as_str()}</option>
+                    }).collect_view()}
+                </select>
+            </section>
+            <Show when=move || posts.is_empty() fallback=|| ()>
+                <p>"No posts"</p>
+            </Show>
+            <ul class="post-list">
+                <For
+                    each=move || posts.items.get()
+                    key=|post| (post.id, post.updated_at)
+                    children=move |post| {
+                        view! {
+                            <li class="post-list-item">
+                                <A href=format!("/add_edit/{}", post.id)>
+                                    <div class="opposites">
+                                        <span>{post.title.clone()}</span>
+                                        <StatusBadge status=post.status />
+                                    </div>
+                                    <small>{get_post_date_label(&post)}</small>
+                                </A>
+                            </li>
+                        }
+                    }
+                />
+            </ul>
+            <LoadMore paged_list=posts end_message="No more posts" />
+        </div>
+    }
+}
+
+#[component]
+fn StatusBadge(status: PostStatus) -> impl IntoView {
+    view! {
+        <span class=format!("status-badge status-{}", status.as_str())>{status.as_str()}</span>
+    }
+}
+
+fn get_post_date_label(post: &Post) -> String {
+    match (post.status, post.published_at) {
+        (PostStatus::Scheduled, Some(published_at)) => format!("Scheduled for {}", convert_datetime_short_readable(published_at)),
+        (PostStatus::Published, Some(published_at)) => format!("Published {}", convert_datetime_short_readable(published_at)),
+        _ => format!("Updated {}", convert_datetime_short_readable(post.updated_at))
     }
-}
\ No newline at end of file
+}
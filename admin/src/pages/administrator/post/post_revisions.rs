@@ -0,0 +1,139 @@
+use leptos::*;
+use leptos::logging::log;
+use rustyindie_common::api::api_service::ApiService;
+use rustyindie_common::api::models::{DiffLine, DiffTag, LoginResponse, RestorePostRevision};
+use rustyindie_common::utils::date_time::convert_datetime_short_readable;
+
+/// Earlier saves of a post, a selected one is compared with the current text and can be restored.
+/// @saved_version changes whenever the post is saved, so the list is reloaded
+#[component]
+pub fn PostRevisions(post_id: i64, saved_version: ReadSignal<i32>, #[prop(into)] on_restored: Callback<()>) -> impl IntoView {
+    let (selected_revision_id, set_selected_revision_id) = create_signal::<Option<i64>>(None);
+    let api_service = expect_context::<ReadSignal<ApiService>>();
+    let (login_resp, _) = expect_context::<(ReadSignal<Option<LoginResponse>>, WriteSignal<Option<LoginResponse>>)>();
+
+    let revisions = create_resource(move || (login_resp().map(|login| login.access_token), saved_version()), move |(token, _)| async move {
+        match token {
+            Some(token) => {
+                let result = api_service.get_untracked().get_post_revisions(post_id, token).await;
+                match result {
+                    Ok(data) => data,
+                    Err(e) => {
+                        log!("Failed to get post revisions: {}", e);
+                        vec![]
+                    }
+                }
+            },
+            None => vec![]
+        }
+    });
+
+    // the newest revision is the post's current text
+    let current_revision_id = move || revisions().and_then(|data| data.first().map(|revision| revision.id));
+    let diff = create_resource(
+        move || (login_resp().map(|login| login.access_token), selected_revision_id(), current_revision_id()),
+        move |(token, from_revision_id, to_revision_id)| async move {
+            match (token, from_revision_id, to_revision_id) {
+                (Some(token), Some(from_revision_id), Some(to_revision_id)) => {
+                    let result = api_service.get_untracked().get_post_revision_diff(from_revision_id, to_revision_id, token).await;
+                    match result {
+                        Ok(data) => data,
+                        Err(e) => {
+                            log!("Failed to get post revision diff: {}", e);
+                            None
+                        }
+                    }
+                },
+                _ => None
+            }
+        }
+    );
+
+    let submit_restore = create_action(move |revision_id: &i64| {
+        let input = RestorePostRevision { post_id, revision_id: *revision_id };
+        async move {
+            match login_resp.get_untracked() {
+                Some(login_result) => {
+                    let result = api_service.get_untracked().restore_post_revision(&input, login_result.access_token).await;
+                    match result {
+                        Ok(_) => {
+                            set_selected_revision_id(None);
+                            on_restored.call(());
+                        },
+                        Err(e) => log!("restore_post_revision failed: {:?}", e)
+                    };
+                },
+                None => log!("restore_post_revision failed: user must login first")
+            }
+        }
+    });
+
+    view! {
+        <section class="form-section">
+            <h3>"Revisions"</h3>
+            <ul class="post-list">
+                <For
+                    each=move || revisions().unwrap_or_default()
+                    key=|revision| revision.id
+                    children=move |revision| {
+                        let revision_id = revision.id;
+
+                        view! {
+                            <li
+                                class="post-list-item revision-item"
+                                class=("mail-selected", move || selected_revision_id() == Some(revision_id))
+                                on:click=move |_| set_selected_revision_id(Some(revision_id))
+                            >
+                                <div class="opposites">
+                                    <span>{revision.title.clone()}</span>
+                                    <small>{convert_datetime_short_readable(revision.created_at)}</small>
+                                </div>
+                            </li>
+                        }
+                    }
+                />
+            </ul>
+            {move || diff().flatten().map(|diff| {
+                let from_revision_id = diff.from_revision_id;
+                let is_current = diff.from_revision_id == diff.to_revision_id;
+                let from_revision = revisions().unwrap_or_default().into_iter().find(|revision| revision.id == from_revision_id);
+                let restore_note = match from_revision.and_then(|revision| revision.slug.zip(revision.tag_names)) {
+                    Some((slug, tag_names)) if tag_names.is_empty() => format!("Restoring also sets the slug to {} and removes the tags", slug),
+                    Some((slug, tag_names)) => format!("Restoring also sets the slug to {} and the tags to {}", slug, tag_names.join(", ")),
+                    None => "Only the text is restored, this revision was saved before slugs and tags were kept".to_string()
+                };
+
+                view! {
+                    <div class="revision-diff">
+                        <DiffLines label="Title" lines=diff.title />
+                        <DiffLines label="Content" lines=diff.message />
+                        <DiffLines label="Excerpt" lines=diff.excerpt />
+                        <Show when=move || !is_current fallback=|| view! { <p>"This is the current text"</p> }>
+                            <p>{restore_note.clone()}</p>
+                            <button type="button" class="secondary-btn" on:click=move |_| submit_restore.dispatch(from_revision_id)>
+                                "Restore This Revision"
+                            </button>
+                        </Show>
+                    </div>
+                }
+            })}
+        </section>
+    }
+}
+
+#[component]
+fn DiffLines(label: &'static str, lines: Vec<DiffLine>) -> impl IntoView {
+    view! {
+        <h4>{label}</h4>
+        <pre class="diff-lines">
+            {lines.into_iter().map(|line| {
+                let (class, prefix) = match line.tag {
+                    DiffTag::Equal => ("diff-equal", "  "),
+                    DiffTag::Insert => ("diff-insert", "+ "),
+                    DiffTag::Delete => ("diff-delete", "- ")
+                };
+                view! { <div class=class>{format!("{}{}", prefix, line.text)}</div> }
+            }).collect_view()}
+        </pre>
+    }
+}
@@ -0,0 +1,281 @@
+use leptos::*;
+use leptos::logging::log;
+use rustyindie_common::api::api_service::ApiService;
+use rustyindie_common::api::models::{
+    AdminRole, Administrator, DeactivateAdministrator, LoginResponse, NewAdministrator, Paged, UpdateAdministrator, UpdateAdministratorPassword
+};
+use rustyindie_common::components::paged_list::{use_paged_list, LoadMore};
+
+const USER_PAGE_SIZE: i32 = 50;
+
+#[component]
+pub fn Users() -> impl IntoView {
+    let (selected_admin, set_selected_admin) = create_signal::<Option<Administrator>>(None);
+    let api_service = expect_context::<ReadSignal<ApiService>>();
+    let (login_resp, _) = expect_context::<(ReadSignal<Option<LoginResponse>>, WriteSignal<Option<LoginResponse>>)>();
+
+    // bumped after a change, so the list loads again from the first page
+    let (list_version, set_list_version) = create_signal(0_u32);
+    let reload_admins = move || set_list_version.update(|version| *version += 1);
+
+    let admins = use_paged_list(move || (login_resp().map(|login| login.access_token), list_version()), move |(token, _), cursor| async move {
+        match token {
+            Some(token) => {
+                let result = api_service.get_untracked().get_administrators(USER_PAGE_SIZE, cursor, token).await;
+                match result {
+                    Ok(data) => data,
+                    Err(e) => {
+                        log!("Failed to get administrators: {}", e);
+                        Paged::default()
+                    }
+                }
+            },
+            None => Paged::default()
+        }
+    });
+
+    let submit_new_admin = create_action(move |new_admin: &NewAdministrator| {
+        let input = new_admin.clone();
+        async move {
+            match login_resp.get_untracked() {
+                Some(login_result) => {
+                    match api_service.get_untracked().create_administrator(&input, login_result.access_token).await {
+                        Ok(_) => reload_admins(),
+                        Err(e) => log!("create_administrator failed: {:?}", e)
+                    };
+                },
+                None => log!("create_administrator failed: user must login first")
+            }
+        }
+    });
+
+    let submit_update_admin = create_action(move |update_admin: &UpdateAdministrator| {
+        let input = update_admin.clone();
+        async move {
+            match login_resp.get_untracked() {
+                Some(login_result) => {
+                    match api_service.get_untracked().update_administrator(&input, login_result.access_token).await {
+                        Ok(_) => reload_admins(),
+                        Err(e) => log!("update_administrator failed: {:?}", e)
+                    };
+                },
+                None => log!("update_administrator failed: user must login first")
+            }
+        }
+    });
+
+    let submit_update_password = create_action(move |update_password: &UpdateAdministratorPassword| {
+        let input = update_password.clone();
+        async move {
+            match login_resp.get_untracked() {
+                Some(login_result) => {
+                    match api_service.get_untracked().update_administrator_password(&input, login_result.access_token).await {
+                        Ok(_) => log!("update_administrator_password success"),
+                        Err(e) => log!("update_administrator_password failed: {:?}", e)
+                    };
+                },
+                None => log!("update_administrator_password failed: user must login first")
+            }
+        }
+    });
+
+    let submit_deactivate_admin = create_action(move |deactivate_admin: &DeactivateAdministrator| {
+        let input = deactivate_admin.clone();
+        async move {
+            match login_resp.get_untracked() {
+                Some(login_result) => {
+                    match api_service.get_untracked().deactivate_administrator(&input, login_result.access_token).await {
+                        Ok(_) => {
+                            set_selected_admin(None);
+                            reload_admins();
+                        },
+                        Err(e) => log!("deactivate_administrator failed: {:?}", e)
+                    };
+                },
+                None => log!("deactivate_administrator failed: user must login first")
+            }
+        }
+    });
+
+    let is_self = move |admin_id: i64| login_resp().map(|login| login.login_user_id == admin_id).unwrap_or_default();
+
+    view! {
+        <div class="home-content">
+            <h2>"Users"</h2>
+            <div class="mail-container">
+                <div class="mail-list-container">
+                    <Show when=move || admins.is_empty() fallback=|| ()>
+                        <p>"No users"</p>
+                    </Show>
+                    <ul class="mail-list">
+                        <For
+                            each=move || admins.items.get()
+                            key=|item| (item.id, item.updated_at, item.is_active)
+                            children=move |item| {
+                                let item_id = item.id;
+                                let clicked_item = item.clone();
+
+                                view! {
+                                    <li
+                                        class="mail-list-item"
+                                        class=("user-inactive", !item.is_active)
+                                        class=("mail-selected", move || selected_admin().map(|selected| selected.id == item_id).unwrap_or_default())
+                                        on:click=move |_| set_selected_admin(Some(clicked_item.clone()))
+                                    >
+                                        <div class="opposites">
+                                            <span>{item.user_name.clone()}</span>
+                                            <small>{if item.is_active { item.role.as_str() } else { "deactivated" }}</small>
+                                        </div>
+                                        <small>{item.email.clone()}</small>
+                                    </li>
+                                }
+                            }
+                        />
+                    </ul>
+                    <LoadMore paged_list=admins />
+                    <button class="secondary-btn small-btn mail-paging" on:click=move |_| set_selected_admin(None)>
+                        "New User"
+                    </button>
+                </div>
+                <div class="mail-detail">
+                    {move || match selected_admin() {
+                        Some(admin) => view! {
+                            <EditUser
+                                admin=admin.clone()
+                                is_self=is_self(admin.id)
+                                submit_update_admin=submit_update_admin
+                                submit_update_password=submit_update_password
+                                submit_deactivate_admin=submit_deactivate_admin
+                            />
+                        }.into_view(),
+                        None => view! { <NewUser submit_new_admin=submit_new_admin /> }.into_view()
+                    }}
+                </div>
+            </div>
+        </div>
+    }
+}
+
+#[component]
+fn NewUser(submit_new_admin: Action<NewAdministrator, ()>) -> impl IntoView {
+    let (user_name, set_user_name) = create_signal("".to_string());
+    let (email, set_email) = create_signal("".to_string());
+    let (password, set_password) = create_signal("".to_string());
+    let (role, set_role) = create_signal(AdminRole::Author);
+
+    view! {
+        <h3>"New User"</h3>
+        <form on:submit=move |ev| {
+            ev.prevent_default();
+            submit_new_admin.dispatch(NewAdministrator { user_name: user_name(), email: email(), password: password(), role: role() });
+        }>
+            <section class="form-section">
+                <label for="user_name">"User Name"</label>
+                <input type="text" id="user_name" name="user_name" on:input=move |ev| set_user_name(event_target_value(&ev)) prop:value=user_name />
+            </section>
+            <section class="form-section">
+                <label for="email">"Email"</label>
+                <input type="text" id="email" name="email" on:input=move |ev| set_email(event_target_value(&ev)) prop:value=email />
+            </section>
+            <section class="form-section">
+                <label for="password">"Password"</label>
+                <input type="password" id="password" name="password" on:input=move |ev| set_password(event_target_value(&ev)) prop:value=password />
+            </section>
+            <RoleSelect role=role set_role=set_role />
+            <section class="form-section">
+                <button type="submit" class="primary-btn">"Create"</button>
+            </section>
+        </form>
+    }
+}
+
+#[component]
+fn EditUser(
+    admin: Administrator,
+    is_self: bool,
+    submit_update_admin: Action<UpdateAdministrator, ()>,
+    submit_update_password: Action<UpdateAdministratorPassword, ()>,
+    submit_deactivate_admin: Action<DeactivateAdministrator, ()>
+) -> impl IntoView {
+    let admin_id = admin.id;
+    let (user_name, set_user_name) = create_signal(admin.user_name.clone());
+    let (email, set_email) = create_signal(admin.email.clone());
+    let (role, set_role) = create_signal(admin.role);
+    let (current_password, set_current_password) = create_signal("".to_string());
+    let (new_password, set_new_password) = create_signal("".to_string());
+
+    view! {
+        <h3>{admin.user_name.clone()}</h3>
+        <form on:submit=move |ev| {
+            ev.prevent_default();
+            submit_update_admin.dispatch(UpdateAdministrator { admin_id, user_name: user_name(), email: email(), role: role() });
+        }>
+            <section class="form-section">
+                <label for="user_name">"User Name"</label>
+                <input type="text" id="user_name" name="user_name" on:input=move |ev| set_user_name(event_target_value(&ev)) prop:value=user_name />
+            </section>
+            <section class="form-section">
+                <label for="email">"Email"</label>
+                <input type="text" id="email" name="email" on:input=move |ev| set_email(event_target_value(&ev)) prop:value=email />
+            </section>
+            <RoleSelect role=role set_role=set_role />
+            <section class="form-section">
+                <button type="submit" class="primary-btn" prop:disabled=!admin.is_active>"Save"</button>
+            </section>
+        </form>
+        <form on:submit=move |ev| {
+            ev.prevent_default();
+            submit_update_password.dispatch(UpdateAdministratorPassword {
+                admin_id,
+                current_password: if is_self { Some(current_password()) } else { None },
+                new_password: new_password()
+            });
+            set_current_password("".to_string());
+            set_new_password("".to_string());
+        }>
+            <Show when=move || is_self fallback=|| ()>
+                <section class="form-section">
+                    <label for="current_password">"Current Password"</label>
+                    <input type="password" id="current_password" name="current_password" on:input=move |ev| set_current_password(event_target_value(&ev)) prop:value=current_password />
+                </section>
+            </Show>
+            <section class="form-section">
+                <label for="new_password">"New Password"</label>
+                <input type="password" id="new_password" name="new_password" on:input=move |ev| set_new_password(event_target_value(&ev)) prop:value=new_password />
+            </section>
+            <section class="mail-actions">
+                <button type="submit" class="secondary-btn" prop:disabled=!admin.is_active>"Change Password"</button>
+                <button
+                    type="button"
+                    class="primary-btn"
+                    prop:disabled=is_self || !admin.is_active
+                    on:click=move |_| submit_deactivate_admin.dispatch(DeactivateAdministrator { admin_id })
+                >
+                    "Deactivate"
+                </button>
+            </section>
+        </form>
+    }
+}
+
+#[component]
+fn RoleSelect(role: ReadSignal<AdminRole>, set_role: WriteSignal<AdminRole>) -> impl IntoView {
+    view! {
+        <section class="form-section">
+            <label for="role">"Role"</label>
+            <select
+                id="role"
+                name="role"
+                on:change=move |ev| {
+                    if let Some(selected) = AdminRole::from_str(&event_target_value(&ev)) {
+                        set_role(selected);
+                    }
+                }
+            >
+                {AdminRole::ALL.into_iter().map(|option| view! {
+                    <option value=option.as_str() selected=move || role() == option>{option.as_str()}</option>
+                }).collect_view()}
+            </select>
+        </section>
+    }
+}
@@ -0,0 +1,2 @@
+-- argon2id phc strings are close to 100 chars, leave room for stronger params later
+alter table administrator alter column "password" type varchar(255);
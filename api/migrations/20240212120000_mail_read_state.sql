@@ -0,0 +1,2 @@
+alter table mail add column "is_read" boolean not null default false;
+alter table mail add column "read_at" timestamptz(3);
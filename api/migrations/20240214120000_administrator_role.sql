@@ -0,0 +1,6 @@
+create type admin_role as enum ('owner', 'editor', 'author');
+
+alter table administrator add column "role" admin_role not null default 'author';
+
+-- admins created before roles existed could already change every post
+update administrator set "role" = 'owner';
@@ -0,0 +1,16 @@
+-- every refresh issues a new token in the same family, so reuse of a rotated token can revoke the whole family
+create table refresh_token (
+    "id" bigserial primary key,
+    "created_at" timestamptz(3) not null default current_timestamp,
+    "updated_at" timestamptz(3) not null default current_timestamp,
+    "jti" varchar(36) not null unique,
+    "family_id" varchar(36) not null,
+    "admin_id" bigint not null,
+    "expires_at" timestamptz(3) not null,
+    "revoked_at" timestamptz(3),
+    "replaced_by" varchar(36),
+
+    constraint fk_admin foreign key(admin_id) references administrator(id) on delete cascade
+);
+
+create index idx_refresh_token_family_id on refresh_token(family_id);
@@ -0,0 +1,2 @@
+alter table administrator add column "failed_login_count" integer not null default 0;
+alter table administrator add column "locked_until" timestamptz(3);
@@ -0,0 +1,16 @@
+-- totp_secret is set when enrollment starts, totp_enabled only once a code from it has been confirmed
+alter table administrator add column "totp_secret" varchar(64);
+alter table administrator add column "totp_enabled" boolean not null default false;
+alter table administrator add column "totp_last_used_step" bigint;
+
+create table administrator_recovery_code (
+    "id" bigserial primary key,
+    "created_at" timestamptz(3) not null default current_timestamp,
+    "admin_id" bigint not null,
+    "code_hash" varchar(255) not null,
+    "used_at" timestamptz(3),
+
+    constraint fk_admin foreign key(admin_id) references administrator(id) on delete cascade
+);
+
+create index idx_administrator_recovery_code_admin_id on administrator_recovery_code(admin_id);
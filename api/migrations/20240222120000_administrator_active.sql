@@ -0,0 +1,2 @@
+-- deactivated administrators keep their posts but can no longer log in
+alter table administrator add column "is_active" boolean not null default true;
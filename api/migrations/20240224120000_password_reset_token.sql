@@ -0,0 +1,11 @@
+-- only the sha-256 of each token is stored, a token can be used once
+create table password_reset_token (
+    "id" bigserial primary key,
+    "created_at" timestamptz(3) not null default current_timestamp,
+    "admin_id" bigint not null,
+    "token_hash" varchar(64) not null unique,
+    "expires_at" timestamptz(3) not null,
+    "used_at" timestamptz(3),
+
+    constraint fk_admin foreign key(admin_id) references administrator(id) on delete cascade
+);
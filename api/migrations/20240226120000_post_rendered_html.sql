@@ -0,0 +1,2 @@
+-- sanitized html of message, posts saved before this are rendered when they are read
+alter table post add column "rendered_html" text;
@@ -0,0 +1,2 @@
+-- html rendered before headings had ids, these posts are rendered when they are read until they are next saved
+update post set "rendered_html" = null;
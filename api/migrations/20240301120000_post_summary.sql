@@ -0,0 +1,4 @@
+-- optional summary written by the author, and the preview html and text made from it, or from the start of message, when the post is saved
+alter table post add column "excerpt" text;
+alter table post add column "summary_html" text;
+alter table post add column "summary_text" text;
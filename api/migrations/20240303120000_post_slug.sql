@@ -0,0 +1,26 @@
+-- posts are also addressed by a slug, made from the title when the post is saved and editable by the author.
+-- existing posts get one here, repeated titles get the post's id added
+alter table post add column "slug" varchar(250);
+
+with base as (
+    select "id", coalesce(nullif(trim(both '-' from left(regexp_replace(lower("title"), '[^a-z0-9]+', '-', 'g'), 100)), ''), 'post') as "slug"
+    from post
+), numbered as (
+    select "id", "slug", row_number() over (partition by "slug" order by "id") as "number"
+    from (select "id", case when "slug" ~ '^[0-9]+$' then 'post-' || "slug" else "slug" end as "slug" from base) as slugs
+)
+update post set "slug" = case when numbered."number" = 1 then numbered."slug" else numbered."slug" || '-' || post."id" end
+from numbered
+where numbered."id" = post."id";
+
+alter table post alter column "slug" set not null;
+alter table post add constraint uq_post_slug unique ("slug");
+
+-- slugs a post had before the author changed it, so old links can be redirected
+create table post_slug_redirect (
+    "slug" varchar(250) primary key,
+    "created_at" timestamptz(3) not null default current_timestamp,
+    "post_id" bigint not null,
+
+    constraint fk_post foreign key(post_id) references post(id) on delete cascade
+);
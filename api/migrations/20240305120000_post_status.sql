@@ -0,0 +1,11 @@
+-- draft, scheduled, published and archived posts
+create type post_status as enum ('draft', 'scheduled', 'published', 'archived');
+
+-- only published posts, and scheduled ones once published_at has passed, are public
+alter table post add column "status" post_status not null default 'draft';
+alter table post add column "published_at" timestamptz(3);
+
+-- posts saved before statuses existed were published as soon as they were saved
+update post set "status" = 'published', "published_at" = "created_at";
+
+create index idx_post_status_published_at on post(status, published_at);
@@ -0,0 +1,19 @@
+-- a copy of the title, message and excerpt every time a post is saved, so earlier versions can be compared and restored
+create table post_revision (
+    "id" bigserial primary key,
+    "created_at" timestamptz(3) not null default current_timestamp,
+    "post_id" bigint not null,
+    "admin_id" bigint not null,
+    "title" varchar(250) not null,
+    "message" text not null,
+    "excerpt" text,
+
+    constraint fk_post foreign key(post_id) references post(id) on delete cascade,
+    constraint fk_admin foreign key(admin_id) references administrator(id)
+);
+
+create index idx_post_revision_post_id on post_revision(post_id, id);
+
+-- posts saved before revisions existed start with their current text
+insert into post_revision ("created_at", "post_id", "admin_id", "title", "message", "excerpt")
+select "updated_at", "id", "admin_id", "title", "message", "excerpt" from post;
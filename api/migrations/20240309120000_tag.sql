@@ -0,0 +1,18 @@
+-- tags are found by the slug of their name, so names differing only in case or punctuation are the same tag
+create table tag (
+    "id" bigserial primary key,
+    "created_at" timestamptz(3) not null default current_timestamp,
+    "name" varchar(100) not null,
+    "slug" varchar(100) not null unique
+);
+
+create table post_tag (
+    "post_id" bigint not null,
+    "tag_id" bigint not null,
+
+    primary key (post_id, tag_id),
+    constraint fk_post foreign key(post_id) references post(id) on delete cascade,
+    constraint fk_tag foreign key(tag_id) references tag(id) on delete cascade
+);
+
+create index idx_post_tag_tag_id on post_tag(tag_id);
@@ -0,0 +1,8 @@
+-- kept up to date by postgres on every save, the title weighs most, then the excerpt, then the message
+alter table post add column "search_vector" tsvector generated always as (
+    setweight(to_tsvector('english', coalesce(title, '')), 'A') || 
+    setweight(to_tsvector('english', coalesce(excerpt, '')), 'B') || 
+    setweight(to_tsvector('english', coalesce(message, '')), 'C')
+) stored;
+
+create index idx_post_search_vector on post using gin(search_vector);
@@ -0,0 +1,4 @@
+-- lists are paged by their sort values rather than an offset, these let each page start where the last one ended
+create index idx_post_published_at_id on post(published_at desc, id desc);
+create index idx_post_created_at_id on post(created_at desc, id desc);
+create index idx_mail_created_at_id on mail(created_at desc, id desc);
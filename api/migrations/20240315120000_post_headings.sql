@@ -0,0 +1,2 @@
+-- headings of each post for its table of contents, null for posts saved before headings were stored, the api renders those again at startup
+alter table post add column "headings" jsonb;
@@ -0,0 +1,2 @@
+-- recovery codes are now looked up by an hmac of the code keyed by the totp secret, argon2 hashes from before cannot be converted
+delete from administrator_recovery_code where code_hash like '$argon2%';
@@ -0,0 +1,2 @@
+-- the message's text without markdown, search snippets are cut from it. null for posts saved before it was stored, the api fills those in at startup
+alter table post add column "message_text" text;
@@ -0,0 +1,15 @@
+-- revisions keep the slug and tag names the post was saved with, so restoring one brings them back too.
+-- null for revisions saved before, restoring those keeps the post's current slug and tags
+alter table post_revision add column "slug" varchar(250);
+alter table post_revision add column "tag_names" jsonb;
+
+-- the newest revision of each post is its current state
+update post_revision set
+    "slug" = post."slug",
+    "tag_names" = (
+        select coalesce(jsonb_agg(tag."name" order by tag."name"), '[]') 
+        from post_tag join tag on tag."id" = post_tag."tag_id" 
+        where post_tag."post_id" = post."id"
+    )
+from post
+where post."id" = post_revision."post_id" and post_revision."id" = (select max(newest."id") from post_revision as newest where newest."post_id" = post."id");
@@ -1,5 +1,8 @@
+use std::{collections::HashMap, env, fs::File, io::{BufReader, Read}};
 use chrono::{Utc, Duration};
-use jsonwebtoken::{DecodingKey, EncodingKey, Validation, encode, decode, Algorithm };
+use dotenv::dotenv;
+use jsonwebtoken::{DecodingKey, EncodingKey, Validation, encode, decode, decode_header, Algorithm, errors::ErrorKind };
+use log::{info, warn};
 use ring::signature::{Ed25519KeyPair, KeyPair};
 use serde::{Deserialize, Serialize};
 use async_trait::async_trait;
@@ -8,53 +11,206 @@ use derive_more::Display;
 pub const STANDARD_REFRESH_TOKEN_EXPIRATION: i64 = 60 * 60 * 24 * 30;
 pub const STANDARD_ACCESS_TOKEN_EXPIRATION: i64 = 60 * 10; // todo: switch to 2 min once testing complete
 pub const REFRESH_TOKEN_LABEL: &str = "refresh_token";
+pub const TOTP_CHALLENGE_TOKEN_EXPIRATION: i64 = 60 * 5;
+pub const TOTP_CHALLENGE_SCOPE: &str = "totp_challenge";
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String, // user_name
-    pub exp: usize
+    pub admin_id: i64,
+    pub exp: usize,
+    /// only set on refresh tokens, identifies the server side refresh_token row
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+    /// set on tokens that only grant one step, like finishing a totp login
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>
 }
 
-#[derive(Display, Debug)]
+impl Claims {
+    /// Refresh and challenge tokens are signed with the same keys but must not be accepted as bearer tokens
+    pub fn is_access_token(&self) -> bool {
+        self.jti.is_none() && self.scope.is_none()
+    }
+}
+
+#[derive(Display, Debug, PartialEq)]
 pub enum AuthenticationError {
     #[display(fmt = "Password Authentication Failure")]
     PasswordAuthenticationFailure,
     #[display(fmt = "Database Authentication Failure")]
-    DatabaseAuthenticationFailure
+    DatabaseAuthenticationFailure,
+    #[display(fmt = "Token Expired")]
+    ExpiredToken,
+    #[display(fmt = "Token Malformed")]
+    MalformedToken,
+    #[display(fmt = "Token Missing")]
+    MissingToken
 }
 
+/// kid of the signing key, used when none is configured
+pub const DEFAULT_SIGNING_KEY_ID: &str = "default";
+
 pub struct AuthKeys {
+    /// kid written into the header of every token issued
+    pub signing_key_id: String,
     pub encoding_key: EncodingKey,
-    pub decoding_key: DecodingKey
+    /// Every key a token may still be verified with, by kid. Includes the signing key
+    pub decoding_keys: HashMap<String, DecodingKey>
 }
 
+impl AuthKeys {
+    /// @signing_key is the kid and PKCS#8 DER document used to sign new tokens
+    /// @retired_keys are kids and PKCS#8 DER documents of rotated out keys whose tokens should still be accepted
+    pub fn from_pkcs8_der(signing_key: (String, Vec<u8>), retired_keys: Vec<(String, Vec<u8>)>) -> AuthKeys {
+        let (signing_key_id, signing_key_der) = signing_key;
+        let encoding_key = EncodingKey::from_ed_der(&signing_key_der);
+
+        let mut decoding_keys = HashMap::new();
+        for (kid, der) in retired_keys {
+            decoding_keys.insert(kid, get_decoding_key_from_pkcs8_der(&der));
+        }
+        decoding_keys.insert(signing_key_id.clone(), get_decoding_key_from_pkcs8_der(&signing_key_der));
+
+        AuthKeys { signing_key_id, encoding_key, decoding_keys }
+    }
+
+    pub fn get_decoding_key(&self, kid: &str) -> Option<&DecodingKey> {
+        self.decoding_keys.get(kid)
+    }
+}
+
+/// Loads keys from configuration so tokens survive restarts and can be shared across api replicas.
+/// JWT_SIGNING_KEY_ID: kid for the active key
+/// JWT_SIGNING_KEY: PKCS#8 PEM contents of the active key, or JWT_SIGNING_KEY_FILE: path to a PKCS#8 PEM or DER file
+/// JWT_RETIRED_KEY_FILES: comma separated kid=path list of rotated out keys that are still accepted
+/// If no signing key is configured a new one is generated and tokens will not survive a restart
 pub async fn init_auth_keys() -> AuthKeys {
-    let doc = Ed25519KeyPair::generate_pkcs8(&ring::rand::SystemRandom::new()).unwrap();
-    let encoding_key = EncodingKey::from_ed_der(doc.as_ref());
+    dotenv().ok();
+    let signing_key_id = env::var("JWT_SIGNING_KEY_ID").unwrap_or(DEFAULT_SIGNING_KEY_ID.to_string());
+
+    let signing_key_der = if let Ok(pem) = env::var("JWT_SIGNING_KEY") {
+        get_pkcs8_der(pem.as_bytes())
+    } else if let Ok(path) = env::var("JWT_SIGNING_KEY_FILE") {
+        get_pkcs8_der_from_file(&path)
+    } else {
+        warn!("No JWT signing key configured, generating a temporary key. Tokens will be invalid after restart");
+        Ed25519KeyPair::generate_pkcs8(&ring::rand::SystemRandom::new()).unwrap().as_ref().to_vec()
+    };
+
+    let retired_keys = match env::var("JWT_RETIRED_KEY_FILES") {
+        Ok(retired_key_files) => retired_key_files
+            .split(',')
+            .filter(|kid_and_path| !kid_and_path.trim().is_empty())
+            .map(|kid_and_path| {
+                let (kid, path) = kid_and_path
+                    .split_once('=')
+                    .expect("JWT_RETIRED_KEY_FILES entries must be formatted as kid=path");
+                (kid.trim().to_string(), get_pkcs8_der_from_file(path.trim()))
+            })
+            .collect::<Vec<(String, Vec<u8>)>>(),
+        Err(_) => vec![]
+    };
+    info!("JWT signing key id: {}, retired key count: {}", signing_key_id, retired_keys.len());
+
+    AuthKeys::from_pkcs8_der((signing_key_id, signing_key_der), retired_keys)
+}
+
+fn get_decoding_key_from_pkcs8_der(der: &[u8]) -> DecodingKey {
+    // maybe_unchecked also accepts v1 documents, which is what openssl genpkey writes
+    let pair = Ed25519KeyPair::from_pkcs8_maybe_unchecked(der).expect("JWT key must be an Ed25519 PKCS#8 document");
+    DecodingKey::from_ed_der(pair.public_key().as_ref())
+}
 
-    let pair = Ed25519KeyPair::from_pkcs8(doc.as_ref()).unwrap();
-    let decoding_key = DecodingKey::from_ed_der(pair.public_key().as_ref());
+fn get_pkcs8_der_from_file(path: &str) -> Vec<u8> {
+    let mut file = File::open(path).unwrap_or_else(|_| panic!("JWT key file {} was not found", path));
+    let mut contents = vec![];
+    file.read_to_end(&mut contents).expect("Failed to read JWT key file");
+    get_pkcs8_der(&contents)
+}
 
-    AuthKeys { encoding_key, decoding_key }
+/// Accepts either PEM or raw DER
+fn get_pkcs8_der(contents: &[u8]) -> Vec<u8> {
+    if !contents.starts_with(b"-----BEGIN") {
+        return contents.to_vec();
+    }
+
+    let mut reader = BufReader::new(contents);
+    let mut keys = rustls_pemfile::pkcs8_private_keys(&mut reader).expect("Failed to parse JWT key PEM");
+    if keys.is_empty() {
+        panic!("JWT key PEM does not contain a PKCS#8 private key");
+    }
+    keys.remove(0)
 }
 
-pub fn get_token(user_name: String, encoding_key: &EncodingKey, exp_duration_seconds: Option<i64>) -> String {
+pub fn get_token(admin_id: i64, user_name: String, auth_keys: &AuthKeys, exp_duration_seconds: Option<i64>) -> String {
     let duration = if let None = exp_duration_seconds {
         STANDARD_REFRESH_TOKEN_EXPIRATION
     } else {
         exp_duration_seconds.unwrap()
     };
-    let claims = Claims { sub: user_name, exp: (Utc::now() + Duration::seconds(duration)).timestamp() as usize };
-    let token = encode(&jsonwebtoken::Header::new(jsonwebtoken::Algorithm::EdDSA), &claims, encoding_key).unwrap();
+    let claims = Claims { sub: user_name, admin_id, exp: (Utc::now() + Duration::seconds(duration)).timestamp() as usize, jti: None, scope: None };
 
-    token
+    encode_claims(&claims, auth_keys)
 }
 
-pub fn decode_token(token: &str, decoding_key: &DecodingKey) -> Claims {
-    let validation = Validation::new(Algorithm::EdDSA);
-    let token_data = decode::<Claims>(token, decoding_key, &validation).unwrap();
+/// Refresh tokens carry a jti so they can be rotated and revoked server side
+pub fn get_refresh_token(admin_id: i64, user_name: String, jti: String, auth_keys: &AuthKeys) -> String {
+    let claims = Claims {
+        sub: user_name,
+        admin_id,
+        exp: (Utc::now() + Duration::seconds(STANDARD_REFRESH_TOKEN_EXPIRATION)).timestamp() as usize,
+        jti: Some(jti),
+        scope: None
+    };
+
+    encode_claims(&claims, auth_keys)
+}
+
+/// Issued by login when the administrator has totp enabled, it can only be exchanged at login/totp
+pub fn get_totp_challenge_token(admin_id: i64, user_name: String, auth_keys: &AuthKeys) -> String {
+    let claims = Claims {
+        sub: user_name,
+        admin_id,
+        exp: (Utc::now() + Duration::seconds(TOTP_CHALLENGE_TOKEN_EXPIRATION)).timestamp() as usize,
+        jti: None,
+        scope: Some(TOTP_CHALLENGE_SCOPE.to_string())
+    };
 
-    token_data.claims
+    encode_claims(&claims, auth_keys)
+}
+
+fn encode_claims(claims: &Claims, auth_keys: &AuthKeys) -> String {
+    let mut header = jsonwebtoken::Header::new(jsonwebtoken::Algorithm::EdDSA);
+    header.kid = Some(auth_keys.signing_key_id.clone());
+    encode(&header, claims, &auth_keys.encoding_key).unwrap()
+}
+
+/// The token's kid header selects the verification key from the keyring
+pub fn decode_token(token: &str, auth_keys: &AuthKeys) -> Result<Claims, AuthenticationError> {
+    decode_token_with_validation(token, auth_keys, true)
+}
+
+/// Verifies the signature but accepts an expired token, for refreshing an access token that has already run out
+pub fn decode_token_ignoring_expiration(token: &str, auth_keys: &AuthKeys) -> Result<Claims, AuthenticationError> {
+    decode_token_with_validation(token, auth_keys, false)
+}
+
+fn decode_token_with_validation(token: &str, auth_keys: &AuthKeys, validate_exp: bool) -> Result<Claims, AuthenticationError> {
+    let header = decode_header(token).map_err(|_| AuthenticationError::MalformedToken)?;
+    let kid = header.kid.unwrap_or(DEFAULT_SIGNING_KEY_ID.to_string());
+    let decoding_key = auth_keys.get_decoding_key(&kid).ok_or(AuthenticationError::MalformedToken)?;
+
+    let mut validation = Validation::new(Algorithm::EdDSA);
+    validation.validate_exp = validate_exp;
+    validation.leeway = 0;
+    match decode::<Claims>(token, decoding_key, &validation) {
+        Ok(token_data) => Ok(token_data.claims),
+        Err(e) => match e.kind() {
+            ErrorKind::ExpiredSignature => Err(AuthenticationError::ExpiredToken),
+            _ => Err(AuthenticationError::MalformedToken)
+        }
+    }
 }
 
 pub struct AuthService;
@@ -63,31 +219,22 @@ pub struct AuthService;
 pub trait Authenticator {
     /// Checks headers for Authorization and Bearer token
     /// @headers is a tuple: 0 is header name and 1 is header value
-    async fn is_authenticated(&self, user_name: String, headers: Vec<(&str, &str)>, decoding_key: &DecodingKey) -> Result<bool, AuthenticationError>;
+    async fn is_authenticated(&self, user_name: String, headers: Vec<(&str, &str)>, auth_keys: &AuthKeys) -> Result<bool, AuthenticationError>;
 }
 
 /// Check that user has already logged in and received their access token
 #[async_trait]
 impl Authenticator for AuthService {    
-    async fn is_authenticated(&self, user_name: String, headers: Vec<(&str, &str)>, decoding_key: &DecodingKey) -> Result<bool, AuthenticationError> {
-        let mut result: Result<bool, AuthenticationError> = Err(AuthenticationError::PasswordAuthenticationFailure);
-
-        _ = headers.iter().for_each(|header| {
-            let header_name = header.0;
-            let header_val = header.1;
-            
-            if header_name.to_lowercase() == "authorization" {
-                let bearer_items: Vec<&str> = header_val.split(' ').collect();
-                let claims = decode_token(bearer_items.get(1).unwrap(), decoding_key);
-                
-                if claims.sub == user_name {
-                    if claims.exp >= (Utc::now().timestamp() as usize) {
-                        result = Ok(true);
-                    }
-                }    
-            }
-        });
-
-        result
+    async fn is_authenticated(&self, user_name: String, headers: Vec<(&str, &str)>, auth_keys: &AuthKeys) -> Result<bool, AuthenticationError> {
+        let authorization = headers
+            .iter()
+            .find(|header| header.0.to_lowercase() == "authorization")
+            .ok_or(AuthenticationError::MissingToken)?;
+        let token = authorization.1
+            .strip_prefix("Bearer ")
+            .ok_or(AuthenticationError::MalformedToken)?;
+        let claims = decode_token(token, auth_keys)?;
+
+        Ok(claims.sub == user_name)
     }
 }
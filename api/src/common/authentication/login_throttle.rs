@@ -0,0 +1,223 @@
+use std::{collections::{HashMap, VecDeque}, net::IpAddr, sync::Mutex, time::{Duration, Instant}};
+use actix_web::HttpRequest;
+
+/// Consecutive failed logins allowed before an account is locked
+pub const MAX_FAILED_LOGIN_ATTEMPTS: i32 = 5;
+/// The first lock is a minute and doubles with every further failure, up to an hour
+pub const LOCKOUT_BASE_SECONDS: i64 = 60;
+pub const LOCKOUT_MAX_SECONDS: i64 = 60 * 60;
+
+pub const LOGIN_ATTEMPTS_PER_IP: usize = 20;
+pub const LOGIN_ATTEMPTS_PER_EMAIL: usize = 10;
+pub const LOGIN_ATTEMPT_WINDOW_SECONDS: u64 = 60 * 15;
+
+pub const PASSWORD_RESET_REQUESTS_PER_IP: usize = 10;
+pub const PASSWORD_RESET_REQUESTS_PER_EMAIL: usize = 3;
+pub const PASSWORD_RESET_REQUEST_WINDOW_SECONDS: u64 = 60 * 60;
+
+pub const CONTACT_MAIL_PER_IP: usize = 5;
+pub const CONTACT_MAIL_PER_SENDER: usize = 3;
+pub const CONTACT_MAIL_WINDOW_SECONDS: u64 = 60 * 60;
+
+/// Sliding window limiter kept in memory, so each api replica limits independently
+pub struct LoginRateLimiter {
+    attempts_per_ip: usize,
+    attempts_per_email: usize,
+    window: Duration,
+    trusted_proxies: Vec<IpAddr>,
+    attempts: Mutex<HashMap<String, VecDeque<Instant>>>
+}
+
+impl Default for LoginRateLimiter {
+    fn default() -> Self {
+        LoginRateLimiter::new(LOGIN_ATTEMPTS_PER_IP, LOGIN_ATTEMPTS_PER_EMAIL, Duration::from_secs(LOGIN_ATTEMPT_WINDOW_SECONDS))
+    }
+}
+
+impl LoginRateLimiter {
+    pub fn new(attempts_per_ip: usize, attempts_per_email: usize, window: Duration) -> Self {
+        LoginRateLimiter { attempts_per_ip, attempts_per_email, window, trusted_proxies: vec![], attempts: Mutex::new(HashMap::new()) }
+    }
+
+    /// Password reset requests are limited apart from logins, so requesting resets cannot use up an ip's logins
+    pub fn password_reset() -> Self {
+        LoginRateLimiter::new(PASSWORD_RESET_REQUESTS_PER_IP, PASSWORD_RESET_REQUESTS_PER_EMAIL, Duration::from_secs(PASSWORD_RESET_REQUEST_WINDOW_SECONDS))
+    }
+
+    /// Contact form mail is limited per ip and per sender address, as anyone can send it
+    pub fn contact_mail() -> Self {
+        LoginRateLimiter::new(CONTACT_MAIL_PER_IP, CONTACT_MAIL_PER_SENDER, Duration::from_secs(CONTACT_MAIL_WINDOW_SECONDS))
+    }
+
+    /// Forwarded headers are only read from requests sent by @trusted_proxies
+    pub fn with_trusted_proxies(mut self, trusted_proxies: Vec<IpAddr>) -> Self {
+        self.trusted_proxies = trusted_proxies;
+        self
+    }
+
+    /// The ip attempts are limited by. This is the peer address, unless the peer is a trusted proxy,
+    /// as Forwarded and X-Forwarded-For are set by the client and could otherwise be rotated to get around the limit
+    pub fn get_client_ip(&self, req: &HttpRequest) -> String {
+        let Some(peer_ip) = req.peer_addr().map(|addr| addr.ip()) else {
+            return "unknown".to_string();
+        };
+        if !self.trusted_proxies.contains(&peer_ip) {
+            return peer_ip.to_string();
+        }
+
+        req.connection_info()
+            .realip_remote_addr()
+            .map(|ip| ip.to_string())
+            .unwrap_or_else(|| peer_ip.to_string())
+    }
+
+    /// Returns the seconds until another attempt is allowed when either key is over its limit
+    pub fn check(&self, ip: &str, email: &str) -> Result<(), u64> {
+        let mut attempts = self.attempts.lock().unwrap();
+        self.get_retry_after(&mut attempts, ip, email, Instant::now())
+    }
+
+    /// Counts an attempt against both keys before it is made. The check and the count share one lock,
+    /// so a burst of concurrent attempts cannot all get past the check. Returns the attempt, to release if it should not count
+    pub fn reserve(&self, ip: &str, email: &str) -> Result<Instant, u64> {
+        let now = Instant::now();
+        let mut attempts = self.attempts.lock().unwrap();
+        self.get_retry_after(&mut attempts, ip, email, now)?;
+
+        attempts.entry(get_ip_key(ip)).or_default().push_back(now);
+        attempts.entry(get_email_key(email)).or_default().push_back(now);
+        Ok(now)
+    }
+
+    /// Takes back an attempt made by reserve, e.g. a login that succeeded
+    pub fn release(&self, ip: &str, email: &str, reserved_at: Instant) {
+        let mut attempts = self.attempts.lock().unwrap();
+        for key in [get_ip_key(ip), get_email_key(email)] {
+            if let Some(times) = attempts.get_mut(&key) {
+                if let Some(position) = times.iter().position(|time| *time == reserved_at) {
+                    times.remove(position);
+                }
+            }
+        }
+    }
+
+    /// Counts every attempt, the attempt is not recorded when over the limit
+    pub fn check_and_record(&self, ip: &str, email: &str) -> Result<(), u64> {
+        self.reserve(ip, email).map(|_| ())
+    }
+
+    fn get_retry_after(&self, attempts: &mut HashMap<String, VecDeque<Instant>>, ip: &str, email: &str, now: Instant) -> Result<(), u64> {
+        // drop expired entries so the map does not grow without bound
+        attempts.retain(|_, times| {
+            while times.front().map(|time| now.duration_since(*time) >= self.window).unwrap_or_default() {
+                times.pop_front();
+            }
+            !times.is_empty()
+        });
+
+        let retry_after = [(get_ip_key(ip), self.attempts_per_ip), (get_email_key(email), self.attempts_per_email)]
+            .iter()
+            .filter_map(|(key, limit)| {
+                let times = attempts.get(key)?;
+                if times.len() < *limit {
+                    return None;
+                }
+                let oldest = times.front()?;
+                Some(self.window.saturating_sub(now.duration_since(*oldest)).as_secs().max(1))
+            })
+            .max();
+        match retry_after {
+            Some(retry_after) => Err(retry_after),
+            None => Ok(())
+        }
+    }
+}
+
+fn get_ip_key(ip: &str) -> String {
+    format!("ip:{}", ip)
+}
+
+fn get_email_key(email: &str) -> String {
+    format!("email:{}", email.trim().to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn test_login_rate_limiter_check_does_not_record() {
+        let limiter = LoginRateLimiter::new(1, 1, Duration::from_secs(60));
+
+        assert!(limiter.check("1.1.1.1", "dave@test.com").is_ok());
+        assert!(limiter.check("1.1.1.1", "dave@test.com").is_ok());
+        limiter.reserve("1.1.1.1", "dave@test.com").unwrap();
+        assert!(limiter.check("1.1.1.1", "jon@test.com").is_err());
+        assert!(limiter.check("2.2.2.2", "dave@test.com").is_err());
+    }
+
+    #[test]
+    fn test_login_rate_limiter_reserves_until_released() {
+        let limiter = LoginRateLimiter::new(2, 2, Duration::from_secs(60));
+
+        let first = limiter.reserve("1.1.1.1", "dave@test.com").unwrap();
+        _ = limiter.reserve("1.1.1.1", "dave@test.com").unwrap();
+        assert!(limiter.reserve("1.1.1.1", "dave@test.com").is_err());
+        limiter.release("1.1.1.1", "dave@test.com", first);
+        assert!(limiter.reserve("1.1.1.1", "dave@test.com").is_ok());
+        assert!(limiter.reserve("1.1.1.1", "dave@test.com").is_err());
+    }
+
+    #[test]
+    fn test_get_client_ip_ignores_forwarded_headers_from_untrusted_peers() {
+        let limiter = LoginRateLimiter::default();
+        let req = TestRequest::default()
+            .peer_addr("10.0.0.1:5000".parse().unwrap())
+            .insert_header(("X-Forwarded-For", "1.2.3.4"))
+            .to_http_request();
+
+        assert!(limiter.get_client_ip(&req) == "10.0.0.1");
+    }
+
+    #[test]
+    fn test_get_client_ip_reads_forwarded_headers_from_trusted_proxies() {
+        let limiter = LoginRateLimiter::default().with_trusted_proxies(vec!["10.0.0.1".parse().unwrap()]);
+        let req = TestRequest::default()
+            .peer_addr("10.0.0.1:5000".parse().unwrap())
+            .insert_header(("X-Forwarded-For", "1.2.3.4"))
+            .to_http_request();
+
+        assert!(limiter.get_client_ip(&req) == "1.2.3.4");
+    }
+
+    #[test]
+    fn test_login_rate_limiter_limits_per_email() {
+        let limiter = LoginRateLimiter::new(10, 2, Duration::from_secs(60));
+
+        assert!(limiter.check_and_record("1.1.1.1", "dave@test.com").is_ok());
+        assert!(limiter.check_and_record("2.2.2.2", "Dave@test.com").is_ok());
+        assert!(limiter.check_and_record("3.3.3.3", "dave@test.com").is_err());
+        assert!(limiter.check_and_record("3.3.3.3", "jon@test.com").is_ok());
+    }
+
+    #[test]
+    fn test_login_rate_limiter_limits_per_ip() {
+        let limiter = LoginRateLimiter::new(2, 10, Duration::from_secs(60));
+
+        assert!(limiter.check_and_record("1.1.1.1", "a@test.com").is_ok());
+        assert!(limiter.check_and_record("1.1.1.1", "b@test.com").is_ok());
+        assert!(limiter.check_and_record("1.1.1.1", "c@test.com").is_err());
+        assert!(limiter.check_and_record("2.2.2.2", "c@test.com").is_ok());
+    }
+
+    #[test]
+    fn test_login_rate_limiter_allows_attempts_after_window() {
+        let limiter = LoginRateLimiter::new(1, 1, Duration::from_millis(10));
+
+        assert!(limiter.check_and_record("1.1.1.1", "dave@test.com").is_ok());
+        assert!(limiter.check_and_record("1.1.1.1", "dave@test.com").is_err());
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(limiter.check_and_record("1.1.1.1", "dave@test.com").is_ok());
+    }
+}
@@ -0,0 +1,117 @@
+use actix_web::web;
+use argon2::{Argon2, password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString, Error}};
+use lazy_static::lazy_static;
+use rand_core::OsRng;
+
+const ARGON2_PHC_PREFIX: &str = "$argon2";
+
+lazy_static! {
+    /// Hashed with the same parameters as stored passwords, so verifying against it takes as long
+    static ref DUMMY_PASSWORD_HASH: String = hash_password("dummy password").unwrap_or_default();
+}
+
+/// Returns an Argon2id PHC string, e.g. $argon2id$v=19$m=19456,t=2,p=1$salt$hash
+pub fn hash_password(password: &str) -> Result<String, Error> {
+    let salt = SaltString::generate(&mut OsRng);
+    let password_hash = Argon2::default().hash_password(password.as_bytes(), &salt)?;
+
+    Ok(password_hash.to_string())
+}
+
+/// Verifies password against a stored PHC string, comparison is done in constant time by argon2
+pub fn verify_password(password: &str, stored_hash: &str) -> bool {
+    match PasswordHash::new(stored_hash) {
+        Ok(parsed_hash) => Argon2::default().verify_password(password.as_bytes(), &parsed_hash).is_ok(),
+        Err(_) => false
+    }
+}
+
+/// Does the work of verify_password for a login with no stored hash, e.g. an unknown email, and always fails.
+/// The time taken then does not reveal whether the email exists
+pub fn verify_dummy_password(password: &str) -> bool {
+    let _ = verify_password(password, &DUMMY_PASSWORD_HASH);
+    false
+}
+
+/// hash_password on the blocking thread pool, argon2 takes long enough that a few logins at once would stall every other request
+pub async fn hash_password_async(password: String) -> Result<String, Error> {
+    web::block(move || hash_password(&password)).await.unwrap_or(Err(Error::Crypto))
+}
+
+/// verify_password on the blocking thread pool
+pub async fn verify_password_async(password: String, stored_hash: String) -> bool {
+    web::block(move || verify_password(&password, &stored_hash)).await.unwrap_or_default()
+}
+
+/// verify_dummy_password on the blocking thread pool
+pub async fn verify_dummy_password_async(password: String) -> bool {
+    web::block(move || verify_dummy_password(&password)).await.unwrap_or_default()
+}
+
+/// Rows created before hashing was introduced hold the plaintext password
+pub fn is_password_hashed(stored_password: &str) -> bool {
+    stored_password.starts_with(ARGON2_PHC_PREFIX)
+}
+
+/// Only used to check legacy plaintext rows so they can be rehashed on next login
+pub fn verify_legacy_password(password: &str, stored_password: &str) -> bool {
+    ring::constant_time::verify_slices_are_equal(password.as_bytes(), stored_password.as_bytes()).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSWORD: &str = "123";
+
+    #[test]
+    fn test_hash_password_returns_argon2id_phc_string() {
+        let password_hash = hash_password(PASSWORD).unwrap();
+
+        assert!(password_hash.starts_with("$argon2id$"));
+        assert!(password_hash != PASSWORD);
+        assert!(is_password_hashed(&password_hash));
+    }
+
+    #[test]
+    fn test_hash_password_uses_unique_salt() {
+        assert!(hash_password(PASSWORD).unwrap() != hash_password(PASSWORD).unwrap());
+    }
+
+    #[test]
+    fn test_verify_password_accepts_correct_and_rejects_wrong_password() {
+        let password_hash = hash_password(PASSWORD).unwrap();
+
+        assert!(verify_password(PASSWORD, &password_hash));
+        assert!(!verify_password("1234", &password_hash));
+    }
+
+    #[test]
+    fn test_verify_dummy_password_rejects_every_password() {
+        assert!(DUMMY_PASSWORD_HASH.starts_with("$argon2id$"));
+        assert!(!verify_dummy_password("dummy password"));
+        assert!(!verify_dummy_password(PASSWORD));
+    }
+
+    #[tokio::test]
+    async fn test_password_async_matches_password() {
+        let password_hash = hash_password_async(PASSWORD.to_string()).await.unwrap();
+
+        assert!(verify_password(PASSWORD, &password_hash));
+        assert!(verify_password_async(PASSWORD.to_string(), password_hash.clone()).await);
+        assert!(!verify_password_async("1234".to_string(), password_hash).await);
+        assert!(!verify_dummy_password_async(PASSWORD.to_string()).await);
+    }
+
+    #[test]
+    fn test_verify_password_rejects_plaintext_stored_password() {
+        assert!(!verify_password(PASSWORD, PASSWORD));
+        assert!(!is_password_hashed(PASSWORD));
+    }
+
+    #[test]
+    fn test_verify_legacy_password_compares_plaintext() {
+        assert!(verify_legacy_password(PASSWORD, PASSWORD));
+        assert!(!verify_legacy_password(PASSWORD, "124"));
+    }
+}
@@ -0,0 +1,39 @@
+use ring::{digest, rand::{SecureRandom, SystemRandom}};
+
+/// Seconds a reset link stays valid
+pub const PASSWORD_RESET_TOKEN_EXPIRATION: i64 = 60 * 30;
+const PASSWORD_RESET_TOKEN_LENGTH: usize = 32;
+
+/// Returns the token to send to the user and the hash to store.
+/// The token is random enough that a fast hash is safe and lets the row be looked up by hash
+pub fn generate_password_reset_token() -> (String, String) {
+    let mut bytes = [0u8; PASSWORD_RESET_TOKEN_LENGTH];
+    SystemRandom::new().fill(&mut bytes).unwrap();
+    let token = encode_hex(&bytes);
+    let token_hash = hash_password_reset_token(&token);
+
+    (token, token_hash)
+}
+
+pub fn hash_password_reset_token(token: &str) -> String {
+    encode_hex(digest::digest(&digest::SHA256, token.trim().as_bytes()).as_ref())
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_password_reset_token_returns_matching_hash() {
+        let (token, token_hash) = generate_password_reset_token();
+
+        assert!(token.len() == PASSWORD_RESET_TOKEN_LENGTH * 2);
+        assert!(token_hash != token);
+        assert!(hash_password_reset_token(&token) == token_hash);
+        assert!(generate_password_reset_token().0 != token);
+    }
+}
@@ -0,0 +1,210 @@
+use ring::{hmac, rand::{SecureRandom, SystemRandom}};
+
+/// RFC 6238 defaults, which is what authenticator apps expect
+pub const TOTP_DIGITS: u32 = 6;
+pub const TOTP_STEP_SECONDS: i64 = 30;
+/// Codes from one step either side are accepted to allow for clock drift
+const TOTP_ALLOWED_DRIFT_STEPS: i64 = 1;
+const TOTP_SECRET_LENGTH: usize = 20;
+pub const TOTP_ISSUER: &str = "RustyIndie";
+
+pub const RECOVERY_CODE_COUNT: usize = 10;
+const RECOVERY_CODE_LENGTH: usize = 10;
+const RECOVERY_CODE_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
+const BASE32_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// New random secret, base32 encoded without padding
+pub fn generate_totp_secret() -> String {
+    let mut secret = [0u8; TOTP_SECRET_LENGTH];
+    SystemRandom::new().fill(&mut secret).unwrap();
+    encode_base32(&secret)
+}
+
+/// The otpauth uri authenticator apps import, this is also the payload to render as a QR code
+pub fn get_provisioning_uri(account_name: &str, secret: &str) -> String {
+    format!(
+        "otpauth://totp/{issuer}:{account}?secret={secret}&issuer={issuer}&algorithm=SHA1&digits={digits}&period={period}",
+        issuer = TOTP_ISSUER,
+        account = encode_uri_component(account_name),
+        secret = secret,
+        digits = TOTP_DIGITS,
+        period = TOTP_STEP_SECONDS
+    )
+}
+
+pub fn get_totp_step(unix_time: i64) -> i64 {
+    unix_time.div_euclid(TOTP_STEP_SECONDS)
+}
+
+pub fn get_totp_code(secret: &str, step: i64) -> Option<String> {
+    let key_bytes = decode_base32(secret)?;
+    let key = hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, &key_bytes);
+    let tag = hmac::sign(&key, &step.to_be_bytes());
+    let digest = tag.as_ref();
+
+    // dynamic truncation, RFC 4226 section 5.3
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([digest[offset] & 0x7f, digest[offset + 1], digest[offset + 2], digest[offset + 3]]);
+    let code = binary % 10_u32.pow(TOTP_DIGITS);
+
+    Some(format!("{:0width$}", code, width = TOTP_DIGITS as usize))
+}
+
+/// Returns the step the code matched so callers can reject it being used again
+pub fn verify_totp_code(secret: &str, code: &str, unix_time: i64) -> Option<i64> {
+    let code = code.trim();
+    if code.len() != TOTP_DIGITS as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let current_step = get_totp_step(unix_time);
+    (current_step - TOTP_ALLOWED_DRIFT_STEPS..=current_step + TOTP_ALLOWED_DRIFT_STEPS).find(|step| {
+        match get_totp_code(secret, *step) {
+            Some(expected) => ring::constant_time::verify_slices_are_equal(expected.as_bytes(), code.as_bytes()).is_ok(),
+            None => false
+        }
+    })
+}
+
+/// Single use codes for when the authenticator device is lost. Only their hashes are stored
+pub fn generate_recovery_codes() -> Vec<String> {
+    let rng = SystemRandom::new();
+
+    (0..RECOVERY_CODE_COUNT).map(|_| {
+        let mut bytes = [0u8; RECOVERY_CODE_LENGTH];
+        rng.fill(&mut bytes).unwrap();
+        let code = bytes
+            .iter()
+            .map(|byte| RECOVERY_CODE_ALPHABET[*byte as usize % RECOVERY_CODE_ALPHABET.len()] as char)
+            .collect::<String>();
+        format!("{}-{}", &code[..RECOVERY_CODE_LENGTH / 2], &code[RECOVERY_CODE_LENGTH / 2..])
+    }).collect()
+}
+
+/// Hmac-sha256 of the normalized @code keyed by the administrator's totp secret, hex encoded. The codes are random enough
+/// that a fast hash is safe, and it lets a code be looked up by its hash rather than verified against each stored one.
+/// Anyone who could recompute it from a stored secret could make totp codes from that secret anyway
+pub fn hash_recovery_code(totp_secret: &str, code: &str) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, totp_secret.as_bytes());
+    hmac::sign(&key, normalize_recovery_code(code).as_bytes())
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Users may type recovery codes in any case, with or without the dash
+pub fn normalize_recovery_code(code: &str) -> String {
+    let code = code.trim().to_lowercase().replace('-', "");
+    if code.len() != RECOVERY_CODE_LENGTH {
+        return code;
+    }
+    format!("{}-{}", &code[..RECOVERY_CODE_LENGTH / 2], &code[RECOVERY_CODE_LENGTH / 2..])
+}
+
+fn encode_base32(bytes: &[u8]) -> String {
+    let mut output = String::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+
+    for byte in bytes {
+        buffer = (buffer << 8) | *byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            output.push(BASE32_ALPHABET[((buffer >> (bits - 5)) & 0x1f) as usize] as char);
+            bits -= 5;
+        }
+    }
+    if bits > 0 {
+        output.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+
+    output
+}
+
+fn decode_base32(input: &str) -> Option<Vec<u8>> {
+    let mut output = vec![];
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+
+    for c in input.trim_end_matches('=').chars().filter(|c| !c.is_whitespace()) {
+        let value = BASE32_ALPHABET.iter().position(|a| *a as char == c.to_ascii_uppercase())? as u32;
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            output.push(((buffer >> (bits - 8)) & 0xff) as u8);
+            bits -= 8;
+        }
+    }
+
+    Some(output)
+}
+
+fn encode_uri_component(value: &str) -> String {
+    value.bytes().map(|byte| {
+        if byte.is_ascii_alphanumeric() || b"-_.~@".contains(&byte) {
+            (byte as char).to_string()
+        } else {
+            format!("%{:02X}", byte)
+        }
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// RFC 6238 appendix B secret "12345678901234567890"
+    const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    #[test]
+    fn test_get_totp_code_matches_rfc_6238_vectors() {
+        assert!(get_totp_code(RFC_SECRET, get_totp_step(59)).unwrap() == "287082");
+        assert!(get_totp_code(RFC_SECRET, get_totp_step(1111111109)).unwrap() == "081804");
+        assert!(get_totp_code(RFC_SECRET, get_totp_step(2000000000)).unwrap() == "279037");
+    }
+
+    #[test]
+    fn test_verify_totp_code_allows_one_step_drift() {
+        assert!(verify_totp_code(RFC_SECRET, "287082", 59) == Some(1));
+        assert!(verify_totp_code(RFC_SECRET, "287082", 59 + TOTP_STEP_SECONDS) == Some(1));
+        assert!(verify_totp_code(RFC_SECRET, "287082", 59 + TOTP_STEP_SECONDS * 3).is_none());
+        assert!(verify_totp_code(RFC_SECRET, "abcdef", 59).is_none());
+    }
+
+    #[test]
+    fn test_base32_round_trips() {
+        let bytes = b"12345678901234567890";
+
+        assert!(encode_base32(bytes) == RFC_SECRET);
+        assert!(decode_base32(RFC_SECRET).unwrap() == bytes.to_vec());
+    }
+
+    #[test]
+    fn test_get_provisioning_uri_encodes_account() {
+        let uri = get_provisioning_uri("dave smith@test.com", RFC_SECRET);
+
+        assert!(uri.starts_with("otpauth://totp/RustyIndie:dave%20smith@test.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ"));
+    }
+
+    #[test]
+    fn test_recovery_codes_are_unique_and_normalize() {
+        let codes = generate_recovery_codes();
+        let first = codes.first().unwrap();
+
+        assert!(codes.len() == RECOVERY_CODE_COUNT);
+        assert!(codes.iter().all(|code| codes.iter().filter(|other| *other == code).count() == 1));
+        assert!(normalize_recovery_code(&first.replace('-', "").to_uppercase()) == *first);
+    }
+
+    #[test]
+    fn test_hash_recovery_code_is_keyed_by_secret_and_ignores_formatting() {
+        let code = generate_recovery_codes().remove(0);
+        let code_hash = hash_recovery_code(RFC_SECRET, &code);
+
+        assert!(code_hash.len() == 64);
+        assert!(hash_recovery_code(RFC_SECRET, &code.replace('-', "").to_uppercase()) == code_hash);
+        assert!(hash_recovery_code(&generate_totp_secret(), &code) != code_hash);
+    }
+}
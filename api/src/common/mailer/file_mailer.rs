@@ -0,0 +1,54 @@
+use std::{fs, path::PathBuf};
+use async_trait::async_trait;
+use chrono::Utc;
+use log::info;
+use uuid::Uuid;
+use super::mail_service::{Mailer, MailerError, OutgoingMail};
+
+/// For dev and tests. Logs every mail and, when @output_dir is set, also writes it there as a text file
+#[derive(Default)]
+pub struct FileMailer {
+    output_dir: Option<PathBuf>
+}
+
+impl FileMailer {
+    pub fn new(output_dir: Option<PathBuf>) -> Self {
+        FileMailer { output_dir }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, mail: OutgoingMail) -> Result<(), MailerError> {
+        info!("Mail to: {}, subject: {}\n{}", mail.to, mail.subject, mail.body);
+
+        if let Some(output_dir) = self.output_dir.as_ref() {
+            let path = output_dir.join(format!("{}-{}.txt", Utc::now().format("%Y%m%d%H%M%S"), Uuid::new_v4()));
+            let contents = format!("To: {}\nSubject: {}\n\n{}", mail.to, mail.subject, mail.body);
+            fs::create_dir_all(output_dir)
+                .and_then(|_| fs::write(&path, contents))
+                .map_err(|e| MailerError::SendFailure(e.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_file_mailer_writes_mail_to_output_dir() {
+        let output_dir = std::env::temp_dir().join(format!("rustyindie-mail-{}", Uuid::new_v4()));
+        let mailer = FileMailer::new(Some(output_dir.clone()));
+
+        mailer.send(OutgoingMail { to: "dave@test.com".to_string(), subject: "subject".to_string(), body: "body".to_string() }).await.unwrap();
+
+        let files = fs::read_dir(&output_dir).unwrap().collect::<Vec<_>>();
+        assert!(files.len() == 1);
+        let contents = fs::read_to_string(files[0].as_ref().unwrap().path()).unwrap();
+        assert!(contents.contains("To: dave@test.com"));
+        assert!(contents.ends_with("body"));
+        fs::remove_dir_all(output_dir).unwrap();
+    }
+}
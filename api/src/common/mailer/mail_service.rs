@@ -0,0 +1,51 @@
+use std::env;
+use async_trait::async_trait;
+use derive_more::Display;
+use dotenv::dotenv;
+use log::{info, warn};
+use super::{file_mailer::FileMailer, smtp_mailer::SmtpMailer};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutgoingMail {
+    pub to: String,
+    pub subject: String,
+    /// plain text
+    pub body: String
+}
+
+#[derive(Display, Debug, PartialEq)]
+pub enum MailerError {
+    #[display(fmt = "Invalid Mail Address: {}", _0)]
+    InvalidAddress(String),
+    #[display(fmt = "Mail Build Failure: {}", _0)]
+    BuildFailure(String),
+    #[display(fmt = "Mail Send Failure: {}", _0)]
+    SendFailure(String)
+}
+
+#[async_trait]
+pub trait Mailer {
+    async fn send(&self, mail: OutgoingMail) -> Result<(), MailerError>;
+}
+
+/// SMTP when SMTP_HOST is set, otherwise mail is only logged and optionally written to MAIL_OUTPUT_DIR
+pub fn init_mailer() -> Box<dyn Mailer + Send + Sync> {
+    dotenv().ok();
+
+    match env::var("SMTP_HOST") {
+        Ok(host) => {
+            let port = env::var("SMTP_PORT").ok().map(|port| port.parse::<u16>().expect("SMTP_PORT must be a port number"));
+            let from = env::var("MAIL_FROM").expect("MAIL_FROM is required when SMTP_HOST is set");
+            let credentials = match (env::var("SMTP_USERNAME"), env::var("SMTP_PASSWORD")) {
+                (Ok(user_name), Ok(password)) => Some((user_name, password)),
+                _ => None
+            };
+            info!("Sending mail through SMTP host {}", host);
+            Box::new(SmtpMailer::new(&host, port, credentials, from).expect("Failed to configure SMTP mailer"))
+        },
+        Err(_) => {
+            warn!("No SMTP_HOST configured, outgoing mail will only be logged");
+            Box::new(FileMailer::new(env::var("MAIL_OUTPUT_DIR").ok().map(Into::into)))
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use async_trait::async_trait;
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor
+};
+use super::mail_service::{Mailer, MailerError, OutgoingMail};
+
+/// Sends through an SMTP relay using STARTTLS
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox
+}
+
+impl SmtpMailer {
+    /// @credentials is user name and password
+    pub fn new(host: &str, port: Option<u16>, credentials: Option<(String, String)>, from: String) -> Result<Self, MailerError> {
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+            .map_err(|e| MailerError::SendFailure(e.to_string()))?;
+        if let Some(port) = port {
+            builder = builder.port(port);
+        }
+        if let Some((user_name, password)) = credentials {
+            builder = builder.credentials(Credentials::new(user_name, password));
+        }
+
+        Ok(SmtpMailer {
+            transport: builder.build(),
+            from: from.parse().map_err(|_| MailerError::InvalidAddress(from))?
+        })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, mail: OutgoingMail) -> Result<(), MailerError> {
+        let to: Mailbox = mail.to.parse().map_err(|_| MailerError::InvalidAddress(mail.to.clone()))?;
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(mail.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(mail.body)
+            .map_err(|e| MailerError::BuildFailure(e.to_string()))?;
+
+        self.transport.send(message).await.map_err(|e| MailerError::SendFailure(e.to_string()))?;
+        Ok(())
+    }
+}
@@ -1,20 +1,82 @@
 use chrono::{DateTime, Utc};
 use serde::{Serialize, Deserialize};
 use sqlx::prelude::FromRow;
+use crate::common::repository::base::PageCursor;
 
 
-#[derive(PartialEq)]
+/// owner: manages administrators and every post
+/// editor: can edit and delete every post
+/// author: can only edit and delete their own posts
+#[derive(sqlx::Type, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[sqlx(type_name = "admin_role", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum AdminRole {
+    Owner,
+    Editor,
+    Author
+}
+
+impl AdminRole {
+    pub fn can_manage_any_post(&self) -> bool {
+        match self {
+            AdminRole::Owner | AdminRole::Editor => true,
+            AdminRole::Author => false
+        }
+    }
+
+    pub fn can_manage_administrators(&self) -> bool {
+        match self {
+            AdminRole::Owner => true,
+            AdminRole::Editor | AdminRole::Author => false
+        }
+    }
+}
+
+#[derive(PartialEq, Debug)]
 pub enum AuthenticateResult {
     Success{ id: i64 },
-    Failure
+    Failure,
+    /// too many consecutive failures, no password is checked until @until
+    Locked{ until: DateTime<Utc> }
 }
 
-#[derive(FromRow, Serialize, Deserialize, Debug)]
+/// Only the fields needed to verify a login attempt
+#[derive(FromRow)]
+pub struct AdministratorCredential {
+    pub id: i64,
+    /// Argon2id PHC string, or plaintext for rows created before hashing was added
+    pub password: String,
+    pub failed_login_count: i32,
+    pub locked_until: Option<DateTime<Utc>>,
+    pub is_active: bool
+}
+
+#[derive(FromRow)]
+pub struct AdministratorTotp {
+    pub id: i64,
+    /// base32, set while enrolling and after
+    pub totp_secret: Option<String>,
+    pub totp_enabled: bool,
+    /// step of the last accepted code, a code can only be used once
+    pub totp_last_used_step: Option<i64>
+}
+
+#[derive(FromRow, Serialize, Deserialize, Debug, Clone)]
 pub struct Administrator {
     pub id: i64,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub user_name: String,
     pub email: String,
-    pub password: String
+    pub password: String,
+    pub role: AdminRole,
+    /// false once deactivated, the row is kept so their posts stay attributed
+    pub is_active: bool
+}
+
+impl Administrator {
+    /// Where the next page of administrators starts
+    pub fn cursor(&self) -> PageCursor {
+        PageCursor { sort_at: self.created_at, id: self.id }
+    }
 }
\ No newline at end of file
@@ -1,24 +1,94 @@
-use sqlx::{Postgres, query_as, Pool, Error};
-use crate::common::repository::{administrator::models::AuthenticateResult, base::{EntityId, DbRepo, ConnGetter}};
+use chrono::{DateTime, Utc};
+use sqlx::{Postgres, query, query_as, query_scalar, Pool, Error};
+use log::error;
+use crate::common::repository::{administrator::models::AuthenticateResult, base::{EntityId, DbRepo, ConnGetter, PageCursor}, error::SqlxError};
+use crate::common::authentication::login_throttle::{LOCKOUT_BASE_SECONDS, LOCKOUT_MAX_SECONDS, MAX_FAILED_LOGIN_ATTEMPTS};
+use crate::common::authentication::totp::{hash_recovery_code, verify_totp_code};
+use crate::common::authentication::password_hasher::{hash_password_async, is_password_hashed, verify_dummy_password_async, verify_legacy_password, verify_password_async};
 use async_trait::async_trait;
-use crate::common::repository::administrator::models::Administrator;
+use crate::common::repository::administrator::models::{AdminRole, Administrator, AdministratorCredential, AdministratorTotp};
 
 mod internal {   
     use super::*;    
 
     pub async fn authenticate_db(conn: &Pool<Postgres>, email: String, password: String) -> Result<AuthenticateResult, sqlx::Error> {        
-        let result = query_as::<_, EntityId>("select id from administrator where email = $1 and password = $2")
+        let result = query_as::<_, AdministratorCredential>("select id, password, failed_login_count, locked_until, is_active from administrator where email = $1")
             .bind(email)
-            .bind(password)
             .fetch_optional(conn)
             .await;
 
-        match result {
-            Ok(opt_entity) => match opt_entity {
-                Some(entity) => Ok(AuthenticateResult::Success { id: entity.id }),
-                None => Ok(AuthenticateResult::Failure)
+        let credential = match result {
+            Ok(Some(credential)) if credential.is_active => credential,
+            Ok(_) => {
+                // as slow as a wrong password, so the time taken does not reveal which emails exist
+                verify_dummy_password_async(password).await;
+                return Ok(AuthenticateResult::Failure);
             },
-            Err(e) => Err(e.into())
+            Err(e) => return Err(e)
+        };
+
+        if let Some(locked_until) = credential.locked_until {
+            if locked_until > Utc::now() {
+                return Ok(AuthenticateResult::Locked { until: locked_until });
+            }
+        }
+
+        let is_valid = if is_password_hashed(&credential.password) {
+            verify_password_async(password, credential.password.clone()).await
+        } else if verify_legacy_password(&password, &credential.password) {
+            // legacy plaintext row, rehash now that we know the password is correct
+            if let Err(e) = update_administrator_password(conn, credential.id, password, false).await {
+                error!("failed to rehash legacy password for administrator {}: {}", credential.id, e);
+            }
+            true
+        } else {
+            false
+        };
+
+        if is_valid {
+            if credential.failed_login_count > 0 || credential.locked_until.is_some() {
+                reset_failed_logins(conn, credential.id).await?;
+            }
+            return Ok(AuthenticateResult::Success { id: credential.id });
+        }
+
+        match record_failed_login(conn, credential.id).await? {
+            Some(until) if until > Utc::now() => Ok(AuthenticateResult::Locked { until }),
+            _ => Ok(AuthenticateResult::Failure)
+        }
+    }
+
+    /// Counts a failed login and returns when the account is locked until.
+    /// Once MAX_FAILED_LOGIN_ATTEMPTS is reached the lock starts at LOCKOUT_BASE_SECONDS and doubles with every further failure, up to LOCKOUT_MAX_SECONDS.
+    /// The count is incremented by the database so concurrent failures are all counted
+    async fn record_failed_login(conn: &Pool<Postgres>, id: i64) -> Result<Option<DateTime<Utc>>, Error> {
+        query_scalar::<_, Option<DateTime<Utc>>>(
+            "update administrator set 
+                failed_login_count = failed_login_count + 1, 
+                locked_until = case when failed_login_count + 1 >= $2 
+                    then current_timestamp + make_interval(secs => least($3 * power(2, least(failed_login_count + 1 - $2, 6)), $4)) 
+                    else locked_until 
+                end 
+            where id = $1 
+            returning locked_until"
+        )
+            .bind(id)
+            .bind(MAX_FAILED_LOGIN_ATTEMPTS)
+            .bind(LOCKOUT_BASE_SECONDS as f64)
+            .bind(LOCKOUT_MAX_SECONDS as f64)
+            .fetch_one(conn)
+            .await
+    }
+
+    async fn reset_failed_logins(conn: &Pool<Postgres>, id: i64) -> Result<(), Error> {
+        let result = query::<_>("update administrator set failed_login_count = 0, locked_until = null where id = $1")
+            .bind(id)
+            .execute(conn)
+            .await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e)
         }
     }
 
@@ -26,22 +96,199 @@ mod internal {
         conn: &Pool<Postgres>,
         user_name: String,
         email: String,
-        password: String
+        password: String,
+        role: AdminRole
     ) -> Result<EntityId, Error> {
-        query_as::<_, EntityId>("insert into administrator (user_name, email, password) values ($1, $2, $3) returning id")
+        let password_hash = get_password_hash(password).await?;
+
+        query_as::<_, EntityId>("insert into administrator (user_name, email, password, role) values ($1, $2, $3, $4) returning id")
             .bind(user_name)
             .bind(email)
-            .bind(password)
+            .bind(password_hash)
+            .bind(role)
             .fetch_one(conn)
             .await
     }
 
+    pub async fn update_administrator(conn: &Pool<Postgres>, id: i64, user_name: String, email: String, role: AdminRole) -> Result<(), Error> {
+        let result = query::<_>("update administrator set user_name = $2, email = $3, role = $4, updated_at = current_timestamp where id = $1")
+            .bind(id)
+            .bind(user_name)
+            .bind(email)
+            .bind(role)
+            .execute(conn)
+            .await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e)
+        }
+    }
+
+    /// Soft delete, also revokes the administrator's refresh tokens so existing sessions cannot be renewed
+    pub async fn deactivate_administrator(conn: &Pool<Postgres>, id: i64) -> Result<(), Error> {
+        let mut tx = conn.begin().await?;
+
+        query::<_>("update administrator set is_active = false, updated_at = current_timestamp where id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        query::<_>("update refresh_token set revoked_at = current_timestamp, updated_at = current_timestamp where admin_id = $1 and revoked_at is null")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await
+    }
+
+    /// With @revoke_refresh_tokens the administrator's sessions end, so one started by whoever knew the old password does not outlive it
+    pub async fn update_administrator_password(conn: &Pool<Postgres>, id: i64, password: String, revoke_refresh_tokens: bool) -> Result<(), Error> {
+        let password_hash = get_password_hash(password).await?;
+        let mut tx = conn.begin().await?;
+
+        query::<_>("update administrator set password = $2, updated_at = current_timestamp where id = $1")
+            .bind(id)
+            .bind(password_hash)
+            .execute(&mut *tx)
+            .await?;
+        if revoke_refresh_tokens {
+            query::<_>("update refresh_token set revoked_at = current_timestamp, updated_at = current_timestamp where admin_id = $1 and revoked_at is null")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await
+    }
+
+    async fn get_password_hash(password: String) -> Result<String, Error> {
+        hash_password_async(password).await.map_err(|e| {
+            error!("failed to hash password: {}", e);
+            Error::Database(Box::new(SqlxError::PasswordHashError))
+        })
+    }
+
     pub async fn query_administrator(conn: &Pool<Postgres>, id: i64) -> Result<Option<Administrator>, Error> {
         query_as::<_, Administrator>("select * from administrator where id = $1")
             .bind(id)
             .fetch_optional(conn)
             .await
     }
+
+    pub async fn query_administrator_by_email(conn: &Pool<Postgres>, email: String) -> Result<Option<Administrator>, Error> {
+        query_as::<_, Administrator>("select * from administrator where email = $1")
+            .bind(email)
+            .fetch_optional(conn)
+            .await
+    }
+
+    /// Oldest first, after @cursor's created_at and id when given
+    pub async fn query_administrators(conn: &Pool<Postgres>, limit: i32, cursor: Option<PageCursor>) -> Result<Vec<Administrator>, Error> {
+        query_as::<_, Administrator>("select * from administrator where $2::timestamptz is null or (created_at, id) > ($2, $3) order by created_at, id limit $1")
+            .bind(limit)
+            .bind(cursor.map(|cursor| cursor.sort_at))
+            .bind(cursor.map(|cursor| cursor.id))
+            .fetch_all(conn)
+            .await
+    }
+
+    pub async fn query_administrator_totp(conn: &Pool<Postgres>, id: i64) -> Result<Option<AdministratorTotp>, Error> {
+        query_as::<_, AdministratorTotp>("select id, totp_secret, totp_enabled, totp_last_used_step from administrator where id = $1")
+            .bind(id)
+            .fetch_optional(conn)
+            .await
+    }
+
+    /// Stores a new pending secret and replaces any recovery codes. Totp stays disabled until enable_totp confirms a code
+    pub async fn insert_totp_enrollment(conn: &Pool<Postgres>, id: i64, totp_secret: String, recovery_codes: Vec<String>) -> Result<(), Error> {
+        let code_hashes = recovery_codes
+            .iter()
+            .map(|code| hash_recovery_code(&totp_secret, code))
+            .collect::<Vec<String>>();
+        let mut tx = conn.begin().await?;
+
+        query::<_>("update administrator set totp_secret = $2, totp_enabled = false, totp_last_used_step = null, updated_at = current_timestamp where id = $1")
+            .bind(id)
+            .bind(totp_secret)
+            .execute(&mut *tx)
+            .await?;
+        query::<_>("delete from administrator_recovery_code where admin_id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        for code_hash in code_hashes {
+            query::<_>("insert into administrator_recovery_code (admin_id, code_hash) values ($1, $2)")
+                .bind(id)
+                .bind(code_hash)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await
+    }
+
+    /// Enables totp if @code is valid for the pending secret
+    pub async fn enable_totp(conn: &Pool<Postgres>, id: i64, code: String) -> Result<bool, Error> {
+        let totp = match query_administrator_totp(conn, id).await? {
+            Some(totp) => totp,
+            None => return Ok(false)
+        };
+        let step = match totp.totp_secret.as_ref().and_then(|secret| verify_totp_code(secret, &code, Utc::now().timestamp())) {
+            Some(step) => step,
+            None => return Ok(false)
+        };
+
+        query::<_>("update administrator set totp_enabled = true, totp_last_used_step = $2, updated_at = current_timestamp where id = $1")
+            .bind(id)
+            .bind(step)
+            .execute(conn)
+            .await?;
+        Ok(true)
+    }
+
+    /// Accepts either a current totp code or an unused recovery code, both are single use.
+    /// Deactivated and locked out administrators are refused
+    pub async fn authenticate_totp(conn: &Pool<Postgres>, id: i64, code: String) -> Result<bool, Error> {
+        // the password was checked before the challenge was issued, the account may have been deactivated or locked since
+        let can_login = query_scalar::<_, bool>("select is_active and (locked_until is null or locked_until <= current_timestamp) from administrator where id = $1")
+            .bind(id)
+            .fetch_optional(conn)
+            .await?
+            .unwrap_or_default();
+        if !can_login {
+            return Ok(false);
+        }
+
+        let totp = match query_administrator_totp(conn, id).await? {
+            Some(totp) if totp.totp_enabled => totp,
+            _ => return Ok(false)
+        };
+
+        let step = totp.totp_secret.as_ref().and_then(|secret| verify_totp_code(secret, &code, Utc::now().timestamp()));
+        if let Some(step) = step {
+            if totp.totp_last_used_step.map(|last_used_step| step <= last_used_step).unwrap_or_default() {
+                return Ok(false);
+            }
+            // the where clause stops two concurrent logins both accepting the same code
+            let result = query::<_>("update administrator set totp_last_used_step = $2 where id = $1 and (totp_last_used_step is null or totp_last_used_step < $2)")
+                .bind(id)
+                .bind(step)
+                .execute(conn)
+                .await?;
+            return Ok(result.rows_affected() == 1);
+        }
+
+        let code_hash = match totp.totp_secret.as_ref() {
+            Some(secret) => hash_recovery_code(secret, &code),
+            None => return Ok(false)
+        };
+        let result = query::<_>("update administrator_recovery_code set used_at = current_timestamp where admin_id = $1 and code_hash = $2 and used_at is null")
+            .bind(id)
+            .bind(code_hash)
+            .execute(conn)
+            .await?;
+        Ok(result.rows_affected() == 1)
+    }
 }
 
 #[async_trait]
@@ -58,13 +305,61 @@ impl AuthenticateDbFn for DbRepo {
 
 #[async_trait]
 pub trait InsertAdminisratorFn {
-    async fn insert_administrator(&self, user_name: String, email: String, password: String) -> Result<EntityId, Error>;
+    async fn insert_administrator(&self, user_name: String, email: String, password: String, role: AdminRole) -> Result<EntityId, Error>;
 }
 
 #[async_trait]
 impl InsertAdminisratorFn for DbRepo {
-    async fn insert_administrator(&self, user_name: String, email: String, password: String) -> Result<EntityId, Error> {
-        internal::insert_administrator(self.get_conn(), user_name, email, password).await
+    async fn insert_administrator(&self, user_name: String, email: String, password: String, role: AdminRole) -> Result<EntityId, Error> {
+        internal::insert_administrator(self.get_conn(), user_name, email, password, role).await
+    }
+}
+
+#[async_trait]
+pub trait UpdateAdministratorFn {
+    async fn update_administrator(&self, id: i64, user_name: String, email: String, role: AdminRole) -> Result<(), Error>;
+}
+
+#[async_trait]
+impl UpdateAdministratorFn for DbRepo {
+    async fn update_administrator(&self, id: i64, user_name: String, email: String, role: AdminRole) -> Result<(), Error> {
+        internal::update_administrator(self.get_conn(), id, user_name, email, role).await
+    }
+}
+
+#[async_trait]
+pub trait UpdateAdministratorPasswordFn {
+    async fn update_administrator_password(&self, id: i64, password: String, revoke_refresh_tokens: bool) -> Result<(), Error>;
+}
+
+#[async_trait]
+impl UpdateAdministratorPasswordFn for DbRepo {
+    async fn update_administrator_password(&self, id: i64, password: String, revoke_refresh_tokens: bool) -> Result<(), Error> {
+        internal::update_administrator_password(self.get_conn(), id, password, revoke_refresh_tokens).await
+    }
+}
+
+#[async_trait]
+pub trait DeactivateAdministratorFn {
+    async fn deactivate_administrator(&self, id: i64) -> Result<(), Error>;
+}
+
+#[async_trait]
+impl DeactivateAdministratorFn for DbRepo {
+    async fn deactivate_administrator(&self, id: i64) -> Result<(), Error> {
+        internal::deactivate_administrator(self.get_conn(), id).await
+    }
+}
+
+#[async_trait]
+pub trait QueryAdministratorsFn {
+    async fn query_administrators(&self, limit: i32, cursor: Option<PageCursor>) -> Result<Vec<Administrator>, Error>;
+}
+
+#[async_trait]
+impl QueryAdministratorsFn for DbRepo {
+    async fn query_administrators(&self, limit: i32, cursor: Option<PageCursor>) -> Result<Vec<Administrator>, Error> {
+        internal::query_administrators(self.get_conn(), limit, cursor).await
     }
 }
 
@@ -78,4 +373,63 @@ impl QueryAdministratorFn for DbRepo {
     async fn query_administrator(&self, id: i64) -> Result<Option<Administrator>, Error> {
         internal::query_administrator(self.get_conn(), id).await
     }
-}
\ No newline at end of file
+}
+#[async_trait]
+pub trait QueryAdministratorByEmailFn {
+    async fn query_administrator_by_email(&self, email: String) -> Result<Option<Administrator>, Error>;
+}
+
+#[async_trait]
+impl QueryAdministratorByEmailFn for DbRepo {
+    async fn query_administrator_by_email(&self, email: String) -> Result<Option<Administrator>, Error> {
+        internal::query_administrator_by_email(self.get_conn(), email).await
+    }
+}
+
+#[async_trait]
+pub trait QueryAdministratorTotpFn {
+    async fn query_administrator_totp(&self, id: i64) -> Result<Option<AdministratorTotp>, Error>;
+}
+
+#[async_trait]
+impl QueryAdministratorTotpFn for DbRepo {
+    async fn query_administrator_totp(&self, id: i64) -> Result<Option<AdministratorTotp>, Error> {
+        internal::query_administrator_totp(self.get_conn(), id).await
+    }
+}
+
+#[async_trait]
+pub trait InsertTotpEnrollmentFn {
+    async fn insert_totp_enrollment(&self, id: i64, totp_secret: String, recovery_codes: Vec<String>) -> Result<(), Error>;
+}
+
+#[async_trait]
+impl InsertTotpEnrollmentFn for DbRepo {
+    async fn insert_totp_enrollment(&self, id: i64, totp_secret: String, recovery_codes: Vec<String>) -> Result<(), Error> {
+        internal::insert_totp_enrollment(self.get_conn(), id, totp_secret, recovery_codes).await
+    }
+}
+
+#[async_trait]
+pub trait EnableTotpFn {
+    async fn enable_totp(&self, id: i64, code: String) -> Result<bool, Error>;
+}
+
+#[async_trait]
+impl EnableTotpFn for DbRepo {
+    async fn enable_totp(&self, id: i64, code: String) -> Result<bool, Error> {
+        internal::enable_totp(self.get_conn(), id, code).await
+    }
+}
+
+#[async_trait]
+pub trait AuthenticateTotpFn {
+    async fn authenticate_totp(&self, id: i64, code: String) -> Result<bool, Error>;
+}
+
+#[async_trait]
+impl AuthenticateTotpFn for DbRepo {
+    async fn authenticate_totp(&self, id: i64, code: String) -> Result<bool, Error> {
+        internal::authenticate_totp(self.get_conn(), id, code).await
+    }
+}
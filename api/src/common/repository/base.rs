@@ -1,4 +1,6 @@
+use chrono::{DateTime, Utc};
 use log::{error, info};
+use serde::{Deserialize, Serialize};
 use sqlx::{Pool, Postgres, migrate, FromRow};
 use std::env;
 use dotenv::dotenv;
@@ -10,6 +12,14 @@ pub struct EntityId {
     pub id: i64
 }
 
+/// The sort values of the last row of a page, the next page starts after it. Lists are sorted by (sort_at, id),
+/// so rows added or edited while paging do not move rows between pages
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct PageCursor {
+    pub sort_at: DateTime<Utc>,
+    pub id: i64
+}
+
 #[async_trait]
 pub trait Repository{
     async fn init() -> Self;
@@ -4,7 +4,9 @@ use std::marker::Send;
 #[derive(Error, Display, Debug)]
 pub enum SqlxError {
     #[display(fmt = "When is_remote is true country_id must be None")]
-    IsRemoteContstraintError
+    IsRemoteContstraintError,
+    #[display(fmt = "Failed to hash password")]
+    PasswordHashError
 }
 
 impl sqlx::error::DatabaseError for SqlxError {
@@ -13,7 +15,10 @@ impl sqlx::error::DatabaseError for SqlxError {
     }
 
     fn kind(&self) -> sqlx::error::ErrorKind {
-        sqlx::error::ErrorKind::ForeignKeyViolation
+        match self {
+            SqlxError::IsRemoteContstraintError => sqlx::error::ErrorKind::ForeignKeyViolation,
+            SqlxError::PasswordHashError => sqlx::error::ErrorKind::Other
+        }
     }
 
     fn as_error(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
@@ -1,6 +1,7 @@
 use chrono::{Utc, DateTime};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use crate::common::repository::base::PageCursor;
 
 #[derive(FromRow, Serialize, Deserialize, Debug, Clone)]
 pub struct Mail {
@@ -9,5 +10,14 @@ pub struct Mail {
     pub updated_at: DateTime<Utc>,
     pub from: String,
     pub subject: String,
-    pub message: String
+    pub message: String,
+    pub is_read: bool,
+    pub read_at: Option<DateTime<Utc>>
+}
+
+impl Mail {
+    /// Where the next page of mail starts
+    pub fn cursor(&self) -> PageCursor {
+        PageCursor { sort_at: self.created_at, id: self.id }
+    }
 }
\ No newline at end of file
@@ -1,15 +1,26 @@
-use sqlx::{Pool, Postgres, query_as, Error};
+use sqlx::{Pool, Postgres, query, query_as, Error};
 use crate::common::repository::mail::models::Mail;
-use crate::common::repository::base::{ConnGetter, DbRepo};
+use crate::common::repository::base::{ConnGetter, DbRepo, EntityId, PageCursor};
 use async_trait::async_trait;
 
 mod internal {  
     use super::*;
 
-    pub async fn query_latest_mail(conn: &Pool<Postgres>, page_size: i32, last_offset: i64) -> Result<Vec<Mail>, Error> {
-        query_as::<_, Mail>("select * from mail order by updated_at desc limit $1 offset $2")
-            .bind(page_size)
-            .bind(last_offset)
+    pub async fn insert_mail(conn: &Pool<Postgres>, from: String, subject: String, message: String) -> Result<EntityId, Error> {
+        query_as::<_, EntityId>(r#"insert into mail ("from", subject, message) values ($1, $2, $3) returning id"#)
+            .bind(from)
+            .bind(subject)
+            .bind(message)
+            .fetch_one(conn)
+            .await
+    }
+
+    /// Newest first, after @cursor's created_at and id when given. Marking mail read does not move it
+    pub async fn query_latest_mail(conn: &Pool<Postgres>, limit: i32, cursor: Option<PageCursor>) -> Result<Vec<Mail>, Error> {
+        query_as::<_, Mail>("select * from mail where $2::timestamptz is null or (created_at, id) < ($2, $3) order by created_at desc, id desc limit $1")
+            .bind(limit)
+            .bind(cursor.map(|cursor| cursor.sort_at))
+            .bind(cursor.map(|cursor| cursor.id))
             .fetch_all(conn)
             .await
     }
@@ -20,17 +31,55 @@ mod internal {
             .fetch_optional(conn)
             .await
     }
+
+    /// read_at is cleared when mail is marked unread
+    pub async fn update_mail_read(conn: &Pool<Postgres>, mail_id: i64, is_read: bool) -> Result<(), Error> {
+        let result = query::<_>("update mail set is_read = $2, read_at = case when $2 then current_timestamp else null end where id = $1")
+            .bind(mail_id)
+            .bind(is_read)
+            .execute(conn)
+            .await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e)
+        }
+    }
+
+    pub async fn delete_mail(conn: &Pool<Postgres>, mail_id: i64) -> Result<(), Error> {
+        let result = query::<_>("delete from mail where id = $1")
+            .bind(mail_id)
+            .execute(conn)
+            .await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e)
+        }
+    }
+}
+
+#[async_trait]
+pub trait InsertMailFn {
+    async fn insert_mail(&self, from: String, subject: String, message: String) -> Result<EntityId, Error>;
+}
+
+#[async_trait]
+impl InsertMailFn for DbRepo {
+    async fn insert_mail(&self, from: String, subject: String, message: String) -> Result<EntityId, Error> {
+        internal::insert_mail(self.get_conn(), from, subject, message).await
+    }
 }
 
 #[async_trait]
 pub trait QueryLatestMailFn {
-    async fn query_latest_mail(&self, page_size: i32, last_offset: i64) -> Result<Vec<Mail>, Error>;
+    async fn query_latest_mail(&self, limit: i32, cursor: Option<PageCursor>) -> Result<Vec<Mail>, Error>;
 }
 
 #[async_trait]
 impl QueryLatestMailFn for DbRepo {
-    async fn query_latest_mail(&self, page_size: i32, last_offset: i64) -> Result<Vec<Mail>, Error> {
-        internal::query_latest_mail(self.get_conn(), page_size, last_offset).await
+    async fn query_latest_mail(&self, limit: i32, cursor: Option<PageCursor>) -> Result<Vec<Mail>, Error> {
+        internal::query_latest_mail(self.get_conn(), limit, cursor).await
     }
 }
 
@@ -44,4 +93,28 @@ impl QueryMailFn for DbRepo {
     async fn query_mail(&self, mail_id: i64) -> Result<Option<Mail>, Error> {
         internal::query_mail(self.get_conn(), mail_id).await
     }
+}
+
+#[async_trait]
+pub trait UpdateMailReadFn {
+    async fn update_mail_read(&self, mail_id: i64, is_read: bool) -> Result<(), Error>;
+}
+
+#[async_trait]
+impl UpdateMailReadFn for DbRepo {
+    async fn update_mail_read(&self, mail_id: i64, is_read: bool) -> Result<(), Error> {
+        internal::update_mail_read(self.get_conn(), mail_id, is_read).await
+    }
+}
+
+#[async_trait]
+pub trait DeleteMailFn {
+    async fn delete_mail(&self, mail_id: i64) -> Result<(), Error>;
+}
+
+#[async_trait]
+impl DeleteMailFn for DbRepo {
+    async fn delete_mail(&self, mail_id: i64) -> Result<(), Error> {
+        internal::delete_mail(self.get_conn(), mail_id).await
+    }
 }
\ No newline at end of file
@@ -0,0 +1,21 @@
+use chrono::{Utc, DateTime};
+use sqlx::FromRow;
+
+#[derive(FromRow, Debug, Clone)]
+pub struct PasswordResetTokenRecord {
+    pub id: i64,
+    pub created_at: DateTime<Utc>,
+    pub admin_id: i64,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ResetPasswordResult {
+    Reset { admin_id: i64 },
+    /// token was already used, or replaced by a newer request
+    Used,
+    Expired,
+    NotFound
+}
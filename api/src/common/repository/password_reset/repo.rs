@@ -0,0 +1,95 @@
+use chrono::{DateTime, Utc};
+use log::error;
+use sqlx::{Pool, Postgres, query, query_as, Error};
+use crate::common::authentication::password_hasher::hash_password_async;
+use crate::common::repository::password_reset::models::{PasswordResetTokenRecord, ResetPasswordResult};
+use crate::common::repository::base::{ConnGetter, DbRepo, EntityId};
+use crate::common::repository::error::SqlxError;
+use async_trait::async_trait;
+
+mod internal {
+    use super::*;
+
+    /// Any earlier unused token for the administrator stops working, only the newest link is valid
+    pub async fn insert_password_reset_token(conn: &Pool<Postgres>, admin_id: i64, token_hash: String, expires_at: DateTime<Utc>) -> Result<EntityId, Error> {
+        let mut tx = conn.begin().await?;
+
+        query::<_>("update password_reset_token set used_at = current_timestamp where admin_id = $1 and used_at is null")
+            .bind(admin_id)
+            .execute(&mut *tx)
+            .await?;
+        let entity = query_as::<_, EntityId>("insert into password_reset_token (admin_id, token_hash, expires_at) values ($1, $2, $3) returning id")
+            .bind(admin_id)
+            .bind(token_hash)
+            .bind(expires_at)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(entity)
+    }
+
+    /// Uses the token and sets the new password in one transaction.
+    /// Also clears any login lockout and revokes refresh tokens, so sessions on other devices end
+    pub async fn reset_password(conn: &Pool<Postgres>, token_hash: String, password: String) -> Result<ResetPasswordResult, Error> {
+        let password_hash = hash_password_async(password).await.map_err(|e| {
+            error!("failed to hash password: {}", e);
+            Error::Database(Box::new(SqlxError::PasswordHashError))
+        })?;
+        let mut tx = conn.begin().await?;
+
+        let current = query_as::<_, PasswordResetTokenRecord>("select * from password_reset_token where token_hash = $1 for update")
+            .bind(token_hash)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+        let result = match current {
+            None => ResetPasswordResult::NotFound,
+            Some(current) if current.used_at.is_some() => ResetPasswordResult::Used,
+            Some(current) if current.expires_at < Utc::now() => ResetPasswordResult::Expired,
+            Some(current) => {
+                query::<_>("update password_reset_token set used_at = current_timestamp where id = $1")
+                    .bind(current.id)
+                    .execute(&mut *tx)
+                    .await?;
+                query::<_>("update administrator set password = $2, failed_login_count = 0, locked_until = null, updated_at = current_timestamp where id = $1")
+                    .bind(current.admin_id)
+                    .bind(password_hash)
+                    .execute(&mut *tx)
+                    .await?;
+                query::<_>("update refresh_token set revoked_at = current_timestamp, updated_at = current_timestamp where admin_id = $1 and revoked_at is null")
+                    .bind(current.admin_id)
+                    .execute(&mut *tx)
+                    .await?;
+                ResetPasswordResult::Reset { admin_id: current.admin_id }
+            }
+        };
+
+        tx.commit().await?;
+        Ok(result)
+    }
+}
+
+#[async_trait]
+pub trait InsertPasswordResetTokenFn {
+    async fn insert_password_reset_token(&self, admin_id: i64, token_hash: String, expires_at: DateTime<Utc>) -> Result<EntityId, Error>;
+}
+
+#[async_trait]
+impl InsertPasswordResetTokenFn for DbRepo {
+    async fn insert_password_reset_token(&self, admin_id: i64, token_hash: String, expires_at: DateTime<Utc>) -> Result<EntityId, Error> {
+        internal::insert_password_reset_token(self.get_conn(), admin_id, token_hash, expires_at).await
+    }
+}
+
+#[async_trait]
+pub trait ResetPasswordFn {
+    async fn reset_password(&self, token_hash: String, password: String) -> Result<ResetPasswordResult, Error>;
+}
+
+#[async_trait]
+impl ResetPasswordFn for DbRepo {
+    async fn reset_password(&self, token_hash: String, password: String) -> Result<ResetPasswordResult, Error> {
+        internal::reset_password(self.get_conn(), token_hash, password).await
+    }
+}
@@ -1,6 +1,46 @@
 use chrono::{Utc, DateTime};
 use serde::{Deserialize, Serialize};
-use sqlx::FromRow;
+use sqlx::{types::Json, FromRow};
+use crate::common::repository::{base::PageCursor, tag::models::Tag};
+
+/// draft: only seen by administrators
+/// scheduled: becomes published at published_at
+/// published: public from published_at
+/// archived: taken down, published_at is kept in case it is published again
+#[derive(sqlx::Type, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[sqlx(type_name = "post_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum PostStatus {
+    Draft,
+    Scheduled,
+    Published,
+    Archived
+}
+
+/// The text of a post that its author writes, every save of it is kept as a revision
+#[derive(Debug, Clone, PartialEq)]
+pub struct PostContent {
+    pub title: String,
+    pub message: String,
+    /// Shown in previews instead of the start of message, when given
+    pub excerpt: Option<String>
+}
+
+/// A heading of a post, for its table of contents. @id is the fragment its anchor in rendered_html links to
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PostHeading {
+    pub id: String,
+    /// 1 to 6, as in h1 to h6
+    pub level: u8,
+    pub text: String
+}
+
+/// Whether, and from when, a post is public
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PostPublication {
+    pub status: PostStatus,
+    pub published_at: Option<DateTime<Utc>>
+}
 
 #[derive(FromRow, Serialize, Deserialize, Debug, Clone)]
 pub struct Post {
@@ -8,6 +48,74 @@ pub struct Post {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub title: String,
+    /// Unique, made from the title unless the author sets it. Never all digits, so it cannot be mistaken for an id
+    pub slug: String,
     pub message: String,
+    /// Sanitized html of message, rendered when the post is saved. None for posts saved before it was added
+    pub rendered_html: Option<String>,
+    /// Headings of message, in order, rendered with rendered_html. None for posts rendered before headings had ids
+    #[sqlx(default)]
+    pub headings: Option<Json<Vec<PostHeading>>>,
+    /// Written by the author to be shown in previews instead of the start of message
+    pub excerpt: Option<String>,
+    /// Sanitized html of the preview summary, from excerpt or the start of message. None for posts saved before it was added
+    pub summary_html: Option<String>,
+    /// The preview summary without markup, for meta descriptions
+    pub summary_text: Option<String>,
+    pub status: PostStatus,
+    /// None for drafts, in the future for scheduled posts
+    pub published_at: Option<DateTime<Utc>>,
+    /// Ordered by name. Empty when the query did not select them
+    #[sqlx(json, default)]
+    pub tags: Vec<Tag>,
     pub admin_id: i64
+}
+
+/// A public post matching a search, as a preview
+#[derive(FromRow, Debug, Clone)]
+pub struct PostSearchResult {
+    #[sqlx(flatten)]
+    pub post: Post,
+    /// Higher is a better match, matches in the title count most
+    pub rank: f32,
+    /// Sanitized html of the parts of message that match, with the matched words in mark tags
+    pub snippet_html: String
+}
+
+impl PostSearchResult {
+    pub fn cursor(&self) -> SearchCursor {
+        SearchCursor { rank: self.rank, id: self.post.id }
+    }
+}
+
+/// The rank and id of the last search result of a page, the next page starts after it
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct SearchCursor {
+    pub rank: f32,
+    pub id: i64
+}
+
+impl Post {
+    /// Scheduled posts are public once their time has passed, even before the publisher task marks them published
+    pub fn is_public(&self) -> bool {
+        match self.status {
+            PostStatus::Published => true,
+            PostStatus::Scheduled => self.published_at.map(|published_at| published_at <= Utc::now()).unwrap_or_default(),
+            PostStatus::Draft | PostStatus::Archived => false
+        }
+    }
+
+    pub fn publication(&self) -> PostPublication {
+        PostPublication { status: self.status, published_at: self.published_at }
+    }
+
+    /// Where the next page of public posts starts, public posts always have a published_at
+    pub fn published_cursor(&self) -> PageCursor {
+        PageCursor { sort_at: self.published_at.unwrap_or(self.created_at), id: self.id }
+    }
+
+    /// Where the next page of posts of any status starts
+    pub fn created_cursor(&self) -> PageCursor {
+        PageCursor { sort_at: self.created_at, id: self.id }
+    }
 }
\ No newline at end of file
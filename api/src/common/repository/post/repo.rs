@@ -1,6 +1,20 @@
 use async_trait::async_trait;
-use sqlx::{Postgres, Pool, query, query_as, Error};
-use crate::common::repository::{post::models::Post, base::{DbRepo, ConnGetter, EntityId}};
+use log::error;
+use sqlx::{types::Json, Postgres, Pool, Transaction, query, query_as, query_scalar, Error};
+use crate::common::{
+    repository::{post::models::{Post, PostContent, PostPublication, PostSearchResult, PostStatus, SearchCursor}, post_revision::repo::insert_post_revision, tag::repo::set_post_tags, base::{DbRepo, ConnGetter, EntityId, PageCursor}},
+    utils::{markdown_utils::{clean_search_snippet_html, get_post_summary, get_post_text, render_post}, slug_utils::{get_slug, get_unique_slug}}
+};
+
+/// Matches Post::is_public, scheduled posts are public once their time has passed even before they are marked published
+pub(crate) const IS_PUBLIC_CONDITION: &str = "(status = 'published' or (status = 'scheduled' and published_at <= current_timestamp))";
+/// Posts are selected with their tags, by name, as the json Post::tags is read from
+const POST_COLUMNS: &str = "post.*, (
+    select coalesce(json_agg(json_build_object('id', tag.id, 'name', tag.name, 'slug', tag.slug) order by tag.name), '[]') 
+    from post_tag join tag on tag.id = post_tag.tag_id 
+    where post_tag.post_id = post.id
+) as tags";
+const RENDER_BATCH_SIZE: i64 = 100;
 
 mod internal {
     use super::*;
@@ -17,42 +31,249 @@ mod internal {
         }
     }
 
-    pub async fn update_post(conn: &Pool<Postgres>, post_id: i64, title: String, message: String) -> Result<(), Error> {
-        let result = query::<_>("update post set title = $2, message = $3 where id = $1")
+    /// A None @slug keeps the post's current one, so renaming a post does not change its url.
+    /// When the slug does change the old one is kept as a redirect. None @tag_names keeps the current tags.
+    /// @admin_id is who saved it, for the revision
+    pub async fn update_post(conn: &Pool<Postgres>, post_id: i64, content: PostContent, slug: Option<String>, tag_names: Option<Vec<String>>, publication: PostPublication, admin_id: i64) -> Result<(), Error> {
+        let rendered = render_post(&content.message);
+        let summary = get_post_summary(&content.message, content.excerpt.as_deref());
+        let message_text = get_post_text(&content.message);
+        let mut tx = conn.begin().await?;
+
+        let current_slug = query_scalar::<_, String>("select slug from post where id = $1 for update")
             .bind(post_id)
-            .bind(title)
-            .bind(message)
-            .execute(conn)
+            .fetch_one(&mut *tx)
+            .await?;
+        let slug = slug.unwrap_or(current_slug.clone());
+        if slug != current_slug {
+            delete_slug_redirect(&mut tx, &slug).await?;
+            query::<_>("insert into post_slug_redirect (slug, post_id) values ($1, $2) on conflict (slug) do update set post_id = excluded.post_id")
+                .bind(current_slug)
+                .bind(post_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        let result = query::<_>("update post set title = $2, message = $3, rendered_html = $4, headings = $5, excerpt = $6, summary_html = $7, summary_text = $8, slug = $9, status = $10, published_at = $11, message_text = $12 where id = $1")
+            .bind(post_id)
+            .bind(&content.title)
+            .bind(&content.message)
+            .bind(rendered.html)
+            .bind(Json(rendered.headings))
+            .bind(&content.excerpt)
+            .bind(summary.html)
+            .bind(summary.text)
+            .bind(slug)
+            .bind(publication.status)
+            .bind(publication.published_at)
+            .bind(message_text)
+            .execute(&mut *tx)
             .await;
 
         match result {
-            Ok(_) => Ok(()),
+            Ok(_) => {
+                if let Some(tag_names) = tag_names {
+                    set_post_tags(&mut tx, post_id, &tag_names).await?;
+                }
+                insert_post_revision(&mut tx, post_id, &content, admin_id).await?;
+                tx.commit().await
+            },
             Err(e) => {
-                println!("update_post failed: {:?}", e);
+                error!("update_post failed: {}", e);
                 Err(e)
             }
         }
     }
 
-    pub async fn insert_post(conn: &Pool<Postgres>, title: String, message: String, admin_id: i64) -> Result<EntityId, Error> {
-        query_as::<_, EntityId>("insert into post (title, message, admin_id) values ($1, $2, $3) returning id")
-            .bind(title)
-            .bind(message)
-            .bind(admin_id)
-            .fetch_one(conn)
+    /// A None @slug is made from @title, with a suffix when another post already has it.
+    /// A post with the same title saved meanwhile can take that slug first, the next suffix is tried then.
+    /// An author's own @slug is never changed, another post having it is a unique violation
+    pub async fn insert_post(conn: &Pool<Postgres>, content: PostContent, slug: Option<String>, tag_names: Vec<String>, publication: PostPublication, admin_id: i64) -> Result<EntityId, Error> {
+        let rendered = render_post(&content.message);
+        let summary = get_post_summary(&content.message, content.excerpt.as_deref());
+        let message_text = get_post_text(&content.message);
+        let mut tx = conn.begin().await?;
+
+        let title_slug = get_slug(&content.title);
+        let mut taken_slugs = match slug {
+            Some(_) => vec![],
+            None => query_scalar::<_, String>("select slug from post where slug = $1 or slug like ($1 || '-%')")
+                .bind(title_slug.clone())
+                .fetch_all(&mut *tx)
+                .await?
+        };
+        let conflict_clause = if slug.is_some() { "" } else { "on conflict (slug) do nothing" };
+
+        let entity = loop {
+            let post_slug = slug.clone().unwrap_or_else(|| get_unique_slug(&title_slug, &taken_slugs));
+            delete_slug_redirect(&mut tx, &post_slug).await?;
+
+            let entity = query_as::<_, EntityId>(&format!(
+                "insert into post (title, slug, message, rendered_html, headings, excerpt, summary_html, summary_text, status, published_at, admin_id, message_text) values ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12) {} returning id",
+                conflict_clause
+            ))
+                .bind(&content.title)
+                .bind(&post_slug)
+                .bind(&content.message)
+                .bind(&rendered.html)
+                .bind(Json(&rendered.headings))
+                .bind(&content.excerpt)
+                .bind(&summary.html)
+                .bind(&summary.text)
+                .bind(publication.status)
+                .bind(publication.published_at)
+                .bind(admin_id)
+                .bind(&message_text)
+                .fetch_optional(&mut *tx)
+                .await?;
+            match entity {
+                Some(entity) => break entity,
+                None => taken_slugs.push(post_slug)
+            }
+        };
+        set_post_tags(&mut tx, entity.id, &tag_names).await?;
+        insert_post_revision(&mut tx, entity.id, &content, admin_id).await?;
+
+        tx.commit().await?;
+        Ok(entity)
+    }
+
+    /// A post taking a slug another post used to have takes over its old links too
+    async fn delete_slug_redirect(tx: &mut Transaction<'_, Postgres>, slug: &str) -> Result<(), Error> {
+        query::<_>("delete from post_slug_redirect where slug = $1")
+            .bind(slug)
+            .execute(&mut **tx)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn query_post_by_slug(conn: &Pool<Postgres>, slug: String) -> Result<Option<Post>, Error> {
+        query_as::<_, Post>(&format!("select {} from post where slug = $1", POST_COLUMNS))
+            .bind(slug)
+            .fetch_optional(conn)
+            .await
+    }
+
+    pub async fn query_post_slug_redirect(conn: &Pool<Postgres>, slug: String) -> Result<Option<String>, Error> {
+        query_scalar::<_, String>("select post.slug from post_slug_redirect join post on post.id = post_slug_redirect.post_id where post_slug_redirect.slug = $1")
+            .bind(slug)
+            .fetch_optional(conn)
+            .await
+    }
+
+    /// Public posts only, newest published first, after @cursor's published_at and id when given
+    pub async fn query_posts(conn: &Pool<Postgres>, limit: i32, cursor: Option<PageCursor>) -> Result<Vec<Post>, Error> {
+        query_as::<_, Post>(&format!(
+            "select {} from post where {} and ($2::timestamptz is null or (published_at, id) < ($2, $3)) 
+            order by published_at desc, id desc limit $1", 
+            POST_COLUMNS, 
+            IS_PUBLIC_CONDITION
+        ))
+            .bind(limit)
+            .bind(cursor.map(|cursor| cursor.sort_at))
+            .bind(cursor.map(|cursor| cursor.id))
+            .fetch_all(conn)
+            .await
+    }
+
+    /// Public posts with the tag, newest published first, after @cursor's published_at and id when given
+    pub async fn query_posts_by_tag(conn: &Pool<Postgres>, tag_slug: String, limit: i32, cursor: Option<PageCursor>) -> Result<Vec<Post>, Error> {
+        query_as::<_, Post>(&format!(
+            "select {} from post where {} and exists (select 1 from post_tag join tag on tag.id = post_tag.tag_id where post_tag.post_id = post.id and tag.slug = $1) 
+            and ($3::timestamptz is null or (published_at, id) < ($3, $4)) 
+            order by published_at desc, id desc limit $2", 
+            POST_COLUMNS, 
+            IS_PUBLIC_CONDITION
+        ))
+            .bind(tag_slug)
+            .bind(limit)
+            .bind(cursor.map(|cursor| cursor.sort_at))
+            .bind(cursor.map(|cursor| cursor.id))
+            .fetch_all(conn)
+            .await
+    }
+
+    /// Public posts matching @search_text, best match first, after @cursor's rank and id when given. The text is read
+    /// as a web search, so quoted phrases, or and a leading - to exclude a word all work.
+    /// Snippets are only made for the posts on the page, from the message's text so they show no markdown
+    pub async fn search_posts(conn: &Pool<Postgres>, search_text: String, limit: i32, cursor: Option<SearchCursor>) -> Result<Vec<PostSearchResult>, Error> {
+        query_as::<_, PostSearchResult>(&format!(
+            "select page.*, ts_headline('english', coalesce(page.message_text, page.message), websearch_to_tsquery('english', $1), 'StartSel=<mark>, StopSel=</mark>, MaxFragments=2, MaxWords=30, MinWords=10') as snippet_html 
+            from (
+                select * from (
+                    select {}, ts_rank(post.search_vector, search_query) as rank 
+                    from post, websearch_to_tsquery('english', $1) as search_query 
+                    where {} and post.search_vector @@ search_query
+                ) as result 
+                where $3::real is null or (result.rank, result.id) < ($3, $4) 
+                order by result.rank desc, result.id desc limit $2
+            ) as page 
+            order by page.rank desc, page.id desc", 
+            POST_COLUMNS, 
+            IS_PUBLIC_CONDITION
+        ))
+            .bind(search_text)
+            .bind(limit)
+            .bind(cursor.map(|cursor| cursor.rank))
+            .bind(cursor.map(|cursor| cursor.id))
+            .fetch_all(conn)
             .await
     }
 
-    pub async fn query_posts(conn: &Pool<Postgres>, page_size: i32, last_offset: i64) -> Result<Vec<Post>, Error> {
-        query_as::<_, Post>("select * from post order by updated_at desc limit $1 offset $2")
-            .bind(page_size)
-            .bind(last_offset)
+    /// Posts of any status, or only @status when given, newest first, after @cursor's created_at and id when given.
+    /// Editing a post does not move it
+    pub async fn query_posts_by_status(conn: &Pool<Postgres>, status: Option<PostStatus>, limit: i32, cursor: Option<PageCursor>) -> Result<Vec<Post>, Error> {
+        query_as::<_, Post>(&format!(
+            "select {} from post where ($1::post_status is null or status = $1) and ($3::timestamptz is null or (created_at, id) < ($3, $4)) 
+            order by created_at desc, id desc limit $2", 
+            POST_COLUMNS
+        ))
+            .bind(status)
+            .bind(limit)
+            .bind(cursor.map(|cursor| cursor.sort_at))
+            .bind(cursor.map(|cursor| cursor.id))
             .fetch_all(conn)
             .await
     }
 
+    /// Returns how many scheduled posts were published
+    pub async fn publish_scheduled_posts(conn: &Pool<Postgres>) -> Result<u64, Error> {
+        query::<_>("update post set status = 'published' where status = 'scheduled' and published_at <= current_timestamp")
+            .execute(conn)
+            .await
+            .map(|result| result.rows_affected())
+    }
+
+    /// Renders posts saved before rendered_html, headings or message_text were stored, RENDER_BATCH_SIZE at a time.
+    /// A post saved again meanwhile is left as it was saved. Returns how many posts were rendered
+    pub async fn render_stale_posts(conn: &Pool<Postgres>) -> Result<u64, Error> {
+        let mut rendered_count = 0;
+        loop {
+            let stale_posts = query_as::<_, (i64, String)>("select id, message from post where rendered_html is null or headings is null or message_text is null order by id limit $1")
+                .bind(RENDER_BATCH_SIZE)
+                .fetch_all(conn)
+                .await?;
+            if stale_posts.is_empty() {
+                return Ok(rendered_count);
+            }
+
+            for (post_id, message) in stale_posts {
+                let rendered = render_post(&message);
+                rendered_count += query::<_>("update post set rendered_html = $2, headings = $3, message_text = $4 where id = $1 and message = $5")
+                    .bind(post_id)
+                    .bind(rendered.html)
+                    .bind(Json(rendered.headings))
+                    .bind(get_post_text(&message))
+                    .bind(message)
+                    .execute(conn)
+                    .await?
+                    .rows_affected();
+            }
+        }
+    }
+
     pub async fn query_post(conn: &Pool<Postgres>, post_id: i64) -> Result<Option<Post>, Error> {
-        query_as::<_, Post>("select * from post where id = $1")
+        query_as::<_, Post>(&format!("select {} from post where id = $1", POST_COLUMNS))
             .bind(post_id)
             .fetch_optional(conn)
             .await
@@ -61,46 +282,104 @@ mod internal {
 
 #[async_trait]
 pub trait InsertPostFn {
-    async fn insert_post(&self, title: String, message: String, admin_id: i64) -> Result<EntityId, Error>;
+    async fn insert_post(&self, content: PostContent, slug: Option<String>, tag_names: Vec<String>, publication: PostPublication, admin_id: i64) -> Result<EntityId, Error>;
 }
 
 #[async_trait]
 impl InsertPostFn for DbRepo {
-    async fn insert_post(&self, title: String, message: String, admin_id: i64) -> Result<EntityId, Error> {
-        internal::insert_post(self.get_conn(), title, message, admin_id).await
+    async fn insert_post(&self, content: PostContent, slug: Option<String>, tag_names: Vec<String>, publication: PostPublication, admin_id: i64) -> Result<EntityId, Error> {
+        internal::insert_post(self.get_conn(), content, slug, tag_names, publication, admin_id).await
     }
 }
 
 #[async_trait]
 pub trait QueryPostsFn {
-    async fn query_posts(&self, page_size: i32, last_offset: i64) -> Result<Vec<Post>, Error>;
+    async fn query_posts(&self, limit: i32, cursor: Option<PageCursor>) -> Result<Vec<Post>, Error>;
 }
 
 #[async_trait]
 impl QueryPostsFn for DbRepo {
-    async fn query_posts(&self, page_size: i32, last_offset: i64) -> Result<Vec<Post>, Error> {
-        internal::query_posts(self.get_conn(), page_size, last_offset).await
+    async fn query_posts(&self, limit: i32, cursor: Option<PageCursor>) -> Result<Vec<Post>, Error> {
+        internal::query_posts(self.get_conn(), limit, cursor).await
+    }
+}
+
+/// For administrators, includes posts that are not public
+#[async_trait]
+pub trait QueryPostsByStatusFn {
+    async fn query_posts_by_status(&self, status: Option<PostStatus>, limit: i32, cursor: Option<PageCursor>) -> Result<Vec<Post>, Error>;
+}
+
+#[async_trait]
+impl QueryPostsByStatusFn for DbRepo {
+    async fn query_posts_by_status(&self, status: Option<PostStatus>, limit: i32, cursor: Option<PageCursor>) -> Result<Vec<Post>, Error> {
+        internal::query_posts_by_status(self.get_conn(), status, limit, cursor).await
+    }
+}
+
+#[async_trait]
+pub trait PublishScheduledPostsFn {
+    async fn publish_scheduled_posts(&self) -> Result<u64, Error>;
+}
+
+#[async_trait]
+impl PublishScheduledPostsFn for DbRepo {
+    async fn publish_scheduled_posts(&self) -> Result<u64, Error> {
+        internal::publish_scheduled_posts(self.get_conn()).await
+    }
+}
+
+#[async_trait]
+pub trait RenderStalePostsFn {
+    async fn render_stale_posts(&self) -> Result<u64, Error>;
+}
+
+#[async_trait]
+impl RenderStalePostsFn for DbRepo {
+    async fn render_stale_posts(&self) -> Result<u64, Error> {
+        internal::render_stale_posts(self.get_conn()).await
     }
 }
 
 #[async_trait]
 pub trait QueryPostsPreviewFn {
-    async fn query_post_previews(&self, page_size: i32, last_offset: i64) -> Result<Vec<Post>, Error>;
+    async fn query_post_previews(&self, limit: i32, cursor: Option<PageCursor>) -> Result<Vec<Post>, Error>;
 }
 
 #[async_trait]
 impl QueryPostsPreviewFn for DbRepo {
-    async fn query_post_previews(&self, page_size: i32, last_offset: i64) -> Result<Vec<Post>, Error> {
-        let post_result = internal::query_posts(self.get_conn(), page_size, last_offset).await;
-        match post_result {
-            Ok(posts) => Ok(posts.iter().map(|post| Post {
-                id: post.id,
-                created_at: post.created_at,
-                updated_at: post.updated_at,
-                title: post.title.to_string(),
-                message: post.message[0..if post.message.len() < 250 { post.message.len() } else { 250 }].to_string(),
-                admin_id: post.admin_id
-            }).collect::<Vec<Post>>()),
+    async fn query_post_previews(&self, limit: i32, cursor: Option<PageCursor>) -> Result<Vec<Post>, Error> {
+        internal::query_posts(self.get_conn(), limit, cursor).await
+    }
+}
+
+#[async_trait]
+pub trait QueryTagPostPreviewsFn {
+    async fn query_tag_post_previews(&self, tag_slug: String, limit: i32, cursor: Option<PageCursor>) -> Result<Vec<Post>, Error>;
+}
+
+#[async_trait]
+impl QueryTagPostPreviewsFn for DbRepo {
+    async fn query_tag_post_previews(&self, tag_slug: String, limit: i32, cursor: Option<PageCursor>) -> Result<Vec<Post>, Error> {
+        internal::query_posts_by_tag(self.get_conn(), tag_slug, limit, cursor).await
+    }
+}
+
+#[async_trait]
+pub trait SearchPostsFn {
+    async fn search_posts(&self, search_text: String, limit: i32, cursor: Option<SearchCursor>) -> Result<Vec<PostSearchResult>, Error>;
+}
+
+#[async_trait]
+impl SearchPostsFn for DbRepo {
+    async fn search_posts(&self, search_text: String, limit: i32, cursor: Option<SearchCursor>) -> Result<Vec<PostSearchResult>, Error> {
+        let search_result = internal::search_posts(self.get_conn(), search_text, limit, cursor).await;
+        match search_result {
+            Ok(results) => Ok(results.into_iter().map(|result| PostSearchResult {
+                // the snippet is cut from the markdown, so it can hold any html the author wrote
+                snippet_html: clean_search_snippet_html(&result.snippet_html),
+                ..result
+            }).collect::<Vec<PostSearchResult>>()),
             Err(e) => Err(e)
         }
     }
@@ -118,6 +397,31 @@ impl QueryPostFn for DbRepo {
     }
 }
 
+#[async_trait]
+pub trait QueryPostBySlugFn {
+    async fn query_post_by_slug(&self, slug: String) -> Result<Option<Post>, Error>;
+}
+
+#[async_trait]
+impl QueryPostBySlugFn for DbRepo {
+    async fn query_post_by_slug(&self, slug: String) -> Result<Option<Post>, Error> {
+        internal::query_post_by_slug(self.get_conn(), slug).await
+    }
+}
+
+/// Returns the current slug of the post that used to have @slug
+#[async_trait]
+pub trait QueryPostSlugRedirectFn {
+    async fn query_post_slug_redirect(&self, slug: String) -> Result<Option<String>, Error>;
+}
+
+#[async_trait]
+impl QueryPostSlugRedirectFn for DbRepo {
+    async fn query_post_slug_redirect(&self, slug: String) -> Result<Option<String>, Error> {
+        internal::query_post_slug_redirect(self.get_conn(), slug).await
+    }
+}
+
 #[async_trait]
 pub trait DeletePostFn {
     async fn delete_post(&self, post_id: i64) -> Result<(), Error>;
@@ -132,12 +436,12 @@ impl DeletePostFn for DbRepo {
 
 #[async_trait]
 pub trait UpdatePostFn {
-    async fn update_post(&self, post_id: i64, title: String, message: String) -> Result<(), Error>;
+    async fn update_post(&self, post_id: i64, content: PostContent, slug: Option<String>, tag_names: Option<Vec<String>>, publication: PostPublication, admin_id: i64) -> Result<(), Error>;
 }
 
 #[async_trait]
 impl UpdatePostFn for DbRepo {
-    async fn update_post(&self, post_id: i64, title: String, message: String) -> Result<(), Error> {
-        internal::update_post(self.get_conn(), post_id, title, message).await
+    async fn update_post(&self, post_id: i64, content: PostContent, slug: Option<String>, tag_names: Option<Vec<String>>, publication: PostPublication, admin_id: i64) -> Result<(), Error> {
+        internal::update_post(self.get_conn(), post_id, content, slug, tag_names, publication, admin_id).await
     }
 }
\ No newline at end of file
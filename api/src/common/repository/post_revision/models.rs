@@ -0,0 +1,26 @@
+use chrono::{Utc, DateTime};
+use serde::{Deserialize, Serialize};
+use sqlx::{types::Json, FromRow};
+use crate::common::repository::post::models::PostContent;
+
+/// A post's text, slug and tags as it was saved, the newest revision is the post's current state
+#[derive(FromRow, Serialize, Deserialize, Debug, Clone)]
+pub struct PostRevision {
+    pub id: i64,
+    pub created_at: DateTime<Utc>,
+    pub post_id: i64,
+    /// The administrator who saved it, not necessarily the post's author
+    pub admin_id: i64,
+    pub title: String,
+    pub message: String,
+    pub excerpt: Option<String>,
+    /// None for revisions saved before slugs and tags were kept
+    pub slug: Option<String>,
+    pub tag_names: Option<Json<Vec<String>>>
+}
+
+impl PostRevision {
+    pub fn content(&self) -> PostContent {
+        PostContent { title: self.title.clone(), message: self.message.clone(), excerpt: self.excerpt.clone() }
+    }
+}
@@ -0,0 +1,66 @@
+use async_trait::async_trait;
+use sqlx::{Postgres, Pool, Transaction, query, query_as, Error};
+use crate::common::repository::{post::models::PostContent, post_revision::models::PostRevision, base::{DbRepo, ConnGetter}};
+
+mod internal {
+    use super::*;
+
+    /// Newest first
+    pub async fn query_post_revisions(conn: &Pool<Postgres>, post_id: i64) -> Result<Vec<PostRevision>, Error> {
+        query_as::<_, PostRevision>("select * from post_revision where post_id = $1 order by id desc")
+            .bind(post_id)
+            .fetch_all(conn)
+            .await
+    }
+
+    pub async fn query_post_revision(conn: &Pool<Postgres>, revision_id: i64) -> Result<Option<PostRevision>, Error> {
+        query_as::<_, PostRevision>("select * from post_revision where id = $1")
+            .bind(revision_id)
+            .fetch_optional(conn)
+            .await
+    }
+}
+
+/// Written by the post repo in the same transaction as the save, so a post never changes without a revision.
+/// The slug and tags are read from the post, so it is written once they are saved
+pub async fn insert_post_revision(tx: &mut Transaction<'_, Postgres>, post_id: i64, content: &PostContent, admin_id: i64) -> Result<(), Error> {
+    query::<_>("insert into post_revision (post_id, admin_id, title, message, excerpt, slug, tag_names) 
+        select $1, $2, $3, $4, $5, post.slug, (
+            select coalesce(jsonb_agg(tag.name order by tag.name), '[]') 
+            from post_tag join tag on tag.id = post_tag.tag_id 
+            where post_tag.post_id = post.id
+        ) 
+        from post where post.id = $1")
+        .bind(post_id)
+        .bind(admin_id)
+        .bind(&content.title)
+        .bind(&content.message)
+        .bind(&content.excerpt)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+#[async_trait]
+pub trait QueryPostRevisionsFn {
+    async fn query_post_revisions(&self, post_id: i64) -> Result<Vec<PostRevision>, Error>;
+}
+
+#[async_trait]
+impl QueryPostRevisionsFn for DbRepo {
+    async fn query_post_revisions(&self, post_id: i64) -> Result<Vec<PostRevision>, Error> {
+        internal::query_post_revisions(self.get_conn(), post_id).await
+    }
+}
+
+#[async_trait]
+pub trait QueryPostRevisionFn {
+    async fn query_post_revision(&self, revision_id: i64) -> Result<Option<PostRevision>, Error>;
+}
+
+#[async_trait]
+impl QueryPostRevisionFn for DbRepo {
+    async fn query_post_revision(&self, revision_id: i64) -> Result<Option<PostRevision>, Error> {
+        internal::query_post_revision(self.get_conn(), revision_id).await
+    }
+}
@@ -0,0 +1,24 @@
+use chrono::{Utc, DateTime};
+use sqlx::FromRow;
+
+#[derive(FromRow, Debug, Clone)]
+pub struct RefreshTokenRecord {
+    pub id: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub jti: String,
+    pub family_id: String,
+    pub admin_id: i64,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub replaced_by: Option<String>
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RotateRefreshTokenResult {
+    Rotated { admin_id: i64 },
+    /// token was already rotated or revoked, its whole family has now been revoked
+    ReuseDetected,
+    Expired,
+    NotFound
+}
@@ -0,0 +1,127 @@
+use chrono::{DateTime, Utc};
+use sqlx::{Pool, Postgres, query, query_as, Error};
+use crate::common::repository::refresh_token::models::{RefreshTokenRecord, RotateRefreshTokenResult};
+use crate::common::repository::base::{ConnGetter, DbRepo, EntityId};
+use async_trait::async_trait;
+
+mod internal {
+    use super::*;
+
+    pub async fn insert_refresh_token(conn: &Pool<Postgres>, admin_id: i64, jti: String, family_id: String, expires_at: DateTime<Utc>) -> Result<EntityId, Error> {
+        query_as::<_, EntityId>("insert into refresh_token (jti, family_id, admin_id, expires_at) values ($1, $2, $3, $4) returning id")
+            .bind(jti)
+            .bind(family_id)
+            .bind(admin_id)
+            .bind(expires_at)
+            .fetch_one(conn)
+            .await
+    }
+
+    /// Replaces the token @jti with @new_jti in the same family.
+    /// Presenting a token that was already rotated or revoked revokes every token in its family
+    pub async fn rotate_refresh_token(conn: &Pool<Postgres>, jti: String, new_jti: String, new_expires_at: DateTime<Utc>) -> Result<RotateRefreshTokenResult, Error> {
+        let mut tx = conn.begin().await?;
+
+        let current = query_as::<_, RefreshTokenRecord>("select * from refresh_token where jti = $1 for update")
+            .bind(jti.clone())
+            .fetch_optional(&mut *tx)
+            .await?;
+
+        let result = match current {
+            None => RotateRefreshTokenResult::NotFound,
+            Some(current) if current.revoked_at.is_some() => {
+                query::<_>("update refresh_token set revoked_at = current_timestamp, updated_at = current_timestamp where family_id = $1 and revoked_at is null")
+                    .bind(current.family_id)
+                    .execute(&mut *tx)
+                    .await?;
+                RotateRefreshTokenResult::ReuseDetected
+            },
+            Some(current) if current.expires_at < Utc::now() => RotateRefreshTokenResult::Expired,
+            Some(current) => {
+                query::<_>("update refresh_token set revoked_at = current_timestamp, updated_at = current_timestamp, replaced_by = $2 where jti = $1")
+                    .bind(jti)
+                    .bind(new_jti.clone())
+                    .execute(&mut *tx)
+                    .await?;
+                query::<_>("insert into refresh_token (jti, family_id, admin_id, expires_at) values ($1, $2, $3, $4)")
+                    .bind(new_jti)
+                    .bind(current.family_id)
+                    .bind(current.admin_id)
+                    .bind(new_expires_at)
+                    .execute(&mut *tx)
+                    .await?;
+                RotateRefreshTokenResult::Rotated { admin_id: current.admin_id }
+            }
+        };
+
+        tx.commit().await?;
+        Ok(result)
+    }
+
+    pub async fn revoke_refresh_token(conn: &Pool<Postgres>, jti: String) -> Result<(), Error> {
+        let result = query::<_>("update refresh_token set revoked_at = current_timestamp, updated_at = current_timestamp where jti = $1 and revoked_at is null")
+            .bind(jti)
+            .execute(conn)
+            .await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e)
+        }
+    }
+
+    pub async fn query_refresh_token(conn: &Pool<Postgres>, jti: String) -> Result<Option<RefreshTokenRecord>, Error> {
+        query_as::<_, RefreshTokenRecord>("select * from refresh_token where jti = $1")
+            .bind(jti)
+            .fetch_optional(conn)
+            .await
+    }
+}
+
+#[async_trait]
+pub trait InsertRefreshTokenFn {
+    async fn insert_refresh_token(&self, admin_id: i64, jti: String, family_id: String, expires_at: DateTime<Utc>) -> Result<EntityId, Error>;
+}
+
+#[async_trait]
+impl InsertRefreshTokenFn for DbRepo {
+    async fn insert_refresh_token(&self, admin_id: i64, jti: String, family_id: String, expires_at: DateTime<Utc>) -> Result<EntityId, Error> {
+        internal::insert_refresh_token(self.get_conn(), admin_id, jti, family_id, expires_at).await
+    }
+}
+
+#[async_trait]
+pub trait RotateRefreshTokenFn {
+    async fn rotate_refresh_token(&self, jti: String, new_jti: String, new_expires_at: DateTime<Utc>) -> Result<RotateRefreshTokenResult, Error>;
+}
+
+#[async_trait]
+impl RotateRefreshTokenFn for DbRepo {
+    async fn rotate_refresh_token(&self, jti: String, new_jti: String, new_expires_at: DateTime<Utc>) -> Result<RotateRefreshTokenResult, Error> {
+        internal::rotate_refresh_token(self.get_conn(), jti, new_jti, new_expires_at).await
+    }
+}
+
+#[async_trait]
+pub trait RevokeRefreshTokenFn {
+    async fn revoke_refresh_token(&self, jti: String) -> Result<(), Error>;
+}
+
+#[async_trait]
+impl RevokeRefreshTokenFn for DbRepo {
+    async fn revoke_refresh_token(&self, jti: String) -> Result<(), Error> {
+        internal::revoke_refresh_token(self.get_conn(), jti).await
+    }
+}
+
+#[async_trait]
+pub trait QueryRefreshTokenFn {
+    async fn query_refresh_token(&self, jti: String) -> Result<Option<RefreshTokenRecord>, Error>;
+}
+
+#[async_trait]
+impl QueryRefreshTokenFn for DbRepo {
+    async fn query_refresh_token(&self, jti: String) -> Result<Option<RefreshTokenRecord>, Error> {
+        internal::query_refresh_token(self.get_conn(), jti).await
+    }
+}
@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(FromRow, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Tag {
+    pub id: i64,
+    pub name: String,
+    /// Unique, made from the name the first time it was used
+    pub slug: String
+}
+
+#[derive(FromRow, Debug, Clone)]
+pub struct TagPostCount {
+    #[sqlx(flatten)]
+    pub tag: Tag,
+    /// Public posts only
+    pub post_count: i64
+}
@@ -0,0 +1,60 @@
+use async_trait::async_trait;
+use sqlx::{Postgres, Pool, Transaction, query, query_as, query_scalar, Error};
+use crate::common::{
+    repository::{post::repo::IS_PUBLIC_CONDITION, tag::models::TagPostCount, base::{DbRepo, ConnGetter}},
+    utils::slug_utils::get_tag_slug
+};
+
+mod internal {
+    use super::*;
+
+    /// Tags of public posts only, by name
+    pub async fn query_tags(conn: &Pool<Postgres>) -> Result<Vec<TagPostCount>, Error> {
+        query_as::<_, TagPostCount>(&format!(
+            "select tag.id, tag.name, tag.slug, count(post.id) as post_count 
+            from tag join post_tag on post_tag.tag_id = tag.id join post on post.id = post_tag.post_id 
+            where {} 
+            group by tag.id 
+            order by tag.name", 
+            IS_PUBLIC_CONDITION
+        ))
+            .fetch_all(conn)
+            .await
+    }
+}
+
+/// Replaces the post's tags, written by the post repo in the same transaction as the save.
+/// A name whose slug has no tag yet adds one
+pub async fn set_post_tags(tx: &mut Transaction<'_, Postgres>, post_id: i64, tag_names: &[String]) -> Result<(), Error> {
+    query::<_>("delete from post_tag where post_id = $1")
+        .bind(post_id)
+        .execute(&mut **tx)
+        .await?;
+
+    for tag_name in tag_names {
+        // the update is only there so returning gives the id of an existing tag too
+        let tag_id = query_scalar::<_, i64>("insert into tag (name, slug) values ($1, $2) on conflict (slug) do update set slug = excluded.slug returning id")
+            .bind(tag_name)
+            .bind(get_tag_slug(tag_name))
+            .fetch_one(&mut **tx)
+            .await?;
+        query::<_>("insert into post_tag (post_id, tag_id) values ($1, $2) on conflict do nothing")
+            .bind(post_id)
+            .bind(tag_id)
+            .execute(&mut **tx)
+            .await?;
+    }
+    Ok(())
+}
+
+#[async_trait]
+pub trait QueryTagsFn {
+    async fn query_tags(&self) -> Result<Vec<TagPostCount>, Error>;
+}
+
+#[async_trait]
+impl QueryTagsFn for DbRepo {
+    async fn query_tags(&self) -> Result<Vec<TagPostCount>, Error> {
+        internal::query_tags(self.get_conn()).await
+    }
+}
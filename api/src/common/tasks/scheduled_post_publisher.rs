@@ -0,0 +1,66 @@
+use std::time::Duration;
+use log::{error, info};
+use crate::common::repository::post::repo::PublishScheduledPostsFn;
+
+/// Public queries already count scheduled posts whose time has passed, so this only catches the stored status up
+pub const PUBLISH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Marks scheduled posts published once their time has passed, every PUBLISH_INTERVAL until the server stops
+pub async fn run_scheduled_post_publisher<T: PublishScheduledPostsFn>(repo: &T) {
+    let mut interval = actix_web::rt::time::interval(PUBLISH_INTERVAL);
+    loop {
+        interval.tick().await;
+        publish_due_posts(repo).await;
+    }
+}
+
+/// Returns how many posts were published, a failure is logged and left for the next run
+pub async fn publish_due_posts<T: PublishScheduledPostsFn>(repo: &T) -> u64 {
+    match repo.publish_scheduled_posts().await {
+        Ok(published_count) => {
+            if published_count > 0 {
+                info!("published {} scheduled posts", published_count);
+            }
+            published_count
+        },
+        Err(e) => {
+            error!("publish_scheduled_posts failed: {:?}", e);
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use sqlx::Error;
+    use super::*;
+
+    struct MockDbRepo;
+
+    #[async_trait]
+    impl PublishScheduledPostsFn for MockDbRepo {
+        async fn publish_scheduled_posts(&self) -> Result<u64, Error> {
+            Ok(2)
+        }
+    }
+
+    struct MockFailingDbRepo;
+
+    #[async_trait]
+    impl PublishScheduledPostsFn for MockFailingDbRepo {
+        async fn publish_scheduled_posts(&self) -> Result<u64, Error> {
+            Err(Error::PoolTimedOut)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_publish_due_posts_returns_published_count() {
+        assert!(publish_due_posts(&MockDbRepo).await == 2);
+    }
+
+    #[tokio::test]
+    async fn test_publish_due_posts_survives_failures() {
+        assert!(publish_due_posts(&MockFailingDbRepo).await == 0);
+    }
+}
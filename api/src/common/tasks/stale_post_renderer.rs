@@ -0,0 +1,54 @@
+use log::{error, info};
+use crate::common::repository::post::repo::RenderStalePostsFn;
+
+/// Renders the posts saved before their rendered_html, headings or message_text were stored, once at startup.
+/// Returns how many posts were rendered, a failure is logged and left for the next startup
+pub async fn run_stale_post_renderer<T: RenderStalePostsFn>(repo: &T) -> u64 {
+    match repo.render_stale_posts().await {
+        Ok(rendered_count) => {
+            if rendered_count > 0 {
+                info!("rendered {} posts saved before their html or headings were stored", rendered_count);
+            }
+            rendered_count
+        },
+        Err(e) => {
+            error!("render_stale_posts failed: {:?}", e);
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use sqlx::Error;
+    use super::*;
+
+    struct MockDbRepo;
+
+    #[async_trait]
+    impl RenderStalePostsFn for MockDbRepo {
+        async fn render_stale_posts(&self) -> Result<u64, Error> {
+            Ok(3)
+        }
+    }
+
+    struct MockFailingDbRepo;
+
+    #[async_trait]
+    impl RenderStalePostsFn for MockFailingDbRepo {
+        async fn render_stale_posts(&self) -> Result<u64, Error> {
+            Err(Error::PoolTimedOut)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_stale_post_renderer_returns_rendered_count() {
+        assert!(run_stale_post_renderer(&MockDbRepo).await == 3);
+    }
+
+    #[tokio::test]
+    async fn test_run_stale_post_renderer_survives_failures() {
+        assert!(run_stale_post_renderer(&MockFailingDbRepo).await == 0);
+    }
+}
@@ -0,0 +1,36 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Makes a page cursor into a url safe token, clients only pass it back to get the next page
+pub fn encode_cursor<C: Serialize>(cursor: &C) -> String {
+    URL_SAFE_NO_PAD.encode(serde_json::to_vec(cursor).unwrap_or_default())
+}
+
+/// None when @token was not made by encode_cursor, e.g. it was edited
+pub fn decode_cursor<C: DeserializeOwned>(token: &str) -> Option<C> {
+    let bytes = URL_SAFE_NO_PAD.decode(token).ok()?;
+    serde_json::from_slice::<C>(&bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Utc};
+    use crate::common::repository::base::PageCursor;
+    use super::*;
+
+    #[test]
+    fn test_decode_cursor_returns_encoded_cursor() {
+        let cursor = PageCursor { sort_at: DateTime::<Utc>::from_timestamp_millis(1_709_990_000_123).unwrap(), id: 42 };
+
+        let token = encode_cursor(&cursor);
+
+        assert!(token.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert!(decode_cursor::<PageCursor>(&token) == Some(cursor));
+    }
+
+    #[test]
+    fn test_decode_cursor_rejects_invalid_tokens() {
+        assert!(decode_cursor::<PageCursor>("not a cursor").is_none());
+        assert!(decode_cursor::<PageCursor>(&URL_SAFE_NO_PAD.encode("{\"id\": 1}")).is_none());
+    }
+}
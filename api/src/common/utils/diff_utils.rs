@@ -0,0 +1,74 @@
+use std::borrow::Cow;
+use serde::Serialize;
+use similar::{ChangeTag, TextDiff};
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffTag {
+    Equal,
+    Insert,
+    Delete
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DiffLine {
+    pub tag: DiffTag,
+    /// Without its line ending
+    pub text: String
+}
+
+/// Every line of @old and @new in order, a changed line is a Delete of the old line and an Insert of the new one
+pub fn get_line_diff(old: &str, new: &str) -> Vec<DiffLine> {
+    let (old, new) = (with_final_newline(old), with_final_newline(new));
+    TextDiff::from_lines(old.as_ref(), new.as_ref())
+        .iter_all_changes()
+        .map(|change| DiffLine {
+            tag: match change.tag() {
+                ChangeTag::Equal => DiffTag::Equal,
+                ChangeTag::Insert => DiffTag::Insert,
+                ChangeTag::Delete => DiffTag::Delete
+            },
+            text: change.value().trim_end_matches(['\r', '\n']).to_string()
+        })
+        .collect()
+}
+
+/// Otherwise adding a line after the last one would also show the last one as changed
+fn with_final_newline(text: &str) -> Cow<'_, str> {
+    if text.is_empty() || text.ends_with('\n') {
+        Cow::Borrowed(text)
+    } else {
+        Cow::Owned(format!("{}\n", text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_tagged_lines(diff: &[DiffLine]) -> Vec<(DiffTag, &str)> {
+        diff.iter().map(|line| (line.tag, line.text.as_str())).collect()
+    }
+
+    #[test]
+    fn test_get_line_diff_marks_changed_lines() {
+        let diff = get_line_diff("# Title\n\nfirst\nsecond\n", "# Title\n\nfirst\nchanged\nadded\n");
+
+        assert!(get_tagged_lines(&diff) == vec![
+            (DiffTag::Equal, "# Title"),
+            (DiffTag::Equal, ""),
+            (DiffTag::Equal, "first"),
+            (DiffTag::Delete, "second"),
+            (DiffTag::Insert, "changed"),
+            (DiffTag::Insert, "added")
+        ]);
+    }
+
+    #[test]
+    fn test_get_line_diff_ignores_line_endings() {
+        let diff = get_line_diff("one\r\ntwo", "one\r\ntwo\nthree");
+
+        assert!(get_tagged_lines(&diff) == vec![(DiffTag::Equal, "one"), (DiffTag::Equal, "two"), (DiffTag::Insert, "three")]);
+        assert!(get_line_diff("", "").is_empty());
+    }
+}
@@ -0,0 +1,214 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+use ammonia::Builder;
+use lazy_static::lazy_static;
+use rustyindie_markdown::{html_node::to_html, markdown_renderer::{MarkdownExtension, MarkdownRenderer}};
+use crate::common::repository::post::models::PostHeading;
+
+/// Graphemes of visible text in a post preview
+pub const PREVIEW_SUMMARY_LENGTH: usize = 250;
+/// Graphemes of a meta description, search engines cut longer ones
+pub const DESCRIPTION_LENGTH: usize = 160;
+
+lazy_static! {
+    /// Allows what the markdown renderer produces and drops everything else, e.g. scripts, styles and on* handlers
+    static ref POST_HTML_SANITIZER: Builder<'static> = {
+        let mut builder = Builder::default();
+        builder
+            .url_schemes(HashSet::from(["http", "https", "mailto"]))
+            .add_tags(["section", "input"])
+            .add_tag_attributes("a", ["id", "aria-label"])
+            .add_tag_attributes("li", ["id"])
+            .add_tag_attributes("h1", ["id"])
+            .add_tag_attributes("h2", ["id"])
+            .add_tag_attributes("h3", ["id"])
+            .add_tag_attributes("h4", ["id"])
+            .add_tag_attributes("h5", ["id"])
+            .add_tag_attributes("h6", ["id"])
+            .add_tag_attributes("code", ["class"])
+            .add_tag_attributes("span", ["class"])
+            .add_tag_attributes("input", ["type", "checked", "disabled"])
+            .add_allowed_classes("a", ["footnote-backref", "heading-anchor"])
+            .add_allowed_classes("sup", ["footnote-ref"])
+            .add_allowed_classes("section", ["footnotes"])
+            .attribute_filter(|element, attribute, value| {
+                let is_allowed = match (element, attribute) {
+                    ("code", "class") => is_single_class_with_prefix(value, "language-"),
+                    ("span", "class") => is_single_class_with_prefix(value, "hl-"),
+                    // task list items, inputs of any other type are not needed
+                    ("input", "type") => value == "checkbox",
+                    _ => true
+                };
+                if is_allowed { Some(Cow::Borrowed(value)) } else { None }
+            });
+        builder
+    };
+    /// Keeps only the mark tags search highlights matches with, as text
+    static ref SEARCH_SNIPPET_SANITIZER: Builder<'static> = {
+        let mut builder = Builder::empty();
+        builder.add_tags(["mark"]);
+        builder
+    };
+}
+
+pub struct RenderedPost {
+    pub html: String,
+    pub headings: Vec<PostHeading>
+}
+
+/// Renders a post's markdown, with all GitHub flavored markdown extensions, into html that is safe to inject into a page.
+/// The headings are returned too, so the front-ends can list them without rendering the markdown themselves
+pub fn render_post(message: &str) -> RenderedPost {
+    let rendered = MarkdownRenderer::new_with_gfm().render(message);
+
+    RenderedPost {
+        html: POST_HTML_SANITIZER.clean(&to_html(&rendered.nodes)).to_string(),
+        headings: rendered.headings
+            .into_iter()
+            .map(|heading| PostHeading { id: heading.id, level: heading.level, text: heading.text })
+            .collect()
+    }
+}
+
+/// The text of a post's markdown, without its markup, code blocks or footnotes, for search snippets to be cut from
+pub fn get_post_text(message: &str) -> String {
+    MarkdownRenderer::new_with_gfm().get_plain_text(message, usize::MAX)
+}
+
+/// Makes a search snippet cut from a post's text safe to inject into a page, leaving its highlighted matches
+pub fn clean_search_snippet_html(snippet: &str) -> String {
+    SEARCH_SNIPPET_SANITIZER.clean(snippet).to_string()
+}
+
+pub struct PostSummary {
+    pub html: String,
+    pub text: String
+}
+
+/// The preview of a post, made from @excerpt when the author wrote one, otherwise from the start of @message.
+/// The start is cut on block and word boundaries, or where <!-- more --> is written
+pub fn get_post_summary(message: &str, excerpt: Option<&str>) -> PostSummary {
+    // several previews are shown on a page, so their headings do not get ids that could clash
+    let renderer = MarkdownExtension::ALL
+        .into_iter()
+        .filter(|extension| *extension != MarkdownExtension::HeadingAnchors)
+        .fold(MarkdownRenderer::new(), |renderer, extension| renderer.with_extension(extension));
+    let summary = match excerpt.map(str::trim) {
+        Some(excerpt) if !excerpt.is_empty() => excerpt.to_string(),
+        _ => renderer.get_summary(message, PREVIEW_SUMMARY_LENGTH)
+    };
+
+    PostSummary {
+        html: POST_HTML_SANITIZER.clean(&renderer.render_html(&summary)).to_string(),
+        text: renderer.get_plain_text(&summary, DESCRIPTION_LENGTH)
+    }
+}
+
+fn is_single_class_with_prefix(value: &str, prefix: &str) -> bool {
+    value.starts_with(prefix) && !value.contains(char::is_whitespace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_post_renders_markdown() {
+        let html = render_post("Some **bold** text\n\n1. one").html;
+
+        assert!(html == "<p>Some <strong>bold</strong> text</p><ol><li>one</li></ol>");
+    }
+
+    #[test]
+    fn test_render_post_keeps_heading_ids_and_anchors() {
+        let html = render_post("## Getting started\n\n## Getting started").html;
+
+        assert!(html.starts_with(concat!(
+            "<h2 id=\"getting-started\">Getting started",
+            "<a href=\"#getting-started\" class=\"heading-anchor\" aria-label=\"Link to this heading\" rel=\"noopener noreferrer\">#</a></h2>"
+        )));
+        assert!(html.contains("<h2 id=\"getting-started-1\">"));
+    }
+
+    #[test]
+    fn test_render_post_returns_headings_with_their_ids() {
+        let headings = render_post("# Intro\n\n## Getting *started*\n\n## Getting started").headings;
+
+        assert!(headings == vec![
+            PostHeading { id: "intro".to_string(), level: 1, text: "Intro".to_string() },
+            PostHeading { id: "getting-started".to_string(), level: 2, text: "Getting started".to_string() },
+            PostHeading { id: "getting-started-1".to_string(), level: 2, text: "Getting started".to_string() }
+        ]);
+    }
+
+    #[test]
+    fn test_render_post_strips_scripts_and_event_handlers() {
+        let html = render_post("<script>alert(1)</script>\n\nHi <img src=\"a.png\" onerror=\"alert(2)\"> <b onclick=\"alert(3)\">there</b>").html;
+
+        assert!(!html.contains("script") && !html.contains("alert"));
+        assert!(html.contains("<img src=\"a.png\">"));
+        assert!(html.contains("<b>there</b>"));
+    }
+
+    #[test]
+    fn test_render_post_only_allows_safe_url_schemes() {
+        let html = render_post("[a](javascript:alert(1)) [b](https://b.com) [c](/posts/1) ![d](data:image/png;base64,AAAA)").html;
+
+        assert!(!html.contains("javascript") && !html.contains("data:"));
+        assert!(html.contains("<a href=\"https://b.com\" rel=\"noopener noreferrer\">b</a>"));
+        assert!(html.contains("<a href=\"/posts/1\" rel=\"noopener noreferrer\">c</a>"));
+    }
+
+    #[test]
+    fn test_render_post_keeps_highlighting_task_lists_and_footnotes() {
+        let html = render_post("- [x] done[^1]\n\n[^1]: note\n\n```rust\nfn f() {}\n```\n\n<span class=\"evil\">x</span><input type=\"text\">").html;
+
+        assert!(html.contains("<input type=\"checkbox\" disabled=\"\" checked=\"\">"));
+        assert!(html.contains("<sup class=\"footnote-ref\"><a href=\"#fn-1\" id=\"fnref-1\" rel=\"noopener noreferrer\">1</a></sup>"));
+        assert!(html.contains("<section class=\"footnotes\">"));
+        assert!(html.contains("<code class=\"language-rust\"><span class=\"hl-keyword\">fn</span>"));
+        assert!(html.contains("<span>x</span><input>"));
+    }
+
+    #[test]
+    fn test_get_post_summary_cuts_multibyte_text_on_word_boundaries() {
+        let message = "ü".repeat(240) + " ünïcödé wörds and more wörds that go past the preview length";
+
+        let summary = get_post_summary(&message, None);
+
+        assert!(summary.html == format!("<p>{} ünïcödé</p>", "ü".repeat(240)));
+        assert!(summary.text.chars().count() <= DESCRIPTION_LENGTH);
+    }
+
+    #[test]
+    fn test_get_post_summary_ends_at_more_marker() {
+        let summary = get_post_summary("## Intro\n\nShort start.\n\n<!-- more -->\n\nThe rest of the post.", None);
+
+        assert!(summary.html == "<h2>Intro</h2><p>Short start.</p>");
+        assert!(summary.text == "Intro Short start.");
+    }
+
+    #[test]
+    fn test_get_post_summary_prefers_excerpt() {
+        let summary = get_post_summary("The whole message.", Some("An *excerpt*<script>alert(1)</script>"));
+        let blank_excerpt_summary = get_post_summary("The whole message.", Some("  "));
+
+        assert!(summary.html == "<p>An <em>excerpt</em></p>");
+        assert!(summary.text == "An excerpt");
+        assert!(blank_excerpt_summary.html == "<p>The whole message.</p>");
+    }
+
+    #[test]
+    fn test_get_post_text_leaves_out_markdown() {
+        let text = get_post_text("## Intro\n\nSome **bold** text with [a link](https://a.com)");
+
+        assert!(text == "Intro Some bold text with a link");
+    }
+
+    #[test]
+    fn test_clean_search_snippet_html_only_keeps_mark_tags() {
+        let snippet = clean_search_snippet_html("A <mark>match</mark> in <a href=\"https://a.com\" onclick=\"x()\">a link</a><img src=x onerror=y>");
+
+        assert!(snippet == "A <mark>match</mark> in a link");
+    }
+}
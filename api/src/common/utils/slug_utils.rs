@@ -0,0 +1,85 @@
+/// Longer titles are cut, at a dash where possible, so urls stay readable
+pub const MAX_SLUG_LENGTH: usize = 100;
+
+/// An ascii slug of @text, e.g. "Über Rust!" becomes uber-rust. Slugs are never all digits,
+/// so a post's url can hold either its slug or, for old links, its id
+pub fn get_slug(text: &str) -> String {
+    let slug = cut_slug(slug::slugify(text));
+
+    if slug.is_empty() {
+        "post".to_string()
+    } else if slug.chars().all(|c| c.is_ascii_digit()) {
+        format!("post-{}", slug)
+    } else {
+        slug
+    }
+}
+
+/// A slug of a tag name. Unlike get_slug + and # are spelled out, so C, C++ and C# are different tags,
+/// and there is no fallback, a name of only symbols gives an empty slug
+pub fn get_tag_slug(name: &str) -> String {
+    cut_slug(slug::slugify(name.replace('+', " plus ").replace('#', " sharp ")))
+}
+
+fn cut_slug(slug: String) -> String {
+    if slug.len() <= MAX_SLUG_LENGTH {
+        return slug;
+    }
+
+    match slug[..=MAX_SLUG_LENGTH].rfind('-') {
+        Some(last_dash) if last_dash > 0 => slug[..last_dash].to_string(),
+        _ => slug[..MAX_SLUG_LENGTH].trim_end_matches('-').to_string()
+    }
+}
+
+/// The first of @slug, @slug-1, @slug-2 and so on that is not in @taken_slugs
+pub fn get_unique_slug(slug: &str, taken_slugs: &[String]) -> String {
+    let mut unique_slug = slug.to_string();
+    let mut suffix = 0;
+    while taken_slugs.contains(&unique_slug) {
+        suffix += 1;
+        unique_slug = format!("{}-{}", slug, suffix);
+    }
+    unique_slug
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_slug_makes_ascii_dashed_slugs() {
+        assert!(get_slug("Rust is hard to learn") == "rust-is-hard-to-learn");
+        assert!(get_slug("  Über Café: async/await!  ") == "uber-cafe-async-await");
+        assert!(get_slug("?!") == "post");
+        assert!(get_slug("2024") == "post-2024");
+        assert!(get_slug("2024 in review") == "2024-in-review");
+    }
+
+    #[test]
+    fn test_get_slug_cuts_long_titles_at_a_dash() {
+        let slug = get_slug(&"word ".repeat(40));
+
+        assert!(slug.len() <= MAX_SLUG_LENGTH);
+        assert!(slug.ends_with("word") && !slug.contains("--"));
+        assert!(get_slug(&"a".repeat(150)).len() == MAX_SLUG_LENGTH);
+    }
+
+    #[test]
+    fn test_get_tag_slug_spells_out_symbols_without_fallback() {
+        assert!(get_tag_slug("C") == "c");
+        assert!(get_tag_slug("C++") == "c-plus-plus");
+        assert!(get_tag_slug("C#") == "c-sharp");
+        assert!(get_tag_slug("Web Dev") == "web-dev");
+        assert!(get_tag_slug("2024") == "2024");
+        assert!(get_tag_slug("?!").is_empty());
+    }
+
+    #[test]
+    fn test_get_unique_slug_adds_the_first_free_suffix() {
+        let taken_slugs = ["my-post".to_string(), "my-post-1".to_string(), "my-post-3".to_string()];
+
+        assert!(get_unique_slug("my-post", &taken_slugs) == "my-post-2");
+        assert!(get_unique_slug("other-post", &taken_slugs) == "other-post");
+    }
+}
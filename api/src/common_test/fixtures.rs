@@ -1,19 +1,21 @@
 use actix_http::header::HeaderValue;
 use actix_web::{http::header, cookie::{time::Duration as ActixWebDuration, Cookie}};
 use actix_web::{HttpRequest, test};
-use jsonwebtoken::EncodingKey;
+use chrono::Utc;
 use serde::Serialize;
+use sqlx::types::Json;
 
-use crate::{common::{repository::base::Repository, authentication::auth_service::{Authenticator, init_auth_keys, get_token, REFRESH_TOKEN_LABEL, STANDARD_REFRESH_TOKEN_EXPIRATION}}, routes::app_state::AppState};
+use crate::{common::{repository::{base::Repository, post::models::{Post, PostStatus}}, mailer::{file_mailer::FileMailer, mail_service::Mailer}, authentication::login_throttle::LoginRateLimiter, authentication::auth_service::{AuthKeys, Authenticator, init_auth_keys, get_token, REFRESH_TOKEN_LABEL, STANDARD_REFRESH_TOKEN_EXPIRATION}}, routes::app_state::AppState};
 
 pub fn get_fake_httprequest_with_bearer_token(
+    admin_id: i64,
     user_name: String,
-    encoding_key: &EncodingKey, 
+    auth_keys: &AuthKeys, 
     url: &str, 
     parameter_data: impl Serialize, 
     token_expiration_duration: Option<i64>
 ) -> HttpRequest {
-    let header_value_string = format!("Bearer {}", get_token(user_name.to_string(), encoding_key, token_expiration_duration));
+    let header_value_string = format!("Bearer {}", get_token(admin_id, user_name.to_string(), auth_keys, token_expiration_duration));
     let header_value = HeaderValue::from_str(&header_value_string).unwrap();
     let req = test::TestRequest
         ::post()
@@ -21,7 +23,7 @@ pub fn get_fake_httprequest_with_bearer_token(
         .uri(url)
         .set_json(parameter_data);     
         
-    let refresh_token = get_token(user_name, encoding_key, None);
+    let refresh_token = get_token(admin_id, user_name, auth_keys, None);
     let refresh_cookie = Cookie::build(REFRESH_TOKEN_LABEL, refresh_token.to_owned())
         .path("/")
         .max_age(ActixWebDuration::new(STANDARD_REFRESH_TOKEN_EXPIRATION, 0))
@@ -33,6 +35,31 @@ pub fn get_fake_httprequest_with_bearer_token(
     req.cookie(refresh_cookie).to_http_request()
 }
 
+/// A published post by administrator 1, rendered as it would be when saved. Tests change what they need with struct update syntax
+pub fn get_fake_post(id: i64) -> Post {
+    Post {
+        id,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+        title: "title".to_string(),
+        slug: "title".to_string(),
+        message: "message".to_string(),
+        rendered_html: Some("<p>message</p>".to_string()),
+        headings: Some(Json(vec![])),
+        excerpt: None,
+        summary_html: Some("<p>message</p>".to_string()),
+        summary_text: Some("message".to_string()),
+        status: PostStatus::Published,
+        published_at: Some(Utc::now()),
+        tags: vec![],
+        admin_id: 1
+    }
+}
+
 pub async fn get_app_data<T: Repository, U: Authenticator>(repo: T, auth_service: U) -> actix_web::web::Data<AppState<T, U>> {
-    actix_web::web::Data::new(AppState { repo, auth_service, auth_keys: init_auth_keys().await })
+    get_app_data_with_mailer(repo, auth_service, FileMailer::default()).await
+}
+
+pub async fn get_app_data_with_mailer<T: Repository, U: Authenticator, M: Mailer + Send + Sync + 'static>(repo: T, auth_service: U, mailer: M) -> actix_web::web::Data<AppState<T, U>> {
+    actix_web::web::Data::new(AppState { repo, auth_service, auth_keys: init_auth_keys().await, login_rate_limiter: LoginRateLimiter::default(), password_reset_rate_limiter: LoginRateLimiter::password_reset(), contact_mail_rate_limiter: LoginRateLimiter::contact_mail(), mailer: Box::new(mailer) })
 }
\ No newline at end of file
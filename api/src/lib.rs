@@ -1,11 +1,28 @@
 pub mod common {
     pub mod authentication {
         pub mod auth_service;
-    }    
+        pub mod login_throttle;
+        pub mod password_hasher;
+        pub mod password_reset;
+        pub mod totp;
+    }
+    pub mod mailer {
+        pub mod file_mailer;
+        pub mod mail_service;
+        pub mod smtp_mailer;
+    }
+    pub mod tasks {
+        pub mod scheduled_post_publisher;
+        pub mod stale_post_renderer;
+    }
     pub mod utils {
+        pub mod cursor_utils;
         pub mod datetime_utils;
         pub mod fs_utils;
+        pub mod diff_utils;
+        pub mod markdown_utils;
         pub mod rand_utils;
+        pub mod slug_utils;
     }
     pub mod repository {
         pub mod base;
@@ -18,25 +35,64 @@ pub mod common {
             pub mod models;
             pub mod repo;
         }
+        pub mod post_revision {
+            pub mod models;
+            pub mod repo;
+        }
+        pub mod tag {
+            pub mod models;
+            pub mod repo;
+        }
         pub mod mail {
             pub mod models;
             pub mod repo;
         }
+        pub mod refresh_token {
+            pub mod models;
+            pub mod repo;
+        }
+        pub mod password_reset {
+            pub mod models;
+            pub mod repo;
+        }
     }
 }
 pub mod routes {
     pub mod route_configs {
         pub mod admin_configs;
+        pub mod mail_configs;
         pub mod post_configs;
+        pub mod tag_configs;
+        pub mod search_configs;
     }
     pub mod authentication {
         pub mod models;
         pub mod routes;
     }
+    pub mod administrator {
+        pub mod models;
+        pub mod routes;
+    }
+    pub mod password_reset {
+        pub mod models;
+        pub mod routes;
+    }
     pub mod post {
         pub mod models;
         pub mod routes;
     }
+    pub mod post_revision {
+        pub mod models;
+        pub mod routes;
+    }
+    pub mod tag {
+        pub mod models;
+        pub mod routes;
+    }
+    pub mod search {
+        pub mod models;
+        pub mod routes;
+    }
     pub mod mail {
         pub mod models;
         pub mod routes;
@@ -55,15 +111,24 @@ use crate::routes::app_state::AppState;
 use std::env;
 use std::fs::File;
 use std::io::BufReader;
+use std::net::IpAddr;
 use actix_cors::Cors;
 use actix_web::{HttpServer, App, http::header, middleware::Logger, web};
 use log::error;
 use rustls::{Certificate, PrivateKey, ServerConfig};
 use rustls_pemfile::{certs, pkcs8_private_keys};
-use common::{repository::base::{DbRepo, Repository}, authentication::auth_service::{AuthService, init_auth_keys}};
+use common::{
+    repository::base::{DbRepo, Repository}, 
+    authentication::{auth_service::{AuthService, init_auth_keys}, login_throttle::LoginRateLimiter}, 
+    mailer::mail_service::init_mailer,
+    tasks::{scheduled_post_publisher::run_scheduled_post_publisher, stale_post_renderer::run_stale_post_renderer}
+};
 use dotenv::dotenv;
 use crate::routes::route_configs::post_configs::post_configs;
+use crate::routes::route_configs::tag_configs::tag_configs;
+use crate::routes::route_configs::search_configs::search_configs;
 use crate::routes::route_configs::admin_configs::admin_configs;
+use crate::routes::route_configs::mail_configs::mail_configs;
 
 fn load_rustls_config() -> rustls::ServerConfig {
     let config = ServerConfig::builder();
@@ -106,13 +171,29 @@ pub async fn run() -> std::io::Result<()> {
     let allowed_local_web_url = env::var("ALLOWED_LOCAL_WEB_URL").unwrap();
     let allowed_ext_web_url = env::var("ALLOWED_EXT_WEB_URL").unwrap();
     let allowed_admin_url = env::var("ALLOWED_ADMIN_URL").unwrap();
+    let trusted_proxies: Vec<IpAddr> = env::var("TRUSTED_PROXY_ADDRS")
+        .map(|addrs| addrs.split(',').filter_map(|addr| addr.trim().parse().ok()).collect())
+        .unwrap_or_default();
     
     let app_data = actix_web::web::Data::new(AppState{
         repo: DbRepo::init().await,
         auth_service: AuthService,
-        auth_keys: init_auth_keys().await
+        auth_keys: init_auth_keys().await,
+        login_rate_limiter: LoginRateLimiter::default().with_trusted_proxies(trusted_proxies.clone()),
+        password_reset_rate_limiter: LoginRateLimiter::password_reset().with_trusted_proxies(trusted_proxies.clone()),
+        contact_mail_rate_limiter: LoginRateLimiter::contact_mail().with_trusted_proxies(trusted_proxies),
+        mailer: init_mailer()
     });    
 
+    let publisher_app_data = app_data.clone();
+    actix_web::rt::spawn(async move {
+        run_scheduled_post_publisher(&publisher_app_data.repo).await;
+    });
+    let renderer_app_data = app_data.clone();
+    actix_web::rt::spawn(async move {
+        run_stale_post_renderer(&renderer_app_data.repo).await;
+    });
+
     HttpServer::new(move || {
         App::new()
             .app_data(app_data.clone())     
@@ -135,6 +216,9 @@ pub async fn run() -> std::io::Result<()> {
                 web::scope("/v1")
                     .configure(admin_configs)
                     .configure(post_configs)
+                    .configure(tag_configs)
+                    .configure(search_configs)
+                    .configure(mail_configs)
             )
     })
     // .bind((host, port)).expect("")
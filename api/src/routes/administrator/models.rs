@@ -0,0 +1,55 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::common::repository::administrator::models::{AdminRole, Administrator};
+
+#[derive(Deserialize)]
+pub struct NewAdministrator {
+    pub user_name: String,
+    pub email: String,
+    pub password: String,
+    pub role: AdminRole
+}
+
+#[derive(Deserialize)]
+pub struct UpdateAdministrator {
+    pub admin_id: i64,
+    pub user_name: String,
+    pub email: String,
+    pub role: AdminRole
+}
+
+/// @current_password is required when administrators change their own password
+#[derive(Deserialize)]
+pub struct UpdateAdministratorPassword {
+    pub admin_id: i64,
+    pub current_password: Option<String>,
+    pub new_password: String
+}
+
+#[derive(Deserialize)]
+pub struct DeactivateAdministrator {
+    pub admin_id: i64
+}
+
+/// Administrator without the password hash
+#[derive(Serialize, Debug)]
+pub struct AdministratorResponder {
+    pub id: i64,
+    pub updated_at: DateTime<Utc>,
+    pub user_name: String,
+    pub email: String,
+    pub role: AdminRole,
+    pub is_active: bool
+}
+
+pub fn convert(admin: &Administrator) -> AdministratorResponder {
+    AdministratorResponder {
+        id: admin.id,
+        updated_at: admin.updated_at,
+        user_name: admin.user_name.to_string(),
+        email: admin.email.to_string(),
+        role: admin.role,
+        is_active: admin.is_active
+    }
+}
@@ -0,0 +1,367 @@
+use actix_web::{web::{Json, Data, Query}, HttpResponse};
+use log::error;
+use crate::{
+    routes::{base_model::{CursorPagingModel, OutputId, PagedResponder}, stripped_down_error::StrippedDownError, app_state::AppState, auth_helper::{check_can_manage_administrators, AuthenticatedAdmin}},
+    common::{
+        repository::{
+            administrator::{
+                models::{AdminRole, Administrator, AuthenticateResult},
+                repo::{AuthenticateDbFn, DeactivateAdministratorFn, InsertAdminisratorFn, QueryAdministratorFn, QueryAdministratorsFn, UpdateAdministratorFn, UpdateAdministratorPasswordFn}
+            },
+            base::Repository
+        },
+        authentication::auth_service::Authenticator
+    }
+};
+use super::models::{convert, AdministratorResponder, DeactivateAdministrator, NewAdministrator, UpdateAdministrator, UpdateAdministratorPassword};
+
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+const MAX_USER_NAME_LENGTH: usize = 50;
+const MAX_EMAIL_LENGTH: usize = 120;
+
+pub async fn create_administrator<T: InsertAdminisratorFn + QueryAdministratorFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, json: Json<NewAdministrator>, admin: AuthenticatedAdmin<T, U>)
+    -> Result<OutputId, StrippedDownError> {
+    check_can_manage_administrators(&admin)?;
+    check_user_name_and_email(&json.user_name, &json.email)?;
+    check_password(&json.password)?;
+
+    let entity_result = app_data.repo.insert_administrator(json.user_name.trim().to_string(), json.email.trim().to_string(), json.password.clone(), json.role).await;
+
+    match entity_result {
+        Ok(entity) => Ok(OutputId { id: entity.id }),
+        Err(e) => {
+            error!("create_administrator failed: {:?}", e);
+            Err(convert_administrator_error(e))
+        }
+    }
+}
+
+pub async fn get_administrators<T: QueryAdministratorsFn + QueryAdministratorFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, paging: Query<CursorPagingModel>, admin: AuthenticatedAdmin<T, U>)
+    -> Result<PagedResponder<AdministratorResponder>, StrippedDownError> {
+    check_can_manage_administrators(&admin)?;
+
+    let admins_result = app_data.repo.query_administrators(paging.get_limit(), paging.get_cursor()?).await;
+
+    match admins_result {
+        Ok(admins) => Ok(PagedResponder::new(admins, &paging, Administrator::cursor, convert)),
+        Err(e) => Err(e.into())
+    }
+}
+
+pub async fn update_administrator<T: UpdateAdministratorFn + QueryAdministratorFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, json: Json<UpdateAdministrator>, admin: AuthenticatedAdmin<T, U>)
+    -> Result<HttpResponse, StrippedDownError> {
+    check_can_manage_administrators(&admin)?;
+    check_user_name_and_email(&json.user_name, &json.email)?;
+    // an owner demoting themselves could leave nobody able to manage administrators
+    if json.admin_id == admin.id && json.role != AdminRole::Owner {
+        return Err(StrippedDownError::ValidationError { field: "role".to_string() });
+    }
+    check_administrator_exists(app_data.clone(), json.admin_id).await?;
+
+    let result = app_data.repo.update_administrator(json.admin_id, json.user_name.trim().to_string(), json.email.trim().to_string(), json.role).await;
+
+    match result {
+        Ok(_) => Ok(HttpResponse::NoContent().into()),
+        Err(e) => {
+            error!("update_administrator failed: {:?}", e);
+            Err(convert_administrator_error(e))
+        }
+    }
+}
+
+/// Any administrator may change their own password given the current one, owners may also reset anyone else's.
+/// A reset ends every session of the administrator whose password it is
+pub async fn update_administrator_password<T: UpdateAdministratorPasswordFn + AuthenticateDbFn + QueryAdministratorFn + Repository, U: Authenticator>(
+    app_data: Data<AppState<T, U>>,
+    json: Json<UpdateAdministratorPassword>,
+    admin: AuthenticatedAdmin<T, U>
+) -> Result<HttpResponse, StrippedDownError> {
+    if json.admin_id == admin.id {
+        let current_password = match json.current_password.as_ref() {
+            Some(current_password) => current_password.clone(),
+            None => return Err(StrippedDownError::ValidationError { field: "current_password".to_string() })
+        };
+        // goes through the normal login check so guesses count towards the account lockout
+        match app_data.repo.authenticate_db(admin.email.clone(), current_password).await {
+            Ok(AuthenticateResult::Success { id }) if id == admin.id => (),
+            Ok(_) => return Err(StrippedDownError::AuthenticationFailed),
+            Err(e) => return Err(e.into())
+        }
+    } else {
+        check_can_manage_administrators(&admin)?;
+        check_administrator_exists(app_data.clone(), json.admin_id).await?;
+    }
+    check_password(&json.new_password)?;
+
+    let result = app_data.repo.update_administrator_password(json.admin_id, json.new_password.clone(), json.admin_id != admin.id).await;
+
+    match result {
+        Ok(_) => Ok(HttpResponse::NoContent().into()),
+        Err(e) => Err(e.into())
+    }
+}
+
+pub async fn deactivate_administrator<T: DeactivateAdministratorFn + QueryAdministratorFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, json: Json<DeactivateAdministrator>, admin: AuthenticatedAdmin<T, U>)
+    -> Result<HttpResponse, StrippedDownError> {
+    check_can_manage_administrators(&admin)?;
+    if json.admin_id == admin.id {
+        return Err(StrippedDownError::ValidationError { field: "admin_id".to_string() });
+    }
+    check_administrator_exists(app_data.clone(), json.admin_id).await?;
+
+    let result = app_data.repo.deactivate_administrator(json.admin_id).await;
+
+    match result {
+        Ok(_) => Ok(HttpResponse::NoContent().into()),
+        Err(e) => Err(e.into())
+    }
+}
+
+async fn check_administrator_exists<T: QueryAdministratorFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, admin_id: i64) -> Result<(), StrippedDownError> {
+    match app_data.repo.query_administrator(admin_id).await {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err(StrippedDownError::NotFound),
+        Err(e) => Err(e.into())
+    }
+}
+
+fn check_user_name_and_email(user_name: &str, email: &str) -> Result<(), StrippedDownError> {
+    let user_name = user_name.trim();
+    if user_name.is_empty() || user_name.len() > MAX_USER_NAME_LENGTH {
+        return Err(StrippedDownError::ValidationError { field: "user_name".to_string() });
+    }
+    let email = email.trim();
+    if !email.contains('@') || email.len() > MAX_EMAIL_LENGTH {
+        return Err(StrippedDownError::ValidationError { field: "email".to_string() });
+    }
+    Ok(())
+}
+
+pub fn check_password(password: &str) -> Result<(), StrippedDownError> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(StrippedDownError::ValidationError { field: "password".to_string() });
+    }
+    Ok(())
+}
+
+/// Email is the only unique column a caller can collide with
+fn convert_administrator_error(e: sqlx::Error) -> StrippedDownError {
+    match e.as_database_error() {
+        Some(db_error) if db_error.is_unique_violation() => StrippedDownError::ValidationError { field: "email".to_string() },
+        _ => e.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use chrono::Utc;
+    use sqlx::Error;
+    use crate::{
+        common::{
+            authentication::auth_service::{AuthService, STANDARD_ACCESS_TOKEN_EXPIRATION},
+            repository::{administrator::models::Administrator, base::{EntityId, PageCursor}}
+        },
+        common_test::fixtures::{get_app_data, get_fake_httprequest_with_bearer_token}
+    };
+    use super::*;
+
+    const OWNER_ID: i64 = 1;
+    const AUTHOR_ID: i64 = 2;
+    const MOCK_ENTITY_ID: i64 = 10;
+    const CURRENT_PASSWORD: &str = "current-password";
+    struct MockDbRepo;
+
+    #[async_trait]
+    impl Repository for MockDbRepo {
+        async fn init() -> Self {
+            MockDbRepo
+        }
+    }
+
+    #[async_trait]
+    impl QueryAdministratorFn for MockDbRepo {
+        async fn query_administrator(&self, id: i64) -> Result<Option<Administrator>, Error> {
+            Ok(Some(Administrator {
+                id,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                user_name: "dave".to_string(),
+                email: "test@test.com".to_string(),
+                password: "123".to_string(),
+                role: if id == OWNER_ID { AdminRole::Owner } else { AdminRole::Author },
+                is_active: true
+            }))
+        }
+    }
+
+    #[async_trait]
+    impl QueryAdministratorsFn for MockDbRepo {
+        async fn query_administrators(&self, _limit: i32, _cursor: Option<PageCursor>) -> Result<Vec<Administrator>, Error> {
+            Ok(vec![self.query_administrator(OWNER_ID).await?.unwrap(), self.query_administrator(AUTHOR_ID).await?.unwrap()])
+        }
+    }
+
+    #[async_trait]
+    impl InsertAdminisratorFn for MockDbRepo {
+        async fn insert_administrator(&self, _user_name: String, _email: String, _password: String, _role: AdminRole) -> Result<EntityId, Error> {
+            Ok(EntityId { id: MOCK_ENTITY_ID })
+        }
+    }
+
+    #[async_trait]
+    impl UpdateAdministratorFn for MockDbRepo {
+        async fn update_administrator(&self, _id: i64, _user_name: String, _email: String, _role: AdminRole) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl UpdateAdministratorPasswordFn for MockDbRepo {
+        async fn update_administrator_password(&self, id: i64, _password: String, revoke_refresh_tokens: bool) -> Result<(), Error> {
+            // only resets of someone else's password end their sessions
+            assert!(revoke_refresh_tokens == (id == MOCK_ENTITY_ID));
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl AuthenticateDbFn for MockDbRepo {
+        async fn authenticate_db(&self, _email: String, password: String) -> Result<AuthenticateResult, Error> {
+            if password == CURRENT_PASSWORD {
+                return Ok(AuthenticateResult::Success { id: AUTHOR_ID });
+            }
+            Ok(AuthenticateResult::Failure)
+        }
+    }
+
+    #[async_trait]
+    impl DeactivateAdministratorFn for MockDbRepo {
+        async fn deactivate_administrator(&self, _id: i64) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    async fn get_admin(app_data: Data<AppState<MockDbRepo, AuthService>>, admin_id: i64) -> AuthenticatedAdmin<MockDbRepo, AuthService> {
+        let req = get_fake_httprequest_with_bearer_token(admin_id, "dave".to_string(), &app_data.auth_keys, "/v1/user", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+        AuthenticatedAdmin::authenticate(app_data, req).await.unwrap()
+    }
+
+    fn get_new_administrator(password: &str) -> NewAdministrator {
+        NewAdministrator {
+            user_name: "jon".to_string(),
+            email: "jon@test.com".to_string(),
+            password: password.to_string(),
+            role: AdminRole::Editor
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_administrator_returns_id_for_owner() {
+        let app_data = get_app_data(MockDbRepo, AuthService).await;
+        let admin = get_admin(app_data.clone(), OWNER_ID).await;
+
+        let result = create_administrator(app_data, Json(get_new_administrator("long-enough-password")), admin).await;
+
+        assert!(result.unwrap().id == MOCK_ENTITY_ID);
+    }
+
+    #[tokio::test]
+    async fn test_create_administrator_fails_authorization_for_author() {
+        let app_data = get_app_data(MockDbRepo, AuthService).await;
+        let admin = get_admin(app_data.clone(), AUTHOR_ID).await;
+
+        let result = create_administrator(app_data, Json(get_new_administrator("long-enough-password")), admin).await;
+
+        assert!(result.err().unwrap() == StrippedDownError::AuthorizationFailed);
+    }
+
+    #[tokio::test]
+    async fn test_create_administrator_rejects_short_password() {
+        let app_data = get_app_data(MockDbRepo, AuthService).await;
+        let admin = get_admin(app_data.clone(), OWNER_ID).await;
+
+        let result = create_administrator(app_data, Json(get_new_administrator("short")), admin).await;
+
+        assert!(result.err().unwrap() == StrippedDownError::ValidationError { field: "password".to_string() });
+    }
+
+    #[tokio::test]
+    async fn test_get_administrators_does_not_return_password() {
+        let app_data = get_app_data(MockDbRepo, AuthService).await;
+        let admin = get_admin(app_data.clone(), OWNER_ID).await;
+
+        let result = get_administrators(app_data, Query(CursorPagingModel::default()), admin).await.unwrap();
+        let json = serde_json::to_string(&result).unwrap();
+
+        assert!(result.items.len() == 2);
+        assert!(!json.contains("password"));
+    }
+
+    #[tokio::test]
+    async fn test_update_administrator_rejects_owner_demoting_themselves() {
+        let app_data = get_app_data(MockDbRepo, AuthService).await;
+        let admin = get_admin(app_data.clone(), OWNER_ID).await;
+
+        let result = update_administrator(app_data, Json(UpdateAdministrator {
+            admin_id: OWNER_ID,
+            user_name: "dave".to_string(),
+            email: "test@test.com".to_string(),
+            role: AdminRole::Editor
+        }), admin).await;
+
+        assert!(result.err().unwrap() == StrippedDownError::ValidationError { field: "role".to_string() });
+    }
+
+    #[tokio::test]
+    async fn test_update_administrator_password_requires_current_password_for_self() {
+        let app_data = get_app_data(MockDbRepo, AuthService).await;
+
+        let missing = update_administrator_password(app_data.clone(), Json(UpdateAdministratorPassword {
+            admin_id: AUTHOR_ID,
+            current_password: None,
+            new_password: "long-enough-password".to_string()
+        }), get_admin(app_data.clone(), AUTHOR_ID).await).await;
+        let wrong = update_administrator_password(app_data.clone(), Json(UpdateAdministratorPassword {
+            admin_id: AUTHOR_ID,
+            current_password: Some("wrong-password".to_string()),
+            new_password: "long-enough-password".to_string()
+        }), get_admin(app_data.clone(), AUTHOR_ID).await).await;
+        let correct = update_administrator_password(app_data.clone(), Json(UpdateAdministratorPassword {
+            admin_id: AUTHOR_ID,
+            current_password: Some(CURRENT_PASSWORD.to_string()),
+            new_password: "long-enough-password".to_string()
+        }), get_admin(app_data.clone(), AUTHOR_ID).await).await;
+
+        assert!(missing.err().unwrap() == StrippedDownError::ValidationError { field: "current_password".to_string() });
+        assert!(wrong.err().unwrap() == StrippedDownError::AuthenticationFailed);
+        assert!(correct.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_update_administrator_password_only_owner_resets_others() {
+        let app_data = get_app_data(MockDbRepo, AuthService).await;
+        let get_input = || Json(UpdateAdministratorPassword {
+            admin_id: MOCK_ENTITY_ID,
+            current_password: None,
+            new_password: "long-enough-password".to_string()
+        });
+
+        let by_author = update_administrator_password(app_data.clone(), get_input(), get_admin(app_data.clone(), AUTHOR_ID).await).await;
+        let by_owner = update_administrator_password(app_data.clone(), get_input(), get_admin(app_data.clone(), OWNER_ID).await).await;
+
+        assert!(by_author.err().unwrap() == StrippedDownError::AuthorizationFailed);
+        assert!(by_owner.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_deactivate_administrator_rejects_self() {
+        let app_data = get_app_data(MockDbRepo, AuthService).await;
+
+        let own = deactivate_administrator(app_data.clone(), Json(DeactivateAdministrator { admin_id: OWNER_ID }), get_admin(app_data.clone(), OWNER_ID).await).await;
+        let other = deactivate_administrator(app_data.clone(), Json(DeactivateAdministrator { admin_id: AUTHOR_ID }), get_admin(app_data.clone(), OWNER_ID).await).await;
+
+        assert!(own.err().unwrap() == StrippedDownError::ValidationError { field: "admin_id".to_string() });
+        assert!(other.is_ok());
+    }
+}
@@ -1,7 +1,11 @@
-use crate::common::{repository::base::Repository, authentication::auth_service::{AuthKeys, Authenticator}};
+use crate::common::{repository::base::Repository, authentication::{auth_service::{AuthKeys, Authenticator}, login_throttle::LoginRateLimiter}, mailer::mail_service::Mailer};
 
 pub struct AppState<T: Repository, U: Authenticator> {
     pub repo: T,
     pub auth_service: U,
-    pub auth_keys: AuthKeys
+    pub auth_keys: AuthKeys,
+    pub login_rate_limiter: LoginRateLimiter,
+    pub password_reset_rate_limiter: LoginRateLimiter,
+    pub contact_mail_rate_limiter: LoginRateLimiter,
+    pub mailer: Box<dyn Mailer + Send + Sync>
 }
\ No newline at end of file
@@ -1,35 +1,232 @@
-use actix_web::{web::Data, HttpRequest, HttpResponse};
+use std::{future::Future, marker::PhantomData, ops::Deref, pin::Pin, rc::Rc};
+use actix_web::{
+    body::{BoxBody, EitherBody},
+    dev::{forward_ready, Payload, Service, ServiceFactory, ServiceRequest, ServiceResponse, Transform},
+    web::{self, Data},
+    Error, FromRequest, HttpMessage, HttpRequest, HttpResponse, Resource, ResponseError
+};
+use futures::future::{ready, LocalBoxFuture, Ready};
 use log::{info, error};
-use crate::{common::{repository::{administrator::repo::QueryAdministratorFn, base::Repository}, authentication::auth_service::Authenticator}, routes::route_utils::get_header_strings};
-use super::{app_state::AppState, authentication::models::LoginResponse};
+use crate::{
+    common::{
+        repository::{administrator::{models::Administrator, repo::QueryAdministratorFn}, base::Repository, post::models::Post}, 
+        authentication::auth_service::{AuthenticationError, Authenticator, decode_token}
+    }, 
+    routes::route_utils::get_header_strings
+};
+use super::{app_state::AppState, authentication::models::LoginResponse, stripped_down_error::StrippedDownError};
 
-pub async fn check_is_authenticated<T: QueryAdministratorFn + Repository, U: Authenticator>(
+/// The authenticated identity is taken from the bearer token itself, never from the request body
+async fn get_authenticated_admin<T: QueryAdministratorFn + Repository, U: Authenticator>(
     app_data: Data<AppState<T, U>>,
-    admin_id: i64,
     req: HttpRequest
-) -> bool {
-    let admin = app_data.repo.query_administrator(admin_id).await.unwrap().unwrap();
-    
+) -> Result<Administrator, StrippedDownError> {
     let headers = get_header_strings(req.headers());
-    let is_authenticated_result = app_data.auth_service.is_authenticated(admin.user_name, headers, &app_data.auth_keys.decoding_key).await;
+    let claims = match get_bearer_token(&headers) {
+        Some(token) => decode_token(token, &app_data.auth_keys).map_err(|e| {
+            info!("Failed authorization, {}", e);
+            StrippedDownError::from(e)
+        })?,
+        None => {
+            info!("Failed authorization, no bearer token");
+            return Err(AuthenticationError::MissingToken.into());
+        }
+    };
+
+    if !claims.is_access_token() {
+        info!("Failed authorization, not an access token");
+        return Err(AuthenticationError::MalformedToken.into());
+    }
+
+    let admin = match app_data.repo.query_administrator(claims.admin_id).await {
+        Ok(Some(admin)) if admin.is_active => admin,
+        Ok(Some(_)) => {
+            info!("Failed authorization, administrator {} is deactivated", claims.admin_id);
+            return Err(StrippedDownError::AuthenticationFailed);
+        },
+        Ok(None) => {
+            error!("Authorization attempt failed, administrator {} not found", claims.admin_id);
+            return Err(StrippedDownError::AuthenticationFailed);
+        },
+        Err(e) => return Err(e.into())
+    };
+
+    let is_authenticated_result = app_data.auth_service.is_authenticated(admin.user_name.clone(), headers, &app_data.auth_keys).await;
     match is_authenticated_result {
         Ok(result) => match result {
             true => {
                 info!("Successfully authorized");
-                true
+                Ok(admin)
             },
             false => {
                 info!("Failed authorization");
-                false
+                Err(StrippedDownError::AuthenticationFailed)
             }
         },
-        Err(_) => {
-            error!("Authorization attempt failed");
-            false
+        Err(e) => {
+            error!("Authorization attempt failed, {}", e);
+            Err(e.into())
         }
     }
 }
 
+/// A resource only authenticated administrators reach. Protected routes are declared with this in the route configs,
+/// so a handler added to one is never public, whether or not it takes AuthenticatedAdmin
+pub fn admin_resource<T: QueryAdministratorFn + Repository + 'static, U: Authenticator + 'static>(path: &str) 
+    -> Resource<impl ServiceFactory<ServiceRequest, Config = (), Response = ServiceResponse<EitherBody<BoxBody>>, Error = Error, InitError = ()>> {
+    web::resource(path).wrap(RequireAdmin::<T, U>::default())
+}
+
+/// Middleware that rejects requests without a valid access token before they reach the handler.
+/// The administrator is kept in the request extensions for AuthenticatedAdmin
+pub struct RequireAdmin<T, U> {
+    app_state: PhantomData<fn() -> (T, U)>
+}
+
+impl<T, U> Default for RequireAdmin<T, U> {
+    fn default() -> Self {
+        RequireAdmin { app_state: PhantomData }
+    }
+}
+
+impl<S, B, T, U> Transform<S, ServiceRequest> for RequireAdmin<T, U>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+    T: QueryAdministratorFn + Repository + 'static,
+    U: Authenticator + 'static
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RequireAdminMiddleware<S, T, U>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequireAdminMiddleware { service: Rc::new(service), app_state: PhantomData }))
+    }
+}
+
+pub struct RequireAdminMiddleware<S, T, U> {
+    service: Rc<S>,
+    app_state: PhantomData<fn() -> (T, U)>
+}
+
+impl<S, B, T, U> Service<ServiceRequest> for RequireAdminMiddleware<S, T, U>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+    T: QueryAdministratorFn + Repository + 'static,
+    U: Authenticator + 'static
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+
+        Box::pin(async move {
+            let admin_result = match req.app_data::<Data<AppState<T, U>>>() {
+                Some(app_data) => get_authenticated_admin(app_data.clone(), req.request().clone()).await,
+                None => {
+                    error!("RequireAdmin used on a route without AppState app_data");
+                    Err(StrippedDownError::InternalError)
+                }
+            };
+
+            match admin_result {
+                Ok(admin) => {
+                    req.extensions_mut().insert(admin);
+                    service.call(req).await.map(ServiceResponse::map_into_left_body)
+                },
+                Err(e) => Ok(req.into_response(e.error_response()).map_into_right_body())
+            }
+        })
+    }
+}
+
+/// The administrator identified by the request's bearer token.
+/// On routes declared with admin_resource this is the administrator the middleware already authenticated
+pub struct AuthenticatedAdmin<T, U> {
+    pub admin: Administrator,
+    app_state: PhantomData<fn() -> (T, U)>
+}
+
+impl<T: QueryAdministratorFn + Repository, U: Authenticator> AuthenticatedAdmin<T, U> {
+    pub fn new(admin: Administrator) -> Self {
+        AuthenticatedAdmin { admin, app_state: PhantomData }
+    }
+
+    pub async fn authenticate(app_data: Data<AppState<T, U>>, req: HttpRequest) -> Result<Self, StrippedDownError> {
+        get_authenticated_admin(app_data, req).await.map(AuthenticatedAdmin::new)
+    }
+}
+
+impl<T, U> Clone for AuthenticatedAdmin<T, U> {
+    fn clone(&self) -> Self {
+        AuthenticatedAdmin { admin: self.admin.clone(), app_state: PhantomData }
+    }
+}
+
+impl<T, U> Deref for AuthenticatedAdmin<T, U> {
+    type Target = Administrator;
+
+    fn deref(&self) -> &Self::Target {
+        &self.admin
+    }
+}
+
+impl<T: QueryAdministratorFn + Repository + 'static, U: Authenticator + 'static> FromRequest for AuthenticatedAdmin<T, U> {
+    type Error = StrippedDownError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let req = req.clone();
+
+        Box::pin(async move {
+            if let Some(admin) = req.extensions().get::<Administrator>().cloned() {
+                return Ok(AuthenticatedAdmin::new(admin));
+            }
+
+            let app_data = match req.app_data::<Data<AppState<T, U>>>() {
+                Some(app_data) => app_data.clone(),
+                None => {
+                    error!("AuthenticatedAdmin used on a route without AppState app_data");
+                    return Err(StrippedDownError::InternalError);
+                }
+            };
+
+            AuthenticatedAdmin::authenticate(app_data, req).await
+        })
+    }
+}
+
+/// Owners and editors may change any post, authors only their own
+pub fn check_can_manage_post(admin: &Administrator, post: &Post) -> Result<(), StrippedDownError> {
+    if admin.role.can_manage_any_post() || admin.id == post.admin_id {
+        return Ok(());
+    }
+    Err(StrippedDownError::AuthorizationFailed)
+}
+
+/// Only owners may create, change or deactivate other administrators
+pub fn check_can_manage_administrators(admin: &Administrator) -> Result<(), StrippedDownError> {
+    if admin.role.can_manage_administrators() {
+        return Ok(());
+    }
+    Err(StrippedDownError::AuthorizationFailed)
+}
+
+fn get_bearer_token<'a>(headers: &[(&'a str, &'a str)]) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|header| header.0.to_lowercase() == "authorization")
+        .and_then(|header| header.1.split(' ').nth(1))
+}
+
 pub async fn get_access_token_from_login_resp_httpresponse(httpresponse: HttpResponse) -> String {
     let (_res, body) = httpresponse.into_parts();
     let bytes = actix_http::body::to_bytes(body).await.unwrap();
@@ -43,4 +240,229 @@ pub async fn get_access_token_from_str_body_httpresponse(httpresponse: HttpRespo
     let bytes = actix_http::body::to_bytes(body).await.unwrap();
     let resp_body = String::from_utf8_lossy(&bytes);
     resp_body.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::StatusCode, test::{call_service, init_service, read_body, TestRequest}, App};
+    use async_trait::async_trait;
+    use chrono::Utc;
+    use crate::{
+        common::{authentication::auth_service::{get_token, get_totp_challenge_token, AuthService, STANDARD_ACCESS_TOKEN_EXPIRATION}, repository::administrator::models::AdminRole},
+        common_test::fixtures::{get_app_data, get_fake_httprequest_with_bearer_token, get_fake_post}
+    };
+    use super::*;
+
+    struct MockDbRepo;
+
+    #[async_trait]
+    impl Repository for MockDbRepo {
+        async fn init() -> Self {
+            MockDbRepo
+        }
+    }
+
+    #[async_trait]
+    impl QueryAdministratorFn for MockDbRepo {
+        async fn query_administrator(&self, id: i64) -> Result<Option<Administrator>, sqlx::Error> {
+            Ok(Some(get_admin(id, AdminRole::Owner)))
+        }
+    }
+
+    struct MockMissingAdminDbRepo;
+
+    #[async_trait]
+    impl Repository for MockMissingAdminDbRepo {
+        async fn init() -> Self {
+            MockMissingAdminDbRepo
+        }
+    }
+
+    #[async_trait]
+    impl QueryAdministratorFn for MockMissingAdminDbRepo {
+        async fn query_administrator(&self, _: i64) -> Result<Option<Administrator>, sqlx::Error> {
+            Ok(None)
+        }
+    }
+
+    struct MockInactiveAdminDbRepo;
+
+    #[async_trait]
+    impl Repository for MockInactiveAdminDbRepo {
+        async fn init() -> Self {
+            MockInactiveAdminDbRepo
+        }
+    }
+
+    #[async_trait]
+    impl QueryAdministratorFn for MockInactiveAdminDbRepo {
+        async fn query_administrator(&self, id: i64) -> Result<Option<Administrator>, sqlx::Error> {
+            Ok(Some(Administrator { is_active: false, ..get_admin(id, AdminRole::Owner) }))
+        }
+    }
+
+    fn get_admin(id: i64, role: AdminRole) -> Administrator {
+        Administrator {
+            id,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            user_name: "dave".to_string(),
+            email: "test@test.com".to_string(),
+            password: "123".to_string(),
+            role,
+            is_active: true
+        }
+    }
+
+    fn get_post(admin_id: i64) -> Post {
+        Post { admin_id, ..get_fake_post(1) }
+    }
+
+    #[tokio::test]
+    async fn test_get_authenticated_admin_returns_admin_for_valid_token() {
+        let app_data = get_app_data(MockDbRepo, AuthService).await;
+        let req = get_fake_httprequest_with_bearer_token(1, "dave".to_string(), &app_data.auth_keys, "/v1/post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+
+        let admin = get_authenticated_admin(app_data, req).await.unwrap();
+
+        assert!(admin.id == 1);
+    }
+
+    #[tokio::test]
+    async fn test_authenticated_admin_extracts_admin_from_request() {
+        let app_data = get_app_data(MockDbRepo, AuthService).await;
+        let token = get_token(1, "dave".to_string(), &app_data.auth_keys, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+        let req = TestRequest::post()
+            .uri("/v1/post")
+            .app_data(app_data)
+            .append_header(("Authorization", format!("Bearer {}", token)))
+            .to_http_request();
+
+        let admin = AuthenticatedAdmin::<MockDbRepo, AuthService>::extract(&req).await.unwrap();
+
+        assert!(admin.id == 1);
+        assert!(admin.user_name == "dave");
+    }
+
+    #[tokio::test]
+    async fn test_get_authenticated_admin_fails_for_missing_token() {
+        let app_data = get_app_data(MockDbRepo, AuthService).await;
+        let req = TestRequest::post().uri("/v1/post").to_http_request();
+
+        let result = get_authenticated_admin(app_data, req).await;
+
+        assert!(result.err().unwrap() == StrippedDownError::AuthenticationFailed);
+    }
+
+    #[tokio::test]
+    async fn test_get_authenticated_admin_fails_for_malformed_token() {
+        let app_data = get_app_data(MockDbRepo, AuthService).await;
+        let req = TestRequest::post()
+            .uri("/v1/post")
+            .append_header(("Authorization", "Bearer not-a-token"))
+            .to_http_request();
+
+        let result = get_authenticated_admin(app_data, req).await;
+
+        assert!(result.err().unwrap() == StrippedDownError::AuthenticationFailed);
+    }
+
+    #[tokio::test]
+    async fn test_get_authenticated_admin_fails_for_totp_challenge_token() {
+        let app_data = get_app_data(MockDbRepo, AuthService).await;
+        let token = get_totp_challenge_token(1, "dave".to_string(), &app_data.auth_keys);
+        let req = TestRequest::post()
+            .uri("/v1/post")
+            .append_header(("Authorization", format!("Bearer {}", token)))
+            .to_http_request();
+
+        let result = get_authenticated_admin(app_data, req).await;
+
+        assert!(result.err().unwrap() == StrippedDownError::AuthenticationFailed);
+    }
+
+    #[tokio::test]
+    async fn test_get_authenticated_admin_fails_for_expired_token() {
+        let app_data = get_app_data(MockDbRepo, AuthService).await;
+        let req = get_fake_httprequest_with_bearer_token(1, "dave".to_string(), &app_data.auth_keys, "/v1/post", 1, Some(-10));
+
+        let result = get_authenticated_admin(app_data, req).await;
+
+        assert!(result.err().unwrap() == StrippedDownError::TokenExpired);
+    }
+
+    #[tokio::test]
+    async fn test_get_authenticated_admin_fails_for_unknown_admin() {
+        let app_data = get_app_data(MockMissingAdminDbRepo, AuthService).await;
+        let req = get_fake_httprequest_with_bearer_token(1, "dave".to_string(), &app_data.auth_keys, "/v1/post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+
+        let result = get_authenticated_admin(app_data, req).await;
+
+        assert!(result.err().unwrap() == StrippedDownError::AuthenticationFailed);
+    }
+
+    #[tokio::test]
+    async fn test_get_authenticated_admin_fails_for_deactivated_admin() {
+        let app_data = get_app_data(MockInactiveAdminDbRepo, AuthService).await;
+        let req = get_fake_httprequest_with_bearer_token(1, "dave".to_string(), &app_data.auth_keys, "/v1/post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+
+        let result = get_authenticated_admin(app_data, req).await;
+
+        assert!(result.err().unwrap() == StrippedDownError::AuthenticationFailed);
+    }
+
+    #[actix_web::test]
+    async fn test_admin_resource_rejects_requests_without_token_before_the_handler() {
+        let app_data = get_app_data(MockDbRepo, AuthService).await;
+        let app = init_service(App::new().app_data(app_data).service(
+            admin_resource::<MockDbRepo, AuthService>("/v1/post").route(web::post().to(|| async { HttpResponse::Ok().finish() }))
+        )).await;
+
+        let resp = call_service(&app, TestRequest::post().uri("/v1/post").to_request()).await;
+
+        assert!(resp.status() == StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn test_admin_resource_passes_authenticated_admin_to_the_handler() {
+        let app_data = get_app_data(MockDbRepo, AuthService).await;
+        let token = get_token(1, "dave".to_string(), &app_data.auth_keys, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+        let app = init_service(App::new().app_data(app_data).service(
+            admin_resource::<MockDbRepo, AuthService>("/v1/post").route(web::post().to(|admin: AuthenticatedAdmin<MockDbRepo, AuthService>| async move {
+                HttpResponse::Ok().body(admin.user_name.clone())
+            }))
+        )).await;
+
+        let resp = call_service(&app, TestRequest::post().uri("/v1/post").append_header(("Authorization", format!("Bearer {}", token))).to_request()).await;
+
+        assert!(resp.status() == StatusCode::OK);
+        assert!(read_body(resp).await == "dave");
+    }
+
+    #[test]
+    fn test_authentication_error_maps_to_stripped_down_error() {
+        assert!(StrippedDownError::from(AuthenticationError::ExpiredToken) == StrippedDownError::TokenExpired);
+        assert!(StrippedDownError::from(AuthenticationError::MalformedToken) == StrippedDownError::AuthenticationFailed);
+        assert!(StrippedDownError::from(AuthenticationError::MissingToken) == StrippedDownError::AuthenticationFailed);
+        assert!(StrippedDownError::from(AuthenticationError::DatabaseAuthenticationFailure) == StrippedDownError::InternalError);
+    }
+
+    #[test]
+    fn test_check_can_manage_administrators_allows_only_owner() {
+        assert!(check_can_manage_administrators(&get_admin(1, AdminRole::Owner)).is_ok());
+        assert!(check_can_manage_administrators(&get_admin(1, AdminRole::Editor)).err().unwrap() == StrippedDownError::AuthorizationFailed);
+        assert!(check_can_manage_administrators(&get_admin(1, AdminRole::Author)).err().unwrap() == StrippedDownError::AuthorizationFailed);
+    }
+
+    #[test]
+    fn test_check_can_manage_post_allows_owner_and_editor_any_post() {
+        assert!(check_can_manage_post(&get_admin(1, AdminRole::Owner), &get_post(2)).is_ok());
+        assert!(check_can_manage_post(&get_admin(1, AdminRole::Editor), &get_post(2)).is_ok());
+    }
+
+    #[test]
+    fn test_check_can_manage_post_allows_author_only_own_post() {
+        assert!(check_can_manage_post(&get_admin(1, AdminRole::Author), &get_post(1)).is_ok());
+        assert!(check_can_manage_post(&get_admin(1, AdminRole::Author), &get_post(2)) == Err(StrippedDownError::AuthorizationFailed));
+    }
 }
\ No newline at end of file
@@ -1,3 +1,4 @@
+use actix_web::{body::BoxBody, http::header::ContentType, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize, Clone)]
@@ -16,3 +17,41 @@ pub struct LoginResponse {
     pub access_token: String,
     pub login_user_id: i64
 }
+
+/// Returned by login instead of LoginResponse when the administrator has totp enabled
+#[derive(Serialize, Deserialize)]
+pub struct TotpChallengeResponse {
+    pub challenge_token: String
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct TotpLoginCredential {
+    pub challenge_token: String,
+    /// current authenticator code or an unused recovery code
+    pub code: String
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ConfirmTotp {
+    pub code: String
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TotpEnrollmentResponse {
+    pub secret: String,
+    /// otpauth uri, also the payload for the enrollment QR code
+    pub provisioning_uri: String,
+    pub recovery_codes: Vec<String>
+}
+
+impl Responder for TotpEnrollmentResponse {
+    type Body = BoxBody;
+
+    fn respond_to(self, _: &actix_web::HttpRequest) -> actix_web::HttpResponse<Self::Body> {
+        let body = serde_json::to_string(&self).unwrap();
+
+        HttpResponse::Ok()
+            .content_type(ContentType::json())
+            .body(body)
+    }
+}
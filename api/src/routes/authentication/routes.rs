@@ -1,62 +1,150 @@
+use std::env;
 use actix_web::{
-    cookie::{time::Duration as ActixWebDuration, Cookie},
+    cookie::{time::Duration as ActixWebDuration, Cookie, SameSite},
     web::{Data, Json}, 
-    HttpResponse,
-    http::header::ContentType, HttpRequest
+    HttpResponse, ResponseError,
+    http::{header::{ContentType, RETRY_AFTER}, StatusCode}, HttpRequest
 };
-use chrono::Utc;
-use log::{error, info};
+use chrono::{Utc, Duration};
+use log::error;
+use uuid::Uuid;
 use crate::{
-    routes::app_state::AppState, 
+    routes::{app_state::AppState, auth_helper::AuthenticatedAdmin, stripped_down_error::StrippedDownError}, 
     common::{
         repository::{
             base::Repository, 
-            administrator::{repo::{AuthenticateDbFn, QueryAdministratorFn}, models::AuthenticateResult}
+            administrator::{repo::{AuthenticateDbFn, AuthenticateTotpFn, EnableTotpFn, InsertTotpEnrollmentFn, QueryAdministratorFn, QueryAdministratorTotpFn}, models::AuthenticateResult},
+            refresh_token::{repo::{InsertRefreshTokenFn, RotateRefreshTokenFn, RevokeRefreshTokenFn}, models::RotateRefreshTokenResult}
         }, 
-        authentication::auth_service::{get_token, STANDARD_REFRESH_TOKEN_EXPIRATION, Authenticator, STANDARD_ACCESS_TOKEN_EXPIRATION, REFRESH_TOKEN_LABEL, decode_token}
+        authentication::auth_service::{get_token, get_refresh_token, STANDARD_REFRESH_TOKEN_EXPIRATION, Authenticator, STANDARD_ACCESS_TOKEN_EXPIRATION, REFRESH_TOKEN_LABEL, TOTP_CHALLENGE_SCOPE, Claims, decode_token, decode_token_ignoring_expiration, get_totp_challenge_token},
+        authentication::totp::{generate_recovery_codes, generate_totp_secret, get_provisioning_uri}
     }
 };
-use super::models::{LoginCredential, LoginResponse, RefreshToken};
+use super::models::{ConfirmTotp, LoginCredential, LoginResponse, RefreshToken, TotpChallengeResponse, TotpEnrollmentResponse, TotpLoginCredential};
 
 
-pub async fn refresh_access_token<T: Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, json: Json<RefreshToken>, req: HttpRequest) -> HttpResponse {    
+/// Every refresh rotates the refresh token, the cookie is replaced along with the access token
+pub async fn refresh_access_token<T: RotateRefreshTokenFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, json: Json<RefreshToken>, req: HttpRequest) -> HttpResponse {    
     let refresh_cookie = req.cookie(REFRESH_TOKEN_LABEL);
 
     match refresh_cookie {
         Some(cookie) => {
             let cookie_val = cookie.value();            
-            let refresh_token = decode_token(cookie_val, &app_data.auth_keys.decoding_key);
-            let refresh_user_name = refresh_token.sub;
-            let current_access_token = decode_token(&json.old_token, &app_data.auth_keys.decoding_key);
-            if refresh_user_name == current_access_token.sub && refresh_token.exp >= (Utc::now().timestamp() as usize) {
-                let new_access_token = get_token(refresh_user_name, &app_data.auth_keys.encoding_key, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+            let refresh_token = match decode_token(cookie_val, &app_data.auth_keys) {
+                Ok(claims) => claims,
+                Err(e) => {
+                    error!("Refresh token rejected: {}", e);
+                    return StrippedDownError::from(e).error_response();
+                }
+            };
+            // the access token being replaced has usually already expired
+            let current_access_token = match decode_token_ignoring_expiration(&json.old_token, &app_data.auth_keys) {
+                Ok(claims) => claims,
+                Err(e) => {
+                    error!("Old access token rejected: {}", e);
+                    return StrippedDownError::from(e).error_response();
+                }
+            };
+            let jti = match refresh_token.jti {
+                Some(jti) => jti,
+                None => {
+                    error!("Refresh token has no jti");
+                    return HttpResponse::BadRequest()
+                        .content_type(ContentType::json())
+                        .body("Refresh access token failed. Your refresh token is invalid");
+                }
+            };
+            if refresh_token.sub != current_access_token.sub || refresh_token.admin_id != current_access_token.admin_id {
+                error!("Refresh access token failed");
+                return HttpResponse::BadRequest()
+                    .content_type(ContentType::json())
+                    .body("Refresh access token failed. Your request token is expired");
+            }
+
+            let new_jti = Uuid::new_v4().to_string();
+            let rotate_result = app_data.repo.rotate_refresh_token(jti, new_jti.clone(), get_refresh_token_expires_at()).await;
+            match rotate_result {
+                Ok(RotateRefreshTokenResult::Rotated { admin_id }) if admin_id == refresh_token.admin_id => {
+                    let new_access_token = get_token(admin_id, refresh_token.sub.clone(), &app_data.auth_keys, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+                    let new_refresh_token = get_refresh_token(admin_id, refresh_token.sub, new_jti, &app_data.auth_keys);
                 
-                return HttpResponse::Ok()
-                    .body(new_access_token);
-            } 
-            error!("Refresh access token failed");
-            return HttpResponse::BadRequest()
-                .content_type(ContentType::json())
-                .body("Refresh access token failed. Your request token is expired");         
+                    HttpResponse::Ok()
+                        .cookie(get_refresh_cookie(new_refresh_token))
+                        .body(new_access_token)
+                },
+                Ok(RotateRefreshTokenResult::ReuseDetected) => {
+                    error!("Refresh token reuse detected for admin {}, token family revoked", refresh_token.admin_id);
+                    HttpResponse::Unauthorized()
+                        .cookie(get_removal_refresh_cookie())
+                        .content_type(ContentType::json())
+                        .body("Refresh access token failed. Your refresh token has been revoked")
+                },
+                Ok(_) => {
+                    error!("Refresh access token failed");
+                    HttpResponse::BadRequest()
+                        .content_type(ContentType::json())
+                        .body("Refresh access token failed. Your request token is expired")
+                },
+                Err(e) => {
+                    error!("Refresh access token failed: {}", e);
+                    HttpResponse::InternalServerError()
+                        .content_type(ContentType::json())
+                        .body("Refresh access token failed. Server error occurred")
+                }
+            }
         },
         None => {
             error!("No refresh cookie found");
-            return HttpResponse::BadRequest()
+            HttpResponse::BadRequest()
                 .content_type(ContentType::json())
                 .body("Authentication failed. Your request is missing the refresh token")
         }
-    };
+    }
+}
+
+/// Revokes the stored refresh token and clears the cookie. Succeeds even without a cookie
+pub async fn logout<T: RevokeRefreshTokenFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, req: HttpRequest) -> HttpResponse {
+    if let Some(cookie) = req.cookie(REFRESH_TOKEN_LABEL) {
+        // an expired or garbled cookie has nothing left to revoke, it only needs clearing
+        if let Ok(Claims { jti: Some(jti), .. }) = decode_token(cookie.value(), &app_data.auth_keys) {
+            if let Err(e) = app_data.repo.revoke_refresh_token(jti).await {
+                error!("Logout failed to revoke refresh token: {}", e);
+                return HttpResponse::InternalServerError()
+                    .content_type(ContentType::json())
+                    .body("Logout failed. Server error occurred");
+            }
+        }
+    }
+
+    HttpResponse::NoContent()
+        .cookie(get_removal_refresh_cookie())
+        .finish()
 }
 
 
-pub async fn login<T: AuthenticateDbFn + QueryAdministratorFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, json: Json<LoginCredential>) 
+/// Failed attempts are rate limited per client ip and per email, and accounts lock after repeated failures.
+/// Each attempt is counted before the password is checked and taken back when it succeeds
+pub async fn login<T: AuthenticateDbFn + QueryAdministratorFn + QueryAdministratorTotpFn + InsertRefreshTokenFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, json: Json<LoginCredential>, req: HttpRequest) 
     -> HttpResponse {
+    let ip = app_data.login_rate_limiter.get_client_ip(&req);
+    let attempt = match app_data.login_rate_limiter.reserve(&ip, &json.email) {
+        Ok(attempt) => attempt,
+        Err(retry_after) => {
+            error!("Login rate limit exceeded for ip {}", ip);
+            return HttpResponse::TooManyRequests()
+                .insert_header((RETRY_AFTER, retry_after.to_string()))
+                .content_type(ContentType::json())
+                .body("Authentication failed. Too many login attempts, please try again later");
+        }
+    };
+
     let auth_result = app_data.repo.authenticate_db(json.email.clone(), json.password.clone()).await;
     
     match auth_result {
         Ok(result) => {
             match result {
                 AuthenticateResult::Success { id } => {
+                    app_data.login_rate_limiter.release(&ip, &json.email, attempt);
                     #[allow(unused)] let mut user_name = "".to_string();                    
                     #[allow(unused)] let mut http_response: Option<HttpResponse> = None;
                     
@@ -65,17 +153,22 @@ pub async fn login<T: AuthenticateDbFn + QueryAdministratorFn + Repository, U: A
                         Ok(opt_user) => {
                             if let Some(usr) = opt_user {
                                 user_name = usr.user_name;
-                                let (refresh_cookie, access_token) = get_refresh_and_access_token_response(app_data, user_name.as_str());
-                                
-                                http_response = Some(
-                                    HttpResponse::Ok()
-                                    .cookie(refresh_cookie)
-                                    .content_type(ContentType::json())
-                                    .body(serde_json::to_string(&LoginResponse {
-                                        access_token,
-                                        login_user_id: id
-                                    }).unwrap())
-                                );
+                                http_response = Some(match app_data.repo.query_administrator_totp(id).await {
+                                    Ok(Some(totp)) if totp.totp_enabled => {
+                                        HttpResponse::Accepted()
+                                            .content_type(ContentType::json())
+                                            .body(serde_json::to_string(&TotpChallengeResponse {
+                                                challenge_token: get_totp_challenge_token(id, user_name, &app_data.auth_keys)
+                                            }).unwrap())
+                                    },
+                                    Ok(_) => get_login_response(app_data, id, user_name.as_str()).await,
+                                    Err(e) => {
+                                        error!("Authentication failed. Could not check totp: {}", e);
+                                        HttpResponse::Unauthorized()
+                                            .content_type(ContentType::json())
+                                            .body("Authentication failed. Server error occurred while trying to authenticate")
+                                    }
+                                });
                             } else {
                                 error!("Authentication failed. Developer not found");
                                 http_response = Some(HttpResponse::Unauthorized()
@@ -93,7 +186,14 @@ pub async fn login<T: AuthenticateDbFn + QueryAdministratorFn + Repository, U: A
 
                     return http_response.unwrap();          
                 },
-                _ => {
+                AuthenticateResult::Locked { until } => {
+                    error!("Authentication failed. Account locked until {}", until);
+                    HttpResponse::build(StatusCode::LOCKED)
+                        .insert_header((RETRY_AFTER, (until - Utc::now()).num_seconds().max(1).to_string()))
+                        .content_type(ContentType::json())
+                        .body("Authentication failed. This account is temporarily locked after too many failed logins")
+                },
+                AuthenticateResult::Failure => {
                     HttpResponse::Unauthorized()
                         .content_type(ContentType::json())
                         .body("Authentication failed. Wrong email or password")
@@ -101,6 +201,8 @@ pub async fn login<T: AuthenticateDbFn + QueryAdministratorFn + Repository, U: A
             }
         }
         Err(_) => {
+            // not the client's failure
+            app_data.login_rate_limiter.release(&ip, &json.email, attempt);
             error!("Authentication failed. Server error");
             HttpResponse::Unauthorized()
                 .content_type(ContentType::json())
@@ -109,18 +211,145 @@ pub async fn login<T: AuthenticateDbFn + QueryAdministratorFn + Repository, U: A
     }  
 }
 
-fn get_refresh_and_access_token_response<'a, T: AuthenticateDbFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, user_name: &'a str) -> (Cookie<'a>, String) {
-    let access_token = get_token(user_name.to_string(), &app_data.auth_keys.encoding_key, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
-    let refresh_token = get_token(user_name.to_string(), &app_data.auth_keys.encoding_key, None);
-    let refresh_cookie = Cookie::build(REFRESH_TOKEN_LABEL, refresh_token.to_owned())
+/// Second login step for administrators with totp enabled, @code may also be a recovery code
+pub async fn login_totp<T: AuthenticateTotpFn + InsertRefreshTokenFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, json: Json<TotpLoginCredential>, req: HttpRequest) 
+    -> HttpResponse {
+    let claims = match decode_token(&json.challenge_token, &app_data.auth_keys) {
+        Ok(claims) if claims.scope.as_deref() == Some(TOTP_CHALLENGE_SCOPE) => claims,
+        Ok(_) => return StrippedDownError::AuthenticationFailed.error_response(),
+        Err(e) => {
+            error!("Totp challenge token rejected: {}", e);
+            return StrippedDownError::from(e).error_response();
+        }
+    };
+
+    let ip = app_data.login_rate_limiter.get_client_ip(&req);
+    let totp_key = format!("totp:{}", claims.admin_id);
+    let attempt = match app_data.login_rate_limiter.reserve(&ip, &totp_key) {
+        Ok(attempt) => attempt,
+        Err(retry_after) => {
+            error!("Totp rate limit exceeded for ip {}", ip);
+            return HttpResponse::TooManyRequests()
+                .insert_header((RETRY_AFTER, retry_after.to_string()))
+                .content_type(ContentType::json())
+                .body("Authentication failed. Too many login attempts, please try again later");
+        }
+    };
+
+    match app_data.repo.authenticate_totp(claims.admin_id, json.code.clone()).await {
+        Ok(true) => {
+            app_data.login_rate_limiter.release(&ip, &totp_key, attempt);
+            get_login_response(app_data, claims.admin_id, claims.sub.as_str()).await
+        },
+        Ok(false) => {
+            HttpResponse::Unauthorized()
+                .content_type(ContentType::json())
+                .body("Authentication failed. Wrong code")
+        },
+        Err(e) => {
+            app_data.login_rate_limiter.release(&ip, &totp_key, attempt);
+            error!("Authentication failed. Server error while checking totp: {}", e);
+            HttpResponse::Unauthorized()
+                .content_type(ContentType::json())
+                .body("Authentication failed. Server error occurred while trying to authenticate")
+        }
+    }
+}
+
+/// Starts enrollment with a new secret. The recovery codes are only ever shown in this response
+pub async fn enroll_totp<T: QueryAdministratorFn + QueryAdministratorTotpFn + InsertTotpEnrollmentFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, admin: AuthenticatedAdmin<T, U>) 
+    -> Result<TotpEnrollmentResponse, StrippedDownError> {
+    // replacing an active secret would let a stolen access token switch off the second factor
+    match app_data.repo.query_administrator_totp(admin.id).await {
+        Ok(Some(totp)) if totp.totp_enabled => return Err(StrippedDownError::ValidationError { field: "totp_enabled".to_string() }),
+        Ok(_) => (),
+        Err(e) => return Err(e.into())
+    }
+
+    let secret = generate_totp_secret();
+    let recovery_codes = generate_recovery_codes();
+    if let Err(e) = app_data.repo.insert_totp_enrollment(admin.id, secret.clone(), recovery_codes.clone()).await {
+        error!("enroll_totp error: {}", e);
+        return Err(e.into());
+    }
+
+    Ok(TotpEnrollmentResponse {
+        provisioning_uri: get_provisioning_uri(&admin.email, &secret),
+        secret,
+        recovery_codes
+    })
+}
+
+/// Finishes enrollment, totp is only enforced once a code from the new secret has been confirmed
+pub async fn confirm_totp<T: QueryAdministratorFn + EnableTotpFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, json: Json<ConfirmTotp>, admin: AuthenticatedAdmin<T, U>) 
+    -> Result<HttpResponse, StrippedDownError> {
+    match app_data.repo.enable_totp(admin.id, json.code.clone()).await {
+        Ok(true) => Ok(HttpResponse::NoContent().into()),
+        Ok(false) => Err(StrippedDownError::ValidationError { field: "code".to_string() }),
+        Err(e) => Err(e.into())
+    }
+}
+
+async fn get_login_response<T: InsertRefreshTokenFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, admin_id: i64, user_name: &str) -> HttpResponse {
+    match get_refresh_and_access_token_response(app_data, admin_id, user_name).await {
+        Ok((refresh_cookie, access_token)) => {
+            HttpResponse::Ok()
+                .cookie(refresh_cookie)
+                .content_type(ContentType::json())
+                .body(serde_json::to_string(&LoginResponse {
+                    access_token,
+                    login_user_id: admin_id
+                }).unwrap())
+        },
+        Err(e) => {
+            error!("Authentication failed. Could not store refresh token: {}", e);
+            HttpResponse::Unauthorized()
+                .content_type(ContentType::json())
+                .body("Authentication failed. Server error occurred while trying to authenticate")
+        }
+    }
+}
+
+async fn get_refresh_and_access_token_response<T: InsertRefreshTokenFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, admin_id: i64, user_name: &str) 
+    -> Result<(Cookie<'static>, String), sqlx::Error> {
+    let access_token = get_token(admin_id, user_name.to_string(), &app_data.auth_keys, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+
+    // a login starts a new token family
+    let jti = Uuid::new_v4().to_string();
+    let family_id = Uuid::new_v4().to_string();
+    app_data.repo.insert_refresh_token(admin_id, jti.clone(), family_id, get_refresh_token_expires_at()).await?;
+    let refresh_token = get_refresh_token(admin_id, user_name.to_string(), jti, &app_data.auth_keys);
+                    
+    Ok((get_refresh_cookie(refresh_token), access_token))
+}
+
+fn get_refresh_token_expires_at() -> chrono::DateTime<Utc> {
+    Utc::now() + Duration::seconds(STANDARD_REFRESH_TOKEN_EXPIRATION)
+}
+
+fn get_refresh_cookie(refresh_token: String) -> Cookie<'static> {
+    Cookie::build(REFRESH_TOKEN_LABEL, refresh_token)
         .path("/")
         .max_age(ActixWebDuration::new(STANDARD_REFRESH_TOKEN_EXPIRATION, 0))
         .http_only(true)
-        .secure(false)
-        //.same_site(SameSite::Lax)
-        .finish();
-                    
-    (refresh_cookie, access_token)
+        .secure(true)
+        .same_site(get_refresh_cookie_same_site())
+        .finish()
+}
+
+fn get_removal_refresh_cookie() -> Cookie<'static> {
+    let mut cookie = get_refresh_cookie("".to_string());
+    cookie.make_removal();
+    cookie
+}
+
+/// REFRESH_COOKIE_SAME_SITE is strict, lax or none. Defaults to lax
+fn get_refresh_cookie_same_site() -> SameSite {
+    match env::var("REFRESH_COOKIE_SAME_SITE").unwrap_or_default().to_lowercase().as_str() {
+        "strict" => SameSite::Strict,
+        "none" => SameSite::None,
+        _ => SameSite::Lax
+    }
 }
 
 #[cfg(test)]
@@ -129,21 +358,21 @@ mod tests {
     use actix_http::{StatusCode, body};
     use async_trait::async_trait;
     use fake::{faker::internet::en::{FreeEmail, Password}, Fake};
-    use jsonwebtoken::DecodingKey;
     use crate::{
         common::{
-            repository::administrator::{repo::AuthenticateDbFn, models::Administrator}, 
-            authentication::auth_service::{STANDARD_REFRESH_TOKEN_EXPIRATION, AuthenticationError}
+            repository::{administrator::{repo::AuthenticateDbFn, models::{Administrator, AdministratorTotp, AdminRole}}, base::EntityId}, 
+            authentication::{auth_service::{STANDARD_REFRESH_TOKEN_EXPIRATION, AuthenticationError, AuthKeys}, login_throttle::LOGIN_ATTEMPTS_PER_EMAIL}
         }, 
         common_test::fixtures::get_app_data
     };
+    use actix_web::test;
 
     const USERNAME: &str = "tester";
     struct MockDbRepo;
     struct MockAuthService;
     #[async_trait]
     impl Authenticator for MockAuthService {
-        async fn is_authenticated(&self, _: String, _: Vec<(&str, &str)>, _: &DecodingKey) -> Result<bool, AuthenticationError> {
+        async fn is_authenticated(&self, _: String, _: Vec<(&str, &str)>, _: &AuthKeys) -> Result<bool, AuthenticationError> {
             Ok(true)
         }
     }
@@ -171,33 +400,337 @@ mod tests {
                 updated_at: Utc::now(),
                 user_name: USERNAME.to_string(),
                 email: FreeEmail().fake::<String>(),
-                password: "123".to_string()
+                password: "123".to_string(),
+                role: AdminRole::Owner,
+                is_active: true
             }))
         }
     }
 
+    #[async_trait]
+    impl QueryAdministratorTotpFn for MockDbRepo {
+        async fn query_administrator_totp(&self, _id: i64) -> Result<Option<AdministratorTotp>, sqlx::Error> {
+            Ok(None)
+        }
+    }
+
+    #[async_trait]
+    impl InsertRefreshTokenFn for MockDbRepo {
+        async fn insert_refresh_token(&self, _: i64, _: String, _: String, _: chrono::DateTime<Utc>) -> Result<EntityId, sqlx::Error> {
+            Ok(EntityId { id: 1 })
+        }
+    }
+
+    #[async_trait]
+    impl RotateRefreshTokenFn for MockDbRepo {
+        async fn rotate_refresh_token(&self, _: String, _: String, _: chrono::DateTime<Utc>) -> Result<RotateRefreshTokenResult, sqlx::Error> {
+            Ok(RotateRefreshTokenResult::Rotated { admin_id: 1 })
+        }
+    }
+
+    #[async_trait]
+    impl RevokeRefreshTokenFn for MockDbRepo {
+        async fn revoke_refresh_token(&self, _: String) -> Result<(), sqlx::Error> {
+            Ok(())
+        }
+    }
+
+    struct MockReusedTokenDbRepo;
+
+    #[async_trait]
+    impl Repository for MockReusedTokenDbRepo {
+        async fn init() -> Self {
+            MockReusedTokenDbRepo
+        }
+    }
+
+    #[async_trait]
+    impl RotateRefreshTokenFn for MockReusedTokenDbRepo {
+        async fn rotate_refresh_token(&self, _: String, _: String, _: chrono::DateTime<Utc>) -> Result<RotateRefreshTokenResult, sqlx::Error> {
+            Ok(RotateRefreshTokenResult::ReuseDetected)
+        }
+    }
+
+    fn get_refresh_request(auth_keys: &AuthKeys) -> (HttpRequest, String) {
+        let access_token = get_token(1, USERNAME.to_string(), auth_keys, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+        let refresh_token = get_refresh_token(1, USERNAME.to_string(), Uuid::new_v4().to_string(), auth_keys);
+        let req = test::TestRequest::post()
+            .cookie(Cookie::new(REFRESH_TOKEN_LABEL, refresh_token))
+            .to_http_request();
+
+        (req, access_token)
+    }
+
+    #[tokio::test]
+    async fn test_refresh_access_token_rotates_refresh_cookie() {
+        let repo = MockDbRepo::init().await;
+        let auth_service = MockAuthService;
+        let app_data = get_app_data(repo, auth_service).await;
+        let (req, old_token) = get_refresh_request(&app_data.auth_keys);
+        let old_jti = decode_token(req.cookie(REFRESH_TOKEN_LABEL).unwrap().value(), &app_data.auth_keys).unwrap().jti;
+
+        let result = refresh_access_token(app_data.clone(), Json(RefreshToken { old_token }), req).await;
+        assert!(result.status() == StatusCode::OK);
+
+        let cookie = result.cookies().last().unwrap();
+        let claims = decode_token(cookie.value(), &app_data.auth_keys).unwrap();
+        assert!(claims.jti.is_some());
+        assert!(claims.jti != old_jti);
+        assert!(cookie.secure() == Some(true));
+    }
+
+    #[tokio::test]
+    async fn test_refresh_access_token_accepts_expired_access_token() {
+        let repo = MockDbRepo::init().await;
+        let auth_service = MockAuthService;
+        let app_data = get_app_data(repo, auth_service).await;
+        let (req, _) = get_refresh_request(&app_data.auth_keys);
+        let old_token = get_token(1, USERNAME.to_string(), &app_data.auth_keys, Some(-10));
+
+        let result = refresh_access_token(app_data.clone(), Json(RefreshToken { old_token }), req).await;
+        assert!(result.status() == StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_access_token_rejects_malformed_refresh_token() {
+        let repo = MockDbRepo::init().await;
+        let auth_service = MockAuthService;
+        let app_data = get_app_data(repo, auth_service).await;
+        let (_, old_token) = get_refresh_request(&app_data.auth_keys);
+        let req = test::TestRequest::post()
+            .cookie(Cookie::new(REFRESH_TOKEN_LABEL, "not-a-token"))
+            .to_http_request();
+
+        let result = refresh_access_token(app_data.clone(), Json(RefreshToken { old_token }), req).await;
+        assert!(result.status() == StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_access_token_rejects_reused_token() {
+        let repo = MockReusedTokenDbRepo::init().await;
+        let auth_service = MockAuthService;
+        let app_data = get_app_data(repo, auth_service).await;
+        let (req, old_token) = get_refresh_request(&app_data.auth_keys);
+
+        let result = refresh_access_token(app_data.clone(), Json(RefreshToken { old_token }), req).await;
+        assert!(result.status() == StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_logout_clears_refresh_cookie() {
+        let repo = MockDbRepo::init().await;
+        let auth_service = MockAuthService;
+        let app_data = get_app_data(repo, auth_service).await;
+        let (req, _) = get_refresh_request(&app_data.auth_keys);
+
+        let result = logout(app_data.clone(), req).await;
+        assert!(result.status() == StatusCode::NO_CONTENT);
+
+        let cookie = result.cookies().last().unwrap();
+        assert!(cookie.name() == REFRESH_TOKEN_LABEL);
+        assert!(cookie.value().is_empty());
+        assert!(cookie.max_age() == Some(ActixWebDuration::ZERO));
+    }
+
+    struct MockLockedDbRepo;
+
+    #[async_trait]
+    impl Repository for MockLockedDbRepo {
+        async fn init() -> Self {
+            MockLockedDbRepo
+        }
+    }
+
+    #[async_trait]
+    impl AuthenticateDbFn for MockLockedDbRepo {
+        async fn authenticate_db(&self, _: String, _: String) -> Result<AuthenticateResult, sqlx::Error> {
+            Ok(AuthenticateResult::Locked { until: Utc::now() + Duration::seconds(60) })
+        }
+    }
+
+    #[async_trait]
+    impl QueryAdministratorFn for MockLockedDbRepo {
+        async fn query_administrator(&self, _id: i64) -> Result<Option<Administrator>, sqlx::Error> {
+            Ok(None)
+        }
+    }
+
+    #[async_trait]
+    impl QueryAdministratorTotpFn for MockLockedDbRepo {
+        async fn query_administrator_totp(&self, _id: i64) -> Result<Option<AdministratorTotp>, sqlx::Error> {
+            Ok(None)
+        }
+    }
+
+    #[async_trait]
+    impl InsertRefreshTokenFn for MockLockedDbRepo {
+        async fn insert_refresh_token(&self, _: i64, _: String, _: String, _: chrono::DateTime<Utc>) -> Result<EntityId, sqlx::Error> {
+            Ok(EntityId { id: 1 })
+        }
+    }
+
+    const TOTP_CODE: &str = "123456";
+    struct MockTotpDbRepo;
+
+    #[async_trait]
+    impl Repository for MockTotpDbRepo {
+        async fn init() -> Self {
+            MockTotpDbRepo
+        }
+    }
+
+    #[async_trait]
+    impl AuthenticateDbFn for MockTotpDbRepo {
+        async fn authenticate_db(&self, _: String, _: String) -> Result<AuthenticateResult, sqlx::Error> {
+            Ok(AuthenticateResult::Success{ id: 1 })
+        }
+    }
+
+    #[async_trait]
+    impl QueryAdministratorFn for MockTotpDbRepo {
+        async fn query_administrator(&self, id: i64) -> Result<Option<Administrator>, sqlx::Error> {
+            MockDbRepo.query_administrator(id).await
+        }
+    }
+
+    #[async_trait]
+    impl QueryAdministratorTotpFn for MockTotpDbRepo {
+        async fn query_administrator_totp(&self, id: i64) -> Result<Option<AdministratorTotp>, sqlx::Error> {
+            Ok(Some(AdministratorTotp { id, totp_secret: Some(generate_totp_secret()), totp_enabled: true, totp_last_used_step: None }))
+        }
+    }
+
+    #[async_trait]
+    impl AuthenticateTotpFn for MockTotpDbRepo {
+        async fn authenticate_totp(&self, _: i64, code: String) -> Result<bool, sqlx::Error> {
+            Ok(code == TOTP_CODE)
+        }
+    }
+
+    #[async_trait]
+    impl InsertRefreshTokenFn for MockTotpDbRepo {
+        async fn insert_refresh_token(&self, _: i64, _: String, _: String, _: chrono::DateTime<Utc>) -> Result<EntityId, sqlx::Error> {
+            Ok(EntityId { id: 1 })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_login_returns_totp_challenge_when_enabled() {
+        let repo = MockTotpDbRepo::init().await;
+        let auth_service = MockAuthService;
+        let app_data = get_app_data(repo, auth_service).await;
+
+        let result = login(app_data.clone(), Json(LoginCredential { email: FreeEmail().fake::<String>(), password: Password(5..10).fake::<String>() }), test::TestRequest::post().to_http_request()).await;
+        assert!(result.status() == StatusCode::ACCEPTED);
+        assert!(result.cookies().next().is_none());
+
+        let bytes = body::to_bytes(result.into_body()).await.ok().unwrap();
+        let challenge: TotpChallengeResponse = serde_json::from_slice(&bytes).unwrap();
+        let claims = decode_token(&challenge.challenge_token, &app_data.auth_keys).unwrap();
+        assert!(claims.scope == Some(TOTP_CHALLENGE_SCOPE.to_string()));
+        assert!(!claims.is_access_token());
+    }
+
+    #[tokio::test]
+    async fn test_login_totp_exchanges_challenge_for_access_token() {
+        let repo = MockTotpDbRepo::init().await;
+        let auth_service = MockAuthService;
+        let app_data = get_app_data(repo, auth_service).await;
+        let challenge_token = get_totp_challenge_token(1, USERNAME.to_string(), &app_data.auth_keys);
+
+        let result = login_totp(app_data.clone(), Json(TotpLoginCredential { challenge_token: challenge_token.clone(), code: "000000".to_string() }), test::TestRequest::post().to_http_request()).await;
+        assert!(result.status() == StatusCode::UNAUTHORIZED);
+
+        let result = login_totp(app_data.clone(), Json(TotpLoginCredential { challenge_token, code: TOTP_CODE.to_string() }), test::TestRequest::post().to_http_request()).await;
+        assert!(result.status() == StatusCode::OK);
+        assert!(result.cookies().any(|cookie| cookie.name() == REFRESH_TOKEN_LABEL));
+
+        let bytes = body::to_bytes(result.into_body()).await.ok().unwrap();
+        let login_response: LoginResponse = serde_json::from_slice(&bytes).unwrap();
+        let claims = decode_token(&login_response.access_token, &app_data.auth_keys).unwrap();
+        assert!(claims.is_access_token());
+        assert!(claims.admin_id == 1);
+    }
+
+    #[tokio::test]
+    async fn test_login_totp_rejects_access_token_as_challenge() {
+        let repo = MockTotpDbRepo::init().await;
+        let auth_service = MockAuthService;
+        let app_data = get_app_data(repo, auth_service).await;
+        let access_token = get_token(1, USERNAME.to_string(), &app_data.auth_keys, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+
+        let result = login_totp(app_data.clone(), Json(TotpLoginCredential { challenge_token: access_token, code: TOTP_CODE.to_string() }), test::TestRequest::post().to_http_request()).await;
+        assert!(result.status() == StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_login_returns_locked_for_locked_account() {
+        let repo = MockLockedDbRepo::init().await;
+        let auth_service = MockAuthService;
+        let app_data = get_app_data(repo, auth_service).await;
+
+        let result = login(app_data, Json(LoginCredential { email: FreeEmail().fake::<String>(), password: Password(5..10).fake::<String>() }), test::TestRequest::post().to_http_request()).await;
+
+        assert!(result.status() == StatusCode::LOCKED);
+        assert!(result.headers().get(RETRY_AFTER).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_login_returns_too_many_requests_after_rate_limit() {
+        let repo = MockLockedDbRepo::init().await;
+        let auth_service = MockAuthService;
+        let app_data = get_app_data(repo, auth_service).await;
+        let email = FreeEmail().fake::<String>();
+
+        for _ in 0..LOGIN_ATTEMPTS_PER_EMAIL {
+            let result = login(app_data.clone(), Json(LoginCredential { email: email.clone(), password: Password(5..10).fake::<String>() }), test::TestRequest::post().to_http_request()).await;
+            assert!(result.status() == StatusCode::LOCKED);
+        }
+        let result = login(app_data.clone(), Json(LoginCredential { email, password: Password(5..10).fake::<String>() }), test::TestRequest::post().to_http_request()).await;
+
+        assert!(result.status() == StatusCode::TOO_MANY_REQUESTS);
+        assert!(result.headers().get(RETRY_AFTER).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_login_does_not_rate_limit_successful_logins() {
+        let repo = MockDbRepo::init().await;
+        let auth_service = MockAuthService;
+        let app_data = get_app_data(repo, auth_service).await;
+        let email = FreeEmail().fake::<String>();
+
+        for _ in 0..=LOGIN_ATTEMPTS_PER_EMAIL {
+            let result = login(app_data.clone(), Json(LoginCredential { email: email.clone(), password: Password(5..10).fake::<String>() }), test::TestRequest::post().to_http_request()).await;
+            assert!(result.status() == StatusCode::OK);
+        }
+    }
+
     #[tokio::test]
     async fn test_login_routes_httpresponse() {
         let repo = MockDbRepo::init().await;
         let auth_service = MockAuthService;
         let app_data = get_app_data(repo, auth_service).await;
 
-        let result = login(app_data.clone(), Json(LoginCredential { email: FreeEmail().fake::<String>(), password: Password(5..10).fake::<String>() })).await;
+        let result = login(app_data.clone(), Json(LoginCredential { email: FreeEmail().fake::<String>(), password: Password(5..10).fake::<String>() }), test::TestRequest::post().to_http_request()).await;
         assert!(result.status() == StatusCode::OK);
 
         let (res, mut body) = result.into_parts();
         let bytes = body::to_bytes(&mut body).await.ok().unwrap();
         let login_response_str = String::from_utf8_lossy(&bytes);
         let login_response: Result<LoginResponse, serde_json::Error> = serde_json::from_str(&login_response_str);
-        let token = decode_token(&login_response.unwrap().access_token, &app_data.auth_keys.decoding_key);
+        let token = decode_token(&login_response.unwrap().access_token, &app_data.auth_keys).unwrap();
         assert!(token.exp >= STANDARD_ACCESS_TOKEN_EXPIRATION as usize);
         assert!(token.sub == USERNAME.to_string());
+        assert!(token.admin_id == 1);
 
         let cookie = res.cookies().last().unwrap();
         let refresh_token = cookie.value();
-        let claims = decode_token(refresh_token, &app_data.auth_keys.decoding_key);
+        let claims = decode_token(refresh_token, &app_data.auth_keys).unwrap();
         
         assert!(claims.exp >= STANDARD_REFRESH_TOKEN_EXPIRATION as usize);
-        assert!(claims.sub == USERNAME.to_string());        
+        assert!(claims.sub == USERNAME.to_string());
+        assert!(claims.jti.is_some());
+        assert!(cookie.secure() == Some(true));
+        assert!(cookie.same_site() == Some(SameSite::Lax));
     }
 }
\ No newline at end of file
@@ -1,6 +1,11 @@
 use actix_http::body::BoxBody;
 use actix_web::{Responder, HttpResponse, http::header::ContentType};
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use crate::{common::utils::cursor_utils::{decode_cursor, encode_cursor}, routes::stripped_down_error::StrippedDownError};
+
+/// Every list endpoint caps its page size, however many rows are asked for
+pub const MAX_PAGE_SIZE: i32 = 50;
+pub const DEFAULT_PAGE_SIZE: i32 = 10;
 
 #[derive(Serialize)]
 pub struct OutputId {
@@ -24,15 +29,106 @@ impl Responder for OutputId {
     }
 }
 
-#[derive(Deserialize, Serialize)]
-pub struct PagingModel {
-    pub page_size: i32,
-    pub last_offset: i64
+/// Query string of list endpoints. No @cursor gets the first page, later pages pass the next_cursor of the one before
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct CursorPagingModel {
+    pub page_size: Option<i32>,
+    pub cursor: Option<String>
+}
+
+impl CursorPagingModel {
+    pub fn get_page_size(&self) -> i32 {
+        self.page_size.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+    }
+
+    /// One row more than the page is queried for, so the extra row tells whether there is another page
+    pub fn get_limit(&self) -> i32 {
+        self.get_page_size() + 1
+    }
+
+    pub fn get_cursor<C: DeserializeOwned>(&self) -> Result<Option<C>, StrippedDownError> {
+        match &self.cursor {
+            Some(token) => match decode_cursor::<C>(token) {
+                Some(cursor) => Ok(Some(cursor)),
+                None => Err(StrippedDownError::ValidationError { field: "cursor".to_string() })
+            },
+            None => Ok(None)
+        }
+    }
+}
+
+/// A page of a list, next_cursor gets the page after it and is None on the last page
+#[derive(Serialize, Debug)]
+pub struct PagedResponder<T: Serialize> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+    pub has_more: bool
 }
 
-#[derive(Deserialize, Serialize, Clone)]
-pub struct IdAndPagingModel {
-    pub id: i64,
-    pub page_size: i32,
-    pub last_offset: i64
-}
\ No newline at end of file
+impl<T: Serialize> PagedResponder<T> {
+    /// @rows were queried with CursorPagingModel::get_limit, @get_cursor gives the sort values of a row
+    pub fn new<R, C: Serialize>(mut rows: Vec<R>, paging: &CursorPagingModel, get_cursor: impl Fn(&R) -> C, convert: impl Fn(&R) -> T) -> Self {
+        let has_more = rows.len() > paging.get_page_size() as usize;
+        rows.truncate(paging.get_page_size() as usize);
+
+        PagedResponder {
+            next_cursor: if has_more { rows.last().map(|row| encode_cursor(&get_cursor(row))) } else { None },
+            items: rows.iter().map(convert).collect::<Vec<T>>(),
+            has_more
+        }
+    }
+}
+
+impl<T: Serialize> Responder for PagedResponder<T> {
+    type Body = BoxBody;
+
+    fn respond_to(self, _: &actix_web::HttpRequest) -> actix_web::HttpResponse<Self::Body> {
+        let json_result = serde_json::to_string(&self);
+
+        match json_result {
+            Ok(body) => HttpResponse::Ok()
+                .content_type(ContentType::json())
+                .body(body),
+            Err(_) => HttpResponse::InternalServerError()
+                .content_type(ContentType::json())
+                .body("Failed to serialize PagedResponder")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_paging(page_size: Option<i32>) -> CursorPagingModel {
+        CursorPagingModel { page_size, cursor: None }
+    }
+
+    #[test]
+    fn test_get_page_size_is_capped() {
+        assert!(get_paging(None).get_page_size() == DEFAULT_PAGE_SIZE);
+        assert!(get_paging(Some(1_000)).get_page_size() == MAX_PAGE_SIZE);
+        assert!(get_paging(Some(-5)).get_page_size() == 1);
+        assert!(get_paging(Some(1_000)).get_limit() == MAX_PAGE_SIZE + 1);
+    }
+
+    #[test]
+    fn test_get_cursor_rejects_invalid_cursor() {
+        let paging = CursorPagingModel { page_size: None, cursor: Some("not a cursor".to_string()) };
+
+        assert!(matches!(paging.get_cursor::<i64>(), Err(StrippedDownError::ValidationError { field }) if field == "cursor"));
+        assert!(get_paging(None).get_cursor::<i64>().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_new_paged_responder_drops_extra_row_and_points_at_last_item() {
+        let paging = get_paging(Some(2));
+
+        let page = PagedResponder::new(vec![5_i64, 4, 3], &paging, |row| *row, |row| row * 10);
+        let last_page = PagedResponder::new(vec![2_i64, 1], &paging, |row| *row, |row| row * 10);
+
+        assert!(page.items == vec![50, 40] && page.has_more);
+        assert!(decode_cursor::<i64>(page.next_cursor.as_deref().unwrap()) == Some(4));
+        assert!(last_page.items == vec![20, 10] && !last_page.has_more && last_page.next_cursor.is_none());
+    }
+}
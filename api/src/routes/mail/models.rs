@@ -1,17 +1,88 @@
 use actix_http::body::BoxBody;
 use actix_web::{Responder, HttpResponse, http::header::ContentType};
 use chrono::{DateTime, Utc};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-use crate::common::repository::mail::models::Mail;
+use crate::{common::repository::mail::models::Mail, routes::stripped_down_error::StrippedDownError};
+
+/// Limits match the mail table's column sizes
+pub const MAX_MAIL_FROM_LENGTH: usize = 150;
+pub const MAX_MAIL_SUBJECT_LENGTH: usize = 250;
+pub const MAX_MAIL_MESSAGE_LENGTH: usize = 10_000;
+
+/// Contact form submission, from is the sender's email address
+#[derive(Deserialize, Serialize, Clone)]
+pub struct NewMail {
+    pub from: String,
+    pub subject: String,
+    pub message: String,
+    /// Honeypot, a form field hidden from people, so only bots fill it in
+    #[serde(default)]
+    pub website: Option<String>
+}
+
+impl NewMail {
+    pub fn is_from_bot(&self) -> bool {
+        self.website.as_ref().map(|website| !website.trim().is_empty()).unwrap_or_default()
+    }
+
+    pub fn validate(&self) -> Result<(), StrippedDownError> {
+        let from = self.from.trim();
+        if from.is_empty() || from.chars().count() > MAX_MAIL_FROM_LENGTH || !is_valid_email(from) {
+            return Err(StrippedDownError::ValidationError { field: "from".to_string() });
+        }
+
+        let subject = self.subject.trim();
+        if subject.is_empty() || subject.chars().count() > MAX_MAIL_SUBJECT_LENGTH {
+            return Err(StrippedDownError::ValidationError { field: "subject".to_string() });
+        }
+
+        let message = self.message.trim();
+        if message.is_empty() || message.chars().count() > MAX_MAIL_MESSAGE_LENGTH {
+            return Err(StrippedDownError::ValidationError { field: "message".to_string() });
+        }
+
+        Ok(())
+    }
+}
+
+/// Intentionally loose, only rejects addresses that clearly cannot be replied to
+fn is_valid_email(email: &str) -> bool {
+    if email.chars().any(|ch| ch.is_whitespace()) {
+        return false;
+    }
+
+    match email.split_once('@') {
+        Some((local, domain)) => !local.is_empty()
+            && !domain.contains('@')
+            && domain.contains('.')
+            && !domain.starts_with('.')
+            && !domain.ends_with('.'),
+        None => false
+    }
+}
+
+#[derive(Deserialize)]
+pub struct UpdateMailRead {
+    pub mail_id: i64,
+    pub is_read: bool
+}
+
+#[derive(Deserialize)]
+pub struct DeleteMail {
+    pub mail_id: i64
+}
 
 #[derive(Serialize, Debug, Clone)]
 pub struct MailResponder {
     pub id: i64,
+    pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub from: String,
     pub subject: String,
-    pub message: String
+    pub message: String,
+    pub is_read: bool,
+    pub read_at: Option<DateTime<Utc>>
 }
 
 impl Responder for MailResponder {
@@ -31,32 +102,15 @@ impl Responder for MailResponder {
     }
 }
 
-#[derive(Serialize, Debug)]
-pub struct MailResponders(pub Vec<MailResponder>);
-
-impl Responder for MailResponders {
-    type Body = BoxBody;
-
-    fn respond_to(self, _: &actix_web::HttpRequest) -> actix_web::HttpResponse<Self::Body> {
-        let json_result = serde_json::to_string(&self);
-
-        match json_result {
-            Ok(body) => HttpResponse::Ok()
-                .content_type(ContentType::json())
-                .body(body),
-            Err(_) => HttpResponse::InternalServerError()
-                .content_type(ContentType::json())
-                .body("Failed to serialize MailResponders")
-        }
-    }
-}
-
 pub fn convert(mail: &Mail) -> MailResponder {
     MailResponder {
         id: mail.id,
+        created_at: mail.created_at,
         updated_at: mail.updated_at,
         from: mail.from.to_string(),
         subject: mail.subject.to_string(),
-        message: mail.message.to_string(),        
+        message: mail.message.to_string(),
+        is_read: mail.is_read,
+        read_at: mail.read_at
     }
 }
\ No newline at end of file
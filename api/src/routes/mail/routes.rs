@@ -1,21 +1,52 @@
-use actix_web::web::{Data, Path};
-use crate::{common::{authentication::auth_service::Authenticator, repository::{base::Repository, mail::repo::{QueryLatestMailFn, QueryMailFn}}}, routes::{app_state::AppState, base_model::PagingModel, stripped_down_error::StrippedDownError}};
-use super::models::{convert, MailResponder, MailResponders};
+use actix_web::{web::{Data, Json, Path, Query}, HttpRequest, HttpResponse};
+use log::{error, info};
+use crate::{
+    common::{authentication::auth_service::Authenticator, repository::{administrator::repo::QueryAdministratorFn, base::Repository, mail::{models::Mail, repo::{DeleteMailFn, InsertMailFn, QueryLatestMailFn, QueryMailFn, UpdateMailReadFn}}}}, 
+    routes::{app_state::AppState, auth_helper::AuthenticatedAdmin, base_model::{CursorPagingModel, OutputId, PagedResponder}, stripped_down_error::StrippedDownError}
+};
+use super::models::{convert, DeleteMail, MailResponder, NewMail, UpdateMailRead};
 
+/// Public contact form submission, does not require authentication so it is rate limited per ip and sender.
+/// Submissions from bots are accepted without being stored, so they do not learn they were caught
+pub async fn create_mail<T: InsertMailFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, new_mail: Json<NewMail>, req: HttpRequest) -> Result<OutputId, StrippedDownError> {
+    let ip = app_data.contact_mail_rate_limiter.get_client_ip(&req);
+    if let Err(retry_after) = app_data.contact_mail_rate_limiter.check_and_record(&ip, &new_mail.from) {
+        error!("Contact mail rate limit exceeded for ip {}", ip);
+        return Err(StrippedDownError::TooManyRequests { retry_after });
+    }
+    if new_mail.is_from_bot() {
+        info!("Contact mail honeypot filled in from ip {}", ip);
+        return Ok(OutputId { id: 0 });
+    }
+
+    if let Err(e) = new_mail.validate() {
+        error!("create_mail error: {}", e);
+        return Err(e);
+    }
 
-pub async fn get_latest_mail<T: QueryLatestMailFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, path: Path<PagingModel>) -> Result<MailResponders, StrippedDownError> {
-    let result = app_data.repo.query_latest_mail(path.page_size, path.last_offset).await;
+    let entity_result = app_data.repo.insert_mail(
+        new_mail.from.trim().to_string(), 
+        new_mail.subject.trim().to_string(), 
+        new_mail.message.trim().to_string()
+    ).await;
+
+    match entity_result {
+        Ok(entity) => Ok(OutputId { id: entity.id }),
+        Err(e) => Err(e.into())
+    }
+}
+
+pub async fn get_latest_mail<T: QueryLatestMailFn + QueryAdministratorFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, paging: Query<CursorPagingModel>, _admin: AuthenticatedAdmin<T, U>) 
+    -> Result<PagedResponder<MailResponder>, StrippedDownError> {
+    let result = app_data.repo.query_latest_mail(paging.get_limit(), paging.get_cursor()?).await;
 
     match result {
-        Ok(mail) => {
-            let post_responders = mail.iter().map(|post| convert(post)).collect::<Vec<MailResponder>>();
-            Ok(MailResponders(post_responders))
-        },
+        Ok(mail) => Ok(PagedResponder::new(mail, &paging, Mail::cursor, convert)),
         Err(e) => Err(e.into())
     }
 }
 
-pub async fn get_mail<T: QueryMailFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, path: Path<i64>) -> Result<Option<MailResponder>, StrippedDownError> {
+pub async fn get_mail<T: QueryMailFn + QueryAdministratorFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, path: Path<i64>, _admin: AuthenticatedAdmin<T, U>) -> Result<Option<MailResponder>, StrippedDownError> {
     let post_result = app_data.repo.query_mail(path.into_inner()).await;
 
     match post_result {
@@ -29,24 +60,48 @@ pub async fn get_mail<T: QueryMailFn + Repository, U: Authenticator>(app_data: D
     }
 }
 
+pub async fn update_mail_read<T: UpdateMailReadFn + QueryAdministratorFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, json: Json<UpdateMailRead>, _admin: AuthenticatedAdmin<T, U>) -> Result<HttpResponse, StrippedDownError> {
+    let result = app_data.repo.update_mail_read(json.mail_id, json.is_read).await;
+
+    match result {
+        Ok(_) => Ok(HttpResponse::NoContent().into()),
+        Err(e) => Err(e.into())
+    }
+}
+
+pub async fn delete_mail<T: DeleteMailFn + QueryAdministratorFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, json: Json<DeleteMail>, _admin: AuthenticatedAdmin<T, U>) -> Result<HttpResponse, StrippedDownError> {
+    let result = app_data.repo.delete_mail(json.mail_id).await;
+
+    match result {
+        Ok(_) => Ok(HttpResponse::NoContent().into()),
+        Err(e) => Err(e.into())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use async_trait::async_trait;
+    use actix_web::{test::{self, TestRequest}, web, App, FromRequest, ResponseError};
     use chrono::Utc;
     use sqlx::Error;
-    use crate::common::repository::mail::models::Mail;
-    use jsonwebtoken::DecodingKey;
-    use crate::{common::authentication::auth_service::{AuthService, AuthenticationError}, common_test::fixtures::get_app_data};
+    use crate::common::repository::{administrator::models::{Administrator, AdminRole}, base::{EntityId, PageCursor}};
+    use crate::{
+        common::authentication::auth_service::{AuthService, AuthenticationError, AuthKeys, STANDARD_ACCESS_TOKEN_EXPIRATION}, 
+        common_test::fixtures::{get_app_data, get_fake_httprequest_with_bearer_token}
+    };
+    use crate::common::authentication::login_throttle::CONTACT_MAIL_PER_SENDER;
+    use crate::routes::mail::models::MAX_MAIL_MESSAGE_LENGTH;
     use super::*;
 
     const FROM: &str = "dave@test.com";
     const SUBJECT: &str = "subject";
     const MESSAGE: &str = "Hello World! How are you?";
+    const MOCK_ENTITY_ID: i64 = 10;
     struct MockDbRepo;
     struct MockAuthService;
     #[async_trait]
     impl Authenticator for MockAuthService {
-        async fn is_authenticated(&self, _: String, _: Vec<(&str, &str)>, _: &DecodingKey) -> Result<bool, AuthenticationError> {
+        async fn is_authenticated(&self, _: String, _: Vec<(&str, &str)>, _: &AuthKeys) -> Result<bool, AuthenticationError> {
             Ok(true)
         }
     }
@@ -58,9 +113,46 @@ mod tests {
         }
     }
 
+    #[async_trait]
+    impl QueryAdministratorFn for MockDbRepo {
+        async fn query_administrator(&self, _id: i64) -> Result<Option<Administrator>, Error> {
+            Ok(Some(Administrator {
+                id: 1,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                user_name: "dave".to_string(),
+                email: "test@test.com".to_string(),
+                password: "123".to_string(),
+                role: AdminRole::Owner,
+                is_active: true
+            }))
+        }
+    }
+
+    #[async_trait]
+    impl InsertMailFn for MockDbRepo {
+        async fn insert_mail(&self, _from: String, _subject: String, _message: String) -> Result<EntityId, Error> {
+            Ok(EntityId { id: MOCK_ENTITY_ID })
+        }
+    }
+
+    #[async_trait]
+    impl UpdateMailReadFn for MockDbRepo {
+        async fn update_mail_read(&self, _mail_id: i64, _is_read: bool) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl DeleteMailFn for MockDbRepo {
+        async fn delete_mail(&self, _mail_id: i64) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
     #[async_trait]
     impl QueryLatestMailFn for MockDbRepo {
-        async fn query_latest_mail(&self, _page_size: i32, _last_offset: i64) -> Result<Vec<Mail>, Error> {
+        async fn query_latest_mail(&self, _limit: i32, _cursor: Option<PageCursor>) -> Result<Vec<Mail>, Error> {
             Ok(vec![
                 Mail {
                     id: 1,
@@ -68,7 +160,9 @@ mod tests {
                     updated_at: Utc::now(),
                     from: FROM.to_string(),
                     subject: SUBJECT.to_string(),
-                    message: MESSAGE.to_string()
+                    message: MESSAGE.to_string(),
+                    is_read: false,
+                    read_at: None
                 }
             ])
         }
@@ -84,7 +178,9 @@ mod tests {
                 updated_at: Utc::now(),
                 from: FROM.to_string(),
                 subject: SUBJECT.to_string(),
-                message: MESSAGE.to_string()
+                message: MESSAGE.to_string(),
+                is_read: false,
+                read_at: None
             }))
         }
     }
@@ -95,15 +191,15 @@ mod tests {
         let auth_service = AuthService;
         let app_data = get_app_data(repo, auth_service).await;        
 
-        let mail = get_latest_mail(app_data, Path::from(PagingModel {
-            page_size: 10,
-            last_offset: 0
-        })).await;
+        let req = get_fake_httprequest_with_bearer_token(1, "dave".to_string(), &app_data.auth_keys, "/v1/mail", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+        let admin = AuthenticatedAdmin::authenticate(app_data.clone(), req).await.unwrap();
+
+        let mail = get_latest_mail(app_data, Query(CursorPagingModel { page_size: Some(10), cursor: None }), admin).await;
 
         assert!(mail.as_ref().is_ok());
-        assert!(mail.as_ref().ok().unwrap().0.get(0).unwrap().from == FROM.to_string());
-        assert!(mail.as_ref().ok().unwrap().0.get(0).unwrap().subject == SUBJECT.to_string());
-        assert!(mail.as_ref().ok().unwrap().0.get(0).unwrap().message == MESSAGE.to_string());
+        assert!(mail.as_ref().ok().unwrap().items.get(0).unwrap().from == FROM.to_string());
+        assert!(mail.as_ref().ok().unwrap().items.get(0).unwrap().subject == SUBJECT.to_string());
+        assert!(mail.as_ref().ok().unwrap().items.get(0).unwrap().message == MESSAGE.to_string());
     }
 
     #[tokio::test]
@@ -112,7 +208,10 @@ mod tests {
         let auth_service = AuthService;
         let app_data = get_app_data(repo, auth_service).await;        
 
-        let mail_resp = get_mail(app_data, Path::from(1)).await;
+        let req = get_fake_httprequest_with_bearer_token(1, "dave".to_string(), &app_data.auth_keys, "/v1/mail/1", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+        let admin = AuthenticatedAdmin::authenticate(app_data.clone(), req).await.unwrap();
+
+        let mail_resp = get_mail(app_data, Path::from(1), admin).await;
 
         assert!(mail_resp.is_ok());
         match mail_resp {
@@ -127,4 +226,139 @@ mod tests {
             _ => panic!("Failed")
         }
     }
+
+    #[tokio::test]
+    async fn test_get_latest_mail_fails_without_bearer_token() {
+        let repo = MockDbRepo::init().await;
+        let auth_service = AuthService;
+        let app_data = get_app_data(repo, auth_service).await;
+        let req = TestRequest::get().uri("/v1/mail").app_data(app_data).to_http_request();
+
+        let admin = AuthenticatedAdmin::<MockDbRepo, AuthService>::extract(&req).await;
+
+        assert!(admin.err().unwrap() == StrippedDownError::AuthenticationFailed);
+    }
+
+    #[tokio::test]
+    async fn test_create_mail_returns_id_successfully() {
+        let repo = MockDbRepo::init().await;
+        let auth_service = AuthService;
+        let app_data = get_app_data(repo, auth_service).await;
+
+        let output_id = create_mail(app_data, Json(NewMail {
+            from: FROM.to_string(),
+            subject: SUBJECT.to_string(),
+            message: MESSAGE.to_string(),
+            website: None
+        }), TestRequest::post().to_http_request()).await;
+
+        assert!(output_id.unwrap().id == MOCK_ENTITY_ID);
+    }
+
+    #[tokio::test]
+    async fn test_create_mail_fails_validation_on_bad_fields() {
+        let repo = MockDbRepo::init().await;
+        let auth_service = AuthService;
+        let app_data = get_app_data(repo, auth_service).await;
+
+        let bad_from = create_mail(app_data.clone(), Json(NewMail {
+            from: "dave.test.com".to_string(),
+            subject: SUBJECT.to_string(),
+            message: MESSAGE.to_string(),
+            website: None
+        }), TestRequest::post().to_http_request()).await;
+        assert!(bad_from.err().unwrap() == StrippedDownError::ValidationError { field: "from".to_string() });
+
+        let empty_subject = create_mail(app_data.clone(), Json(NewMail {
+            from: FROM.to_string(),
+            subject: "  ".to_string(),
+            message: MESSAGE.to_string(),
+            website: None
+        }), TestRequest::post().to_http_request()).await;
+        assert!(empty_subject.err().unwrap() == StrippedDownError::ValidationError { field: "subject".to_string() });
+
+        let long_message = create_mail(app_data, Json(NewMail {
+            from: FROM.to_string(),
+            subject: SUBJECT.to_string(),
+            message: "a".repeat(MAX_MAIL_MESSAGE_LENGTH + 1),
+            website: None
+        }), TestRequest::post().to_http_request()).await;
+        assert!(long_message.err().unwrap() == StrippedDownError::ValidationError { field: "message".to_string() });
+    }
+
+    #[tokio::test]
+    async fn test_create_mail_accepts_but_does_not_store_honeypot_submissions() {
+        let repo = MockDbRepo::init().await;
+        let auth_service = AuthService;
+        let app_data = get_app_data(repo, auth_service).await;
+
+        let output_id = create_mail(app_data, Json(NewMail {
+            from: FROM.to_string(),
+            subject: SUBJECT.to_string(),
+            message: MESSAGE.to_string(),
+            website: Some("http://spam.test.com".to_string())
+        }), TestRequest::post().to_http_request()).await;
+
+        assert!(output_id.unwrap().id == 0);
+    }
+
+    #[tokio::test]
+    async fn test_create_mail_is_rate_limited_per_sender() {
+        let repo = MockDbRepo::init().await;
+        let auth_service = AuthService;
+        let app_data = get_app_data(repo, auth_service).await;
+
+        for _ in 0..CONTACT_MAIL_PER_SENDER {
+            let output_id = create_mail(app_data.clone(), Json(NewMail {
+                from: FROM.to_string(),
+                subject: SUBJECT.to_string(),
+                message: MESSAGE.to_string(),
+                website: None
+            }), TestRequest::post().to_http_request()).await;
+            assert!(output_id.is_ok());
+        }
+
+        let limited = create_mail(app_data, Json(NewMail {
+            from: FROM.to_string(),
+            subject: SUBJECT.to_string(),
+            message: MESSAGE.to_string(),
+            website: None
+        }), TestRequest::post().to_http_request()).await;
+
+        let err = limited.err().unwrap();
+        assert!(matches!(err, StrippedDownError::TooManyRequests { .. }));
+        let resp = err.error_response();
+        assert!(resp.status() == actix_http::StatusCode::TOO_MANY_REQUESTS);
+        assert!(resp.headers().contains_key(actix_web::http::header::RETRY_AFTER));
+    }
+
+    #[tokio::test]
+    async fn test_update_mail_read_returns_no_content() {
+        let repo = MockDbRepo::init().await;
+        let auth_service = AuthService;
+        let app_data = get_app_data(repo, auth_service).await;
+        let req = get_fake_httprequest_with_bearer_token(1, "dave".to_string(), &app_data.auth_keys, "/v1/mail_read", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+        let admin = AuthenticatedAdmin::authenticate(app_data.clone(), req).await.unwrap();
+
+        let resp = update_mail_read(app_data, Json(UpdateMailRead { mail_id: 1, is_read: true }), admin).await;
+
+        assert!(resp.unwrap().status() == actix_http::StatusCode::NO_CONTENT);
+    }
+
+    #[tokio::test]
+    async fn test_delete_mail_fails_without_bearer_token() {
+        let repo = MockDbRepo::init().await;
+        let auth_service = AuthService;
+        let app_data = get_app_data(repo, auth_service).await;
+        let app = test::init_service(
+            App::new()
+                .app_data(app_data)
+                .route("/v1/delete_mail", web::post().to(delete_mail::<MockDbRepo, AuthService>))
+        ).await;
+        let req = TestRequest::post().uri("/v1/delete_mail").set_json(serde_json::json!({ "mail_id": 1 })).to_request();
+
+        let resp = test::call_service(&app, req).await;
+
+        assert!(resp.status() == actix_http::StatusCode::UNAUTHORIZED);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize)]
+pub struct PasswordResetRequest {
+    pub email: String
+}
+
+/// @token is the value from the reset email, not its hash
+#[derive(Deserialize, Serialize)]
+pub struct PasswordResetConfirm {
+    pub token: String,
+    pub new_password: String
+}
@@ -0,0 +1,272 @@
+use std::env;
+use actix_web::{
+    web::{Data, Json},
+    HttpRequest, HttpResponse,
+    http::header::{ContentType, RETRY_AFTER}
+};
+use chrono::{Duration, Utc};
+use log::{error, info};
+use crate::{
+    routes::{administrator::routes::check_password, app_state::AppState, stripped_down_error::StrippedDownError},
+    common::{
+        repository::{
+            base::Repository,
+            administrator::repo::QueryAdministratorByEmailFn,
+            password_reset::{repo::{InsertPasswordResetTokenFn, ResetPasswordFn}, models::ResetPasswordResult}
+        },
+        authentication::{
+            auth_service::Authenticator,
+            password_reset::{generate_password_reset_token, hash_password_reset_token, PASSWORD_RESET_TOKEN_EXPIRATION}
+        },
+        mailer::mail_service::OutgoingMail
+    }
+};
+use super::models::{PasswordResetConfirm, PasswordResetRequest};
+
+const PASSWORD_RESET_SUBJECT: &str = "Reset your RustyIndie password";
+
+/// Always accepted right away, the token is made and mailed in the background,
+/// so neither the response nor the time it takes reveals whether @email belongs to an administrator
+pub async fn request_password_reset<T: QueryAdministratorByEmailFn + InsertPasswordResetTokenFn + Repository + 'static, U: Authenticator + 'static>(app_data: Data<AppState<T, U>>, json: Json<PasswordResetRequest>, req: HttpRequest)
+    -> HttpResponse {
+    let ip = app_data.password_reset_rate_limiter.get_client_ip(&req);
+    if let Err(retry_after) = app_data.password_reset_rate_limiter.check_and_record(&ip, &json.email) {
+        error!("Password reset rate limit exceeded for ip {}", ip);
+        return HttpResponse::TooManyRequests()
+            .insert_header((RETRY_AFTER, retry_after.to_string()))
+            .content_type(ContentType::json())
+            .body("Too many password reset requests, please try again later");
+    }
+
+    let email = json.email.trim().to_string();
+    actix_web::rt::spawn(async move {
+        send_password_reset(app_data, email).await;
+    });
+    HttpResponse::Accepted().finish()
+}
+
+/// Failures are only logged, reporting them to the caller would also reveal the email exists
+async fn send_password_reset<T: QueryAdministratorByEmailFn + InsertPasswordResetTokenFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, email: String) {
+    let admin = match app_data.repo.query_administrator_by_email(email).await {
+        Ok(Some(admin)) if admin.is_active => admin,
+        Ok(_) => {
+            info!("Password reset requested for unknown or deactivated email");
+            return;
+        },
+        Err(e) => {
+            error!("request_password_reset failed: {}", e);
+            return;
+        }
+    };
+
+    let (token, token_hash) = generate_password_reset_token();
+    let expires_at = Utc::now() + Duration::seconds(PASSWORD_RESET_TOKEN_EXPIRATION);
+    if let Err(e) = app_data.repo.insert_password_reset_token(admin.id, token_hash, expires_at).await {
+        error!("request_password_reset failed to store token: {}", e);
+        return;
+    }
+
+    if let Err(e) = app_data.mailer.send(get_password_reset_mail(admin.email, &token)).await {
+        error!("request_password_reset failed to send mail: {}", e);
+    }
+}
+
+pub async fn confirm_password_reset<T: ResetPasswordFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, json: Json<PasswordResetConfirm>)
+    -> Result<HttpResponse, StrippedDownError> {
+    check_password(&json.new_password)?;
+
+    let result = app_data.repo.reset_password(hash_password_reset_token(&json.token), json.new_password.clone()).await;
+
+    match result {
+        Ok(ResetPasswordResult::Reset { admin_id }) => {
+            info!("Password reset for administrator {}", admin_id);
+            Ok(HttpResponse::NoContent().into())
+        },
+        Ok(result) => {
+            info!("Password reset token rejected: {:?}", result);
+            Err(StrippedDownError::ValidationError { field: "token".to_string() })
+        },
+        Err(e) => Err(e.into())
+    }
+}
+
+/// PASSWORD_RESET_URL is the admin page that takes the token, e.g. https://admin.example.com/reset_password
+fn get_password_reset_mail(to: String, token: &str) -> OutgoingMail {
+    let reset_instruction = match env::var("PASSWORD_RESET_URL") {
+        Ok(url) => format!("Open this link to choose a new password:\n{}?token={}", url, token),
+        Err(_) => format!("Use this token to choose a new password:\n{}", token)
+    };
+
+    OutgoingMail {
+        to,
+        subject: PASSWORD_RESET_SUBJECT.to_string(),
+        body: format!(
+            "A password reset was requested for your RustyIndie administrator account.\n\n{}\n\nThis expires in {} minutes. If you did not request it you can ignore this email.",
+            reset_instruction,
+            PASSWORD_RESET_TOKEN_EXPIRATION / 60
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use actix_http::StatusCode;
+    use actix_web::test::TestRequest;
+    use async_trait::async_trait;
+    use chrono::DateTime;
+    use sqlx::Error;
+    use crate::{
+        common::{
+            authentication::{auth_service::AuthService, login_throttle::{LOGIN_ATTEMPTS_PER_IP, PASSWORD_RESET_REQUESTS_PER_IP}},
+            mailer::mail_service::{Mailer, MailerError},
+            repository::{administrator::models::{AdminRole, Administrator}, base::EntityId}
+        },
+        common_test::fixtures::get_app_data_with_mailer
+    };
+    use super::*;
+
+    const ADMIN_EMAIL: &str = "dave@test.com";
+
+    #[derive(Default)]
+    struct MockDbRepo {
+        token_hash: Mutex<Option<String>>
+    }
+
+    #[async_trait]
+    impl Repository for MockDbRepo {
+        async fn init() -> Self {
+            MockDbRepo::default()
+        }
+    }
+
+    #[async_trait]
+    impl QueryAdministratorByEmailFn for MockDbRepo {
+        async fn query_administrator_by_email(&self, email: String) -> Result<Option<Administrator>, Error> {
+            if email != ADMIN_EMAIL {
+                return Ok(None);
+            }
+            Ok(Some(Administrator {
+                id: 1,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                user_name: "dave".to_string(),
+                email,
+                password: "123".to_string(),
+                role: AdminRole::Owner,
+                is_active: true
+            }))
+        }
+    }
+
+    #[async_trait]
+    impl InsertPasswordResetTokenFn for MockDbRepo {
+        async fn insert_password_reset_token(&self, _admin_id: i64, token_hash: String, _expires_at: DateTime<Utc>) -> Result<EntityId, Error> {
+            *self.token_hash.lock().unwrap() = Some(token_hash);
+            Ok(EntityId { id: 1 })
+        }
+    }
+
+    #[async_trait]
+    impl ResetPasswordFn for MockDbRepo {
+        async fn reset_password(&self, token_hash: String, _password: String) -> Result<ResetPasswordResult, Error> {
+            let mut stored = self.token_hash.lock().unwrap();
+            if stored.as_ref() == Some(&token_hash) {
+                *stored = None;
+                return Ok(ResetPasswordResult::Reset { admin_id: 1 });
+            }
+            Ok(ResetPasswordResult::NotFound)
+        }
+    }
+
+    #[derive(Default, Clone)]
+    struct MockMailer {
+        sent: Arc<Mutex<Vec<OutgoingMail>>>
+    }
+
+    #[async_trait]
+    impl Mailer for MockMailer {
+        async fn send(&self, mail: OutgoingMail) -> Result<(), MailerError> {
+            self.sent.lock().unwrap().push(mail);
+            Ok(())
+        }
+    }
+
+    /// Gives the background task that sends the mail a chance to finish
+    async fn wait_for_password_reset() {
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    fn get_token_from_mail(mail: &OutgoingMail) -> String {
+        mail.body
+            .lines()
+            .find(|line| line.len() == 64 && line.chars().all(|c| c.is_ascii_hexdigit()))
+            .unwrap()
+            .to_string()
+    }
+
+    #[actix_web::test]
+    async fn test_request_password_reset_mails_token_for_known_email() {
+        let mailer = MockMailer::default();
+        let app_data = get_app_data_with_mailer(MockDbRepo::default(), AuthService, mailer.clone()).await;
+
+        let result = request_password_reset(app_data.clone(), Json(PasswordResetRequest { email: ADMIN_EMAIL.to_string() }), TestRequest::post().to_http_request()).await;
+        wait_for_password_reset().await;
+
+        assert!(result.status() == StatusCode::ACCEPTED);
+        let sent = mailer.sent.lock().unwrap();
+        assert!(sent.len() == 1);
+        assert!(sent[0].to == ADMIN_EMAIL);
+        let token = get_token_from_mail(&sent[0]);
+        assert!(*app_data.repo.token_hash.lock().unwrap() == Some(hash_password_reset_token(&token)));
+    }
+
+    #[actix_web::test]
+    async fn test_request_password_reset_accepts_unknown_email_without_mail() {
+        let mailer = MockMailer::default();
+        let app_data = get_app_data_with_mailer(MockDbRepo::default(), AuthService, mailer.clone()).await;
+
+        let result = request_password_reset(app_data, Json(PasswordResetRequest { email: "unknown@test.com".to_string() }), TestRequest::post().to_http_request()).await;
+        wait_for_password_reset().await;
+
+        assert!(result.status() == StatusCode::ACCEPTED);
+        assert!(mailer.sent.lock().unwrap().is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_request_password_reset_does_not_use_up_logins() {
+        let app_data = get_app_data_with_mailer(MockDbRepo::default(), AuthService, MockMailer::default()).await;
+        let req = TestRequest::post().peer_addr("1.1.1.1:5000".parse().unwrap()).to_http_request();
+        let ip = app_data.login_rate_limiter.get_client_ip(&req);
+
+        let mut results = vec![];
+        for i in 0..LOGIN_ATTEMPTS_PER_IP {
+            results.push(request_password_reset(app_data.clone(), Json(PasswordResetRequest { email: format!("{}{}", i, ADMIN_EMAIL) }), req.clone()).await);
+        }
+        wait_for_password_reset().await;
+
+        assert!(results.iter().filter(|result| result.status() == StatusCode::ACCEPTED).count() == PASSWORD_RESET_REQUESTS_PER_IP);
+        assert!(results.last().unwrap().status() == StatusCode::TOO_MANY_REQUESTS);
+        assert!(app_data.login_rate_limiter.check(&ip, ADMIN_EMAIL).is_ok());
+    }
+
+    #[actix_web::test]
+    async fn test_confirm_password_reset_accepts_mailed_token_once() {
+        let mailer = MockMailer::default();
+        let app_data = get_app_data_with_mailer(MockDbRepo::default(), AuthService, mailer.clone()).await;
+        _ = request_password_reset(app_data.clone(), Json(PasswordResetRequest { email: ADMIN_EMAIL.to_string() }), TestRequest::post().to_http_request()).await;
+        wait_for_password_reset().await;
+        let token = get_token_from_mail(&mailer.sent.lock().unwrap()[0]);
+        let get_input = |new_password: &str| Json(PasswordResetConfirm { token: token.clone(), new_password: new_password.to_string() });
+
+        let short = confirm_password_reset(app_data.clone(), get_input("short")).await;
+        let first = confirm_password_reset(app_data.clone(), get_input("long-enough-password")).await;
+        let second = confirm_password_reset(app_data.clone(), get_input("long-enough-password")).await;
+
+        assert!(short.err().unwrap() == StrippedDownError::ValidationError { field: "password".to_string() });
+        assert!(first.unwrap().status() == StatusCode::NO_CONTENT);
+        assert!(second.err().unwrap() == StrippedDownError::ValidationError { field: "token".to_string() });
+    }
+}
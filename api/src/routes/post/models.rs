@@ -3,27 +3,77 @@ use actix_web::{Responder, HttpResponse, http::header::ContentType};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::common::repository::post::models::Post;
+use crate::{
+    common::{repository::post::models::{Post, PostHeading, PostPublication, PostStatus}, utils::markdown_utils::{get_post_summary, render_post, PostSummary, RenderedPost}},
+    routes::{stripped_down_error::StrippedDownError, tag::models::{convert as convert_tag, TagResponder}}
+};
 
+/// The acting administrator is taken from the bearer token, not the body
 #[derive(Deserialize)]
 pub struct DeletePost {
-    pub post_id: i64,
-    pub admin_id: i64
+    pub post_id: i64
 }
 
 #[derive(Deserialize, Clone)]
 pub struct UpdatePost {
     pub post_id: i64,
-    pub admin_id: i64,
     pub title: String,
-    pub message: String
+    pub message: String,
+    /// Shown in previews instead of the start of message, when given
+    pub excerpt: Option<String>,
+    /// Made into a slug, the current one is kept when None. A changed slug redirects from the old one
+    pub slug: Option<String>,
+    /// Tag names, the current tags are kept when None
+    pub tags: Option<Vec<String>>,
+    pub status: PostStatus,
+    /// Required when scheduling, defaults to now, or when the post was first published, when publishing
+    pub published_at: Option<DateTime<Utc>>
 }
 
+/// The post's admin_id is the authenticated administrator
 #[derive(Deserialize)]
 pub struct NewPost {
     pub title: String,
     pub message: String,
-    pub admin_id: i64
+    /// Shown in previews instead of the start of message, when given
+    pub excerpt: Option<String>,
+    /// Made into a slug, made from the title when None
+    pub slug: Option<String>,
+    /// Tag names, a tag is added for a name that has none yet
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub status: PostStatus,
+    /// Required when scheduling, defaults to now when publishing
+    pub published_at: Option<DateTime<Utc>>
+}
+
+/// Only posts with @status are listed when it is given
+#[derive(Deserialize)]
+pub struct PostStatusFilter {
+    pub status: Option<PostStatus>
+}
+
+/// Works out when a post saved with @status is public from. @current_published_at is the saved post's, so
+/// republishing or archiving a post keeps its original date. Scheduled posts need a time in the future,
+/// and published ones cannot be dated in the future, that is what scheduling is for
+pub fn get_publication(status: PostStatus, published_at: Option<DateTime<Utc>>, current_published_at: Option<DateTime<Utc>>) -> Result<PostPublication, StrippedDownError> {
+    let now = Utc::now();
+    let published_at = match status {
+        PostStatus::Draft => None,
+        PostStatus::Scheduled => match published_at {
+            Some(published_at) if published_at > now => Some(published_at),
+            _ => return Err(StrippedDownError::ValidationError { field: "published_at".to_string() })
+        },
+        PostStatus::Published => match published_at {
+            Some(published_at) if published_at > now => return Err(StrippedDownError::ValidationError { field: "published_at".to_string() }),
+            Some(published_at) => Some(published_at),
+            // a scheduled post published early goes out now
+            None => Some(current_published_at.filter(|current_published_at| *current_published_at <= now).unwrap_or(now))
+        },
+        PostStatus::Archived => current_published_at.or(published_at)
+    };
+
+    Ok(PostPublication { status, published_at })
 }
 
 #[derive(Serialize, Debug)]
@@ -31,7 +81,20 @@ pub struct PostResponder {
     pub id: i64,
     pub updated_at: DateTime<Utc>,
     pub title: String,
+    pub slug: String,
     pub message: String,
+    /// Sanitized html of message, ready to be injected by the front-ends
+    pub rendered_html: String,
+    /// Headings of rendered_html in order, for a table of contents
+    pub headings: Vec<PostHeading>,
+    pub excerpt: Option<String>,
+    /// Sanitized html of the start of message, or of the excerpt, for previews
+    pub summary_html: String,
+    /// The preview summary without markup, for meta descriptions
+    pub summary_text: String,
+    pub status: PostStatus,
+    pub published_at: Option<DateTime<Utc>>,
+    pub tags: Vec<TagResponder>,
     pub admin_id: i64
 }
 
@@ -52,32 +115,32 @@ impl Responder for PostResponder {
     }
 }
 
-#[derive(Serialize, Debug)]
-pub struct PostResponders(pub Vec<PostResponder>);
-
-impl Responder for PostResponders {
-    type Body = BoxBody;
-
-    fn respond_to(self, _: &actix_web::HttpRequest) -> actix_web::HttpResponse<Self::Body> {
-        let json_result = serde_json::to_string(&self);
-
-        match json_result {
-            Ok(body) => HttpResponse::Ok()
-                .content_type(ContentType::json())
-                .body(body),
-            Err(_) => HttpResponse::InternalServerError()
-                .content_type(ContentType::json())
-                .body("Failed to serialize PostResponders")
-        }
-    }
-}
-
 pub fn convert(post: &Post) -> PostResponder {
+    let rendered = match (&post.rendered_html, &post.headings) {
+        (Some(rendered_html), Some(headings)) => RenderedPost { html: rendered_html.to_string(), headings: headings.0.clone() },
+        // posts saved before these were stored are rendered at startup, until then they are rendered here
+        _ => render_post(&post.message)
+    };
+    let summary = match (&post.summary_html, &post.summary_text) {
+        (Some(summary_html), Some(summary_text)) => PostSummary { html: summary_html.to_string(), text: summary_text.to_string() },
+        // posts saved before summaries were stored are summarised here
+        _ => get_post_summary(&post.message, post.excerpt.as_deref())
+    };
+
     PostResponder {
         id: post.id,
         updated_at: post.updated_at,
         title: post.title.to_string(),
+        slug: post.slug.to_string(),
         message: post.message.to_string(),
+        rendered_html: rendered.html,
+        headings: rendered.headings,
+        excerpt: post.excerpt.clone(),
+        summary_html: summary.html,
+        summary_text: summary.text,
+        status: post.status,
+        published_at: post.published_at,
+        tags: post.tags.iter().map(convert_tag).collect(),
         admin_id: post.admin_id
     }
 }
\ No newline at end of file
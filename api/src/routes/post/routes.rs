@@ -1,55 +1,55 @@
-use actix_web::{web::{Json, Data, Path}, HttpRequest, HttpResponse};
+use actix_web::{web::{Json, Data, Path, Query}, http::header, Either, HttpResponse};
 use log::error;
 use crate::{
-    routes::{base_model::{OutputId, PagingModel}, stripped_down_error::StrippedDownError, app_state::AppState, auth_helper::check_is_authenticated}, 
+    routes::{base_model::{CursorPagingModel, OutputId, PagedResponder}, stripped_down_error::StrippedDownError, app_state::AppState, auth_helper::{check_can_manage_post, AuthenticatedAdmin}}, 
     common::{
-        repository::{administrator::repo::QueryAdministratorFn, base::Repository, post::repo::{DeletePostFn, InsertPostFn, QueryPostFn, QueryPostsFn, QueryPostsPreviewFn, UpdatePostFn}}, 
-        authentication::auth_service::Authenticator
+        repository::{
+            administrator::repo::QueryAdministratorFn, 
+            base::Repository, 
+            post::{
+                models::{Post, PostContent},
+                repo::{DeletePostFn, InsertPostFn, QueryPostBySlugFn, QueryPostFn, QueryPostSlugRedirectFn, QueryPostsByStatusFn, QueryPostsFn, QueryPostsPreviewFn, UpdatePostFn}
+            }
+        }, 
+        authentication::auth_service::Authenticator,
+        utils::slug_utils::{get_slug, get_tag_slug}
     }
 };
-use super::models::{convert, DeletePost, UpdatePost, NewPost, PostResponder, PostResponders};
+use super::models::{convert, get_publication, DeletePost, UpdatePost, NewPost, PostResponder, PostStatusFilter};
 
-pub async fn create_post<T: InsertPostFn + QueryAdministratorFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, new_post: Json<NewPost>, req: HttpRequest) -> Result<OutputId, StrippedDownError> {
-    let is_authenticated = check_is_authenticated(app_data.clone(), new_post.admin_id, req).await;
-    if !is_authenticated {
-        error!("create_post error: Authentication Failed");
-        return Err(StrippedDownError::AuthenticationFailed);
-    }
+/// Fits the tag table's name column
+const MAX_TAG_NAME_LENGTH: usize = 100;
 
-    let entity_result = app_data.repo.insert_post(new_post.title.clone(), new_post.message.clone(), new_post.admin_id).await;
+pub async fn create_post<T: InsertPostFn + QueryAdministratorFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, new_post: Json<NewPost>, admin: AuthenticatedAdmin<T, U>) -> Result<OutputId, StrippedDownError> {
+    let publication = get_publication(new_post.status, new_post.published_at, None)?;
+    let content = PostContent { title: new_post.title.clone(), message: new_post.message.clone(), excerpt: new_post.excerpt.clone() };
+    let tag_names = get_requested_tags(&new_post.tags)?;
+    let entity_result = app_data.repo.insert_post(content, get_requested_slug(&new_post.slug), tag_names, publication, admin.id).await;
 
     match entity_result {
         Ok(entity) => Ok(OutputId { id: entity.id }),
-        Err(e) => Err(e.into())
+        Err(e) => Err(convert_post_error(e))
     }
 }
 
-pub async fn get_posts<T: QueryPostsFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, path: Path<PagingModel>) -> Result<PostResponders, StrippedDownError> {
-    let posts_result = app_data.repo.query_posts(path.page_size, path.last_offset).await;
+pub async fn get_posts<T: QueryPostsFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, paging: Query<CursorPagingModel>) -> Result<PagedResponder<PostResponder>, StrippedDownError> {
+    let posts_result = app_data.repo.query_posts(paging.get_limit(), paging.get_cursor()?).await;
 
     match posts_result {
-        Ok(posts) => {
-            let post_responders = posts.iter().map(|post| convert(post)).collect::<Vec<PostResponder>>();
-            Ok(PostResponders(post_responders))
-        },
+        Ok(posts) => Ok(PagedResponder::new(posts, &paging, Post::published_cursor, convert)),
         Err(e) => Err(e.into())
     }
 }
 
+/// Posts that are not public yet, or any more, are not found
 pub async fn get_post<T: QueryPostFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, path: Path<i64>) -> Result<Option<PostResponder>, StrippedDownError> {
     let post_result = app_data.repo.query_post(path.into_inner()).await;
 
     match post_result {
         Ok(opt_post) => {
             match opt_post {
-                Some(post) => Ok(Some(PostResponder {
-                    id: post.id,
-                    updated_at: post.updated_at,
-                    title: post.title,
-                    message: post.message,
-                    admin_id: post.admin_id
-                })),
-                None => Ok(None)
+                Some(post) if post.is_public() => Ok(Some(convert(&post))),
+                _ => Ok(None)
             }
             
         },
@@ -57,55 +57,168 @@ pub async fn get_post<T: QueryPostFn + Repository, U: Authenticator>(app_data: D
     }
 }
 
-pub async fn get_post_previews<T: QueryPostsPreviewFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, path: Path<PagingModel>) -> Result<PostResponders, StrippedDownError> {
-    let posts_result = app_data.repo.query_post_previews(path.page_size, path.last_offset).await;
+/// A slug the post had before the author changed it is redirected to the post's current slug
+pub async fn get_post_by_slug<T: QueryPostBySlugFn + QueryPostSlugRedirectFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, path: Path<String>) 
+    -> Result<Either<Option<PostResponder>, HttpResponse>, StrippedDownError> {
+    let slug = path.into_inner();
+    match app_data.repo.query_post_by_slug(slug.clone()).await {
+        Ok(Some(post)) => return Ok(Either::Left(if post.is_public() { Some(convert(&post)) } else { None })),
+        Ok(None) => (),
+        Err(e) => return Err(e.into())
+    }
+
+    match app_data.repo.query_post_slug_redirect(slug).await {
+        // relative to this route, so it does not depend on where the api is mounted
+        Ok(Some(current_slug)) => Ok(Either::Right(HttpResponse::MovedPermanently().insert_header((header::LOCATION, current_slug)).finish())),
+        Ok(None) => Ok(Either::Left(None)),
+        Err(e) => Err(e.into())
+    }
+}
+
+/// Post page urls are proxied here by the web server. A post's id, from before posts had slugs, or a slug the post had
+/// before the author changed it is permanently redirected to the page of the post's current slug.
+/// Anything else is not found, and the web server serves the page itself
+pub async fn redirect_post_page<T: QueryPostFn + QueryPostSlugRedirectFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, path: Path<String>) -> Result<HttpResponse, StrippedDownError> {
+    let post_slug = path.into_inner();
+    // slugs are never all digits, so a number is an id
+    let current_slug = match post_slug.parse::<i64>() {
+        Ok(id) => app_data.repo.query_post(id).await.map(|post| post.filter(Post::is_public).map(|post| post.slug)),
+        Err(_) => app_data.repo.query_post_slug_redirect(post_slug).await
+    };
+
+    match current_slug {
+        Ok(Some(current_slug)) => Ok(HttpResponse::MovedPermanently().insert_header((header::LOCATION, format!("/post/{}", current_slug))).finish()),
+        Ok(None) => Err(StrippedDownError::NotFound),
+        Err(e) => Err(e.into())
+    }
+}
+
+pub async fn get_post_previews<T: QueryPostsPreviewFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, paging: Query<CursorPagingModel>) -> Result<PagedResponder<PostResponder>, StrippedDownError> {
+    let posts_result = app_data.repo.query_post_previews(paging.get_limit(), paging.get_cursor()?).await;
 
     match posts_result {
-        Ok(posts) => {
-            let post_responders = posts.iter().map(|post| convert(post)).collect::<Vec<PostResponder>>();
-            Ok(PostResponders(post_responders))
-        },
+        Ok(posts) => Ok(PagedResponder::new(posts, &paging, Post::published_cursor, convert)),
+        Err(e) => Err(e.into())
+    }
+}
+
+/// Posts of every status, for administrators to manage
+pub async fn get_managed_posts<T: QueryPostsByStatusFn + QueryAdministratorFn + Repository, U: Authenticator>(
+    app_data: Data<AppState<T, U>>, 
+    paging: Query<CursorPagingModel>, 
+    filter: Query<PostStatusFilter>, 
+    _admin: AuthenticatedAdmin<T, U>
+) -> Result<PagedResponder<PostResponder>, StrippedDownError> {
+    let posts_result = app_data.repo.query_posts_by_status(filter.status, paging.get_limit(), paging.get_cursor()?).await;
+
+    match posts_result {
+        Ok(posts) => Ok(PagedResponder::new(posts, &paging, Post::created_cursor, convert)),
+        Err(e) => Err(e.into())
+    }
+}
+
+/// A post of any status, for administrators to edit
+pub async fn get_managed_post<T: QueryPostFn + QueryAdministratorFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, path: Path<i64>, _admin: AuthenticatedAdmin<T, U>) 
+    -> Result<Option<PostResponder>, StrippedDownError> {
+    match app_data.repo.query_post(path.into_inner()).await {
+        Ok(opt_post) => Ok(opt_post.as_ref().map(convert)),
         Err(e) => Err(e.into())
     }
 }
 
-pub async fn delete_post<T: DeletePostFn + QueryAdministratorFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, json: Json<DeletePost>, req: HttpRequest) 
-    -> HttpResponse {
-    let is_authenticated = check_is_authenticated(app_data.clone(), json.admin_id, req).await;
-    if !is_authenticated {
-        error!("delete_post error: Authentication Failed");
-        return HttpResponse::Unauthorized().body("Failed to authorize for deletion");
+pub async fn delete_post<T: DeletePostFn + QueryPostFn + QueryAdministratorFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, json: Json<DeletePost>, admin: AuthenticatedAdmin<T, U>) 
+    -> Result<HttpResponse, StrippedDownError> {
+    if let Err(e) = check_is_authorized_for_post(app_data.clone(), json.post_id, &admin).await {
+        error!("delete_post error: {}", e);
+        return Err(e);
     }
 
     let result = app_data.repo.delete_post(json.post_id).await;
 
     match result {
-        Ok(_) => HttpResponse::NoContent().into(),
-        Err(_) => HttpResponse::InternalServerError().body("Failed to delete")
+        Ok(_) => Ok(HttpResponse::NoContent().into()),
+        Err(e) => Err(e.into())
     }
 }
 
-pub async fn update_post<T: UpdatePostFn + QueryAdministratorFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, json: Json<UpdatePost>, req: HttpRequest) 
-    -> HttpResponse {
-    let is_authenticated = check_is_authenticated(app_data.clone(), json.admin_id, req).await;
-    if !is_authenticated {
-        error!("update_post error: Authentication Failed");
-        return HttpResponse::Unauthorized().body("Failed to authorize for update");
-    }
+pub async fn update_post<T: UpdatePostFn + QueryPostFn + QueryAdministratorFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, json: Json<UpdatePost>, admin: AuthenticatedAdmin<T, U>) 
+    -> Result<HttpResponse, StrippedDownError> {
+    let post = match check_is_authorized_for_post(app_data.clone(), json.post_id, &admin).await {
+        Ok(post) => post,
+        Err(e) => {
+            error!("update_post error: {}", e);
+            return Err(e);
+        }
+    };
+    let publication = get_publication(json.status, json.published_at, post.published_at)?;
 
-    let result = app_data.repo.update_post(json.post_id, json.title.clone(), json.message.clone()).await;
+    let content = PostContent { title: json.title.clone(), message: json.message.clone(), excerpt: json.excerpt.clone() };
+    let tag_names = match &json.tags {
+        Some(tags) => Some(get_requested_tags(tags)?),
+        None => None
+    };
+    let result = app_data.repo.update_post(json.post_id, content, get_requested_slug(&json.slug), tag_names, publication, admin.id).await;
 
     match result {
-        Ok(_) => HttpResponse::NoContent().into(),
+        Ok(_) => Ok(HttpResponse::NoContent().into()),
         Err(e) => {
             error!("update_post failed: {:?}", e);
-            HttpResponse::InternalServerError().body("Failed to update")
+            Err(convert_post_error(e))
         }
     }
 }
 
+/// Whatever the author typed is made into a slug, a blank one is the same as none
+fn get_requested_slug(slug: &Option<String>) -> Option<String> {
+    slug.as_deref()
+        .map(str::trim)
+        .filter(|slug| !slug.is_empty())
+        .map(get_slug)
+}
+
+/// Trimmed, without blanks or repeats differing only in case. A name longer than MAX_TAG_NAME_LENGTH, without a slug,
+/// or with the same slug as a different name, e.g. "Web Dev" and "web-dev", is a validation error
+fn get_requested_tags(tags: &[String]) -> Result<Vec<String>, StrippedDownError> {
+    let mut tag_names: Vec<String> = vec![];
+    let mut tag_slugs: Vec<String> = vec![];
+    for tag in tags.iter().map(|tag| tag.trim()).filter(|tag| !tag.is_empty()) {
+        let tag_slug = get_tag_slug(tag);
+        if tag.chars().count() > MAX_TAG_NAME_LENGTH || tag_slug.is_empty() {
+            return Err(StrippedDownError::ValidationError { field: "tags".to_string() });
+        }
+        match tag_slugs.iter().position(|slug| *slug == tag_slug) {
+            Some(index) if tag_names[index].to_lowercase() == tag.to_lowercase() => {},
+            Some(_) => return Err(StrippedDownError::ValidationError { field: "tags".to_string() }),
+            None => {
+                tag_slugs.push(tag_slug);
+                tag_names.push(tag.to_string());
+            }
+        }
+    }
+    Ok(tag_names)
+}
+
+/// Slugs are unique, so one another post has is a validation error
+fn convert_post_error(e: sqlx::Error) -> StrippedDownError {
+    match e.as_database_error() {
+        Some(db_error) if db_error.is_unique_violation() => StrippedDownError::ValidationError { field: "slug".to_string() },
+        _ => e.into()
+    }
+}
+
+/// Checks the post exists and that the administrator's role allows changing it, returning the post
+pub(crate) async fn check_is_authorized_for_post<T: QueryPostFn + QueryAdministratorFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, post_id: i64, admin: &AuthenticatedAdmin<T, U>) 
+    -> Result<Post, StrippedDownError> {
+    match app_data.repo.query_post(post_id).await {
+        Ok(Some(post)) => check_can_manage_post(admin, &post).map(|_| post),
+        Ok(None) => Err(StrippedDownError::NotFound),
+        Err(e) => Err(e.into())
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use actix_http::StatusCode;
     use async_trait::async_trait;
     use chrono::Utc;
     use fake::{faker::lorem::en::Sentence, Fake};
@@ -113,12 +226,11 @@ mod tests {
     use crate::{
         common::{
             authentication::auth_service::STANDARD_ACCESS_TOKEN_EXPIRATION, 
-            repository::{administrator::models::Administrator, base::EntityId, post::{models::Post, repo::InsertPostFn}}
+            repository::{administrator::models::{Administrator, AdminRole}, base::{EntityId, PageCursor}, post::{models::{Post, PostContent, PostPublication, PostStatus}, repo::InsertPostFn}}
         }, 
-        common_test::fixtures::get_fake_httprequest_with_bearer_token
+        common_test::fixtures::{get_fake_httprequest_with_bearer_token, get_fake_post}
     };
-    use jsonwebtoken::DecodingKey;
-    use crate::{common::authentication::auth_service::{AuthService, AuthenticationError}, common_test::fixtures::get_app_data};
+    use crate::{common::authentication::auth_service::{AuthService, AuthenticationError, AuthKeys}, common_test::fixtures::get_app_data};
     use super::*;
 
     const MOCK_ENTITY_ID: i64 = 10;
@@ -126,7 +238,7 @@ mod tests {
     struct MockAuthService;
     #[async_trait]
     impl Authenticator for MockAuthService {
-        async fn is_authenticated(&self, _: String, _: Vec<(&str, &str)>, _: &DecodingKey) -> Result<bool, AuthenticationError> {
+        async fn is_authenticated(&self, _: String, _: Vec<(&str, &str)>, _: &AuthKeys) -> Result<bool, AuthenticationError> {
             Ok(true)
         }
     }
@@ -140,43 +252,46 @@ mod tests {
 
     #[async_trait]
     impl QueryPostsFn for MockDbRepo {
-        async fn query_posts(&self, _page_size: i32, _last_offset: i64) -> Result<Vec<Post>, Error> {
-            Ok(vec![Post {
-                id: 1,
-                created_at: Utc::now(),
-                updated_at: Utc::now(),
-                title: "title".to_string(),
-                message: "message".to_string(),
-                admin_id: 1
-            }])
+        async fn query_posts(&self, _limit: i32, _cursor: Option<PageCursor>) -> Result<Vec<Post>, Error> {
+            Ok(vec![get_fake_post(1)])
+        }
+    }
+
+    #[async_trait]
+    impl QueryPostsByStatusFn for MockDbRepo {
+        async fn query_posts_by_status(&self, status: Option<PostStatus>, _limit: i32, _cursor: Option<PageCursor>) -> Result<Vec<Post>, Error> {
+            Ok(vec![Post { status: status.unwrap_or(PostStatus::Draft), published_at: None, ..get_fake_post(1) }])
         }
     }
 
     #[async_trait]
     impl QueryPostsPreviewFn for MockDbRepo {
-        async fn query_post_previews(&self, _page_size: i32, _last_offset: i64) -> Result<Vec<Post>, Error> {
-            Ok(vec![Post {
-                id: 1,
-                created_at: Utc::now(),
-                updated_at: Utc::now(),
-                title: "title".to_string(),
-                message: "message".to_string(),
-                admin_id: 1
-            }])
+        async fn query_post_previews(&self, _limit: i32, _cursor: Option<PageCursor>) -> Result<Vec<Post>, Error> {
+            Ok(vec![get_fake_post(1)])
         }
     }
 
     #[async_trait]
     impl QueryPostFn for MockDbRepo {
         async fn query_post(&self, _id: i64) -> Result<Option<Post>, Error> {
-            Ok(Some(Post {
-                id: MOCK_ENTITY_ID,
-                created_at: Utc::now(),
-                updated_at: Utc::now(),
-                title: "title".to_string(),
-                message: "message".to_string(),
-                admin_id: 1
-            }))
+            Ok(Some(get_fake_post(MOCK_ENTITY_ID)))
+        }
+    }
+
+    #[async_trait]
+    impl QueryPostBySlugFn for MockDbRepo {
+        async fn query_post_by_slug(&self, slug: String) -> Result<Option<Post>, Error> {
+            if slug != "title" {
+                return Ok(None);
+            }
+            Ok(Some(get_fake_post(MOCK_ENTITY_ID)))
+        }
+    }
+
+    #[async_trait]
+    impl QueryPostSlugRedirectFn for MockDbRepo {
+        async fn query_post_slug_redirect(&self, slug: String) -> Result<Option<String>, Error> {
+            Ok(if slug == "old-title" { Some("title".to_string()) } else { None })
         }
     }
 
@@ -189,14 +304,14 @@ mod tests {
 
     #[async_trait]
     impl UpdatePostFn for MockDbRepo {
-        async fn update_post(&self, _id: i64, _title: String, _message: String) -> Result<(), Error> {
+        async fn update_post(&self, _id: i64, _content: PostContent, _slug: Option<String>, _tag_names: Option<Vec<String>>, _publication: PostPublication, _admin_id: i64) -> Result<(), Error> {
             Ok(())
         }
     }
 
     #[async_trait]
     impl InsertPostFn for MockDbRepo {
-        async fn insert_post(&self, _title: String, _message: String, _admin_id: i64) -> Result<EntityId, Error> {
+        async fn insert_post(&self, _content: PostContent, _slug: Option<String>, _tag_names: Vec<String>, _publication: PostPublication, _admin_id: i64) -> Result<EntityId, Error> {
             Ok(EntityId { id: MOCK_ENTITY_ID })
         }
     }
@@ -210,7 +325,9 @@ mod tests {
                 updated_at: Utc::now(),
                 user_name: "dave".to_string(),
                 email: "test@test.com".to_string(),
-                password: "123".to_string()
+                password: "123".to_string(),
+                role: AdminRole::Author,
+                is_active: true
             }))
         }
     }
@@ -224,13 +341,18 @@ mod tests {
         let title = "title".to_string();
         let message = Sentence(1..2).fake::<String>();
 
-        let req = get_fake_httprequest_with_bearer_token(user_name, &app_data.auth_keys.encoding_key, "/v1/post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+        let req = get_fake_httprequest_with_bearer_token(1, user_name, &app_data.auth_keys, "/v1/post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+        let admin = AuthenticatedAdmin::authenticate(app_data.clone(), req).await.unwrap();
 
         let created_post = create_post(app_data, Json(NewPost {
             title,
             message,
-            admin_id: 1
-        }), req).await;
+            excerpt: None,
+            slug: None,
+            tags: vec![],
+            status: PostStatus::Published,
+            published_at: None
+        }), admin).await;
 
         assert!(created_post.as_ref().is_ok());
         assert!(created_post.unwrap().id == MOCK_ENTITY_ID);
@@ -245,21 +367,23 @@ mod tests {
         let title = "title".to_string();
         let message = Sentence(1..2).fake::<String>();
 
-        let req = get_fake_httprequest_with_bearer_token(user_name, &app_data.auth_keys.encoding_key, "/v1/post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+        let req = get_fake_httprequest_with_bearer_token(1, user_name, &app_data.auth_keys, "/v1/post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+        let admin = AuthenticatedAdmin::authenticate(app_data.clone(), req).await.unwrap();
 
         _ = create_post(app_data.clone(), Json(NewPost {
             title,
             message,
-            admin_id: 1
-        }), req).await;
+            excerpt: None,
+            slug: None,
+            tags: vec![],
+            status: PostStatus::Published,
+            published_at: None
+        }), admin).await;
 
-        let posts = get_posts(app_data, Path::from(PagingModel {
-            page_size: 10,
-            last_offset: 0
-        })).await;
+        let posts = get_posts(app_data, Query(CursorPagingModel { page_size: Some(10), cursor: None })).await;
 
         assert!(posts.is_ok());
-        assert!(posts.unwrap().0.len() > 0);
+        assert!(posts.unwrap().items.len() > 0);
     }
 
     #[tokio::test]
@@ -271,21 +395,23 @@ mod tests {
         let title = "title".to_string();
         let message = Sentence(1..2).fake::<String>();
 
-        let req = get_fake_httprequest_with_bearer_token(user_name, &app_data.auth_keys.encoding_key, "/v1/post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+        let req = get_fake_httprequest_with_bearer_token(1, user_name, &app_data.auth_keys, "/v1/post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+        let admin = AuthenticatedAdmin::authenticate(app_data.clone(), req).await.unwrap();
 
         _ = create_post(app_data.clone(), Json(NewPost {
             title,
             message,
-            admin_id: 1
-        }), req).await;
+            excerpt: None,
+            slug: None,
+            tags: vec![],
+            status: PostStatus::Published,
+            published_at: None
+        }), admin).await;
 
-        let posts = get_post_previews(app_data, Path::from(PagingModel {
-            page_size: 10,
-            last_offset: 0
-        })).await;
+        let posts = get_post_previews(app_data, Query(CursorPagingModel { page_size: Some(10), cursor: None })).await;
 
         assert!(posts.is_ok());
-        assert!(posts.unwrap().0.len() > 0);
+        assert!(posts.unwrap().items.len() > 0);
     }
 
     #[tokio::test]
@@ -297,13 +423,18 @@ mod tests {
         let title = "title".to_string();
         let message = Sentence(1..2).fake::<String>();
 
-        let req = get_fake_httprequest_with_bearer_token(user_name, &app_data.auth_keys.encoding_key, "/v1/post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+        let req = get_fake_httprequest_with_bearer_token(1, user_name, &app_data.auth_keys, "/v1/post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+        let admin = AuthenticatedAdmin::authenticate(app_data.clone(), req).await.unwrap();
 
         let created_post = create_post(app_data.clone(), Json(NewPost {
             title,
             message,
-            admin_id: 1
-        }), req).await;
+            excerpt: None,
+            slug: None,
+            tags: vec![],
+            status: PostStatus::Published,
+            published_at: None
+        }), admin).await;
         let created_post_id = created_post.unwrap().id;
 
         let post_resp = get_post(app_data, Path::from(created_post_id)).await;
@@ -311,7 +442,10 @@ mod tests {
         match post_resp {
             Ok(post_opt) => {
                 match post_opt {
-                    Some(post) => assert!(post.id == created_post_id),
+                    Some(post) => {
+                        assert!(post.id == created_post_id);
+                        assert!(post.rendered_html == "<p>message</p>");
+                    },
                     None => panic!("failed None")
                 }
             },
@@ -319,6 +453,175 @@ mod tests {
         }        
     }
 
+    #[tokio::test]
+    async fn test_get_managed_posts_returns_posts_of_requested_status() {
+        let repo = MockDbRepo::init().await;
+        let app_data = get_app_data(repo, AuthService).await;
+
+        let req = get_fake_httprequest_with_bearer_token(1, "dave".to_string(), &app_data.auth_keys, "/v1/manage_post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+        let admin = AuthenticatedAdmin::authenticate(app_data.clone(), req).await.unwrap();
+
+        let posts = get_managed_posts(app_data, Query(CursorPagingModel { page_size: Some(10), cursor: None }), Query(PostStatusFilter { status: Some(PostStatus::Scheduled) }), admin).await;
+
+        assert!(posts.unwrap().items.iter().all(|post| post.status == PostStatus::Scheduled));
+    }
+
+    /// Only has a draft post
+    struct MockDraftDbRepo;
+
+    #[async_trait]
+    impl Repository for MockDraftDbRepo {
+        async fn init() -> Self {
+            MockDraftDbRepo
+        }
+    }
+
+    #[async_trait]
+    impl QueryPostFn for MockDraftDbRepo {
+        async fn query_post(&self, _id: i64) -> Result<Option<Post>, Error> {
+            Ok(Some(Post { status: PostStatus::Draft, published_at: None, ..get_fake_post(MOCK_ENTITY_ID) }))
+        }
+    }
+
+    #[async_trait]
+    impl QueryPostSlugRedirectFn for MockDraftDbRepo {
+        async fn query_post_slug_redirect(&self, _slug: String) -> Result<Option<String>, Error> {
+            Ok(None)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_post_does_not_return_drafts() {
+        let repo = MockDraftDbRepo::init().await;
+        let app_data = get_app_data(repo, AuthService).await;
+
+        let post_resp = get_post(app_data, Path::from(MOCK_ENTITY_ID)).await;
+
+        assert!(matches!(post_resp, Ok(None)));
+    }
+
+    #[test]
+    fn test_get_publication_validates_published_at() {
+        let past = Utc::now() - chrono::Duration::days(1);
+        let future = Utc::now() + chrono::Duration::days(1);
+        let published_at_error = StrippedDownError::ValidationError { field: "published_at".to_string() };
+
+        assert!(get_publication(PostStatus::Scheduled, Some(past), None).err().unwrap() == published_at_error);
+        assert!(get_publication(PostStatus::Scheduled, None, None).err().unwrap() == published_at_error);
+        assert!(get_publication(PostStatus::Published, Some(future), None).err().unwrap() == published_at_error);
+        assert!(get_publication(PostStatus::Scheduled, Some(future), None).unwrap().published_at == Some(future));
+        assert!(get_publication(PostStatus::Draft, Some(past), None).unwrap().published_at.is_none());
+    }
+
+    #[test]
+    fn test_get_publication_keeps_or_brings_forward_published_at() {
+        let past = Utc::now() - chrono::Duration::days(1);
+        let future = Utc::now() + chrono::Duration::days(1);
+
+        assert!(get_publication(PostStatus::Published, None, Some(past)).unwrap().published_at == Some(past));
+        assert!(get_publication(PostStatus::Archived, None, Some(past)).unwrap().published_at == Some(past));
+        // publishing a scheduled post early
+        let published_at = get_publication(PostStatus::Published, None, Some(future)).unwrap().published_at.unwrap();
+        assert!(published_at <= Utc::now());
+    }
+
+    #[tokio::test]
+    async fn test_get_post_by_slug_returns_post_successfully() {
+        let repo = MockDbRepo::init().await;
+        let app_data = get_app_data(repo, AuthService).await;
+
+        let post_resp = get_post_by_slug(app_data, Path::from("title".to_string())).await;
+
+        match post_resp {
+            Ok(Either::Left(Some(post))) => assert!(post.id == MOCK_ENTITY_ID && post.slug == "title"),
+            _ => panic!("failed to get post by slug")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_post_by_slug_redirects_old_slugs() {
+        let repo = MockDbRepo::init().await;
+        let app_data = get_app_data(repo, AuthService).await;
+
+        let post_resp = get_post_by_slug(app_data.clone(), Path::from("old-title".to_string())).await;
+        let missing_post_resp = get_post_by_slug(app_data, Path::from("missing".to_string())).await;
+
+        match post_resp {
+            Ok(Either::Right(resp)) => {
+                assert!(resp.status() == StatusCode::MOVED_PERMANENTLY);
+                assert!(resp.headers().get(header::LOCATION).unwrap() == "title");
+            },
+            _ => panic!("failed to redirect old slug")
+        }
+        assert!(matches!(missing_post_resp, Ok(Either::Left(None))));
+    }
+
+    #[tokio::test]
+    async fn test_redirect_post_page_redirects_ids_of_public_posts_to_their_slug() {
+        let post_resp = redirect_post_page(get_app_data(MockDbRepo::init().await, AuthService).await, Path::from(MOCK_ENTITY_ID.to_string())).await.unwrap();
+        let draft_resp = redirect_post_page(get_app_data(MockDraftDbRepo::init().await, AuthService).await, Path::from(MOCK_ENTITY_ID.to_string())).await;
+
+        assert!(post_resp.status() == StatusCode::MOVED_PERMANENTLY);
+        assert!(post_resp.headers().get(header::LOCATION).unwrap() == "/post/title");
+        assert!(draft_resp.err().unwrap() == StrippedDownError::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_redirect_post_page_redirects_old_slugs_to_the_current_slug() {
+        let app_data = get_app_data(MockDbRepo::init().await, AuthService).await;
+
+        let old_slug_resp = redirect_post_page(app_data.clone(), Path::from("old-title".to_string())).await.unwrap();
+        let current_slug_resp = redirect_post_page(app_data, Path::from("title".to_string())).await;
+
+        assert!(old_slug_resp.status() == StatusCode::MOVED_PERMANENTLY);
+        assert!(old_slug_resp.headers().get(header::LOCATION).unwrap() == "/post/title");
+        assert!(current_slug_resp.err().unwrap() == StrippedDownError::NotFound);
+    }
+
+    #[test]
+    fn test_get_requested_slug_makes_slugs_and_ignores_blank_ones() {
+        assert!(get_requested_slug(&Some(" My Title ".to_string())) == Some("my-title".to_string()));
+        assert!(get_requested_slug(&Some("  ".to_string())).is_none());
+        assert!(get_requested_slug(&None).is_none());
+    }
+
+    #[test]
+    fn test_get_requested_tags_trims_and_drops_blank_and_repeated_tags() {
+        let tags = vec![" Rust ".to_string(), "".to_string(), "rust".to_string(), "Web Dev".to_string()];
+        assert!(get_requested_tags(&tags).unwrap() == vec!["Rust".to_string(), "Web Dev".to_string()]);
+        assert!(matches!(get_requested_tags(&["a".repeat(MAX_TAG_NAME_LENGTH + 1)]), Err(StrippedDownError::ValidationError { .. })));
+    }
+
+    #[test]
+    fn test_get_requested_tags_keeps_tags_with_symbols_apart() {
+        let tags = vec!["C".to_string(), "C++".to_string(), "C#".to_string(), "2024".to_string()];
+        assert!(get_requested_tags(&tags).unwrap() == tags);
+    }
+
+    #[test]
+    fn test_get_requested_tags_rejects_tags_without_slug_or_with_same_slug() {
+        assert!(get_requested_tags(&["?!".to_string()]).err().unwrap() == StrippedDownError::ValidationError { field: "tags".to_string() });
+        assert!(get_requested_tags(&["Web Dev".to_string(), "web-dev".to_string()]).err().unwrap() == StrippedDownError::ValidationError { field: "tags".to_string() });
+    }
+
+    #[tokio::test]
+    async fn test_convert_renders_posts_saved_without_rendered_html() {
+        let post = Post {
+            message: "Some **bold** text<script>alert(1)</script>".to_string(),
+            rendered_html: None,
+            headings: None,
+            summary_html: None,
+            summary_text: None,
+            ..get_fake_post(1)
+        };
+
+        let post_responder = convert(&post);
+
+        assert!(post_responder.rendered_html == "<p>Some <strong>bold</strong> text</p>");
+        assert!(post_responder.summary_html == "<p>Some <strong>bold</strong> text</p>");
+        assert!(post_responder.summary_text == "Some bold text");
+    }
+
     #[tokio::test]
     async fn test_delete_post_deletes_post_successfully() {
         let repo = MockDbRepo::init().await;
@@ -328,21 +631,25 @@ mod tests {
         let title = "title".to_string();
         let message = Sentence(1..2).fake::<String>();
 
-        let req = get_fake_httprequest_with_bearer_token(user_name, &app_data.auth_keys.encoding_key, "/v1/post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+        let req = get_fake_httprequest_with_bearer_token(1, user_name, &app_data.auth_keys, "/v1/post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+        let admin = AuthenticatedAdmin::authenticate(app_data.clone(), req).await.unwrap();
 
         let created_post = create_post(app_data.clone(), Json(NewPost {
             title,
             message,
-            admin_id: 1
-        }), req.clone()).await;
+            excerpt: None,
+            slug: None,
+            tags: vec![],
+            status: PostStatus::Published,
+            published_at: None
+        }), admin.clone()).await;
         let created_post_id = created_post.unwrap().id;
 
         let post_resp = delete_post(app_data, Json(DeletePost {
-            post_id: created_post_id,
-            admin_id: 1
-        }), req).await;
+            post_id: created_post_id
+        }), admin).await;
 
-        assert!(post_resp.error().is_none());
+        assert!(post_resp.is_ok());
     }
 
     #[tokio::test]
@@ -356,22 +663,131 @@ mod tests {
         let update_title = Sentence(1..2).fake::<String>();
         let update_message = Sentence(3..4).fake::<String>();
 
-        let req = get_fake_httprequest_with_bearer_token(user_name, &app_data.auth_keys.encoding_key, "/v1/update_post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+        let req = get_fake_httprequest_with_bearer_token(1, user_name, &app_data.auth_keys, "/v1/update_post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+        let admin = AuthenticatedAdmin::authenticate(app_data.clone(), req).await.unwrap();
 
         let created_post = create_post(app_data.clone(), Json(NewPost {
             title: start_title,
             message: start_message,
-            admin_id: 1
-        }), req.clone()).await;
+            excerpt: None,
+            slug: None,
+            tags: vec![],
+            status: PostStatus::Published,
+            published_at: None
+        }), admin.clone()).await;
         let created_post_id = created_post.unwrap().id;
 
         let post_resp = update_post(app_data, Json(UpdatePost {
             post_id: created_post_id,
-            admin_id: 1,
-            title: update_title,
-            message: update_message
-        }), req).await;
+            title: update_title.clone(),
+            message: update_message,
+            excerpt: Some(Sentence(1..2).fake::<String>()),
+            slug: Some(update_title),
+            tags: None,
+            status: PostStatus::Published,
+            published_at: None
+        }), admin).await;
+
+        assert!(post_resp.is_ok());
+    }
+
+    /// An author who does not own the post
+    struct MockOtherAuthorDbRepo;
+
+    #[async_trait]
+    impl Repository for MockOtherAuthorDbRepo {
+        async fn init() -> Self {
+            MockOtherAuthorDbRepo
+        }
+    }
+
+    #[async_trait]
+    impl QueryAdministratorFn for MockOtherAuthorDbRepo {
+        async fn query_administrator(&self, _id: i64) -> Result<Option<Administrator>, Error> {
+            Ok(Some(Administrator {
+                id: 2,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                user_name: "jon".to_string(),
+                email: "jon@test.com".to_string(),
+                password: "123".to_string(),
+                role: AdminRole::Author,
+                is_active: true
+            }))
+        }
+    }
+
+    #[async_trait]
+    impl QueryPostFn for MockOtherAuthorDbRepo {
+        async fn query_post(&self, _id: i64) -> Result<Option<Post>, Error> {
+            Ok(Some(get_fake_post(MOCK_ENTITY_ID)))
+        }
+    }
+
+    #[async_trait]
+    impl DeletePostFn for MockOtherAuthorDbRepo {
+        async fn delete_post(&self, _id: i64) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl UpdatePostFn for MockOtherAuthorDbRepo {
+        async fn update_post(&self, _id: i64, _content: PostContent, _slug: Option<String>, _tag_names: Option<Vec<String>>, _publication: PostPublication, _admin_id: i64) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delete_post_fails_authorization_for_other_author() {
+        let repo = MockOtherAuthorDbRepo::init().await;
+        let auth_service = AuthService;
+        let app_data = get_app_data(repo, auth_service).await;
+
+        let req = get_fake_httprequest_with_bearer_token(2, "jon".to_string(), &app_data.auth_keys, "/v1/delete_post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+        let admin = AuthenticatedAdmin::authenticate(app_data.clone(), req).await.unwrap();
+
+        let post_resp = delete_post(app_data, Json(DeletePost {
+            post_id: MOCK_ENTITY_ID
+        }), admin).await;
+
+        assert!(post_resp.err().unwrap() == StrippedDownError::AuthorizationFailed);
+    }
+
+    #[tokio::test]
+    async fn test_update_post_fails_authorization_for_other_author() {
+        let repo = MockOtherAuthorDbRepo::init().await;
+        let auth_service = AuthService;
+        let app_data = get_app_data(repo, auth_service).await;
+
+        let req = get_fake_httprequest_with_bearer_token(2, "jon".to_string(), &app_data.auth_keys, "/v1/update_post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+        let admin = AuthenticatedAdmin::authenticate(app_data.clone(), req).await.unwrap();
+
+        let post_resp = update_post(app_data, Json(UpdatePost {
+            post_id: MOCK_ENTITY_ID,
+            title: "title".to_string(),
+            message: "message".to_string(),
+            excerpt: None,
+            slug: None,
+            tags: None,
+            status: PostStatus::Published,
+            published_at: None
+        }), admin).await;
+
+        assert!(post_resp.err().unwrap() == StrippedDownError::AuthorizationFailed);
+    }
+
+    #[tokio::test]
+    async fn test_create_post_fails_authentication_when_token_user_does_not_match() {
+        let repo = MockDbRepo::init().await;
+        let auth_service = AuthService;
+        let app_data = get_app_data(repo, auth_service).await;
+
+        // token claims admin 1 but with a different user_name than the stored administrator
+        let req = get_fake_httprequest_with_bearer_token(1, "jon".to_string(), &app_data.auth_keys, "/v1/post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+
+        let admin = AuthenticatedAdmin::authenticate(app_data, req).await;
 
-        assert!(post_resp.error().is_none());
+        assert!(admin.err().unwrap() == StrippedDownError::AuthenticationFailed);
     }
 }
\ No newline at end of file
@@ -0,0 +1,120 @@
+use actix_http::body::BoxBody;
+use actix_web::{Responder, HttpResponse, http::header::ContentType};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::common::{repository::post_revision::models::PostRevision, utils::diff_utils::{get_line_diff, DiffLine}};
+
+/// The post is saved with the revision's title, message, excerpt, slug and tags, which adds a new revision.
+/// Its status is left as it is, as are the slug and tags for revisions saved before those were kept
+#[derive(Deserialize)]
+pub struct RestorePostRevision {
+    pub post_id: i64,
+    pub revision_id: i64
+}
+
+#[derive(Deserialize)]
+pub struct PostRevisionDiffPath {
+    pub from_revision_id: i64,
+    pub to_revision_id: i64
+}
+
+#[derive(Serialize, Debug)]
+pub struct PostRevisionResponder {
+    pub id: i64,
+    pub created_at: DateTime<Utc>,
+    pub post_id: i64,
+    pub admin_id: i64,
+    pub title: String,
+    pub message: String,
+    pub excerpt: Option<String>,
+    pub slug: Option<String>,
+    pub tag_names: Option<Vec<String>>
+}
+
+impl Responder for PostRevisionResponder {
+    type Body = BoxBody;
+
+    fn respond_to(self, _: &actix_web::HttpRequest) -> actix_web::HttpResponse<Self::Body> {
+        let json_result = serde_json::to_string(&self);
+        
+        match json_result {
+            Ok(body) => HttpResponse::Ok()
+                .content_type(ContentType::json())
+                .body(body),
+            Err(_) => HttpResponse::InternalServerError()
+                .content_type(ContentType::json())
+                .body("Failed to serialize PostRevisionResponder")
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct PostRevisionResponders(pub Vec<PostRevisionResponder>);
+
+impl Responder for PostRevisionResponders {
+    type Body = BoxBody;
+
+    fn respond_to(self, _: &actix_web::HttpRequest) -> actix_web::HttpResponse<Self::Body> {
+        let json_result = serde_json::to_string(&self);
+
+        match json_result {
+            Ok(body) => HttpResponse::Ok()
+                .content_type(ContentType::json())
+                .body(body),
+            Err(_) => HttpResponse::InternalServerError()
+                .content_type(ContentType::json())
+                .body("Failed to serialize PostRevisionResponders")
+        }
+    }
+}
+
+/// Line by line changes from one revision to another, of each field
+#[derive(Serialize, Debug)]
+pub struct PostRevisionDiffResponder {
+    pub from_revision_id: i64,
+    pub to_revision_id: i64,
+    pub title: Vec<DiffLine>,
+    pub message: Vec<DiffLine>,
+    pub excerpt: Vec<DiffLine>
+}
+
+impl Responder for PostRevisionDiffResponder {
+    type Body = BoxBody;
+
+    fn respond_to(self, _: &actix_web::HttpRequest) -> actix_web::HttpResponse<Self::Body> {
+        let json_result = serde_json::to_string(&self);
+
+        match json_result {
+            Ok(body) => HttpResponse::Ok()
+                .content_type(ContentType::json())
+                .body(body),
+            Err(_) => HttpResponse::InternalServerError()
+                .content_type(ContentType::json())
+                .body("Failed to serialize PostRevisionDiffResponder")
+        }
+    }
+}
+
+pub fn convert(revision: &PostRevision) -> PostRevisionResponder {
+    PostRevisionResponder {
+        id: revision.id,
+        created_at: revision.created_at,
+        post_id: revision.post_id,
+        admin_id: revision.admin_id,
+        title: revision.title.to_string(),
+        message: revision.message.to_string(),
+        excerpt: revision.excerpt.clone(),
+        slug: revision.slug.clone(),
+        tag_names: revision.tag_names.as_ref().map(|tag_names| tag_names.0.clone())
+    }
+}
+
+pub fn get_revision_diff(from_revision: &PostRevision, to_revision: &PostRevision) -> PostRevisionDiffResponder {
+    PostRevisionDiffResponder {
+        from_revision_id: from_revision.id,
+        to_revision_id: to_revision.id,
+        title: get_line_diff(&from_revision.title, &to_revision.title),
+        message: get_line_diff(&from_revision.message, &to_revision.message),
+        excerpt: get_line_diff(from_revision.excerpt.as_deref().unwrap_or_default(), to_revision.excerpt.as_deref().unwrap_or_default())
+    }
+}
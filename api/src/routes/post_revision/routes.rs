@@ -0,0 +1,247 @@
+use actix_web::{web::{Data, Json, Path}, HttpResponse};
+use log::error;
+use crate::{
+    common::{
+        authentication::auth_service::Authenticator, 
+        repository::{
+            administrator::repo::QueryAdministratorFn, 
+            base::Repository, 
+            post::repo::{QueryPostFn, UpdatePostFn}, 
+            post_revision::repo::{QueryPostRevisionFn, QueryPostRevisionsFn}
+        }
+    }, 
+    routes::{app_state::AppState, auth_helper::AuthenticatedAdmin, post::routes::check_is_authorized_for_post, stripped_down_error::StrippedDownError}
+};
+use super::models::{convert, get_revision_diff, PostRevisionDiffPath, PostRevisionDiffResponder, PostRevisionResponder, PostRevisionResponders, RestorePostRevision};
+
+/// Newest first, the first is the post's current text. Only for administrators who can edit the post
+pub async fn get_post_revisions<T: QueryPostRevisionsFn + QueryPostFn + QueryAdministratorFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, path: Path<i64>, admin: AuthenticatedAdmin<T, U>) 
+    -> Result<PostRevisionResponders, StrippedDownError> {
+    let post = check_is_authorized_for_post(app_data.clone(), path.into_inner(), &admin).await?;
+
+    match app_data.repo.query_post_revisions(post.id).await {
+        Ok(revisions) => Ok(PostRevisionResponders(revisions.iter().map(convert).collect::<Vec<PostRevisionResponder>>())),
+        Err(e) => Err(e.into())
+    }
+}
+
+/// Not found unless both revisions exist and are of the same post, which the administrator can edit
+pub async fn get_post_revision_diff<T: QueryPostRevisionFn + QueryPostFn + QueryAdministratorFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, path: Path<PostRevisionDiffPath>, admin: AuthenticatedAdmin<T, U>) 
+    -> Result<Option<PostRevisionDiffResponder>, StrippedDownError> {
+    let from_revision_result = app_data.repo.query_post_revision(path.from_revision_id).await;
+    let to_revision_result = app_data.repo.query_post_revision(path.to_revision_id).await;
+
+    match (from_revision_result, to_revision_result) {
+        (Ok(Some(from_revision)), Ok(Some(to_revision))) if from_revision.post_id == to_revision.post_id => {
+            check_is_authorized_for_post(app_data.clone(), from_revision.post_id, &admin).await?;
+            Ok(Some(get_revision_diff(&from_revision, &to_revision)))
+        },
+        (Err(e), _) | (_, Err(e)) => Err(e.into()),
+        _ => Ok(None)
+    }
+}
+
+pub async fn restore_post_revision<T: QueryPostRevisionFn + UpdatePostFn + QueryPostFn + QueryAdministratorFn + Repository, U: Authenticator>(
+    app_data: Data<AppState<T, U>>, 
+    json: Json<RestorePostRevision>, 
+    admin: AuthenticatedAdmin<T, U>
+) -> Result<HttpResponse, StrippedDownError> {
+    let post = match check_is_authorized_for_post(app_data.clone(), json.post_id, &admin).await {
+        Ok(post) => post,
+        Err(e) => {
+            error!("restore_post_revision error: {}", e);
+            return Err(e);
+        }
+    };
+    let revision = match app_data.repo.query_post_revision(json.revision_id).await {
+        Ok(Some(revision)) if revision.post_id == post.id => revision,
+        Ok(_) => return Err(StrippedDownError::NotFound),
+        Err(e) => return Err(e.into())
+    };
+
+    let tag_names = revision.tag_names.as_ref().map(|tag_names| tag_names.0.clone());
+    let result = app_data.repo.update_post(post.id, revision.content(), revision.slug.clone(), tag_names, post.publication(), admin.id).await;
+
+    match result {
+        Ok(_) => Ok(HttpResponse::NoContent().into()),
+        Err(e) => {
+            error!("restore_post_revision failed: {:?}", e);
+            Err(e.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use chrono::Utc;
+    use sqlx::{types::Json as DbJson, Error};
+    use crate::{
+        common::{
+            authentication::auth_service::{AuthService, STANDARD_ACCESS_TOKEN_EXPIRATION}, 
+            repository::{
+                administrator::models::{Administrator, AdminRole}, 
+                post::models::{Post, PostContent, PostPublication}, 
+                post_revision::models::PostRevision
+            },
+            utils::diff_utils::DiffTag
+        }, 
+        common_test::fixtures::{get_app_data, get_fake_httprequest_with_bearer_token, get_fake_post}
+    };
+    use super::*;
+
+    const MOCK_POST_ID: i64 = 10;
+    const OTHER_POST_REVISION_ID: i64 = 3;
+    struct MockDbRepo;
+
+    fn get_revision(id: i64) -> PostRevision {
+        PostRevision {
+            id,
+            created_at: Utc::now(),
+            post_id: if id == OTHER_POST_REVISION_ID { MOCK_POST_ID + 1 } else { MOCK_POST_ID },
+            admin_id: 1,
+            title: "title".to_string(),
+            message: format!("first line\nrevision {}", id),
+            excerpt: None,
+            // the first revision was saved before slugs and tags were kept
+            slug: if id == 1 { None } else { Some(format!("title-{}", id)) },
+            tag_names: if id == 1 { None } else { Some(DbJson(vec![format!("tag {}", id)])) }
+        }
+    }
+
+    #[async_trait]
+    impl Repository for MockDbRepo {
+        async fn init() -> Self {
+            MockDbRepo
+        }
+    }
+
+    #[async_trait]
+    impl QueryAdministratorFn for MockDbRepo {
+        async fn query_administrator(&self, _id: i64) -> Result<Option<Administrator>, Error> {
+            Ok(Some(Administrator {
+                id: 1,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                user_name: "dave".to_string(),
+                email: "test@test.com".to_string(),
+                password: "123".to_string(),
+                role: AdminRole::Author,
+                is_active: true
+            }))
+        }
+    }
+
+    #[async_trait]
+    impl QueryPostRevisionsFn for MockDbRepo {
+        async fn query_post_revisions(&self, _post_id: i64) -> Result<Vec<PostRevision>, Error> {
+            Ok(vec![get_revision(2), get_revision(1)])
+        }
+    }
+
+    #[async_trait]
+    impl QueryPostRevisionFn for MockDbRepo {
+        async fn query_post_revision(&self, revision_id: i64) -> Result<Option<PostRevision>, Error> {
+            Ok(if revision_id <= OTHER_POST_REVISION_ID { Some(get_revision(revision_id)) } else { None })
+        }
+    }
+
+    #[async_trait]
+    impl QueryPostFn for MockDbRepo {
+        async fn query_post(&self, id: i64) -> Result<Option<Post>, Error> {
+            Ok(Some(Post {
+                message: "first line\nrevision 2".to_string(),
+                // another author's post
+                admin_id: if id == MOCK_POST_ID { 1 } else { 2 },
+                ..get_fake_post(id)
+            }))
+        }
+    }
+
+    #[async_trait]
+    impl UpdatePostFn for MockDbRepo {
+        async fn update_post(&self, _id: i64, content: PostContent, slug: Option<String>, tag_names: Option<Vec<String>>, _publication: PostPublication, _admin_id: i64) -> Result<(), Error> {
+            if content.message.ends_with("revision 1") {
+                assert!(slug.is_none() && tag_names.is_none());
+            } else {
+                assert!(slug == Some("title-2".to_string()) && tag_names == Some(vec!["tag 2".to_string()]));
+            }
+            Ok(())
+        }
+    }
+
+    async fn get_admin(app_data: Data<AppState<MockDbRepo, AuthService>>) -> AuthenticatedAdmin<MockDbRepo, AuthService> {
+        let req = get_fake_httprequest_with_bearer_token(1, "dave".to_string(), &app_data.auth_keys, "/v1/post_revisions", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+        AuthenticatedAdmin::authenticate(app_data, req).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_get_post_revisions_returns_revisions() {
+        let app_data = get_app_data(MockDbRepo, AuthService).await;
+        let admin = get_admin(app_data.clone()).await;
+
+        let revisions = get_post_revisions(app_data, Path::from(MOCK_POST_ID), admin).await;
+
+        assert!(revisions.unwrap().0.iter().map(|revision| revision.id).collect::<Vec<i64>>() == vec![2, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_get_post_revisions_fails_for_another_authors_post() {
+        let app_data = get_app_data(MockDbRepo, AuthService).await;
+        let admin = get_admin(app_data.clone()).await;
+
+        let revisions = get_post_revisions(app_data, Path::from(MOCK_POST_ID + 1), admin).await;
+
+        assert!(revisions.err().unwrap() == StrippedDownError::AuthorizationFailed);
+    }
+
+    #[tokio::test]
+    async fn test_get_post_revision_diff_returns_changed_lines() {
+        let app_data = get_app_data(MockDbRepo, AuthService).await;
+        let admin = get_admin(app_data.clone()).await;
+
+        let diff = get_post_revision_diff(app_data, Path::from(PostRevisionDiffPath { from_revision_id: 1, to_revision_id: 2 }), admin).await.unwrap().unwrap();
+
+        assert!(diff.title.iter().all(|line| line.tag == DiffTag::Equal));
+        assert!(diff.message.iter().map(|line| (line.tag, line.text.as_str())).collect::<Vec<(DiffTag, &str)>>() == vec![
+            (DiffTag::Equal, "first line"),
+            (DiffTag::Delete, "revision 1"),
+            (DiffTag::Insert, "revision 2")
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_get_post_revision_diff_does_not_compare_different_posts() {
+        let app_data = get_app_data(MockDbRepo, AuthService).await;
+        let admin = get_admin(app_data.clone()).await;
+
+        let other_post_diff = get_post_revision_diff(app_data.clone(), Path::from(PostRevisionDiffPath { from_revision_id: 1, to_revision_id: OTHER_POST_REVISION_ID }), admin.clone()).await;
+        let missing_diff = get_post_revision_diff(app_data, Path::from(PostRevisionDiffPath { from_revision_id: 1, to_revision_id: 100 }), admin).await;
+
+        assert!(matches!(other_post_diff, Ok(None)));
+        assert!(matches!(missing_diff, Ok(None)));
+    }
+
+    #[tokio::test]
+    async fn test_restore_post_revision_only_restores_the_posts_revisions() {
+        let app_data = get_app_data(MockDbRepo, AuthService).await;
+        let admin = get_admin(app_data.clone()).await;
+
+        let restore_resp = restore_post_revision(app_data.clone(), Json(RestorePostRevision { post_id: MOCK_POST_ID, revision_id: 1 }), admin.clone()).await;
+        let other_post_restore_resp = restore_post_revision(app_data, Json(RestorePostRevision { post_id: MOCK_POST_ID, revision_id: OTHER_POST_REVISION_ID }), admin).await;
+
+        assert!(restore_resp.is_ok());
+        assert!(other_post_restore_resp.err().unwrap() == StrippedDownError::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_restore_post_revision_restores_its_slug_and_tags() {
+        let app_data = get_app_data(MockDbRepo, AuthService).await;
+        let admin = get_admin(app_data.clone()).await;
+
+        // the mock update_post checks the slug and tags it is given
+        let restore_resp = restore_post_revision(app_data, Json(RestorePostRevision { post_id: MOCK_POST_ID, revision_id: 2 }), admin).await;
+
+        assert!(restore_resp.is_ok());
+    }
+}
@@ -1,11 +1,51 @@
 use actix_web::web::{ServiceConfig, self};
-use crate::{common::{authentication::auth_service::AuthService, repository::base::DbRepo}, routes::authentication::routes::login};
+use crate::{
+    common::{authentication::auth_service::AuthService, repository::base::DbRepo}, 
+    routes::{
+        auth_helper::admin_resource,
+        administrator::routes::{create_administrator, deactivate_administrator, get_administrators, update_administrator, update_administrator_password},
+        authentication::routes::{confirm_totp, enroll_totp, login, login_totp, logout, refresh_access_token},
+        password_reset::routes::{confirm_password_reset, request_password_reset}
+    }
+};
 
 pub fn admin_configs(cfg: &mut ServiceConfig) {
     cfg.service(
-        web::resource("/user")            
+        admin_resource::<DbRepo, AuthService>("/user")
+            .route(web::post().to(create_administrator::<DbRepo, AuthService>))
+            .route(web::get().to(get_administrators::<DbRepo, AuthService>))
+    ).service(
+        admin_resource::<DbRepo, AuthService>("/update_user")
+            .route(web::post().to(update_administrator::<DbRepo, AuthService>))
+    ).service(
+        admin_resource::<DbRepo, AuthService>("/update_user_password")
+            .route(web::post().to(update_administrator_password::<DbRepo, AuthService>))
+    ).service(
+        admin_resource::<DbRepo, AuthService>("/deactivate_user")
+            .route(web::post().to(deactivate_administrator::<DbRepo, AuthService>))
     ).service(
         web::resource("/login")
             .route(web::post().to(login::<DbRepo, AuthService>))   
+    ).service(
+        web::resource("/login/totp")
+            .route(web::post().to(login_totp::<DbRepo, AuthService>))
+    ).service(
+        admin_resource::<DbRepo, AuthService>("/totp/enroll")
+            .route(web::post().to(enroll_totp::<DbRepo, AuthService>))
+    ).service(
+        admin_resource::<DbRepo, AuthService>("/totp/confirm")
+            .route(web::post().to(confirm_totp::<DbRepo, AuthService>))
+    ).service(
+        web::resource("/logout")
+            .route(web::post().to(logout::<DbRepo, AuthService>))
+    ).service(
+        web::resource("/password_reset/request")
+            .route(web::post().to(request_password_reset::<DbRepo, AuthService>))
+    ).service(
+        web::resource("/password_reset/confirm")
+            .route(web::post().to(confirm_password_reset::<DbRepo, AuthService>))
+    ).service(
+        web::resource("/refresh_access_token")
+            .route(web::post().to(refresh_access_token::<DbRepo, AuthService>))
     );
 }
\ No newline at end of file
@@ -0,0 +1,24 @@
+use actix_web::{guard, web::{ServiceConfig, self}};
+use crate::routes::auth_helper::admin_resource;
+use crate::routes::mail::routes::{create_mail, delete_mail, get_latest_mail, get_mail, update_mail_read};
+use crate::common::{authentication::auth_service::AuthService, repository::base::DbRepo};
+
+pub fn mail_configs(cfg: &mut ServiceConfig) {
+    cfg.service(
+        admin_resource::<DbRepo, AuthService>("/mail")
+            .guard(guard::Get())
+            .route(web::get().to(get_latest_mail::<DbRepo, AuthService>))
+    ).service(
+        web::resource("/mail")
+            .route(web::post().to(create_mail::<DbRepo, AuthService>))
+    ).service(
+        admin_resource::<DbRepo, AuthService>("/mail/{mail_id}")
+            .route(web::get().to(get_mail::<DbRepo, AuthService>))
+    ).service(
+        admin_resource::<DbRepo, AuthService>("/mail_read")
+            .route(web::post().to(update_mail_read::<DbRepo, AuthService>))
+    ).service(
+        admin_resource::<DbRepo, AuthService>("/delete_mail")
+            .route(web::post().to(delete_mail::<DbRepo, AuthService>))
+    );
+}
@@ -1,23 +1,46 @@
-use actix_web::web::{ServiceConfig, self};
-use crate::routes::post::routes::{create_post, delete_post, get_post, get_post_previews, update_post};
+use actix_web::{guard, web::{ServiceConfig, self}};
+use crate::routes::auth_helper::admin_resource;
+use crate::routes::post::routes::{create_post, delete_post, get_managed_post, get_managed_posts, get_post, get_post_by_slug, get_post_previews, redirect_post_page, update_post};
+use crate::routes::post_revision::routes::{get_post_revision_diff, get_post_revisions, restore_post_revision};
 use crate::common::{authentication::auth_service::AuthService, repository::base::DbRepo};
 
 pub fn post_configs(cfg: &mut ServiceConfig) {
     cfg.service(
-        web::resource("/post")            
+        admin_resource::<DbRepo, AuthService>("/post")
+            .guard(guard::Post())
             .route(web::post().to(create_post::<DbRepo, AuthService>))
-    )
-    .service(
-        web::resource("/post/{page_size}/{last_offset}")
+    ).service(
+        web::resource("/post")
             .route(web::get().to(get_post_previews::<DbRepo, AuthService>))
+    ).service(
+        web::resource("/post/slug/{slug}")
+            .route(web::get().to(get_post_by_slug::<DbRepo, AuthService>))
     ).service(
         web::resource("/post/{post_id}")
             .route(web::get().to(get_post::<DbRepo, AuthService>))
     ).service(
-        web::resource("/delete_post")
+        web::resource("/post_page/{post_slug}")
+            .route(web::get().to(redirect_post_page::<DbRepo, AuthService>))
+    ).service(
+        admin_resource::<DbRepo, AuthService>("/manage_post")
+            .route(web::get().to(get_managed_posts::<DbRepo, AuthService>))
+    ).service(
+        admin_resource::<DbRepo, AuthService>("/manage_post/{post_id}")
+            .route(web::get().to(get_managed_post::<DbRepo, AuthService>))
+    ).service(
+        admin_resource::<DbRepo, AuthService>("/delete_post")
             .route(web::post().to(delete_post::<DbRepo, AuthService>))
     ).service(
-        web::resource("/update_post")
+        admin_resource::<DbRepo, AuthService>("/update_post")
             .route(web::post().to(update_post::<DbRepo, AuthService>))
+    ).service(
+        admin_resource::<DbRepo, AuthService>("/post_revisions/{post_id}")
+            .route(web::get().to(get_post_revisions::<DbRepo, AuthService>))
+    ).service(
+        admin_resource::<DbRepo, AuthService>("/post_revision_diff/{from_revision_id}/{to_revision_id}")
+            .route(web::get().to(get_post_revision_diff::<DbRepo, AuthService>))
+    ).service(
+        admin_resource::<DbRepo, AuthService>("/restore_post_revision")
+            .route(web::post().to(restore_post_revision::<DbRepo, AuthService>))
     );
 }
\ No newline at end of file
@@ -0,0 +1,10 @@
+use actix_web::web::{ServiceConfig, self};
+use crate::routes::search::routes::search_posts;
+use crate::common::{authentication::auth_service::AuthService, repository::base::DbRepo};
+
+pub fn search_configs(cfg: &mut ServiceConfig) {
+    cfg.service(
+        web::resource("/search")
+            .route(web::get().to(search_posts::<DbRepo, AuthService>))
+    );
+}
@@ -0,0 +1,13 @@
+use actix_web::web::{ServiceConfig, self};
+use crate::routes::tag::routes::{get_tag_post_previews, get_tags};
+use crate::common::{authentication::auth_service::AuthService, repository::base::DbRepo};
+
+pub fn tag_configs(cfg: &mut ServiceConfig) {
+    cfg.service(
+        web::resource("/tag")
+            .route(web::get().to(get_tags::<DbRepo, AuthService>))
+    ).service(
+        web::resource("/tag/{slug}/post")
+            .route(web::get().to(get_tag_post_previews::<DbRepo, AuthService>))
+    );
+}
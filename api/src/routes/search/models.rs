@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+use crate::{common::repository::post::models::PostSearchResult, routes::post::models::{convert as convert_post, PostResponder}};
+
+/// @q is read as a web search, quoted phrases and a leading - to exclude a word work
+#[derive(Deserialize)]
+pub struct SearchQuery {
+    pub q: String
+}
+
+#[derive(Serialize, Debug)]
+pub struct PostSearchResultResponder {
+    /// A preview of the post
+    pub post: PostResponder,
+    pub rank: f32,
+    /// Sanitized html of the matching parts of the post, with the matched words in mark tags
+    pub snippet_html: String
+}
+
+pub fn convert(result: &PostSearchResult) -> PostSearchResultResponder {
+    PostSearchResultResponder {
+        post: convert_post(&result.post),
+        rank: result.rank,
+        snippet_html: result.snippet_html.to_string()
+    }
+}
@@ -0,0 +1,90 @@
+use actix_web::web::{Data, Query};
+use crate::{
+    common::{authentication::auth_service::Authenticator, repository::{base::Repository, post::{models::PostSearchResult, repo::SearchPostsFn}}}, 
+    routes::{app_state::AppState, base_model::{CursorPagingModel, PagedResponder}, stripped_down_error::StrippedDownError}
+};
+use super::models::{convert, PostSearchResultResponder, SearchQuery};
+
+const MAX_SEARCH_TEXT_LENGTH: usize = 200;
+
+/// Public posts matching the search text, best match first. Blank text matches nothing
+pub async fn search_posts<T: SearchPostsFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, query: Query<SearchQuery>, paging: Query<CursorPagingModel>) 
+    -> Result<PagedResponder<PostSearchResultResponder>, StrippedDownError> {
+    let search_text = query.q.trim();
+    if search_text.chars().count() > MAX_SEARCH_TEXT_LENGTH {
+        return Err(StrippedDownError::ValidationError { field: "q".to_string() });
+    }
+    if search_text.is_empty() {
+        return Ok(PagedResponder::new(vec![], &paging, PostSearchResult::cursor, convert));
+    }
+
+    match app_data.repo.search_posts(search_text.to_string(), paging.get_limit(), paging.get_cursor()?).await {
+        Ok(results) => Ok(PagedResponder::new(results, &paging, PostSearchResult::cursor, convert)),
+        Err(e) => Err(e.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use sqlx::Error;
+    use crate::{
+        common::{
+            authentication::auth_service::AuthService, 
+            repository::post::models::{Post, SearchCursor}
+        }, 
+        common_test::fixtures::{get_app_data, get_fake_post}
+    };
+    use super::*;
+
+    struct MockDbRepo;
+
+    #[async_trait]
+    impl Repository for MockDbRepo {
+        async fn init() -> Self {
+            MockDbRepo
+        }
+    }
+
+    #[async_trait]
+    impl SearchPostsFn for MockDbRepo {
+        async fn search_posts(&self, search_text: String, _limit: i32, _cursor: Option<SearchCursor>) -> Result<Vec<PostSearchResult>, Error> {
+            if search_text != "rust" {
+                return Ok(vec![]);
+            }
+            Ok(vec![PostSearchResult {
+                post: Post { title: "Learning Rust".to_string(), slug: "learning-rust".to_string(), ..get_fake_post(1) },
+                rank: 0.5,
+                snippet_html: "Learning <mark>Rust</mark>".to_string()
+            }])
+        }
+    }
+
+    fn get_query(q: &str) -> Query<SearchQuery> {
+        Query(SearchQuery { q: q.to_string() })
+    }
+
+    #[tokio::test]
+    async fn test_search_posts_returns_matching_posts_with_snippets() {
+        let app_data = get_app_data(MockDbRepo, AuthService).await;
+
+        let results = search_posts(app_data.clone(), get_query(" rust "), Query(CursorPagingModel::default())).await.unwrap().items;
+        let no_results = search_posts(app_data, get_query("python"), Query(CursorPagingModel::default())).await.unwrap().items;
+
+        assert!(results.len() == 1);
+        assert!(results[0].post.slug == "learning-rust");
+        assert!(results[0].snippet_html == "Learning <mark>Rust</mark>");
+        assert!(no_results.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_search_posts_ignores_blank_text_and_rejects_long_text() {
+        let app_data = get_app_data(MockDbRepo, AuthService).await;
+
+        let blank_results = search_posts(app_data.clone(), get_query("  "), Query(CursorPagingModel::default())).await.unwrap().items;
+        let long_result = search_posts(app_data, get_query(&"a".repeat(MAX_SEARCH_TEXT_LENGTH + 1)), Query(CursorPagingModel::default())).await;
+
+        assert!(blank_results.is_empty());
+        assert!(matches!(long_result, Err(StrippedDownError::ValidationError { .. })));
+    }
+}
@@ -1,6 +1,7 @@
 use actix_http::StatusCode;
-use actix_web::{ResponseError, HttpResponse, http::header::ContentType};
+use actix_web::{ResponseError, HttpResponse, http::header::{ContentType, RETRY_AFTER}};
 use derive_more::{Display, Error};
+use crate::common::authentication::auth_service::AuthenticationError;
 
 #[derive(Debug, Display, Error, PartialEq)]
 pub enum StrippedDownError {
@@ -10,8 +11,14 @@ pub enum StrippedDownError {
     ValidationError { field: String },
     #[display(fmt = "Authentication Failed. Email or password is incorrect.")]
     AuthenticationFailed,
+    #[display(fmt = "Authentication Failed. Token has expired.")]
+    TokenExpired,
     #[display(fmt = "Authorization for Resource Failed.")]
     AuthorizationFailed,
+    #[display(fmt = "Resource Not Found.")]
+    NotFound,
+    #[display(fmt = "Too many requests. Please try again later.")]
+    TooManyRequests { retry_after: u64 },
 }
 
 impl StrippedDownError {
@@ -32,7 +39,11 @@ impl StrippedDownError {
 
 impl ResponseError for StrippedDownError {
     fn error_response(&self) -> HttpResponse {
-        HttpResponse::build(self.status_code())
+        let mut response = HttpResponse::build(self.status_code());
+        if let StrippedDownError::TooManyRequests { retry_after } = self {
+            response.insert_header((RETRY_AFTER, retry_after.to_string()));
+        }
+        response
             .insert_header(ContentType::plaintext())
             .body(self.to_string())
     }
@@ -42,7 +53,10 @@ impl ResponseError for StrippedDownError {
             StrippedDownError::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
             StrippedDownError::ValidationError { .. } => StatusCode::BAD_REQUEST,
             StrippedDownError::AuthenticationFailed => StatusCode::UNAUTHORIZED,
-            StrippedDownError::AuthorizationFailed => StatusCode::UNAUTHORIZED
+            StrippedDownError::TokenExpired => StatusCode::UNAUTHORIZED,
+            StrippedDownError::AuthorizationFailed => StatusCode::FORBIDDEN,
+            StrippedDownError::NotFound => StatusCode::NOT_FOUND,
+            StrippedDownError::TooManyRequests { .. } => StatusCode::TOO_MANY_REQUESTS
         }
     }
 }
@@ -51,4 +65,16 @@ impl Into<StrippedDownError> for sqlx::Error {
     fn into(self) -> StrippedDownError {
         StrippedDownError::convert_to_stripped_error(self)
     }
-}
\ No newline at end of file
+}
+
+impl From<AuthenticationError> for StrippedDownError {
+    fn from(e: AuthenticationError) -> StrippedDownError {
+        match e {
+            AuthenticationError::ExpiredToken => StrippedDownError::TokenExpired,
+            AuthenticationError::DatabaseAuthenticationFailure => StrippedDownError::InternalError,
+            AuthenticationError::PasswordAuthenticationFailure
+                | AuthenticationError::MalformedToken
+                | AuthenticationError::MissingToken => StrippedDownError::AuthenticationFailed
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use actix_http::body::BoxBody;
+use actix_web::{Responder, HttpResponse, http::header::ContentType};
+use serde::Serialize;
+use crate::common::repository::tag::models::{Tag, TagPostCount};
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct TagResponder {
+    pub id: i64,
+    pub name: String,
+    pub slug: String
+}
+
+#[derive(Serialize, Debug)]
+pub struct TagPostCountResponder {
+    pub id: i64,
+    pub name: String,
+    pub slug: String,
+    pub post_count: i64
+}
+
+#[derive(Serialize, Debug)]
+pub struct TagPostCountResponders(pub Vec<TagPostCountResponder>);
+
+impl Responder for TagPostCountResponders {
+    type Body = BoxBody;
+
+    fn respond_to(self, _: &actix_web::HttpRequest) -> actix_web::HttpResponse<Self::Body> {
+        let json_result = serde_json::to_string(&self);
+
+        match json_result {
+            Ok(body) => HttpResponse::Ok()
+                .content_type(ContentType::json())
+                .body(body),
+            Err(_) => HttpResponse::InternalServerError()
+                .content_type(ContentType::json())
+                .body("Failed to serialize TagPostCountResponders")
+        }
+    }
+}
+
+pub fn convert(tag: &Tag) -> TagResponder {
+    TagResponder {
+        id: tag.id,
+        name: tag.name.to_string(),
+        slug: tag.slug.to_string()
+    }
+}
+
+pub fn convert_post_count(tag_post_count: &TagPostCount) -> TagPostCountResponder {
+    TagPostCountResponder {
+        id: tag_post_count.tag.id,
+        name: tag_post_count.tag.name.to_string(),
+        slug: tag_post_count.tag.slug.to_string(),
+        post_count: tag_post_count.post_count
+    }
+}
@@ -0,0 +1,91 @@
+use actix_web::web::{Data, Path, Query};
+use crate::{
+    common::{authentication::auth_service::Authenticator, repository::{base::Repository, post::{models::Post, repo::QueryTagPostPreviewsFn}, tag::repo::QueryTagsFn}}, 
+    routes::{app_state::AppState, base_model::{CursorPagingModel, PagedResponder}, post::models::{convert, PostResponder}, stripped_down_error::StrippedDownError}
+};
+use super::models::{convert_post_count, TagPostCountResponder, TagPostCountResponders};
+
+/// Tags that have public posts, by name
+pub async fn get_tags<T: QueryTagsFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>) -> Result<TagPostCountResponders, StrippedDownError> {
+    match app_data.repo.query_tags().await {
+        Ok(tags) => Ok(TagPostCountResponders(tags.iter().map(convert_post_count).collect::<Vec<TagPostCountResponder>>())),
+        Err(e) => Err(e.into())
+    }
+}
+
+/// Previews of the public posts with the tag, empty for an unknown tag
+pub async fn get_tag_post_previews<T: QueryTagPostPreviewsFn + Repository, U: Authenticator>(app_data: Data<AppState<T, U>>, path: Path<String>, paging: Query<CursorPagingModel>) 
+    -> Result<PagedResponder<PostResponder>, StrippedDownError> {
+    let posts_result = app_data.repo.query_tag_post_previews(path.into_inner(), paging.get_limit(), paging.get_cursor()?).await;
+
+    match posts_result {
+        Ok(posts) => Ok(PagedResponder::new(posts, &paging, Post::published_cursor, convert)),
+        Err(e) => Err(e.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use sqlx::Error;
+    use crate::{
+        common::{
+            authentication::auth_service::AuthService, 
+            repository::{base::PageCursor, tag::models::{Tag, TagPostCount}}
+        }, 
+        common_test::fixtures::{get_app_data, get_fake_post}
+    };
+    use super::*;
+
+    struct MockDbRepo;
+
+    fn get_tag() -> Tag {
+        Tag { id: 1, name: "Rust".to_string(), slug: "rust".to_string() }
+    }
+
+    #[async_trait]
+    impl Repository for MockDbRepo {
+        async fn init() -> Self {
+            MockDbRepo
+        }
+    }
+
+    #[async_trait]
+    impl QueryTagsFn for MockDbRepo {
+        async fn query_tags(&self) -> Result<Vec<TagPostCount>, Error> {
+            Ok(vec![TagPostCount { tag: get_tag(), post_count: 2 }])
+        }
+    }
+
+    #[async_trait]
+    impl QueryTagPostPreviewsFn for MockDbRepo {
+        async fn query_tag_post_previews(&self, tag_slug: String, _limit: i32, _cursor: Option<PageCursor>) -> Result<Vec<Post>, Error> {
+            if tag_slug != "rust" {
+                return Ok(vec![]);
+            }
+            Ok(vec![Post { tags: vec![get_tag()], ..get_fake_post(1) }])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_tags_returns_tags_with_post_counts() {
+        let app_data = get_app_data(MockDbRepo, AuthService).await;
+
+        let tags = get_tags(app_data).await.unwrap().0;
+
+        assert!(tags.len() == 1);
+        assert!(tags[0].slug == "rust" && tags[0].post_count == 2);
+    }
+
+    #[tokio::test]
+    async fn test_get_tag_post_previews_returns_posts_with_tag() {
+        let app_data = get_app_data(MockDbRepo, AuthService).await;
+
+        let posts = get_tag_post_previews(app_data.clone(), Path::from("rust".to_string()), Query(CursorPagingModel::default())).await.unwrap().items;
+        let unknown_tag_posts = get_tag_post_previews(app_data, Path::from("missing".to_string()), Query(CursorPagingModel::default())).await.unwrap().items;
+
+        assert!(posts.len() == 1);
+        assert!(posts[0].tags.iter().any(|tag| tag.slug == "rust"));
+        assert!(unknown_tag_posts.is_empty());
+    }
+}
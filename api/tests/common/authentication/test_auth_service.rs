@@ -1,7 +1,8 @@
 use fake::Fake;
 use fake::faker::internet::en::Username;
 use rustyindie_api::common::repository::base::{Repository, DbRepo};
-use rustyindie_api::common::authentication::auth_service::{AuthService, Authenticator, STANDARD_ACCESS_TOKEN_EXPIRATION, init_auth_keys, get_token, decode_token};
+use ring::signature::Ed25519KeyPair;
+use rustyindie_api::common::authentication::auth_service::{AuthKeys, AuthService, AuthenticationError, Authenticator, decode_token_ignoring_expiration, STANDARD_ACCESS_TOKEN_EXPIRATION, init_auth_keys, get_token, decode_token};
 use rustyindie_api::common_test::fixtures::{get_app_data, get_fake_httprequest_with_bearer_token};
 use rustyindie_api::routes::route_utils::get_header_strings;
 
@@ -13,7 +14,7 @@ async fn test_init_auth_keys_does_not_panic() {
 #[tokio::test]
 async fn test_get_token_returns_valid_token() {
     let auth_keys = init_auth_keys().await;
-    let token = get_token(Username().fake::<String>(), &auth_keys.encoding_key, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+    let token = get_token(1, Username().fake::<String>(), &auth_keys, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
 
     assert!(token.len() > 0);
 }
@@ -23,13 +24,31 @@ async fn test_decode_token_returns_valid_claims() {
     let user_name = Username().fake::<String>();
     
     let auth_keys = init_auth_keys().await;
-    let token = get_token(user_name.clone(), &auth_keys.encoding_key, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
-    let claims = decode_token(&token, &auth_keys.decoding_key);
+    let token = get_token(1, user_name.clone(), &auth_keys, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+    let claims = decode_token(&token, &auth_keys).unwrap();
 
     assert!(claims.sub == user_name);
+    assert!(claims.admin_id == 1);
     assert!(claims.exp >= STANDARD_ACCESS_TOKEN_EXPIRATION as usize);
 }
 
+#[tokio::test]
+async fn test_decode_token_accepts_tokens_signed_by_retired_key() {
+    let user_name = Username().fake::<String>();
+    let old_key = Ed25519KeyPair::generate_pkcs8(&ring::rand::SystemRandom::new()).unwrap().as_ref().to_vec();
+    let new_key = Ed25519KeyPair::generate_pkcs8(&ring::rand::SystemRandom::new()).unwrap().as_ref().to_vec();
+
+    let old_auth_keys = AuthKeys::from_pkcs8_der(("key-1".to_string(), old_key.clone()), vec![]);
+    let old_token = get_token(1, user_name.clone(), &old_auth_keys, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+
+    let rotated_auth_keys = AuthKeys::from_pkcs8_der(("key-2".to_string(), new_key), vec![("key-1".to_string(), old_key)]);
+    let new_token = get_token(1, user_name.clone(), &rotated_auth_keys, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+
+    assert!(decode_token(&old_token, &rotated_auth_keys).unwrap().sub == user_name);
+    assert!(decode_token(&new_token, &rotated_auth_keys).unwrap().sub == user_name);
+    assert!(jsonwebtoken::decode_header(&new_token).unwrap().kid == Some("key-2".to_string()));
+}
+
 #[tokio::test]
 async fn test_is_authenticated_returns_correct_boolean() {    
     let repo = DbRepo::init().await;
@@ -38,12 +57,48 @@ async fn test_is_authenticated_returns_correct_boolean() {
     let user_name = Username().fake::<String>();
     
     let req = get_fake_httprequest_with_bearer_token(
-        user_name.clone(), &app_data.auth_keys.encoding_key, "/v1/administrator", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION)
+        1, user_name.clone(), &app_data.auth_keys, "/v1/administrator", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION)
     );
     let headers = get_header_strings(req.headers());
 
-    let result = app_data.auth_service.is_authenticated(user_name, headers, &app_data.auth_keys.decoding_key).await.unwrap();
+    let result = app_data.auth_service.is_authenticated(user_name, headers, &app_data.auth_keys).await.unwrap();
 
     assert!(result == true);
 }
 
+
+#[tokio::test]
+async fn test_decode_token_returns_expired_error() {
+    let auth_keys = init_auth_keys().await;
+    let token = get_token(1, Username().fake::<String>(), &auth_keys, Some(-10));
+
+    assert!(decode_token(&token, &auth_keys).err().unwrap() == AuthenticationError::ExpiredToken);
+    assert!(decode_token_ignoring_expiration(&token, &auth_keys).unwrap().admin_id == 1);
+}
+
+#[tokio::test]
+async fn test_decode_token_returns_malformed_error() {
+    let auth_keys = init_auth_keys().await;
+    let other_auth_keys = AuthKeys::from_pkcs8_der(
+        ("other".to_string(), Ed25519KeyPair::generate_pkcs8(&ring::rand::SystemRandom::new()).unwrap().as_ref().to_vec()),
+        vec![]
+    );
+    let token_from_unknown_key = get_token(1, Username().fake::<String>(), &other_auth_keys, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+
+    assert!(decode_token("not-a-token", &auth_keys).err().unwrap() == AuthenticationError::MalformedToken);
+    assert!(decode_token(&token_from_unknown_key, &auth_keys).err().unwrap() == AuthenticationError::MalformedToken);
+}
+
+#[tokio::test]
+async fn test_is_authenticated_returns_missing_and_malformed_errors() {
+    let auth_keys = init_auth_keys().await;
+    let user_name = Username().fake::<String>();
+
+    let missing = AuthService.is_authenticated(user_name.clone(), vec![("content-type", "application/json")], &auth_keys).await;
+    let no_bearer = AuthService.is_authenticated(user_name.clone(), vec![("authorization", "Bearer")], &auth_keys).await;
+    let garbage = AuthService.is_authenticated(user_name, vec![("authorization", "Bearer abc")], &auth_keys).await;
+
+    assert!(missing.err().unwrap() == AuthenticationError::MissingToken);
+    assert!(no_bearer.err().unwrap() == AuthenticationError::MalformedToken);
+    assert!(garbage.err().unwrap() == AuthenticationError::MalformedToken);
+}
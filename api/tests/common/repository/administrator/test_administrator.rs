@@ -1,7 +1,18 @@
 use rustyindie_api::{
-    common::{repository::{base::{DbRepo, Repository}, administrator::{repo::{InsertAdminisratorFn, QueryAdministratorFn, AuthenticateDbFn}, models::AuthenticateResult}}, authentication::auth_service::AuthService}, 
+    common::{
+        repository::{base::{DbRepo, Repository, ConnGetter, EntityId}, refresh_token::repo::{InsertRefreshTokenFn, QueryRefreshTokenFn}, administrator::{repo::{InsertAdminisratorFn, QueryAdministratorFn, QueryAdministratorsFn, UpdateAdministratorFn, UpdateAdministratorPasswordFn, DeactivateAdministratorFn, AuthenticateDbFn, QueryAdministratorTotpFn, InsertTotpEnrollmentFn, EnableTotpFn, AuthenticateTotpFn}, models::{AdminRole, AuthenticateResult}}}, 
+        authentication::{
+            auth_service::AuthService, 
+            login_throttle::MAX_FAILED_LOGIN_ATTEMPTS, 
+            password_hasher::{is_password_hashed, verify_password}, 
+            totp::{generate_recovery_codes, generate_totp_secret, get_totp_code, get_totp_step}
+        }
+    }, 
     common_test::fixtures::get_app_data
 };
+use chrono::{Duration, Utc};
+use futures::future::join_all;
+use uuid::Uuid;
 use fake::{Fake, faker::internet::en::{Username, SafeEmail, Password}};
 
 #[tokio::test]
@@ -9,7 +20,7 @@ async fn test_insert_administrator_returns_valid_admin() {
     let repo = DbRepo::init().await;
     let app_data = get_app_data(repo, AuthService).await;
 
-    let entity_result = app_data.repo.insert_administrator(Username().fake::<String>(), SafeEmail().fake::<String>(), Password(5..10).fake::<String>()).await.unwrap();
+    let entity_result = app_data.repo.insert_administrator(Username().fake::<String>(), SafeEmail().fake::<String>(), Password(5..10).fake::<String>(), AdminRole::Author).await.unwrap();
     
     assert!(entity_result.id > 0);
 }
@@ -22,13 +33,14 @@ async fn test_query_administrator_returns_correct_admin() {
     let email = SafeEmail().fake::<String>();
     let password = Password(5..10).fake::<String>();
 
-    let entity_result = app_data.repo.insert_administrator(user_name.clone(), email.clone(), password.clone()).await.unwrap();
+    let entity_result = app_data.repo.insert_administrator(user_name.clone(), email.clone(), password.clone(), AdminRole::Author).await.unwrap();
     let admin = app_data.repo.query_administrator(entity_result.id).await.unwrap().unwrap();
 
     assert!(admin.id == entity_result.id);
     assert!(admin.user_name == user_name);
     assert!(admin.email == email);
-    assert!(admin.password == password);
+    assert!(admin.password != password);
+    assert!(verify_password(&password, &admin.password));
 }
 
 #[tokio::test]
@@ -39,8 +51,232 @@ async fn test_authenticate_db_returns_correct_authenticateresult() {
     let email = SafeEmail().fake::<String>();
     let password = Password(5..10).fake::<String>();
 
-    let entity_result = app_data.repo.insert_administrator(user_name.clone(), email.clone(), password.clone()).await.unwrap();
+    let entity_result = app_data.repo.insert_administrator(user_name.clone(), email.clone(), password.clone(), AdminRole::Author).await.unwrap();
     let auth_result = app_data.repo.authenticate_db(email, password).await.unwrap();
 
     assert!(auth_result == AuthenticateResult::Success { id: entity_result.id });
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_authenticate_db_returns_failure_on_wrong_password() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+    let user_name = Username().fake::<String>();
+    let email = SafeEmail().fake::<String>();
+    let password = Password(5..10).fake::<String>();
+
+    _ = app_data.repo.insert_administrator(user_name.clone(), email.clone(), password.clone(), AdminRole::Author).await.unwrap();
+    let auth_result = app_data.repo.authenticate_db(email, format!("{}x", password)).await.unwrap();
+
+    assert!(auth_result == AuthenticateResult::Failure);
+}
+
+#[tokio::test]
+async fn test_authenticate_db_rehashes_legacy_plaintext_password() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+    let user_name = Username().fake::<String>();
+    let email = SafeEmail().fake::<String>();
+    let password = Password(5..10).fake::<String>();
+
+    // simulate a row created before passwords were hashed
+    let entity_result = sqlx::query_as::<_, EntityId>("insert into administrator (user_name, email, password) values ($1, $2, $3) returning id")
+        .bind(user_name)
+        .bind(email.clone())
+        .bind(password.clone())
+        .fetch_one(app_data.repo.get_conn())
+        .await
+        .unwrap();
+
+    let auth_result = app_data.repo.authenticate_db(email.clone(), password.clone()).await.unwrap();
+    assert!(auth_result == AuthenticateResult::Success { id: entity_result.id });
+
+    let admin = app_data.repo.query_administrator(entity_result.id).await.unwrap().unwrap();
+    assert!(is_password_hashed(&admin.password));
+    assert!(verify_password(&password, &admin.password));
+
+    let auth_result = app_data.repo.authenticate_db(email, password).await.unwrap();
+    assert!(auth_result == AuthenticateResult::Success { id: entity_result.id });
+}
+
+#[tokio::test]
+async fn test_authenticate_db_locks_account_after_repeated_failures() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+    let user_name = Username().fake::<String>();
+    let email = SafeEmail().fake::<String>();
+    let password = Password(5..10).fake::<String>();
+
+    _ = app_data.repo.insert_administrator(user_name, email.clone(), password.clone(), AdminRole::Author).await.unwrap();
+    for _ in 1..MAX_FAILED_LOGIN_ATTEMPTS {
+        let auth_result = app_data.repo.authenticate_db(email.clone(), format!("{}x", password)).await.unwrap();
+        assert!(auth_result == AuthenticateResult::Failure);
+    }
+    let locking_result = app_data.repo.authenticate_db(email.clone(), format!("{}x", password)).await.unwrap();
+    // the correct password is not accepted while locked
+    let locked_result = app_data.repo.authenticate_db(email, password).await.unwrap();
+
+    assert!(matches!(locking_result, AuthenticateResult::Locked { .. }));
+    assert!(matches!(locked_result, AuthenticateResult::Locked { .. }));
+}
+
+#[tokio::test]
+async fn test_authenticate_db_counts_concurrent_failures() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+    let user_name = Username().fake::<String>();
+    let email = SafeEmail().fake::<String>();
+    let password = Password(5..10).fake::<String>();
+
+    _ = app_data.repo.insert_administrator(user_name, email.clone(), password.clone(), AdminRole::Author).await.unwrap();
+    let auth_results = join_all((0..MAX_FAILED_LOGIN_ATTEMPTS).map(|_| app_data.repo.authenticate_db(email.clone(), format!("{}x", password)))).await;
+    // no failure is lost, so the account is locked once the limit is reached
+    let locked_result = app_data.repo.authenticate_db(email, password).await.unwrap();
+
+    assert!(auth_results.iter().all(|result| result.is_ok()));
+    assert!(matches!(locked_result, AuthenticateResult::Locked { .. }));
+}
+
+#[tokio::test]
+async fn test_authenticate_db_resets_failures_on_success() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+    let user_name = Username().fake::<String>();
+    let email = SafeEmail().fake::<String>();
+    let password = Password(5..10).fake::<String>();
+
+    _ = app_data.repo.insert_administrator(user_name, email.clone(), password.clone(), AdminRole::Author).await.unwrap();
+    for _ in 1..MAX_FAILED_LOGIN_ATTEMPTS {
+        _ = app_data.repo.authenticate_db(email.clone(), format!("{}x", password)).await.unwrap();
+    }
+    let success_result = app_data.repo.authenticate_db(email.clone(), password.clone()).await.unwrap();
+    let failure_result = app_data.repo.authenticate_db(email, format!("{}x", password)).await.unwrap();
+
+    assert!(matches!(success_result, AuthenticateResult::Success { .. }));
+    assert!(failure_result == AuthenticateResult::Failure);
+}
+
+#[tokio::test]
+async fn test_enable_totp_requires_valid_code() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+    let entity_result = app_data.repo.insert_administrator(Username().fake::<String>(), SafeEmail().fake::<String>(), Password(5..10).fake::<String>(), AdminRole::Author).await.unwrap();
+    let secret = generate_totp_secret();
+
+    app_data.repo.insert_totp_enrollment(entity_result.id, secret.clone(), generate_recovery_codes()).await.unwrap();
+    let pending = app_data.repo.query_administrator_totp(entity_result.id).await.unwrap().unwrap();
+    assert!(pending.totp_secret == Some(secret.clone()));
+    assert!(!pending.totp_enabled);
+
+    assert!(!app_data.repo.enable_totp(entity_result.id, "abcdef".to_string()).await.unwrap());
+    let code = get_totp_code(&secret, get_totp_step(Utc::now().timestamp())).unwrap();
+    assert!(app_data.repo.enable_totp(entity_result.id, code).await.unwrap());
+    assert!(app_data.repo.query_administrator_totp(entity_result.id).await.unwrap().unwrap().totp_enabled);
+}
+
+#[tokio::test]
+async fn test_authenticate_totp_rejects_reused_code() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+    let entity_result = app_data.repo.insert_administrator(Username().fake::<String>(), SafeEmail().fake::<String>(), Password(5..10).fake::<String>(), AdminRole::Author).await.unwrap();
+    let secret = generate_totp_secret();
+    app_data.repo.insert_totp_enrollment(entity_result.id, secret.clone(), generate_recovery_codes()).await.unwrap();
+    let step = get_totp_step(Utc::now().timestamp());
+    app_data.repo.enable_totp(entity_result.id, get_totp_code(&secret, step).unwrap()).await.unwrap();
+
+    // the code used to confirm enrollment cannot log in again, the next step's code is still within drift
+    assert!(!app_data.repo.authenticate_totp(entity_result.id, get_totp_code(&secret, step).unwrap()).await.unwrap());
+    assert!(app_data.repo.authenticate_totp(entity_result.id, get_totp_code(&secret, step + 1).unwrap()).await.unwrap());
+    assert!(!app_data.repo.authenticate_totp(entity_result.id, get_totp_code(&secret, step + 1).unwrap()).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_authenticate_totp_accepts_recovery_code_once() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+    let entity_result = app_data.repo.insert_administrator(Username().fake::<String>(), SafeEmail().fake::<String>(), Password(5..10).fake::<String>(), AdminRole::Author).await.unwrap();
+    let secret = generate_totp_secret();
+    let recovery_codes = generate_recovery_codes();
+    app_data.repo.insert_totp_enrollment(entity_result.id, secret.clone(), recovery_codes.clone()).await.unwrap();
+    app_data.repo.enable_totp(entity_result.id, get_totp_code(&secret, get_totp_step(Utc::now().timestamp())).unwrap()).await.unwrap();
+    let recovery_code = recovery_codes.first().unwrap();
+
+    assert!(app_data.repo.authenticate_totp(entity_result.id, recovery_code.to_uppercase()).await.unwrap());
+    assert!(!app_data.repo.authenticate_totp(entity_result.id, recovery_code.clone()).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_authenticate_totp_refuses_deactivated_administrator() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+    let entity_result = app_data.repo.insert_administrator(Username().fake::<String>(), SafeEmail().fake::<String>(), Password(5..10).fake::<String>(), AdminRole::Author).await.unwrap();
+    let secret = generate_totp_secret();
+    let recovery_codes = generate_recovery_codes();
+    app_data.repo.insert_totp_enrollment(entity_result.id, secret.clone(), recovery_codes.clone()).await.unwrap();
+    let step = get_totp_step(Utc::now().timestamp());
+    app_data.repo.enable_totp(entity_result.id, get_totp_code(&secret, step).unwrap()).await.unwrap();
+
+    app_data.repo.deactivate_administrator(entity_result.id).await.unwrap();
+
+    assert!(!app_data.repo.authenticate_totp(entity_result.id, get_totp_code(&secret, step + 1).unwrap()).await.unwrap());
+    assert!(!app_data.repo.authenticate_totp(entity_result.id, recovery_codes.first().unwrap().clone()).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_update_administrator_changes_values() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+    let entity_result = app_data.repo.insert_administrator(Username().fake::<String>(), SafeEmail().fake::<String>(), Password(5..10).fake::<String>(), AdminRole::Author).await.unwrap();
+    let user_name = Username().fake::<String>();
+    let email = SafeEmail().fake::<String>();
+
+    app_data.repo.update_administrator(entity_result.id, user_name.clone(), email.clone(), AdminRole::Editor).await.unwrap();
+    let admin = app_data.repo.query_administrator(entity_result.id).await.unwrap().unwrap();
+
+    assert!(admin.user_name == user_name);
+    assert!(admin.email == email);
+    assert!(admin.role == AdminRole::Editor);
+}
+
+#[tokio::test]
+async fn test_query_administrators_returns_inserted_admin() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+    let entity_result = app_data.repo.insert_administrator(Username().fake::<String>(), SafeEmail().fake::<String>(), Password(5..10).fake::<String>(), AdminRole::Author).await.unwrap();
+
+    let admins = app_data.repo.query_administrators(i32::MAX, None).await.unwrap();
+
+    assert!(admins.iter().any(|admin| admin.id == entity_result.id && admin.is_active));
+}
+
+#[tokio::test]
+async fn test_deactivate_administrator_prevents_login() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+    let email = SafeEmail().fake::<String>();
+    let password = Password(5..10).fake::<String>();
+    let entity_result = app_data.repo.insert_administrator(Username().fake::<String>(), email.clone(), password.clone(), AdminRole::Author).await.unwrap();
+
+    app_data.repo.deactivate_administrator(entity_result.id).await.unwrap();
+
+    assert!(!app_data.repo.query_administrator(entity_result.id).await.unwrap().unwrap().is_active);
+    assert!(app_data.repo.authenticate_db(email, password).await.unwrap() == AuthenticateResult::Failure);
+}
+
+#[tokio::test]
+async fn test_update_administrator_password_revokes_refresh_tokens_when_asked() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+    let admin_id = app_data.repo.insert_administrator(Username().fake::<String>(), SafeEmail().fake::<String>(), Password(5..10).fake::<String>(), AdminRole::Author).await.unwrap().id;
+    let kept_jti = Uuid::new_v4().to_string();
+    let revoked_jti = Uuid::new_v4().to_string();
+
+    app_data.repo.insert_refresh_token(admin_id, kept_jti.clone(), Uuid::new_v4().to_string(), Utc::now() + Duration::days(1)).await.unwrap();
+    app_data.repo.update_administrator_password(admin_id, Password(8..12).fake::<String>(), false).await.unwrap();
+    assert!(app_data.repo.query_refresh_token(kept_jti.clone()).await.unwrap().unwrap().revoked_at.is_none());
+
+    app_data.repo.insert_refresh_token(admin_id, revoked_jti.clone(), Uuid::new_v4().to_string(), Utc::now() + Duration::days(1)).await.unwrap();
+    app_data.repo.update_administrator_password(admin_id, Password(8..12).fake::<String>(), true).await.unwrap();
+    assert!(app_data.repo.query_refresh_token(kept_jti).await.unwrap().unwrap().revoked_at.is_some());
+    assert!(app_data.repo.query_refresh_token(revoked_jti).await.unwrap().unwrap().revoked_at.is_some());
+}
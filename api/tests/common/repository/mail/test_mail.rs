@@ -1,7 +1,24 @@
 use rustyindie_api::{
-    common::{repository::{base::{DbRepo, Repository}, mail::repo::{QueryMailFn, QueryLatestMailFn}}, authentication::auth_service::AuthService}, 
+    common::{repository::{base::{DbRepo, Repository}, mail::repo::{DeleteMailFn, InsertMailFn, QueryMailFn, QueryLatestMailFn, UpdateMailReadFn}}, authentication::auth_service::AuthService}, 
     common_test::fixtures::get_app_data
 };
+use fake::{Fake, faker::{internet::en::SafeEmail, lorem::en::Sentence}};
+
+#[tokio::test]
+async fn test_insert_mail_returns_valid_id() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+    let from = SafeEmail().fake::<String>();
+    let subject = Sentence(1..2).fake::<String>();
+    let message = Sentence(3..5).fake::<String>();
+
+    let entity_result = app_data.repo.insert_mail(from.clone(), subject, message.clone()).await.unwrap();
+    let mail = app_data.repo.query_mail(entity_result.id).await.unwrap().unwrap();
+
+    assert!(entity_result.id > 0);
+    assert!(mail.from == from);
+    assert!(mail.message == message);
+}
 
 #[tokio::test]
 async fn test_query_latest_mail_return_correct_mail() {
@@ -9,7 +26,7 @@ async fn test_query_latest_mail_return_correct_mail() {
     let app_data = get_app_data(repo, AuthService).await;
 
     // requires the db test setup scripts to run
-    let mail_result = app_data.repo.query_latest_mail(10, 0).await.unwrap();
+    let mail_result = app_data.repo.query_latest_mail(10, None).await.unwrap();
     
     assert!(mail_result.len() > 0);
     assert!(mail_result.first().unwrap().updated_at > mail_result.last().unwrap().updated_at);
@@ -24,4 +41,37 @@ async fn test_query_mail_return_correct_mail() {
     let mail_result = app_data.repo.query_mail(1).await.unwrap();
     
     assert!(mail_result.is_some());
+}
+
+#[tokio::test]
+async fn test_update_mail_read_sets_and_clears_read_at() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+
+    let entity_result = app_data.repo.insert_mail(SafeEmail().fake::<String>(), Sentence(1..2).fake::<String>(), Sentence(3..5).fake::<String>()).await.unwrap();
+    let mail = app_data.repo.query_mail(entity_result.id).await.unwrap().unwrap();
+    assert!(!mail.is_read);
+    assert!(mail.read_at.is_none());
+
+    app_data.repo.update_mail_read(entity_result.id, true).await.unwrap();
+    let mail = app_data.repo.query_mail(entity_result.id).await.unwrap().unwrap();
+    assert!(mail.is_read);
+    assert!(mail.read_at.is_some());
+
+    app_data.repo.update_mail_read(entity_result.id, false).await.unwrap();
+    let mail = app_data.repo.query_mail(entity_result.id).await.unwrap().unwrap();
+    assert!(!mail.is_read);
+    assert!(mail.read_at.is_none());
+}
+
+#[tokio::test]
+async fn test_delete_mail_removes_mail() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+
+    let entity_result = app_data.repo.insert_mail(SafeEmail().fake::<String>(), Sentence(1..2).fake::<String>(), Sentence(3..5).fake::<String>()).await.unwrap();
+    app_data.repo.delete_mail(entity_result.id).await.unwrap();
+    let mail = app_data.repo.query_mail(entity_result.id).await.unwrap();
+
+    assert!(mail.is_none());
 }
\ No newline at end of file
@@ -0,0 +1,58 @@
+use chrono::{Duration, Utc};
+use fake::{Fake, faker::internet::en::{Password, SafeEmail, Username}};
+use rustyindie_api::{
+    common::{
+        repository::{
+            base::{DbRepo, Repository},
+            administrator::{repo::{AuthenticateDbFn, InsertAdminisratorFn}, models::{AdminRole, AuthenticateResult}},
+            password_reset::{repo::{InsertPasswordResetTokenFn, ResetPasswordFn}, models::ResetPasswordResult}
+        },
+        authentication::{auth_service::AuthService, password_reset::generate_password_reset_token}
+    },
+    common_test::fixtures::get_app_data
+};
+
+#[tokio::test]
+async fn test_reset_password_sets_new_password_once() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+    let email = SafeEmail().fake::<String>();
+    let admin_id = app_data.repo.insert_administrator(Username().fake::<String>(), email.clone(), Password(5..10).fake::<String>(), AdminRole::Author).await.unwrap().id;
+    let (_, token_hash) = generate_password_reset_token();
+    let new_password = Password(10..20).fake::<String>();
+
+    app_data.repo.insert_password_reset_token(admin_id, token_hash.clone(), Utc::now() + Duration::minutes(30)).await.unwrap();
+    let first = app_data.repo.reset_password(token_hash.clone(), new_password.clone()).await.unwrap();
+    let second = app_data.repo.reset_password(token_hash, Password(10..20).fake::<String>()).await.unwrap();
+
+    assert!(first == ResetPasswordResult::Reset { admin_id });
+    assert!(second == ResetPasswordResult::Used);
+    assert!(app_data.repo.authenticate_db(email, new_password).await.unwrap() == AuthenticateResult::Success { id: admin_id });
+}
+
+#[tokio::test]
+async fn test_reset_password_rejects_expired_and_replaced_tokens() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+    let admin_id = app_data.repo.insert_administrator(Username().fake::<String>(), SafeEmail().fake::<String>(), Password(5..10).fake::<String>(), AdminRole::Author).await.unwrap().id;
+    let (_, expired_hash) = generate_password_reset_token();
+    let (_, replaced_hash) = generate_password_reset_token();
+    let (_, newest_hash) = generate_password_reset_token();
+
+    app_data.repo.insert_password_reset_token(admin_id, expired_hash.clone(), Utc::now() - Duration::minutes(1)).await.unwrap();
+    assert!(app_data.repo.reset_password(expired_hash, Password(10..20).fake::<String>()).await.unwrap() == ResetPasswordResult::Expired);
+
+    app_data.repo.insert_password_reset_token(admin_id, replaced_hash.clone(), Utc::now() + Duration::minutes(30)).await.unwrap();
+    app_data.repo.insert_password_reset_token(admin_id, newest_hash.clone(), Utc::now() + Duration::minutes(30)).await.unwrap();
+    assert!(app_data.repo.reset_password(replaced_hash, Password(10..20).fake::<String>()).await.unwrap() == ResetPasswordResult::Used);
+    assert!(app_data.repo.reset_password(newest_hash, Password(10..20).fake::<String>()).await.unwrap() == ResetPasswordResult::Reset { admin_id });
+}
+
+#[tokio::test]
+async fn test_reset_password_returns_not_found_for_unknown_token() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+    let (_, token_hash) = generate_password_reset_token();
+
+    assert!(app_data.repo.reset_password(token_hash, Password(10..20).fake::<String>()).await.unwrap() == ResetPasswordResult::NotFound);
+}
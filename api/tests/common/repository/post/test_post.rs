@@ -1,9 +1,20 @@
 use rustyindie_api::{
-    common::{repository::{base::{DbRepo, Repository}, administrator::repo::InsertAdminisratorFn, post::repo::{InsertPostFn, QueryPostsFn}}, authentication::auth_service::AuthService}, 
+    common::{repository::{base::{ConnGetter, DbRepo, PageCursor, Repository}, administrator::{repo::InsertAdminisratorFn, models::AdminRole}, post::{models::{PostContent, PostHeading, PostPublication, PostStatus}, repo::{InsertPostFn, PublishScheduledPostsFn, QueryPostBySlugFn, RenderStalePostsFn, QueryPostFn, QueryPostSlugRedirectFn, QueryPostsByStatusFn, QueryPostsFn, SearchPostsFn, UpdatePostFn}}}, authentication::auth_service::AuthService}, 
     common_test::fixtures::get_app_data
 };
+use chrono::{Duration, Utc};
+use futures::future::join_all;
+use sqlx::query;
 use fake::{Fake, faker::{internet::en::{Username, SafeEmail, Password}, lorem::en::Sentence}};
 
+fn get_content(title: String, message: String) -> PostContent {
+    PostContent { title, message, excerpt: None }
+}
+
+fn get_publication() -> PostPublication {
+    PostPublication { status: PostStatus::Published, published_at: Some(Utc::now()) }
+}
+
 #[tokio::test]
 async fn test_query_posts_return_correct_posts() {
     let repo = DbRepo::init().await;
@@ -11,12 +22,15 @@ async fn test_query_posts_return_correct_posts() {
     let title = Sentence(1..2).fake::<String>();
     let message = Sentence(1..5).fake::<String>();
 
-    let entity_admin_result = app_data.repo.insert_administrator(Username().fake::<String>(), SafeEmail().fake::<String>(), Password(5..10).fake::<String>()).await.unwrap();
-    _ = app_data.repo.insert_post(title.clone(), message.clone(), entity_admin_result.id).await.unwrap();
-    let post_result = app_data.repo.query_posts(10, 0).await.unwrap();
+    let entity_admin_result = app_data.repo.insert_administrator(Username().fake::<String>(), SafeEmail().fake::<String>(), Password(5..10).fake::<String>(), AdminRole::Author).await.unwrap();
+    _ = app_data.repo.insert_post(get_content(title.clone(), message.clone()), None, vec![], get_publication(), entity_admin_result.id).await.unwrap();
+    let post_result = app_data.repo.query_posts(10, None).await.unwrap();
     
     assert!(post_result.len() > 0);
     assert!(post_result.first().unwrap().message == message);
+    assert!(post_result.first().unwrap().rendered_html == Some(format!("<p>{}</p>", message)));
+    assert!(post_result.first().unwrap().summary_html == Some(format!("<p>{}</p>", message)));
+    assert!(post_result.first().unwrap().summary_text == Some(message.clone()));
     assert!(post_result.first().unwrap().admin_id == entity_admin_result.id);
 }
 
@@ -27,8 +41,205 @@ async fn test_insert_post_returns_valid_admin() {
     let title = Sentence(1..2).fake::<String>();
     let message = Sentence(1..5).fake::<String>();
 
-    let entity_admin_result = app_data.repo.insert_administrator(Username().fake::<String>(), SafeEmail().fake::<String>(), Password(5..10).fake::<String>()).await.unwrap();
-    let entity_post_result = app_data.repo.insert_post(title.clone(), message.clone(), entity_admin_result.id).await.unwrap();
+    let entity_admin_result = app_data.repo.insert_administrator(Username().fake::<String>(), SafeEmail().fake::<String>(), Password(5..10).fake::<String>(), AdminRole::Author).await.unwrap();
+    let entity_post_result = app_data.repo.insert_post(get_content(title.clone(), message.clone()), None, vec![], get_publication(), entity_admin_result.id).await.unwrap();
     
     assert!(entity_post_result.id > 0);
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_insert_post_gives_repeated_titles_unique_slugs() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+    let title = format!("Repeated title {}", Username().fake::<String>());
+
+    let entity_admin_result = app_data.repo.insert_administrator(Username().fake::<String>(), SafeEmail().fake::<String>(), Password(5..10).fake::<String>(), AdminRole::Author).await.unwrap();
+    let first_post_id = app_data.repo.insert_post(get_content(title.clone(), Sentence(1..5).fake::<String>()), None, vec![], get_publication(), entity_admin_result.id).await.unwrap().id;
+    let second_post_id = app_data.repo.insert_post(get_content(title.clone(), Sentence(1..5).fake::<String>()), None, vec![], get_publication(), entity_admin_result.id).await.unwrap().id;
+
+    let first_slug = app_data.repo.query_post(first_post_id).await.unwrap().unwrap().slug;
+    let second_slug = app_data.repo.query_post(second_post_id).await.unwrap().unwrap().slug;
+    assert!(first_slug.starts_with("repeated-title-"));
+    assert!(second_slug == format!("{}-1", first_slug));
+}
+
+#[tokio::test]
+async fn test_insert_post_gives_concurrent_repeated_titles_unique_slugs() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+    let title = format!("Concurrent title {}", Username().fake::<String>());
+
+    let entity_admin_result = app_data.repo.insert_administrator(Username().fake::<String>(), SafeEmail().fake::<String>(), Password(5..10).fake::<String>(), AdminRole::Author).await.unwrap();
+    let insert_results = join_all((0..5).map(|_| app_data.repo.insert_post(get_content(title.clone(), Sentence(1..5).fake::<String>()), None, vec![], get_publication(), entity_admin_result.id))).await;
+
+    let mut slugs = vec![];
+    for insert_result in insert_results {
+        slugs.push(app_data.repo.query_post(insert_result.unwrap().id).await.unwrap().unwrap().slug);
+    }
+    slugs.sort();
+    slugs.dedup();
+    assert!(slugs.len() == 5);
+}
+
+#[tokio::test]
+async fn test_update_post_keeps_old_slug_as_redirect() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+    let title = format!("Renamed {}", Username().fake::<String>());
+    let message = Sentence(1..5).fake::<String>();
+
+    let entity_admin_result = app_data.repo.insert_administrator(Username().fake::<String>(), SafeEmail().fake::<String>(), Password(5..10).fake::<String>(), AdminRole::Author).await.unwrap();
+    let post_id = app_data.repo.insert_post(get_content(title.clone(), message.clone()), None, vec![], get_publication(), entity_admin_result.id).await.unwrap().id;
+    let old_slug = app_data.repo.query_post(post_id).await.unwrap().unwrap().slug;
+    let new_slug = format!("{}-new", old_slug);
+    app_data.repo.update_post(post_id, get_content(title.clone(), message.clone()), Some(new_slug.clone()), None, get_publication(), entity_admin_result.id).await.unwrap();
+
+    assert!(app_data.repo.query_post_by_slug(new_slug.clone()).await.unwrap().unwrap().id == post_id);
+    assert!(app_data.repo.query_post_by_slug(old_slug.clone()).await.unwrap().is_none());
+    assert!(app_data.repo.query_post_slug_redirect(old_slug.clone()).await.unwrap() == Some(new_slug.clone()));
+
+    // no slug keeps the current one, even when the title changes
+    app_data.repo.update_post(post_id, get_content(format!("{} again", title), message), None, None, get_publication(), entity_admin_result.id).await.unwrap();
+    assert!(app_data.repo.query_post(post_id).await.unwrap().unwrap().slug == new_slug);
+}
+
+#[tokio::test]
+async fn test_query_posts_only_returns_public_posts() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+
+    let entity_admin_result = app_data.repo.insert_administrator(Username().fake::<String>(), SafeEmail().fake::<String>(), Password(5..10).fake::<String>(), AdminRole::Author).await.unwrap();
+    let draft_post_id = app_data.repo.insert_post(
+        get_content(Sentence(1..2).fake::<String>(), Sentence(1..5).fake::<String>()), 
+        None, 
+        vec![], 
+        PostPublication { status: PostStatus::Draft, published_at: None }, 
+        entity_admin_result.id
+    ).await.unwrap().id;
+    let scheduled_post_id = app_data.repo.insert_post(
+        get_content(Sentence(1..2).fake::<String>(), Sentence(1..5).fake::<String>()), 
+        None, 
+        vec![], 
+        PostPublication { status: PostStatus::Scheduled, published_at: Some(Utc::now() + Duration::days(1)) }, 
+        entity_admin_result.id
+    ).await.unwrap().id;
+    let published_post_id = app_data.repo.insert_post(get_content(Sentence(1..2).fake::<String>(), Sentence(1..5).fake::<String>()), None, vec![], get_publication(), entity_admin_result.id).await.unwrap().id;
+
+    let post_ids = app_data.repo.query_posts(100, None).await.unwrap().iter().map(|post| post.id).collect::<Vec<i64>>();
+    assert!(post_ids.contains(&published_post_id));
+    assert!(!post_ids.contains(&draft_post_id) && !post_ids.contains(&scheduled_post_id));
+
+    let draft_post_ids = app_data.repo.query_posts_by_status(Some(PostStatus::Draft), 100, None).await.unwrap().iter().map(|post| post.id).collect::<Vec<i64>>();
+    assert!(draft_post_ids.contains(&draft_post_id) && !draft_post_ids.contains(&published_post_id));
+}
+
+#[tokio::test]
+async fn test_query_posts_pages_by_cursor_without_overlap() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+    // far enough back that no other test's posts sit between these
+    let published_at = Utc::now() - Duration::days((1_000..100_000).fake::<i64>());
+
+    let entity_admin_result = app_data.repo.insert_administrator(Username().fake::<String>(), SafeEmail().fake::<String>(), Password(5..10).fake::<String>(), AdminRole::Author).await.unwrap();
+    let mut post_ids = vec![];
+    for post_published_at in [published_at - Duration::seconds(1), published_at, published_at] {
+        post_ids.push(app_data.repo.insert_post(
+            get_content(Sentence(1..2).fake::<String>(), Sentence(1..5).fake::<String>()), 
+            None, 
+            vec![], 
+            PostPublication { status: PostStatus::Published, published_at: Some(post_published_at) }, 
+            entity_admin_result.id
+        ).await.unwrap().id);
+    }
+
+    let first_page = app_data.repo.query_posts(2, Some(PageCursor { sort_at: published_at + Duration::seconds(1), id: 0 })).await.unwrap();
+    let second_page = app_data.repo.query_posts(2, Some(first_page.last().unwrap().published_cursor())).await.unwrap();
+
+    // posts published at the same time are ordered by id
+    assert!(first_page.iter().map(|post| post.id).collect::<Vec<i64>>() == vec![post_ids[2], post_ids[1]]);
+    assert!(second_page.first().unwrap().id == post_ids[0]);
+}
+
+#[tokio::test]
+async fn test_publish_scheduled_posts_publishes_posts_whose_time_has_passed() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+
+    let entity_admin_result = app_data.repo.insert_administrator(Username().fake::<String>(), SafeEmail().fake::<String>(), Password(5..10).fake::<String>(), AdminRole::Author).await.unwrap();
+    let post_id = app_data.repo.insert_post(
+        get_content(Sentence(1..2).fake::<String>(), Sentence(1..5).fake::<String>()), 
+        None, 
+        vec![], 
+        PostPublication { status: PostStatus::Scheduled, published_at: Some(Utc::now() - Duration::minutes(1)) }, 
+        entity_admin_result.id
+    ).await.unwrap().id;
+
+    let published_count = app_data.repo.publish_scheduled_posts().await.unwrap();
+
+    assert!(published_count > 0);
+    assert!(app_data.repo.query_post(post_id).await.unwrap().unwrap().status == PostStatus::Published);
+}
+
+#[tokio::test]
+async fn test_search_posts_ranks_title_matches_first_and_highlights_matches() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+    // made up, so no other post matches it
+    let search_word = format!("zq{}", Username().fake::<String>().to_lowercase().replace(|c: char| !c.is_ascii_alphabetic(), ""));
+
+    let entity_admin_result = app_data.repo.insert_administrator(Username().fake::<String>(), SafeEmail().fake::<String>(), Password(5..10).fake::<String>(), AdminRole::Author).await.unwrap();
+    let message_match_id = app_data.repo.insert_post(
+        get_content(Sentence(1..2).fake::<String>(), format!("Something **about {}** in [the message](https://example.com/link).", search_word)), 
+        None, 
+        vec![], 
+        get_publication(), 
+        entity_admin_result.id
+    ).await.unwrap().id;
+    let title_match_id = app_data.repo.insert_post(
+        get_content(format!("All about {}", search_word), format!("Mentions {} once more.", search_word)), 
+        None, 
+        vec![], 
+        get_publication(), 
+        entity_admin_result.id
+    ).await.unwrap().id;
+    let draft_match_id = app_data.repo.insert_post(
+        get_content(format!("Draft about {}", search_word), Sentence(1..5).fake::<String>()), 
+        None, 
+        vec![], 
+        PostPublication { status: PostStatus::Draft, published_at: None }, 
+        entity_admin_result.id
+    ).await.unwrap().id;
+
+    let results = app_data.repo.search_posts(search_word.clone(), 10, None).await.unwrap();
+    let result_ids = results.iter().map(|result| result.post.id).collect::<Vec<i64>>();
+
+    assert!(result_ids == vec![title_match_id, message_match_id]);
+    assert!(!result_ids.contains(&draft_match_id));
+    assert!(results[1].snippet_html.contains(&format!("<mark>{}</mark>", search_word)));
+    // cut from the message's text, so no markdown shows
+    assert!(!results[1].snippet_html.contains("**") && !results[1].snippet_html.contains("https://example.com"));
+    assert!(app_data.repo.search_posts(format!("{} -message", search_word), 10, None).await.unwrap().iter().all(|result| result.post.id != message_match_id));
+}
+
+#[tokio::test]
+async fn test_insert_post_stores_headings_and_renders_stale_posts() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+    let message = "## Getting started\n\nSome text\n\n### Install".to_string();
+
+    let entity_admin_result = app_data.repo.insert_administrator(Username().fake::<String>(), SafeEmail().fake::<String>(), Password(5..10).fake::<String>(), AdminRole::Author).await.unwrap();
+    let post_id = app_data.repo.insert_post(get_content(Sentence(1..2).fake::<String>(), message), None, vec![], get_publication(), entity_admin_result.id).await.unwrap().id;
+    let headings = app_data.repo.query_post(post_id).await.unwrap().unwrap().headings.unwrap().0;
+    // as a post rendered before headings were stored
+    query("update post set rendered_html = null, headings = null where id = $1").bind(post_id).execute(app_data.repo.get_conn()).await.unwrap();
+    let rendered_count = app_data.repo.render_stale_posts().await.unwrap();
+    let rendered_post = app_data.repo.query_post(post_id).await.unwrap().unwrap();
+
+    assert!(headings == vec![
+        PostHeading { id: "getting-started".to_string(), level: 2, text: "Getting started".to_string() },
+        PostHeading { id: "install".to_string(), level: 3, text: "Install".to_string() }
+    ]);
+    assert!(rendered_count >= 1);
+    assert!(rendered_post.rendered_html.unwrap().starts_with("<h2 id=\"getting-started\">"));
+    assert!(rendered_post.headings.unwrap().0 == headings);
+}
@@ -0,0 +1,43 @@
+use rustyindie_api::{
+    common::{
+        repository::{
+            base::{DbRepo, Repository}, 
+            administrator::{repo::InsertAdminisratorFn, models::AdminRole}, 
+            post::{models::{PostContent, PostPublication, PostStatus}, repo::{InsertPostFn, UpdatePostFn}}, 
+            post_revision::repo::{QueryPostRevisionFn, QueryPostRevisionsFn}
+        }, 
+        authentication::auth_service::AuthService
+    }, 
+    common_test::fixtures::get_app_data
+};
+use chrono::Utc;
+use fake::{Fake, faker::{internet::en::{Username, SafeEmail, Password}, lorem::en::Sentence}};
+
+fn get_content(title: String, message: String) -> PostContent {
+    PostContent { title, message, excerpt: None }
+}
+
+fn get_publication() -> PostPublication {
+    PostPublication { status: PostStatus::Published, published_at: Some(Utc::now()) }
+}
+
+#[tokio::test]
+async fn test_saving_a_post_adds_a_revision() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+    let title = Sentence(1..2).fake::<String>();
+    let message = Sentence(1..5).fake::<String>();
+    let updated_message = format!("{}\n\nAn added paragraph.", message);
+
+    let entity_admin_result = app_data.repo.insert_administrator(Username().fake::<String>(), SafeEmail().fake::<String>(), Password(5..10).fake::<String>(), AdminRole::Author).await.unwrap();
+    let post_id = app_data.repo.insert_post(get_content(title.clone(), message.clone()), None, vec![], get_publication(), entity_admin_result.id).await.unwrap().id;
+    app_data.repo.update_post(post_id, get_content(title.clone(), updated_message.clone()), None, None, get_publication(), entity_admin_result.id).await.unwrap();
+
+    let revisions = app_data.repo.query_post_revisions(post_id).await.unwrap();
+    assert!(revisions.len() == 2);
+    assert!(revisions[0].message == updated_message && revisions[1].message == message);
+    assert!(revisions.iter().all(|revision| revision.post_id == post_id && revision.admin_id == entity_admin_result.id));
+
+    let first_revision = app_data.repo.query_post_revision(revisions[1].id).await.unwrap().unwrap();
+    assert!(first_revision.content() == get_content(title, message));
+}
\ No newline at end of file
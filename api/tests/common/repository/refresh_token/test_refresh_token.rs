@@ -0,0 +1,77 @@
+use chrono::{Duration, Utc};
+use fake::{Fake, faker::internet::en::{Password, SafeEmail, Username}};
+use rustyindie_api::{
+    common::{
+        repository::{
+            base::{DbRepo, Repository},
+            administrator::{repo::InsertAdminisratorFn, models::AdminRole},
+            refresh_token::{repo::{InsertRefreshTokenFn, QueryRefreshTokenFn, RevokeRefreshTokenFn, RotateRefreshTokenFn}, models::RotateRefreshTokenResult}
+        },
+        authentication::auth_service::AuthService
+    },
+    common_test::fixtures::get_app_data
+};
+use uuid::Uuid;
+
+async fn insert_admin_and_token(repo: &DbRepo) -> (i64, String) {
+    let admin_id = repo.insert_administrator(Username().fake::<String>(), SafeEmail().fake::<String>(), Password(5..10).fake::<String>(), AdminRole::Author).await.unwrap().id;
+    let jti = Uuid::new_v4().to_string();
+    repo.insert_refresh_token(admin_id, jti.clone(), Uuid::new_v4().to_string(), Utc::now() + Duration::days(1)).await.unwrap();
+
+    (admin_id, jti)
+}
+
+#[tokio::test]
+async fn test_rotate_refresh_token_replaces_token() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+    let (admin_id, jti) = insert_admin_and_token(&app_data.repo).await;
+    let new_jti = Uuid::new_v4().to_string();
+
+    let result = app_data.repo.rotate_refresh_token(jti.clone(), new_jti.clone(), Utc::now() + Duration::days(1)).await.unwrap();
+    let old_token = app_data.repo.query_refresh_token(jti).await.unwrap().unwrap();
+    let new_token = app_data.repo.query_refresh_token(new_jti.clone()).await.unwrap().unwrap();
+
+    assert!(result == RotateRefreshTokenResult::Rotated { admin_id });
+    assert!(old_token.revoked_at.is_some());
+    assert!(old_token.replaced_by == Some(new_jti));
+    assert!(new_token.family_id == old_token.family_id);
+    assert!(new_token.revoked_at.is_none());
+}
+
+#[tokio::test]
+async fn test_rotate_refresh_token_reuse_revokes_family() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+    let (_, jti) = insert_admin_and_token(&app_data.repo).await;
+    let new_jti = Uuid::new_v4().to_string();
+
+    _ = app_data.repo.rotate_refresh_token(jti.clone(), new_jti.clone(), Utc::now() + Duration::days(1)).await.unwrap();
+    let reuse_result = app_data.repo.rotate_refresh_token(jti, Uuid::new_v4().to_string(), Utc::now() + Duration::days(1)).await.unwrap();
+    let new_token = app_data.repo.query_refresh_token(new_jti).await.unwrap().unwrap();
+
+    assert!(reuse_result == RotateRefreshTokenResult::ReuseDetected);
+    assert!(new_token.revoked_at.is_some());
+}
+
+#[tokio::test]
+async fn test_revoke_refresh_token_prevents_rotation() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+    let (_, jti) = insert_admin_and_token(&app_data.repo).await;
+
+    app_data.repo.revoke_refresh_token(jti.clone()).await.unwrap();
+    let result = app_data.repo.rotate_refresh_token(jti, Uuid::new_v4().to_string(), Utc::now() + Duration::days(1)).await.unwrap();
+
+    assert!(result == RotateRefreshTokenResult::ReuseDetected);
+}
+
+#[tokio::test]
+async fn test_rotate_refresh_token_returns_not_found_for_unknown_jti() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+
+    let result = app_data.repo.rotate_refresh_token(Uuid::new_v4().to_string(), Uuid::new_v4().to_string(), Utc::now() + Duration::days(1)).await.unwrap();
+
+    assert!(result == RotateRefreshTokenResult::NotFound);
+}
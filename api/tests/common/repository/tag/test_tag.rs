@@ -0,0 +1,82 @@
+use rustyindie_api::{
+    common::{
+        repository::{
+            base::{DbRepo, Repository}, 
+            administrator::{repo::InsertAdminisratorFn, models::AdminRole}, 
+            post::{models::{PostContent, PostPublication, PostStatus}, repo::{InsertPostFn, QueryPostFn, QueryTagPostPreviewsFn, UpdatePostFn}}, 
+            tag::repo::QueryTagsFn
+        }, 
+        authentication::auth_service::AuthService
+    }, 
+    common_test::fixtures::get_app_data
+};
+use chrono::Utc;
+use fake::{Fake, faker::{internet::en::{Username, SafeEmail, Password}, lorem::en::Sentence}};
+
+fn get_content() -> PostContent {
+    PostContent { title: Sentence(1..2).fake::<String>(), message: Sentence(1..5).fake::<String>(), excerpt: None }
+}
+
+fn get_publication() -> PostPublication {
+    PostPublication { status: PostStatus::Published, published_at: Some(Utc::now()) }
+}
+
+#[tokio::test]
+async fn test_insert_post_adds_tags_and_lists_them_with_post_counts() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+    let tag_name = format!("Tag {}", Username().fake::<String>());
+
+    let entity_admin_result = app_data.repo.insert_administrator(Username().fake::<String>(), SafeEmail().fake::<String>(), Password(5..10).fake::<String>(), AdminRole::Author).await.unwrap();
+    let post_id = app_data.repo.insert_post(get_content(), None, vec![tag_name.clone()], get_publication(), entity_admin_result.id).await.unwrap().id;
+    _ = app_data.repo.insert_post(get_content(), None, vec![tag_name.clone()], get_publication(), entity_admin_result.id).await.unwrap();
+    // drafts are not counted
+    _ = app_data.repo.insert_post(get_content(), None, vec![tag_name.clone()], PostPublication { status: PostStatus::Draft, published_at: None }, entity_admin_result.id).await.unwrap();
+
+    let post_tags = app_data.repo.query_post(post_id).await.unwrap().unwrap().tags;
+    assert!(post_tags.len() == 1);
+    assert!(post_tags[0].name == tag_name);
+
+    let tags = app_data.repo.query_tags().await.unwrap();
+    let tag = tags.iter().find(|tag| tag.tag.name == tag_name).unwrap();
+    assert!(tag.tag.slug == post_tags[0].slug);
+    assert!(tag.post_count == 2);
+}
+
+#[tokio::test]
+async fn test_query_tag_post_previews_only_returns_posts_with_the_tag() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+    let tag_name = format!("Tag {}", Username().fake::<String>());
+    let other_tag_name = format!("Other {}", Username().fake::<String>());
+
+    let entity_admin_result = app_data.repo.insert_administrator(Username().fake::<String>(), SafeEmail().fake::<String>(), Password(5..10).fake::<String>(), AdminRole::Author).await.unwrap();
+    let tagged_post_id = app_data.repo.insert_post(get_content(), None, vec![tag_name.clone(), other_tag_name.clone()], get_publication(), entity_admin_result.id).await.unwrap().id;
+    let untagged_post_id = app_data.repo.insert_post(get_content(), None, vec![other_tag_name.clone()], get_publication(), entity_admin_result.id).await.unwrap().id;
+    let tag_slug = app_data.repo.query_post(tagged_post_id).await.unwrap().unwrap().tags.into_iter().find(|tag| tag.name == tag_name).unwrap().slug;
+
+    let post_ids = app_data.repo.query_tag_post_previews(tag_slug.clone(), 10, None).await.unwrap().iter().map(|post| post.id).collect::<Vec<i64>>();
+    assert!(post_ids == vec![tagged_post_id]);
+    assert!(!post_ids.contains(&untagged_post_id));
+
+    // removing the tag takes the post out of the listing, no tags keeps the current ones
+    app_data.repo.update_post(tagged_post_id, get_content(), None, Some(vec![other_tag_name.clone()]), get_publication(), entity_admin_result.id).await.unwrap();
+    app_data.repo.update_post(tagged_post_id, get_content(), None, None, get_publication(), entity_admin_result.id).await.unwrap();
+    assert!(app_data.repo.query_tag_post_previews(tag_slug, 10, None).await.unwrap().is_empty());
+    assert!(app_data.repo.query_post(tagged_post_id).await.unwrap().unwrap().tags.len() == 1);
+}
+
+#[tokio::test]
+async fn test_insert_post_keeps_tags_differing_by_symbols_apart() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+    let tag_name = format!("Lang {}", Username().fake::<String>());
+    let tag_names = vec![tag_name.clone(), format!("{}++", tag_name), format!("{}#", tag_name)];
+
+    let entity_admin_result = app_data.repo.insert_administrator(Username().fake::<String>(), SafeEmail().fake::<String>(), Password(5..10).fake::<String>(), AdminRole::Author).await.unwrap();
+    let post_id = app_data.repo.insert_post(get_content(), None, tag_names.clone(), get_publication(), entity_admin_result.id).await.unwrap().id;
+
+    let post_tags = app_data.repo.query_post(post_id).await.unwrap().unwrap().tags;
+    assert!(post_tags.len() == 3);
+    assert!(tag_names.iter().all(|tag_name| post_tags.iter().any(|tag| tag.name == *tag_name)));
+}
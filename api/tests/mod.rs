@@ -9,9 +9,21 @@ pub mod common {
         pub mod post {
             pub mod test_post;
         }
+        pub mod post_revision {
+            pub mod test_post_revision;
+        }
+        pub mod tag {
+            pub mod test_tag;
+        }
         pub mod mail {
             pub mod test_mail;
         }
+        pub mod refresh_token {
+            pub mod test_refresh_token;
+        }
+        pub mod password_reset {
+            pub mod test_password_reset;
+        }
     }    
 }
 pub mod routes {
@@ -21,6 +33,9 @@ pub mod routes {
     pub mod post {
         pub mod test_post_routes;
     }
+    pub mod post_revision {
+        pub mod test_post_revision_routes;
+    }
     pub mod mail {
         pub mod test_mail_routes;
     }
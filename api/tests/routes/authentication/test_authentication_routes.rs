@@ -1,39 +1,86 @@
-use actix_web::web::Json;
-use fake::{faker::internet::en::Username, Fake};
+use actix_http::StatusCode;
+use actix_web::{cookie::Cookie, test, web::{Data, Json}, HttpRequest};
 use rustyindie_api::{
-    common::{authentication::auth_service::{decode_token, AuthService, STANDARD_ACCESS_TOKEN_EXPIRATION}, repository::base::{DbRepo, Repository}}, 
-    routes::{auth_helper::{get_access_token_from_login_resp_httpresponse, get_access_token_from_str_body_httpresponse}, authentication::{models::{LoginCredential, RefreshToken}, routes::{login, refresh_access_token}}, route_utils::get_header_strings}
+    common::{authentication::auth_service::{decode_token, AuthService, REFRESH_TOKEN_LABEL, STANDARD_ACCESS_TOKEN_EXPIRATION}, repository::base::{DbRepo, Repository}}, 
+    routes::{app_state::AppState, auth_helper::{get_access_token_from_login_resp_httpresponse, get_access_token_from_str_body_httpresponse}, authentication::{models::{LoginCredential, RefreshToken}, routes::{login, logout, refresh_access_token}}}
 };
-use rustyindie_api::common_test::fixtures::{get_app_data, get_fake_httprequest_with_bearer_token};
+use rustyindie_api::common_test::fixtures::get_app_data;
+
+async fn login_and_get_tokens(app_data: &Data<AppState<DbRepo, AuthService>>) -> (String, String) {
+    let login_resp = login(app_data.clone(), Json(LoginCredential {
+        email: "dharric@live.com".to_string(),
+        password: "123".to_string()
+    }), test::TestRequest::post().to_http_request()).await;
+    let refresh_token = login_resp.cookies().find(|cookie| cookie.name() == REFRESH_TOKEN_LABEL).unwrap().value().to_string();
+    let access_token = get_access_token_from_login_resp_httpresponse(login_resp).await;
+
+    (access_token, refresh_token)
+}
+
+fn get_refresh_request(refresh_token: &str) -> HttpRequest {
+    test::TestRequest::post()
+        .cookie(Cookie::new(REFRESH_TOKEN_LABEL, refresh_token.to_string()))
+        .to_http_request()
+}
 
 #[tokio::test]
 async fn test_refresh_access_token_is_valid() {
     let repo = DbRepo::init().await;
     let auth_service = AuthService;
     let app_data = get_app_data(repo, auth_service).await;
-    let user_name = Username().fake::<String>();
-
-    let req = get_fake_httprequest_with_bearer_token(user_name.to_string(), &app_data.auth_keys.encoding_key, "/v1/authentication", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
-    let headers = get_header_strings(req.headers());
-    let bearer_header = headers
-        .iter()
-        .find(|header| {
-            let name = header.0;
-            name.to_lowercase() == "authorization"
-        }).unwrap();
-    let value = bearer_header.1;
-    let bearer = value.split(' ').collect::<Vec<&str>>();
-    let old_token = bearer.get(1).unwrap().to_string();
+    let (old_token, refresh_token) = login_and_get_tokens(&app_data).await;
     
     let httpresponse = refresh_access_token(app_data.clone(), Json(RefreshToken{
         old_token
-    }), req).await;
+    }), get_refresh_request(&refresh_token)).await;
+    let new_refresh_token = httpresponse.cookies().find(|cookie| cookie.name() == REFRESH_TOKEN_LABEL).unwrap().value().to_string();
 
     let token = get_access_token_from_str_body_httpresponse(httpresponse).await;
-    let claims = decode_token(&token, &app_data.auth_keys.decoding_key);
+    let claims = decode_token(&token, &app_data.auth_keys).unwrap();
 
-    assert!(claims.sub == user_name);
+    assert!(claims.sub == "dave");
     assert!(claims.exp >= STANDARD_ACCESS_TOKEN_EXPIRATION as usize);
+    assert!(new_refresh_token != refresh_token);
+}
+
+#[tokio::test]
+async fn test_refresh_access_token_reuse_revokes_family() {
+    let repo = DbRepo::init().await;
+    let auth_service = AuthService;
+    let app_data = get_app_data(repo, auth_service).await;
+    let (old_token, refresh_token) = login_and_get_tokens(&app_data).await;
+
+    let first_resp = refresh_access_token(app_data.clone(), Json(RefreshToken{
+        old_token: old_token.clone()
+    }), get_refresh_request(&refresh_token)).await;
+    let rotated_refresh_token = first_resp.cookies().find(|cookie| cookie.name() == REFRESH_TOKEN_LABEL).unwrap().value().to_string();
+
+    let reuse_resp = refresh_access_token(app_data.clone(), Json(RefreshToken{
+        old_token: old_token.clone()
+    }), get_refresh_request(&refresh_token)).await;
+    let rotated_resp = refresh_access_token(app_data.clone(), Json(RefreshToken{
+        old_token
+    }), get_refresh_request(&rotated_refresh_token)).await;
+
+    assert!(first_resp.status() == StatusCode::OK);
+    assert!(reuse_resp.status() == StatusCode::UNAUTHORIZED);
+    assert!(rotated_resp.status() == StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_logout_revokes_refresh_token() {
+    let repo = DbRepo::init().await;
+    let auth_service = AuthService;
+    let app_data = get_app_data(repo, auth_service).await;
+    let (old_token, refresh_token) = login_and_get_tokens(&app_data).await;
+
+    let logout_resp = logout(app_data.clone(), get_refresh_request(&refresh_token)).await;
+    let refresh_resp = refresh_access_token(app_data.clone(), Json(RefreshToken{
+        old_token
+    }), get_refresh_request(&refresh_token)).await;
+
+    assert!(logout_resp.status() == StatusCode::NO_CONTENT);
+    assert!(refresh_resp.status() != StatusCode::OK);
 }
 
 #[tokio::test]
@@ -46,10 +93,10 @@ async fn test_login_logs_in_successfully() {
     let login_resp = login(app_data.clone(), Json(LoginCredential {
         email: "dharric@live.com".to_string(),
         password: "123".to_string()
-    })).await;
+    }), test::TestRequest::post().to_http_request()).await;
 
     let token = get_access_token_from_login_resp_httpresponse(login_resp).await;
-    let claims = decode_token(&token, &app_data.auth_keys.decoding_key);
+    let claims = decode_token(&token, &app_data.auth_keys).unwrap();
 
     assert!(claims.sub == user_name);
     assert!(claims.exp >= STANDARD_ACCESS_TOKEN_EXPIRATION as usize);
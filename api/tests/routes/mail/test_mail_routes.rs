@@ -1,13 +1,15 @@
+use fake::{faker::{internet::en::SafeEmail, lorem::en::Sentence}, Fake};
 use rustyindie_api::{
-    common::{authentication::auth_service::AuthService, repository::base::{DbRepo, Repository}}, 
-    common_test::fixtures::get_app_data, 
+    common::{authentication::auth_service::{AuthService, STANDARD_ACCESS_TOKEN_EXPIRATION}, repository::base::{DbRepo, Repository}}, 
+    common_test::fixtures::{get_app_data, get_fake_httprequest_with_bearer_token}, 
     routes::{
-        base_model::PagingModel, 
-        mail::routes::{get_latest_mail, get_mail}
+        auth_helper::AuthenticatedAdmin,
+        base_model::CursorPagingModel, 
+        mail::{models::NewMail, routes::{create_mail, get_latest_mail, get_mail}}
     }
     
 };
-use actix_web::web::Path;
+use actix_web::{test::TestRequest, web::{Json, Path, Query}};
 
 #[tokio::test]
 async fn test_get_latest_mail_route_returns_atleast_two_mail() {
@@ -15,15 +17,15 @@ async fn test_get_latest_mail_route_returns_atleast_two_mail() {
     let auth_service = AuthService;
     let app_data = get_app_data(repo, auth_service).await;
 
+    let req = get_fake_httprequest_with_bearer_token(1, "dave".to_string(), &app_data.auth_keys, "/v1/mail", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+    let admin = AuthenticatedAdmin::authenticate(app_data.clone(), req).await.unwrap();
+
     // mails created in db setup code already
-    let mail_res = get_latest_mail(app_data, Path::from(PagingModel {
-        page_size: 10,
-        last_offset: 0
-    }))
+    let mail_res = get_latest_mail(app_data, Query(CursorPagingModel { page_size: Some(10), cursor: None }), admin)
     .await;
 
     assert!(mail_res.is_ok());
-    assert!(mail_res.unwrap().0.len() > 1);
+    assert!(mail_res.unwrap().items.len() > 1);
 }
 
 #[tokio::test]
@@ -32,9 +34,36 @@ async fn test_get_mail_route_returns_mail() {
     let auth_service = AuthService;
     let app_data = get_app_data(repo, auth_service).await;
 
+    let req = get_fake_httprequest_with_bearer_token(1, "dave".to_string(), &app_data.auth_keys, "/v1/mail/1", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+    let admin = AuthenticatedAdmin::authenticate(app_data.clone(), req).await.unwrap();
+
     // mails created in db setup code already
-    let mail_res = get_mail(app_data, Path::from(1)).await;
+    let mail_res = get_mail(app_data, Path::from(1), admin).await;
 
     assert!(mail_res.is_ok());
     assert!(mail_res.ok().is_some());
+}
+
+#[tokio::test]
+async fn test_create_mail_route_creates_mail() {
+    let repo = DbRepo::init().await;
+    let auth_service = AuthService;
+    let app_data = get_app_data(repo, auth_service).await;
+    let from = SafeEmail().fake::<String>();
+    let subject = Sentence(1..2).fake::<String>();
+
+    let create_res = create_mail(app_data.clone(), Json(NewMail {
+        from: from.clone(),
+        subject: subject.clone(),
+        message: Sentence(3..5).fake::<String>(),
+        website: None
+    }), TestRequest::post().to_http_request()).await;
+    let mail_id = create_res.unwrap().id;
+
+    let req = get_fake_httprequest_with_bearer_token(1, "dave".to_string(), &app_data.auth_keys, "/v1/mail/1", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+    let admin = AuthenticatedAdmin::authenticate(app_data.clone(), req).await.unwrap();
+    let mail = get_mail(app_data, Path::from(mail_id), admin).await.unwrap().unwrap();
+
+    assert!(mail.from == from);
+    assert!(mail.subject == subject);
 }
\ No newline at end of file
@@ -1,11 +1,11 @@
 use fake::{faker::lorem::en::Sentence, Fake};
 use rustyindie_api::{
-    common::{authentication::auth_service::{AuthService, STANDARD_ACCESS_TOKEN_EXPIRATION}, repository::base::{DbRepo, Repository}}, 
+    common::{authentication::auth_service::{AuthService, STANDARD_ACCESS_TOKEN_EXPIRATION}, repository::{base::{DbRepo, Repository}, post::models::PostStatus}}, 
     common_test::fixtures::{get_app_data, get_fake_httprequest_with_bearer_token}, 
-    routes::{base_model::PagingModel, post::{models::{DeletePost, NewPost, UpdatePost}, routes::{create_post, get_post, get_posts, get_post_previews, delete_post, update_post}}}
+    routes::{auth_helper::AuthenticatedAdmin, base_model::CursorPagingModel, post::{models::{DeletePost, NewPost, UpdatePost}, routes::{create_post, get_post, get_post_by_slug, get_posts, get_post_previews, delete_post, update_post}}}
     
 };
-use actix_web::web::{Path, Json};
+use actix_web::{http::{header, StatusCode}, web::{Path, Json, Query}, Either};
 
 #[tokio::test]
 async fn test_create_post_completes_successfully() {
@@ -14,13 +14,18 @@ async fn test_create_post_completes_successfully() {
     let app_data = get_app_data(repo, auth_service).await;
     let user_name = "dave";
 
-    let req = get_fake_httprequest_with_bearer_token(user_name.to_string(), &app_data.auth_keys.encoding_key, "/v1/authentication", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+    let req = get_fake_httprequest_with_bearer_token(1, user_name.to_string(), &app_data.auth_keys, "/v1/authentication", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+    let admin = AuthenticatedAdmin::authenticate(app_data.clone(), req).await.unwrap();
 
     let post_res = create_post(app_data, Json(NewPost {
         title: Sentence(1..2).fake::<String>(),
         message: Sentence(3..5).fake::<String>(),
-        admin_id: 1
-    }), req).await;
+        excerpt: None,
+        slug: None,
+        tags: vec![],
+        status: PostStatus::Published,
+        published_at: None
+    }), admin).await;
 
     assert!(post_res.is_ok());
     assert!(post_res.unwrap().id > 0);
@@ -35,27 +40,60 @@ async fn test_get_post_previews_returns_correct_previews() {
     let app_data = get_app_data(repo, auth_service).await;
     let user_name = "dave";
 
-    let req = get_fake_httprequest_with_bearer_token(user_name.to_string(), &app_data.auth_keys.encoding_key, "/v1/post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+    let req = get_fake_httprequest_with_bearer_token(1, user_name.to_string(), &app_data.auth_keys, "/v1/post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+    let admin = AuthenticatedAdmin::authenticate(app_data.clone(), req).await.unwrap();
 
     let created_post_res = create_post(app_data.clone(), Json(NewPost {
         title: Sentence(1..2).fake::<String>(),
         message: Sentence(3..5).fake::<String>(),
-        admin_id: 1
-    }), req).await;
+        excerpt: None,
+        slug: None,
+        tags: vec![],
+        status: PostStatus::Published,
+        published_at: None
+    }), admin).await;
     let post_id = created_post_res.unwrap().id;
 
-    let get_post_res = get_post_previews(app_data, Path::from(PagingModel {
-        page_size: 10,
-        last_offset: 0
-    }))
+    let get_post_res = get_post_previews(app_data, Query(CursorPagingModel { page_size: Some(10), cursor: None }))
     .await;
 
     assert!(get_post_res.is_ok());
-    assert!(get_post_res.unwrap().0.iter().find(|post| {
+    assert!(get_post_res.unwrap().items.iter().find(|post| {
         post.id == post_id
     }).is_some());
 }
 
+#[tokio::test]
+async fn test_get_post_previews_summarise_multibyte_messages() {
+    let repo = DbRepo::init().await;
+    let auth_service = AuthService;
+    let app_data = get_app_data(repo, auth_service).await;
+    let user_name = "dave";
+
+    let req = get_fake_httprequest_with_bearer_token(1, user_name.to_string(), &app_data.auth_keys, "/v1/post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+    let admin = AuthenticatedAdmin::authenticate(app_data.clone(), req).await.unwrap();
+
+    // 249 bytes of ascii then a multibyte character, which cutting at 250 bytes split
+    let message = format!("{} é and some more words", "a".repeat(248));
+    let created_post_res = create_post(app_data.clone(), Json(NewPost {
+        title: Sentence(1..2).fake::<String>(),
+        message,
+        excerpt: None,
+        slug: None,
+        tags: vec![],
+        status: PostStatus::Published,
+        published_at: None
+    }), admin).await;
+    let post_id = created_post_res.unwrap().id;
+
+    let get_post_res = get_post_previews(app_data, Query(CursorPagingModel { page_size: Some(10), cursor: None }))
+    .await;
+
+    let posts = get_post_res.unwrap();
+    let preview = posts.items.iter().find(|post| post.id == post_id).unwrap();
+    assert!(preview.summary_html == format!("<p>{} é and</p>", "a".repeat(248)));
+}
+
 #[tokio::test]
 async fn test_get_posts_returns_correct_posts() {
     let repo = DbRepo::init().await;
@@ -63,23 +101,25 @@ async fn test_get_posts_returns_correct_posts() {
     let app_data = get_app_data(repo, auth_service).await;
     let user_name = "dave";
 
-    let req = get_fake_httprequest_with_bearer_token(user_name.to_string(), &app_data.auth_keys.encoding_key, "/v1/post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+    let req = get_fake_httprequest_with_bearer_token(1, user_name.to_string(), &app_data.auth_keys, "/v1/post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+    let admin = AuthenticatedAdmin::authenticate(app_data.clone(), req).await.unwrap();
 
     let created_post_res = create_post(app_data.clone(), Json(NewPost {
         title: Sentence(1..2).fake::<String>(),
         message: Sentence(3..5).fake::<String>(),
-        admin_id: 1
-    }), req).await;
+        excerpt: None,
+        slug: None,
+        tags: vec![],
+        status: PostStatus::Published,
+        published_at: None
+    }), admin).await;
     let post_id = created_post_res.unwrap().id;
 
-    let get_post_res = get_posts(app_data, Path::from(PagingModel {
-        page_size: 10,
-        last_offset: 0
-    }))
+    let get_post_res = get_posts(app_data, Query(CursorPagingModel { page_size: Some(10), cursor: None }))
     .await;
 
     assert!(get_post_res.is_ok());
-    assert!(get_post_res.unwrap().0.iter().find(|post| {
+    assert!(get_post_res.unwrap().items.iter().find(|post| {
         post.id == post_id
     }).is_some());
 }
@@ -91,13 +131,18 @@ async fn test_get_post_returns_correct_post() {
     let app_data = get_app_data(repo, auth_service).await;
     let user_name = "dave";
 
-    let req = get_fake_httprequest_with_bearer_token(user_name.to_string(), &app_data.auth_keys.encoding_key, "/v1/post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+    let req = get_fake_httprequest_with_bearer_token(1, user_name.to_string(), &app_data.auth_keys, "/v1/post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+    let admin = AuthenticatedAdmin::authenticate(app_data.clone(), req).await.unwrap();
 
     let created_post_res = create_post(app_data.clone(), Json(NewPost {
         title: Sentence(1..2).fake::<String>(),
         message: Sentence(3..5).fake::<String>(),
-        admin_id: 1
-    }), req).await;
+        excerpt: None,
+        slug: None,
+        tags: vec![],
+        status: PostStatus::Published,
+        published_at: None
+    }), admin).await;
     let post_id = created_post_res.unwrap().id;
 
     let get_post_res = get_post(app_data, Path::from(post_id)).await;
@@ -113,17 +158,22 @@ async fn test_delete_post_does_deletion() {
     let app_data = get_app_data(repo, auth_service).await;
     let user_name = "dave";
 
-    let req = get_fake_httprequest_with_bearer_token(user_name.to_string(), &app_data.auth_keys.encoding_key, "/v1/delete_post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+    let req = get_fake_httprequest_with_bearer_token(1, user_name.to_string(), &app_data.auth_keys, "/v1/delete_post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+    let admin = AuthenticatedAdmin::authenticate(app_data.clone(), req).await.unwrap();
 
     let created_post_res = create_post(app_data.clone(), Json(NewPost {
         title: Sentence(1..2).fake::<String>(),
         message: Sentence(3..5).fake::<String>(),
-        admin_id: 1
-    }), req.clone()).await;
+        excerpt: None,
+        slug: None,
+        tags: vec![],
+        status: PostStatus::Published,
+        published_at: None
+    }), admin.clone()).await;
     let post_id = created_post_res.unwrap().id;
 
-    let delete_post_res = delete_post(app_data.clone(), Json(DeletePost {post_id, admin_id: 1}), req).await;
-    assert!(delete_post_res.error().is_none());
+    let delete_post_res = delete_post(app_data.clone(), Json(DeletePost { post_id }), admin).await;
+    assert!(delete_post_res.is_ok());
 
     let get_post_res = get_post(app_data, Path::from(post_id)).await;
     assert!(get_post_res.is_ok());
@@ -140,21 +190,95 @@ async fn test_update_post_does_update_post() {
     let start_message = Sentence(2..4).fake::<String>();
     let update_title = Sentence(1..2).fake::<String>();
     let update_message = Sentence(3..4).fake::<String>();
+    let update_excerpt = Sentence(1..2).fake::<String>();
 
-    let req = get_fake_httprequest_with_bearer_token(user_name.to_string(), &app_data.auth_keys.encoding_key, "/v1/update_post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+    let req = get_fake_httprequest_with_bearer_token(1, user_name.to_string(), &app_data.auth_keys, "/v1/update_post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+    let admin = AuthenticatedAdmin::authenticate(app_data.clone(), req).await.unwrap();
 
     let created_post_res = create_post(app_data.clone(), Json(NewPost {
         title: start_title,
         message: start_message,
-        admin_id: 1
-    }), req.clone()).await;
+        excerpt: None,
+        slug: None,
+        tags: vec![],
+        status: PostStatus::Published,
+        published_at: None
+    }), admin.clone()).await;
     let post_id = created_post_res.unwrap().id;
 
-    let update_post_res = update_post(app_data.clone(), Json(UpdatePost {post_id, admin_id: 1, title: update_title.clone(), message: update_message.clone() }), req).await;
-    assert!(update_post_res.error().is_none());
+    let update_post_res = update_post(app_data.clone(), Json(UpdatePost {post_id, title: update_title.clone(), message: update_message.clone(), excerpt: Some(update_excerpt.clone()), slug: None, tags: None, status: PostStatus::Published, published_at: None }), admin).await;
+    assert!(update_post_res.is_ok());
 
     let get_post_res = get_post(app_data, Path::from(post_id)).await;
     assert!(get_post_res.is_ok());
     assert!(get_post_res.as_ref().unwrap().as_ref().unwrap().title == update_title);
     assert!(get_post_res.as_ref().unwrap().as_ref().unwrap().message == update_message);
-}
\ No newline at end of file
+    assert!(get_post_res.as_ref().unwrap().as_ref().unwrap().rendered_html == format!("<p>{}</p>", update_message));
+    assert!(get_post_res.as_ref().unwrap().as_ref().unwrap().excerpt == Some(update_excerpt.clone()));
+    assert!(get_post_res.as_ref().unwrap().as_ref().unwrap().summary_text == update_excerpt);
+}
+
+#[tokio::test]
+async fn test_get_post_by_slug_redirects_renamed_slugs() {
+    let repo = DbRepo::init().await;
+    let auth_service = AuthService;
+    let app_data = get_app_data(repo, auth_service).await;
+    let user_name = "dave";
+    let title = Sentence(1..2).fake::<String>();
+    let message = Sentence(3..5).fake::<String>();
+
+    let req = get_fake_httprequest_with_bearer_token(1, user_name.to_string(), &app_data.auth_keys, "/v1/post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+    let admin = AuthenticatedAdmin::authenticate(app_data.clone(), req).await.unwrap();
+
+    let created_post_res = create_post(app_data.clone(), Json(NewPost {
+        title: title.clone(),
+        message: message.clone(),
+        excerpt: None,
+        slug: None,
+        tags: vec![],
+        status: PostStatus::Published,
+        published_at: None
+    }), admin.clone()).await;
+    let post_id = created_post_res.unwrap().id;
+    let old_slug = get_post(app_data.clone(), Path::from(post_id)).await.unwrap().unwrap().slug;
+
+    let new_slug = format!("{} renamed", old_slug);
+    let update_post_res = update_post(app_data.clone(), Json(UpdatePost { post_id, title, message, excerpt: None, slug: Some(new_slug), tags: None, status: PostStatus::Published, published_at: None }), admin).await;
+    assert!(update_post_res.is_ok());
+
+    let current_slug = format!("{}-renamed", old_slug);
+    match get_post_by_slug(app_data.clone(), Path::from(current_slug.clone())).await {
+        Ok(Either::Left(Some(post))) => assert!(post.id == post_id),
+        _ => panic!("failed to get post by its new slug")
+    }
+    match get_post_by_slug(app_data, Path::from(old_slug)).await {
+        Ok(Either::Right(resp)) => {
+            assert!(resp.status() == StatusCode::MOVED_PERMANENTLY);
+            assert!(resp.headers().get(header::LOCATION).unwrap().to_str().unwrap() == current_slug);
+        },
+        _ => panic!("failed to redirect the old slug")
+    }
+}
+
+#[tokio::test]
+async fn test_get_post_does_not_return_drafts() {
+    let repo = DbRepo::init().await;
+    let app_data = get_app_data(repo, AuthService).await;
+
+    let req = get_fake_httprequest_with_bearer_token(1, "dave".to_string(), &app_data.auth_keys, "/v1/post", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+    let admin = AuthenticatedAdmin::authenticate(app_data.clone(), req).await.unwrap();
+
+    let created_post_res = create_post(app_data.clone(), Json(NewPost {
+        title: Sentence(1..2).fake::<String>(),
+        message: Sentence(3..5).fake::<String>(),
+        excerpt: None,
+        slug: None,
+        tags: vec![],
+        status: PostStatus::Draft,
+        published_at: None
+    }), admin).await;
+    let post_id = created_post_res.unwrap().id;
+
+    assert!(matches!(get_post(app_data.clone(), Path::from(post_id)).await, Ok(None)));
+    assert!(get_posts(app_data, Query(CursorPagingModel { page_size: Some(100), cursor: None })).await.unwrap().items.iter().all(|post| post.id != post_id));
+}
@@ -0,0 +1,69 @@
+use fake::{faker::lorem::en::Sentence, Fake};
+use rustyindie_api::{
+    common::{authentication::auth_service::{AuthService, STANDARD_ACCESS_TOKEN_EXPIRATION}, repository::{base::{DbRepo, Repository}, post::models::PostStatus}, utils::diff_utils::DiffTag}, 
+    common_test::fixtures::{get_app_data, get_fake_httprequest_with_bearer_token}, 
+    routes::{
+        auth_helper::AuthenticatedAdmin, 
+        post::{models::{NewPost, UpdatePost}, routes::{create_post, get_post, update_post}},
+        post_revision::{models::{PostRevisionDiffPath, RestorePostRevision}, routes::{get_post_revision_diff, get_post_revisions, restore_post_revision}}
+    }
+};
+use actix_web::web::{Path, Json};
+
+#[tokio::test]
+async fn test_restore_post_revision_restores_earlier_text_slug_and_tags() {
+    let repo = DbRepo::init().await;
+    let auth_service = AuthService;
+    let app_data = get_app_data(repo, auth_service).await;
+    let title = Sentence(1..2).fake::<String>();
+    let message = Sentence(3..5).fake::<String>();
+    let update_message = format!("{}\n\n{}", message, Sentence(3..5).fake::<String>());
+    let tag_name = Sentence(1..2).fake::<String>().trim_end_matches('.').to_string();
+
+    let req = get_fake_httprequest_with_bearer_token(1, "dave".to_string(), &app_data.auth_keys, "/v1/restore_post_revision", 1, Some(STANDARD_ACCESS_TOKEN_EXPIRATION));
+    let admin = AuthenticatedAdmin::authenticate(app_data.clone(), req).await.unwrap();
+
+    let created_post_res = create_post(app_data.clone(), Json(NewPost {
+        title: title.clone(),
+        message: message.clone(),
+        excerpt: None,
+        slug: None,
+        tags: vec![tag_name.clone()],
+        status: PostStatus::Published,
+        published_at: None
+    }), admin.clone()).await;
+    let post_id = created_post_res.unwrap().id;
+    let created_slug = get_post(app_data.clone(), Path::from(post_id)).await.unwrap().unwrap().slug;
+    let update_post_res = update_post(app_data.clone(), Json(UpdatePost { 
+        post_id, 
+        title, 
+        message: update_message, 
+        excerpt: None, 
+        slug: Some(format!("{}-renamed", created_slug)), 
+        tags: Some(vec![]),
+        status: PostStatus::Published, 
+        published_at: None 
+    }), admin.clone()).await;
+    assert!(update_post_res.is_ok());
+
+    let revisions = get_post_revisions(app_data.clone(), Path::from(post_id), admin.clone()).await.unwrap().0;
+    assert!(revisions.len() == 2);
+    let (latest_revision_id, first_revision_id) = (revisions[0].id, revisions[1].id);
+
+    let diff = get_post_revision_diff(app_data.clone(), Path::from(PostRevisionDiffPath { from_revision_id: first_revision_id, to_revision_id: latest_revision_id }), admin.clone())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(diff.message.iter().any(|line| line.tag == DiffTag::Insert));
+    assert!(diff.message.iter().all(|line| line.tag != DiffTag::Delete));
+
+    let restore_res = restore_post_revision(app_data.clone(), Json(RestorePostRevision { post_id, revision_id: first_revision_id }), admin.clone()).await;
+    assert!(restore_res.is_ok());
+
+    let restored_post = get_post(app_data.clone(), Path::from(post_id)).await.unwrap().unwrap();
+    assert!(restored_post.message == message);
+    assert!(restored_post.slug == created_slug);
+    assert!(restored_post.tags.iter().map(|tag| tag.name.clone()).collect::<Vec<String>>() == vec![tag_name]);
+    // restoring is a save too, so it can be undone
+    assert!(get_post_revisions(app_data, Path::from(post_id), admin).await.unwrap().0.len() == 3);
+}
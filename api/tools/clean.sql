@@ -1,3 +1,3 @@
 TRUNCATE administrator RESTART IDENTITY CASCADE;
 TRUNCATE post RESTART IDENTITY CASCADE;
-insert into administrator (user_name, email, password) values ('dave', 'dharric@live.com', '123');
\ No newline at end of file
+insert into administrator (user_name, email, password, "role") values ('dave', 'dharric@live.com', '123', 'owner');
\ No newline at end of file
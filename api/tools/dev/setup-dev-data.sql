@@ -1,4 +1,4 @@
-insert into administrator (user_name, email, "password") values ('dave', 'dharric@live.com', '123');
+insert into administrator (user_name, email, "password", "role") values ('dave', 'dharric@live.com', '123', 'owner');
 insert into post (title, "message", admin_id) values ('Title AAA', '# Page 1
 
 ## Introduction
@@ -1,10 +1,17 @@
-use super::models::{LoginCredential, LoginResponse, UpdatePost, Post};
-use super::models::{OutputId, NewPost};
+use super::models::{LoginCredential, LoginResponse, LoginResult, TotpChallenge, TotpLoginCredential, UpdatePost, Post, PostStatus};
+use super::models::{Mail, UpdateMailRead, DeleteMail};
+use super::models::{OutputId, NewPost, Paged};
+use super::models::{PostRevision, PostRevisionDiff, RestorePostRevision};
+use super::models::{PostSearchResult, TagPostCount};
+use super::models::{Administrator, NewAdministrator, UpdateAdministrator, UpdateAdministratorPassword, DeactivateAdministrator};
 use leptos::logging::log;
 use reqwest::header::HeaderMap;
 use reqwest::{Client, StatusCode};
 use reqwest::Error;
 
+/// Posts in a page of previews, on the home page, a tag's page and search results
+pub const POST_PREVIEW_PAGE_SIZE: i32 = 10;
+
 #[derive(Clone, Debug, Default)]
 pub struct ApiService {
     client: Client,
@@ -58,12 +65,31 @@ impl ApiService {
         }
     }
 
-    pub async fn login(&self, credentials: &LoginCredential) -> Result<LoginResponse, Error> {
+    /// Administrators with totp enabled get a challenge that must be completed with login_totp
+    pub async fn login(&self, credentials: &LoginCredential) -> Result<LoginResult, Error> {
         let login_res = self.client.post(format!("{}/{}", self.api_url, "login"))
             .json(credentials)
             .send()
             .await;
 
+        match login_res {
+            Ok(res) => {
+                match res.status() {
+                    StatusCode::OK => res.json::<LoginResponse>().await.map(LoginResult::Complete),
+                    StatusCode::ACCEPTED => res.json::<TotpChallenge>().await.map(LoginResult::TotpRequired),
+                    _ => Err(res.error_for_status().err().unwrap())
+                } 
+            },
+            Err(e) => Err(e)
+        }               
+    }
+
+    pub async fn login_totp(&self, credentials: &TotpLoginCredential) -> Result<LoginResponse, Error> {
+        let login_res = self.client.post(format!("{}/{}", self.api_url, "login/totp"))
+            .json(credentials)
+            .send()
+            .await;
+
         match login_res {
             Ok(res) => {
                 match res.status() {
@@ -75,15 +101,16 @@ impl ApiService {
         }               
     }
 
-    pub async fn get_latest_posts(&self, last_offset: i32) -> Result<Vec<Post>, Error> {
-        let posts = self.client.get(format!("{}/{}/10/{}", self.api_url, "post", last_offset))
+    pub async fn get_latest_posts(&self, cursor: Option<String>) -> Result<Paged<Post>, Error> {
+        let posts = self.client.get(format!("{}/{}", self.api_url, "post"))
+            .query(&get_paging_query(POST_PREVIEW_PAGE_SIZE, cursor))
             .send()
             .await;
 
         match posts {
             Ok(res) => {
                 match res.status() {
-                    StatusCode::OK => res.json::<Vec<Post>>().await,
+                    StatusCode::OK => res.json::<Paged<Post>>().await,
                     _ => Err(res.error_for_status().err().unwrap())
                 }
             },
@@ -106,4 +133,382 @@ impl ApiService {
             Err(e) => Err(e)
         }
     }
-}
\ No newline at end of file
+
+    /// Old slugs are redirected by the api, so the returned post's slug can differ from @slug
+    pub async fn get_post_by_slug(&self, slug: &str) -> Result<Option<Post>, Error> {
+        let post_resp = self.client.get(format!("{}/{}/{}", self.api_url, "post/slug", slug))
+            .send()
+            .await;
+
+        match post_resp {
+            Ok(res) => {
+                match res.status() {
+                    StatusCode::OK => res.json::<Option<Post>>().await,
+                    StatusCode::NOT_FOUND => Ok(None),
+                    _ => Err(res.error_for_status().err().unwrap())
+                }
+            },
+            Err(e) => Err(e)
+        }
+    }
+
+    /// Public posts matching @search_text, best match first
+    pub async fn search_posts(&self, search_text: &str, cursor: Option<String>) -> Result<Paged<PostSearchResult>, Error> {
+        let results = self.client.get(format!("{}/{}", self.api_url, "search"))
+            .query(&[("q", search_text.to_string())])
+            .query(&get_paging_query(POST_PREVIEW_PAGE_SIZE, cursor))
+            .send()
+            .await;
+
+        match results {
+            Ok(res) => {
+                match res.status() {
+                    StatusCode::OK => res.json::<Paged<PostSearchResult>>().await,
+                    _ => Err(res.error_for_status().err().unwrap())
+                }
+            },
+            Err(e) => Err(e)
+        }
+    }
+
+    /// Tags that have public posts, by name
+    pub async fn get_tags(&self) -> Result<Vec<TagPostCount>, Error> {
+        let tags = self.client.get(format!("{}/{}", self.api_url, "tag"))
+            .send()
+            .await;
+
+        match tags {
+            Ok(res) => {
+                match res.status() {
+                    StatusCode::OK => res.json::<Vec<TagPostCount>>().await,
+                    _ => Err(res.error_for_status().err().unwrap())
+                }
+            },
+            Err(e) => Err(e)
+        }
+    }
+
+    /// Previews of the public posts with the tag
+    pub async fn get_tag_post_previews(&self, tag_slug: &str, cursor: Option<String>) -> Result<Paged<Post>, Error> {
+        let posts = self.client.get(format!("{}/{}/{}/post", self.api_url, "tag", tag_slug))
+            .query(&get_paging_query(POST_PREVIEW_PAGE_SIZE, cursor))
+            .send()
+            .await;
+
+        match posts {
+            Ok(res) => {
+                match res.status() {
+                    StatusCode::OK => res.json::<Paged<Post>>().await,
+                    _ => Err(res.error_for_status().err().unwrap())
+                }
+            },
+            Err(e) => Err(e)
+        }
+    }
+
+    /// Posts of every status, or only @status when given, for administrators
+    pub async fn get_managed_posts(&self, page_size: i32, cursor: Option<String>, status: Option<PostStatus>, token: String) -> Result<Paged<Post>, Error> {
+        let mut headers = HeaderMap::new();
+        headers.insert("Authorization", format!("Bearer {}", token).parse().unwrap());
+
+        let posts_resp = self.client.get(format!("{}/{}", self.api_url, "manage_post"))
+            .query(&get_paging_query(page_size, cursor))
+            .query(&status.map(|status| [("status", status.as_str())]))
+            .headers(headers)
+            .send()
+            .await;
+
+        match posts_resp {
+            Ok(res) => {
+                match res.status() {
+                    StatusCode::OK => res.json::<Paged<Post>>().await,
+                    _ => Err(res.error_for_status().err().unwrap())
+                }
+            },
+            Err(e) => Err(e)
+        }
+    }
+
+    /// A post of any status, get_post only returns public ones
+    pub async fn get_managed_post(&self, post_id: i64, token: String) -> Result<Option<Post>, Error> {
+        let mut headers = HeaderMap::new();
+        headers.insert("Authorization", format!("Bearer {}", token).parse().unwrap());
+
+        let post_resp = self.client.get(format!("{}/{}/{}", self.api_url, "manage_post", post_id))
+            .headers(headers)
+            .send()
+            .await;
+
+        match post_resp {
+            Ok(res) => {
+                match res.status() {
+                    StatusCode::OK => res.json::<Option<Post>>().await,
+                    StatusCode::NOT_FOUND => Ok(None),
+                    _ => Err(res.error_for_status().err().unwrap())
+                }
+            },
+            Err(e) => Err(e)
+        }
+    }
+
+    /// Newest first, the first is the post's current text
+    pub async fn get_post_revisions(&self, post_id: i64, token: String) -> Result<Vec<PostRevision>, Error> {
+        let mut headers = HeaderMap::new();
+        headers.insert("Authorization", format!("Bearer {}", token).parse().unwrap());
+
+        let revisions_resp = self.client.get(format!("{}/{}/{}", self.api_url, "post_revisions", post_id))
+            .headers(headers)
+            .send()
+            .await;
+
+        match revisions_resp {
+            Ok(res) => {
+                match res.status() {
+                    StatusCode::OK => res.json::<Vec<PostRevision>>().await,
+                    _ => Err(res.error_for_status().err().unwrap())
+                }
+            },
+            Err(e) => Err(e)
+        }
+    }
+
+    /// None unless both revisions are of the same post
+    pub async fn get_post_revision_diff(&self, from_revision_id: i64, to_revision_id: i64, token: String) -> Result<Option<PostRevisionDiff>, Error> {
+        let mut headers = HeaderMap::new();
+        headers.insert("Authorization", format!("Bearer {}", token).parse().unwrap());
+
+        let diff_resp = self.client.get(format!("{}/{}/{}/{}", self.api_url, "post_revision_diff", from_revision_id, to_revision_id))
+            .headers(headers)
+            .send()
+            .await;
+
+        match diff_resp {
+            Ok(res) => {
+                match res.status() {
+                    StatusCode::OK => res.json::<Option<PostRevisionDiff>>().await,
+                    StatusCode::NOT_FOUND => Ok(None),
+                    _ => Err(res.error_for_status().err().unwrap())
+                }
+            },
+            Err(e) => Err(e)
+        }
+    }
+
+    pub async fn restore_post_revision(&self, restore_post_revision: &RestorePostRevision, token: String) -> Result<(), Error> {
+        let mut headers = HeaderMap::new();
+        headers.insert("Authorization", format!("Bearer {}", token).parse().unwrap());
+
+        let restore_resp = self.client.post(format!("{}/{}", self.api_url, "restore_post_revision"))
+            .headers(headers)
+            .json(restore_post_revision)
+            .send()
+            .await;
+
+        match restore_resp {
+            Ok(resp) => {
+                match resp.status() {
+                    StatusCode::NO_CONTENT => Ok(()),
+                    _ => Err(resp.error_for_status().err().unwrap())
+                }
+            },
+            Err(e) => Err(e)
+        }
+    }
+
+    pub async fn get_latest_mail(&self, page_size: i32, cursor: Option<String>, token: String) -> Result<Paged<Mail>, Error> {
+        let mut headers = HeaderMap::new();
+        headers.insert("Authorization", format!("Bearer {}", token).parse().unwrap());
+
+        let mail_resp = self.client.get(format!("{}/{}", self.api_url, "mail"))
+            .query(&get_paging_query(page_size, cursor))
+            .headers(headers)
+            .send()
+            .await;
+
+        match mail_resp {
+            Ok(res) => {
+                match res.status() {
+                    StatusCode::OK => res.json::<Paged<Mail>>().await,
+                    _ => Err(res.error_for_status().err().unwrap())
+                }
+            },
+            Err(e) => Err(e)
+        }
+    }
+
+    pub async fn get_mail(&self, mail_id: i64, token: String) -> Result<Option<Mail>, Error> {
+        let mut headers = HeaderMap::new();
+        headers.insert("Authorization", format!("Bearer {}", token).parse().unwrap());
+
+        let mail_resp = self.client.get(format!("{}/{}/{}", self.api_url, "mail", mail_id))
+            .headers(headers)
+            .send()
+            .await;
+
+        match mail_resp {
+            Ok(res) => {
+                match res.status() {
+                    StatusCode::OK => res.json::<Option<Mail>>().await,
+                    _ => Err(res.error_for_status().err().unwrap())
+                }
+            },
+            Err(e) => Err(e)
+        }
+    }
+
+    pub async fn update_mail_read(&self, update_mail_read: &UpdateMailRead, token: String) -> Result<(), Error> {
+        let mut headers = HeaderMap::new();
+        headers.insert("Authorization", format!("Bearer {}", token).parse().unwrap());
+
+        let mail_resp = self.client.post(format!("{}/{}", self.api_url, "mail_read"))
+            .headers(headers)
+            .json(update_mail_read)
+            .send()
+            .await;
+
+        match mail_resp {
+            Ok(resp) => {
+                match resp.status() {
+                    StatusCode::NO_CONTENT => Ok(()),
+                    _ => Err(resp.error_for_status().err().unwrap())
+                }
+            },
+            Err(e) => Err(e)
+        }
+    }
+
+    pub async fn delete_mail(&self, delete_mail: &DeleteMail, token: String) -> Result<(), Error> {
+        let mut headers = HeaderMap::new();
+        headers.insert("Authorization", format!("Bearer {}", token).parse().unwrap());
+
+        let mail_resp = self.client.post(format!("{}/{}", self.api_url, "delete_mail"))
+            .headers(headers)
+            .json(delete_mail)
+            .send()
+            .await;
+
+        match mail_resp {
+            Ok(resp) => {
+                match resp.status() {
+                    StatusCode::NO_CONTENT => Ok(()),
+                    _ => Err(resp.error_for_status().err().unwrap())
+                }
+            },
+            Err(e) => Err(e)
+        }
+    }
+
+    pub async fn get_administrators(&self, page_size: i32, cursor: Option<String>, token: String) -> Result<Paged<Administrator>, Error> {
+        let mut headers = HeaderMap::new();
+        headers.insert("Authorization", format!("Bearer {}", token).parse().unwrap());
+
+        let admin_resp = self.client.get(format!("{}/{}", self.api_url, "user"))
+            .query(&get_paging_query(page_size, cursor))
+            .headers(headers)
+            .send()
+            .await;
+
+        match admin_resp {
+            Ok(res) => {
+                match res.status() {
+                    StatusCode::OK => res.json::<Paged<Administrator>>().await,
+                    _ => Err(res.error_for_status().err().unwrap())
+                }
+            },
+            Err(e) => Err(e)
+        }
+    }
+
+    pub async fn create_administrator(&self, new_administrator: &NewAdministrator, token: String) -> Result<OutputId, Error> {
+        let mut headers = HeaderMap::new();
+        headers.insert("Authorization", format!("Bearer {}", token).parse().unwrap());
+
+        let admin_resp = self.client.post(format!("{}/{}", self.api_url, "user"))
+            .headers(headers)
+            .json(new_administrator)
+            .send()
+            .await;
+
+        match admin_resp {
+            Ok(res) => {
+                match res.status() {
+                    StatusCode::OK => res.json::<OutputId>().await,
+                    _ => Err(res.error_for_status().err().unwrap())
+                }
+            },
+            Err(e) => Err(e)
+        }
+    }
+
+    pub async fn update_administrator(&self, update_administrator: &UpdateAdministrator, token: String) -> Result<(), Error> {
+        let mut headers = HeaderMap::new();
+        headers.insert("Authorization", format!("Bearer {}", token).parse().unwrap());
+
+        let admin_resp = self.client.post(format!("{}/{}", self.api_url, "update_user"))
+            .headers(headers)
+            .json(update_administrator)
+            .send()
+            .await;
+
+        match admin_resp {
+            Ok(resp) => {
+                match resp.status() {
+                    StatusCode::NO_CONTENT => Ok(()),
+                    _ => Err(resp.error_for_status().err().unwrap())
+                }
+            },
+            Err(e) => Err(e)
+        }
+    }
+
+    pub async fn update_administrator_password(&self, update_password: &UpdateAdministratorPassword, token: String) -> Result<(), Error> {
+        let mut headers = HeaderMap::new();
+        headers.insert("Authorization", format!("Bearer {}", token).parse().unwrap());
+
+        let admin_resp = self.client.post(format!("{}/{}", self.api_url, "update_user_password"))
+            .headers(headers)
+            .json(update_password)
+            .send()
+            .await;
+
+        match admin_resp {
+            Ok(resp) => {
+                match resp.status() {
+                    StatusCode::NO_CONTENT => Ok(()),
+                    _ => Err(resp.error_for_status().err().unwrap())
+                }
+            },
+            Err(e) => Err(e)
+        }
+    }
+
+    pub async fn deactivate_administrator(&self, deactivate_administrator: &DeactivateAdministrator, token: String) -> Result<(), Error> {
+        let mut headers = HeaderMap::new();
+        headers.insert("Authorization", format!("Bearer {}", token).parse().unwrap());
+
+        let admin_resp = self.client.post(format!("{}/{}", self.api_url, "deactivate_user"))
+            .headers(headers)
+            .json(deactivate_administrator)
+            .send()
+            .await;
+
+        match admin_resp {
+            Ok(resp) => {
+                match resp.status() {
+                    StatusCode::NO_CONTENT => Ok(()),
+                    _ => Err(resp.error_for_status().err().unwrap())
+                }
+            },
+            Err(e) => Err(e)
+        }
+    }
+}
+
+/// No @cursor asks for the first page
+fn get_paging_query(page_size: i32, cursor: Option<String>) -> Vec<(&'static str, String)> {
+    let mut paging_query = vec![("page_size", page_size.to_string())];
+    if let Some(cursor) = cursor {
+        paging_query.push(("cursor", cursor));
+    }
+    paging_query
+}
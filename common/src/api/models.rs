@@ -7,20 +7,36 @@ pub struct OutputId {
     pub id: i64
 }
 
-/// Used as parameter type to create new Post
+/// Used as parameter type to create new Post, the author is taken from the access token
 #[derive(Serialize, Clone)]
 pub struct NewPost {
     pub title: String,
     pub message: String,
-    pub admin_id: i64
+    /// Shown in previews instead of the start of message, when given
+    pub excerpt: Option<String>,
+    /// Made from the title by the api when None
+    pub slug: Option<String>,
+    /// Tag names, the api adds a tag for a name that has none yet
+    pub tags: Vec<String>,
+    pub status: PostStatus,
+    /// Required when scheduling, the api uses now when publishing without one
+    pub published_at: Option<DateTime<Utc>>
 }
 
 #[derive(Serialize, Clone)]
 pub struct UpdatePost {
     pub post_id: i64,
-    pub admin_id: i64,
     pub title: String,
-    pub message: String    
+    pub message: String,
+    /// Shown in previews instead of the start of message, when given
+    pub excerpt: Option<String>,
+    /// The current slug is kept when None, a changed slug redirects from the old one
+    pub slug: Option<String>,
+    /// Tag names, the current tags are kept when None
+    pub tags: Option<Vec<String>>,
+    pub status: PostStatus,
+    /// Required when scheduling, the api keeps the first publish date when publishing without one
+    pub published_at: Option<DateTime<Utc>>
 }
 
 #[derive(Serialize, Clone)]
@@ -35,12 +51,259 @@ pub struct LoginResponse {
     pub login_user_id: i64
 }
 
+/// Returned by login instead of LoginResponse when the administrator has totp enabled
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct TotpChallenge {
+    pub challenge_token: String
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct TotpLoginCredential {
+    pub challenge_token: String,
+    /// current authenticator code or an unused recovery code
+    pub code: String
+}
+
+#[derive(Clone, Debug)]
+pub enum LoginResult {
+    Complete(LoginResponse),
+    TotpRequired(TotpChallenge)
+}
+
+/// A page of a list, pass next_cursor back to get the page after it
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Paged<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+    pub has_more: bool
+}
+
+impl<T> Default for Paged<T> {
+    fn default() -> Self {
+        Paged { items: vec![], next_cursor: None, has_more: false }
+    }
+}
+
 /// Receiving type for post queries
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct Post {
     pub id: i64,
     pub updated_at: DateTime<Utc>,
     pub title: String,
+    /// Used in the post's url instead of its id
+    pub slug: String,
     pub message: String,
+    /// Sanitized html of message, rendered by the api
+    pub rendered_html: String,
+    /// Headings of rendered_html in order, for the table of contents
+    #[serde(default)]
+    pub headings: Vec<Heading>,
+    pub excerpt: Option<String>,
+    /// Sanitized html of the start of message, or of the excerpt, for previews
+    pub summary_html: String,
+    /// The preview summary without markup, for meta descriptions
+    pub summary_text: String,
+    pub status: PostStatus,
+    /// None for drafts, in the future for scheduled posts
+    pub published_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub tags: Vec<Tag>,
     pub admin_id: i64
-}
\ No newline at end of file
+}
+
+/// @id is the fragment the heading's anchor in rendered_html links to
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Heading {
+    pub id: String,
+    /// 1 to 6, as in h1 to h6
+    pub level: u8,
+    pub text: String
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Tag {
+    pub id: i64,
+    pub name: String,
+    /// Used in the tag's url
+    pub slug: String
+}
+
+/// A tag with the number of its public posts
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct TagPostCount {
+    pub id: i64,
+    pub name: String,
+    pub slug: String,
+    pub post_count: i64
+}
+
+/// A public post matching a search
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct PostSearchResult {
+    /// A preview of the post
+    pub post: Post,
+    pub rank: f32,
+    /// Sanitized html of the matching parts of the post, with the matched words in mark tags
+    pub snippet_html: String
+}
+
+/// Only published posts, and scheduled ones whose time has passed, are public
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PostStatus {
+    Draft,
+    Scheduled,
+    Published,
+    Archived
+}
+
+impl PostStatus {
+    pub const ALL: [PostStatus; 4] = [PostStatus::Draft, PostStatus::Scheduled, PostStatus::Published, PostStatus::Archived];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PostStatus::Draft => "draft",
+            PostStatus::Scheduled => "scheduled",
+            PostStatus::Published => "published",
+            PostStatus::Archived => "archived"
+        }
+    }
+
+    pub fn from_str(status: &str) -> Option<PostStatus> {
+        PostStatus::ALL.into_iter().find(|post_status| post_status.as_str() == status)
+    }
+}
+
+/// A post's title, message and excerpt as they were saved, newest first when listed
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct PostRevision {
+    pub id: i64,
+    pub created_at: DateTime<Utc>,
+    pub post_id: i64,
+    /// The administrator who saved it
+    pub admin_id: i64,
+    pub title: String,
+    pub message: String,
+    pub excerpt: Option<String>,
+    /// None for revisions saved before slugs and tags were kept
+    pub slug: Option<String>,
+    pub tag_names: Option<Vec<String>>
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffTag {
+    Equal,
+    Insert,
+    Delete
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct DiffLine {
+    pub tag: DiffTag,
+    pub text: String
+}
+
+/// Line by line changes from one revision to another
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct PostRevisionDiff {
+    pub from_revision_id: i64,
+    pub to_revision_id: i64,
+    pub title: Vec<DiffLine>,
+    pub message: Vec<DiffLine>,
+    pub excerpt: Vec<DiffLine>
+}
+
+/// Saves the post with the revision's text, slug and tags, as a new revision
+#[derive(Serialize, Clone)]
+pub struct RestorePostRevision {
+    pub post_id: i64,
+    pub revision_id: i64
+}
+
+/// Receiving type for mail queries
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Mail {
+    pub id: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub from: String,
+    pub subject: String,
+    pub message: String,
+    pub is_read: bool,
+    pub read_at: Option<DateTime<Utc>>
+}
+
+#[derive(Serialize, Clone)]
+pub struct UpdateMailRead {
+    pub mail_id: i64,
+    pub is_read: bool
+}
+
+#[derive(Serialize, Clone)]
+pub struct DeleteMail {
+    pub mail_id: i64
+}
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AdminRole {
+    Owner,
+    Editor,
+    Author
+}
+
+impl AdminRole {
+    pub const ALL: [AdminRole; 3] = [AdminRole::Owner, AdminRole::Editor, AdminRole::Author];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AdminRole::Owner => "owner",
+            AdminRole::Editor => "editor",
+            AdminRole::Author => "author"
+        }
+    }
+
+    pub fn from_str(role: &str) -> Option<AdminRole> {
+        AdminRole::ALL.into_iter().find(|admin_role| admin_role.as_str() == role)
+    }
+}
+
+/// Receiving type for administrator queries, the api never returns passwords
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Administrator {
+    pub id: i64,
+    pub updated_at: DateTime<Utc>,
+    pub user_name: String,
+    pub email: String,
+    pub role: AdminRole,
+    pub is_active: bool
+}
+
+#[derive(Serialize, Clone)]
+pub struct NewAdministrator {
+    pub user_name: String,
+    pub email: String,
+    pub password: String,
+    pub role: AdminRole
+}
+
+#[derive(Serialize, Clone)]
+pub struct UpdateAdministrator {
+    pub admin_id: i64,
+    pub user_name: String,
+    pub email: String,
+    pub role: AdminRole
+}
+
+/// @current_password is only needed when changing your own password
+#[derive(Serialize, Clone)]
+pub struct UpdateAdministratorPassword {
+    pub admin_id: i64,
+    pub current_password: Option<String>,
+    pub new_password: String
+}
+
+#[derive(Serialize, Clone)]
+pub struct DeactivateAdministrator {
+    pub admin_id: i64
+}
@@ -1,12 +1,15 @@
 use leptos::*;
 use leptos::logging::log;
 use crate::api::api_service::ApiService;
-use crate::api::models::{LoginCredential, LoginResponse};
+use crate::api::models::{LoginCredential, LoginResponse, LoginResult, TotpChallenge, TotpLoginCredential};
 
 #[component]
 pub fn Login() -> impl IntoView {
     let (email, set_email) = create_signal("".to_string());
     let (password, set_password) = create_signal("".to_string());
+    let (code, set_code) = create_signal("".to_string());
+    // set once the password step succeeds for an administrator with totp enabled
+    let (challenge, set_challenge) = create_signal::<Option<TotpChallenge>>(None);
     let api_service = expect_context::<ReadSignal<ApiService>>();
     let (_, set_login_resp) = expect_context::<(ReadSignal<Option<LoginResponse>>, WriteSignal<Option<LoginResponse>>)>();
     let submit_post = create_action(move |credentials: &LoginCredential| {
@@ -15,15 +18,65 @@ pub fn Login() -> impl IntoView {
         async move {
             let login_result = api_service.get_untracked().login(&credentials).await;
             match login_result {
-                Ok(login_resp) => {
-                    set_login_resp(Some(login_resp.clone()));
+                Ok(LoginResult::Complete(login_resp)) => {
+                    set_login_resp(Some(login_resp));
+                },
+                Ok(LoginResult::TotpRequired(totp_challenge)) => {
+                    set_challenge(Some(totp_challenge));
                 },
                 Err(_) => log!("login failed")
             };
         }
     });
-        
-    view! {
+    let submit_totp = create_action(move |credentials: &TotpLoginCredential| {
+        let credentials = credentials.clone();
+
+        async move {
+            let login_result = api_service.get_untracked().login_totp(&credentials).await;
+            match login_result {
+                Ok(login_resp) => {
+                    set_challenge(None);
+                    set_code("".to_string());
+                    set_login_resp(Some(login_resp));
+                },
+                Err(_) => log!("totp login failed")
+            };
+        }
+    });
+
+    let totp_form = move |totp_challenge: TotpChallenge| view! {
+        <form
+            on:submit=move |ev| {
+                ev.prevent_default();
+
+                submit_totp.dispatch(TotpLoginCredential {
+                    challenge_token: totp_challenge.challenge_token.clone(),
+                    code: code()
+                });
+            }
+        >
+            <section class="form-section">
+                <label for="code">
+                    "Authenticator or recovery code"
+                </label>
+                <input 
+                    type="text" 
+                    id="code"
+                    name="code"
+                    autocomplete="one-time-code"
+                    on:input=move |ev| {
+                        set_code(event_target_value(&ev));
+                    } 
+                    prop:value=code
+                />
+            </section>
+            <section class="form-section">
+                <button type="submit" class="primary-btn" >"Verify"</button>
+            </section>
+        </form>
+    };
+
+    let password_form = move || view! {
         <form
             on:submit=move |ev| {
                 ev.prevent_default();
@@ -66,5 +119,12 @@ pub fn Login() -> impl IntoView {
                 <button type="submit" class="primary-btn" >"Login"</button>
             </section>
         </form>
+    };
+        
+    view! {
+        {move || match challenge() {
+            Some(totp_challenge) => totp_form(totp_challenge).into_view(),
+            None => password_form().into_view()
+        }}
     }
 }
\ No newline at end of file
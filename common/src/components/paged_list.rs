@@ -0,0 +1,119 @@
+use std::future::Future;
+use leptos::*;
+use leptos::html::Div;
+use leptos_use::use_element_visibility;
+use crate::api::models::Paged;
+
+/// The pages of a list loaded so far, in order. Made by use_paged_list
+pub struct PagedList<T: 'static, S: 'static> {
+    pub items: ReadSignal<Vec<T>>,
+    pub has_more: ReadSignal<bool>,
+    pub is_loading: ReadSignal<bool>,
+    next_cursor: ReadSignal<Option<String>>,
+    source: StoredValue<Option<S>>,
+    generation: StoredValue<u32>,
+    load_page: Action<(u32, S, Option<String>), ()>
+}
+
+impl<T: 'static, S: 'static> Clone for PagedList<T, S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: 'static, S: 'static> Copy for PagedList<T, S> {}
+
+impl<T: 'static, S: Clone + 'static> PagedList<T, S> {
+    /// Appends the next page. Does nothing while a page is loading or after the last page, so no page is fetched twice
+    pub fn load_more(&self) {
+        if self.is_loading.get_untracked() || !self.has_more.get_untracked() {
+            return;
+        }
+        if let Some(source) = self.source.get_value() {
+            self.load_page.dispatch((self.generation.get_value(), source, self.next_cursor.get_untracked()));
+        }
+    }
+
+    /// True once the list has loaded with no items
+    pub fn is_empty(&self) -> bool {
+        !self.is_loading.get() && self.items.with(Vec::is_empty)
+    }
+}
+
+/// Gets a list a page at a time with @fetch_page, given the next_cursor of the page before or None for the first page.
+/// @source is what the list is of, e.g. a tag, and when it changes the list starts again from the first page
+pub fn use_paged_list<T, S, F, Fut>(source: impl Fn() -> S + 'static, fetch_page: F) -> PagedList<T, S>
+where
+    T: 'static,
+    S: Clone + PartialEq + 'static,
+    F: Fn(S, Option<String>) -> Fut + 'static,
+    Fut: Future<Output = Paged<T>> + 'static
+{
+    let (items, set_items) = create_signal::<Vec<T>>(vec![]);
+    let (next_cursor, set_next_cursor) = create_signal::<Option<String>>(None);
+    let (has_more, set_has_more) = create_signal(false);
+    let current_source = store_value::<Option<S>>(None);
+    let generation = store_value(0_u32);
+
+    let load_page = create_action(move |(page_generation, source, cursor): &(u32, S, Option<String>)| {
+        let page_generation = *page_generation;
+        let page_result = fetch_page(source.clone(), cursor.clone());
+        async move {
+            let page = page_result.await;
+            // a page of a source that has since changed is dropped
+            if page_generation != generation.get_value() {
+                return;
+            }
+            set_items.update(|items| items.extend(page.items));
+            set_next_cursor(page.next_cursor);
+            set_has_more(page.has_more);
+        }
+    });
+
+    let source = create_memo(move |_| source());
+    create_effect(move |_| {
+        let source = source();
+        generation.update_value(|generation| *generation += 1);
+        current_source.set_value(Some(source.clone()));
+        set_items(vec![]);
+        set_next_cursor(None);
+        set_has_more(false);
+        load_page.dispatch((generation.get_value(), source, None));
+    });
+
+    PagedList { items, has_more, is_loading: load_page.pending(), next_cursor, source: current_source, generation, load_page }
+}
+
+/// Goes at the end of a paged list. The next page loads when this scrolls into view, or its button is clicked.
+/// @end_message is shown after the last page of a list that has items
+#[component]
+pub fn LoadMore<T: 'static, S: Clone + 'static>(paged_list: PagedList<T, S>, #[prop(optional)] end_message: Option<&'static str>) -> impl IntoView {
+    let container_ref = create_node_ref::<Div>();
+    let is_visible = use_element_visibility(container_ref);
+
+    // keeps loading while the end is in view, a page at a time
+    create_effect(move |_| {
+        if is_visible() && paged_list.has_more.get() && !paged_list.is_loading.get() {
+            paged_list.load_more();
+        }
+    });
+
+    view! {
+        <div class="load-more" node_ref=container_ref>
+            {move || {
+                if paged_list.is_loading.get() {
+                    view! { <p>"Loading ..."</p> }.into_view()
+                } else if paged_list.has_more.get() {
+                    view! {
+                        <button class="secondary-btn small-btn" on:click=move |_| paged_list.load_more()>"Load more"</button>
+                    }.into_view()
+                } else {
+                    end_message
+                        .filter(|_| !paged_list.is_empty())
+                        .map(|message| view! { <small>{message}</small> })
+                        .into_view()
+                }
+            }}
+        </div>
+    }
+}
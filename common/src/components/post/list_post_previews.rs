@@ -1,25 +1,32 @@
 use leptos::*;
-use crate::{api::models::Post, components::post::post_preview::{PostPreview, PostPreviewParams}, utils::date_time::convert_datetime_long_readable};
+use crate::{
+    api::models::Post,
+    components::{paged_list::{LoadMore, PagedList}, post::post_preview::{PostPreview, PostPreviewParams}},
+    utils::date_time::convert_datetime_long_readable
+};
 
+/// @posts can be of any source, such as the latest posts or a tag's posts, and more pages load as the list is scrolled.
+/// @empty_message is shown once @posts has loaded with none
 #[component]
-pub fn ListPostPreviews(posts: Resource<i32, Vec<Post>>, editable: bool) -> impl IntoView {
+pub fn ListPostPreviews<S: Clone + 'static>(posts: PagedList<Post, S>, editable: bool, #[prop(optional)] empty_message: Option<&'static str>) -> impl IntoView {
     view! {
         <div class="home-content">
+            {move || empty_message.filter(|_| posts.is_empty()).map(|message| view! { <p>{message}</p> })}
             <ul>
                 <For
-                    each=move || match posts() {
-                        None => vec![],
-                        Some(data) => data
-                    }
+                    each=move || posts.items.get()
                     key=|post| post.id
                     children=move |post| {
                         view! {
                             <li style="margin-bottom: 2.5em">
                                 <PostPreview post=PostPreviewParams {
                                     id: post.id,
+                                    slug: post.slug.to_string(),
                                     updated_at: convert_datetime_long_readable(post.updated_at),
                                     title: post.title.to_string(),
-                                    content: post.message.to_string(),
+                                    description: post.summary_text.to_string(),
+                                    content_html: post.summary_html.to_string(),
+                                    tags: post.tags.clone(),
                                     editable
                                 } />
                                 <hr class="preview-separator"/>
@@ -27,7 +34,8 @@ pub fn ListPostPreviews(posts: Resource<i32, Vec<Post>>, editable: bool) -> impl
                         }
                     }
                 />
-            </ul>            
-        </div>        
+            </ul>
+            <LoadMore paged_list=posts end_message="No more posts" />
+        </div>
     }
-}
\ No newline at end of file
+}
@@ -1,33 +1,36 @@
 use leptos::*;
-use crate::{api::models::Post, utils::date_time::convert_datetime_short_readable};
-use crate::utils::markdown_to_html::MarkdownToHtmlConverter;
+use leptos_meta::Meta;
+use crate::{
+    api::models::Post, 
+    components::{page_not_found::PageNotFound, post::{table_of_contents::{get_table_of_contents, TableOfContents}, tag_chips::TagChips}}, 
+    utils::date_time::convert_datetime_short_readable
+};
 
+/// A None @post, e.g. a draft or a post that does not exist, shows the not found page
 #[component]
-pub fn PostDetail(post: Resource<i64, Option<Post>>) -> impl IntoView {
-    let html_content = move || {
-        let md_to_html = MarkdownToHtmlConverter::new();
-        let msg_content = post().map(|p| {
-            p.unwrap().message
-        }).unwrap_or_default();
-
-        let html = md_to_html.convert_md_to_html(msg_content);
-        html
-    };
-    let updated_at = move || {        
-        convert_datetime_short_readable(post.get().unwrap().unwrap().updated_at)
-    };
-
+pub fn PostDetail(post: Resource<String, Option<Post>>) -> impl IntoView {
     view! {
         <Suspense fallback={move || view! { <p>"Loading ..."</p> }}>
             <div class="post-detail-container">
-                {move || post().map(|p| view! {
-                    <div>                        
-                        <h1>{p.clone().unwrap().title}</h1>
-                        <small><b>{updated_at}</b></small>
-                    </div>
-                    <div>{html_content}</div>
+                {move || post().map(|p| match p {
+                    Some(p) => {
+                        // the api sends the headings with rendered_html, so their ids match
+                        let table_of_contents = get_table_of_contents(&p.headings);
+                        view! {
+                            <div>
+                                <Meta name="description" content=p.summary_text />
+                                <h1>{p.title}</h1>
+                                <small><b>{convert_datetime_short_readable(p.updated_at)}</b></small>
+                                <TagChips tags=p.tags />
+                            </div>
+                            <TableOfContents entries=table_of_contents />
+                            // already sanitized by the api when the post was saved
+                            <div inner_html=p.rendered_html></div>
+                        }.into_view()
+                    },
+                    None => view! { <PageNotFound /> }.into_view()
                 })}
             </div>
         </Suspense>
     }
-}
\ No newline at end of file
+}
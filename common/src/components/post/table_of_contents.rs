@@ -0,0 +1,82 @@
+use leptos::*;
+use crate::api::models::Heading;
+
+/// A heading with the lower level headings that follow it, before the next heading of its level or higher
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableOfContentsEntry {
+    pub heading: Heading,
+    pub children: Vec<TableOfContentsEntry>
+}
+
+/// Nests @headings by level. A skipped level, e.g. an h4 straight after an h2, is nested under the nearest heading above it
+pub fn get_table_of_contents(headings: &[Heading]) -> Vec<TableOfContentsEntry> {
+    let mut entries = vec![];
+    let mut index = 0;
+    while index < headings.len() {
+        let heading = headings[index].clone();
+        let end = headings[index + 1..]
+            .iter()
+            .position(|next| next.level <= heading.level)
+            .map(|length| index + 1 + length)
+            .unwrap_or(headings.len());
+
+        entries.push(TableOfContentsEntry { children: get_table_of_contents(&headings[index + 1..end]), heading });
+        index = end;
+    }
+    entries
+}
+
+/// Links to each heading of a post, nested by heading level. Renders nothing when there are no headings
+#[component]
+pub fn TableOfContents(entries: Vec<TableOfContentsEntry>) -> impl IntoView {
+    (!entries.is_empty()).then(|| view! {
+        <nav class="table-of-contents" aria-label="Table of contents">
+            <strong>"Contents"</strong>
+            {get_entry_list(entries)}
+        </nav>
+    })
+}
+
+/// Returns View rather than impl IntoView, since it calls itself for each level
+fn get_entry_list(entries: Vec<TableOfContentsEntry>) -> View {
+    view! {
+        <ol>
+            {entries.into_iter().map(|entry| view! {
+                <li>
+                    <a href=format!("#{}", entry.heading.id)>{entry.heading.text}</a>
+                    {(!entry.children.is_empty()).then(|| get_entry_list(entry.children))}
+                </li>
+            }).collect_view()}
+        </ol>
+    }.into_view()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_heading(level: u8, id: &str) -> Heading {
+        Heading { level, id: id.to_string(), text: id.to_string() }
+    }
+
+    #[test]
+    fn test_get_table_of_contents_nests_by_level() {
+        let headings = [get_heading(1, "a"), get_heading(2, "b"), get_heading(4, "c"), get_heading(3, "d"), get_heading(2, "e"), get_heading(1, "f")];
+
+        let entries = get_table_of_contents(&headings);
+
+        assert!(entries.len() == 2);
+        assert!(entries[0].heading.id == "a" && entries[1].heading.id == "f");
+        let second_level = &entries[0].children;
+        assert!(second_level.iter().map(|entry| entry.heading.id.as_str()).collect::<Vec<&str>>() == ["b", "e"]);
+        assert!(second_level[0].children.iter().map(|entry| entry.heading.id.as_str()).collect::<Vec<&str>>() == ["c", "d"]);
+        assert!(entries[1].children.is_empty());
+    }
+
+    #[test]
+    fn test_get_table_of_contents_keeps_headings_that_start_below_the_top_level() {
+        let entries = get_table_of_contents(&[get_heading(3, "a"), get_heading(2, "b")]);
+
+        assert!(entries.len() == 2);
+    }
+}
@@ -0,0 +1,17 @@
+use leptos::*;
+use leptos_router::A;
+use crate::api::models::Tag;
+
+/// Links to each tag's post listing. Renders nothing when there are no tags
+#[component]
+pub fn TagChips(tags: Vec<Tag>) -> impl IntoView {
+    (!tags.is_empty()).then(|| view! {
+        <ul class="tag-chips">
+            {tags.into_iter().map(|tag| view! {
+                <li>
+                    <A href=format!("/tag/{}", tag.slug) class="tag-chip">{tag.name}</A>
+                </li>
+            }).collect_view()}
+        </ul>
+    })
+}
@@ -10,6 +10,7 @@ pub mod utils {
 pub mod components {
     pub mod layout;
     pub mod modal;
+    pub mod paged_list;
     pub mod page_not_found;
     pub mod authentication {
         pub mod login;
@@ -18,5 +19,7 @@ pub mod components {
         pub mod post_detail;
         pub mod post_preview;
         pub mod list_post_previews;
+        pub mod table_of_contents;
+        pub mod tag_chips;
     }
 }
\ No newline at end of file
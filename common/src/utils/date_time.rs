@@ -1,4 +1,4 @@
-use chrono::{Duration, NaiveDateTime, DateTime, Utc, Local};
+use chrono::{Duration, NaiveDateTime, DateTime, Utc, Local, TimeZone};
 
 pub fn convert_timestamp_to_local_datetime(duration_secs: i64) -> DateTime<Local> {
     let duration = Duration::seconds(duration_secs).num_microseconds().unwrap();
@@ -13,4 +13,15 @@ pub fn convert_datetime_short_readable(datetime: DateTime<Utc>) -> String {
 
 pub fn convert_datetime_long_readable(datetime: DateTime<Utc>) -> String {
     datetime.format("%b %d %Y %l:%M %P").to_string()
-}
\ No newline at end of file
+}
+
+/// The value of a datetime-local input, which is in the browser's time zone
+pub fn convert_datetime_to_local_input(datetime: DateTime<Utc>) -> String {
+    datetime.with_timezone(&Local).format("%Y-%m-%dT%H:%M").to_string()
+}
+
+/// None when @input is empty or not a valid datetime-local value
+pub fn convert_local_input_to_datetime(input: &str) -> Option<DateTime<Utc>> {
+    let naive_date = NaiveDateTime::parse_from_str(input, "%Y-%m-%dT%H:%M").ok()?;
+    Local.from_local_datetime(&naive_date).earliest().map(|datetime| datetime.with_timezone(&Utc))
+}
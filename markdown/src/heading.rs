@@ -0,0 +1,70 @@
+use std::collections::HashSet;
+
+/// A heading as it appears in the rendered html, @id is the fragment its anchor links to
+#[derive(Clone, Debug, PartialEq)]
+pub struct Heading {
+    /// 1 to 6, as in h1 to h6
+    pub level: u8,
+    pub id: String,
+    pub text: String
+}
+
+/// Gives each heading a unique id, repeated titles get -1, -2 and so on added, as GitHub does
+#[derive(Default)]
+pub struct HeadingIds {
+    used_ids: HashSet<String>
+}
+
+impl HeadingIds {
+    pub fn get_unique_id(&mut self, text: &str) -> String {
+        let slug = slugify(text);
+        let slug = if slug.is_empty() { "heading".to_string() } else { slug };
+
+        let mut id = slug.clone();
+        let mut suffix = 0;
+        while self.used_ids.contains(&id) {
+            suffix += 1;
+            id = format!("{}-{}", slug, suffix);
+        }
+        self.used_ids.insert(id.clone());
+        id
+    }
+}
+
+/// Lowercases @text, turns spaces into dashes and drops punctuation other than - and _
+pub fn slugify(text: &str) -> String {
+    text.trim()
+        .chars()
+        .filter_map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                Some(c.to_lowercase().collect::<String>())
+            } else if c.is_whitespace() {
+                Some("-".to_string())
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slugify_keeps_letters_numbers_dashes_and_underscores() {
+        assert!(slugify("Hello, World!") == "hello-world");
+        assert!(slugify(" Rust's `async` fn_name -- 2024 ") == "rusts-async-fn_name----2024");
+        assert!(slugify("Über Café") == "über-café");
+        assert!(slugify("?!").is_empty());
+    }
+
+    #[test]
+    fn test_get_unique_id_adds_suffix_to_repeated_titles() {
+        let mut heading_ids = HeadingIds::default();
+
+        let ids = ["Intro", "Intro", "Intro 1", "Intro", "!!"].map(|text| heading_ids.get_unique_id(text));
+
+        assert!(ids == ["intro", "intro-1", "intro-1-1", "intro-2", "heading"]);
+    }
+}
@@ -0,0 +1,106 @@
+/// Rendered markdown as a tree, so it can be written out as an html string or turned into elements by a front-end
+#[derive(Clone, Debug, PartialEq)]
+pub enum HtmlNode {
+    Element(HtmlElementNode),
+    Text(String),
+    /// Html written in the markdown itself, passed through as is
+    Raw(String)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct HtmlElementNode {
+    pub tag: &'static str,
+    pub attributes: Vec<(&'static str, String)>,
+    pub children: Vec<HtmlNode>
+}
+
+/// Elements that never have children, written as <br />
+const VOID_TAGS: [&str; 4] = ["br", "hr", "img", "input"];
+
+impl HtmlElementNode {
+    pub fn new(tag: &'static str) -> Self {
+        HtmlElementNode { tag, attributes: vec![], children: vec![] }
+    }
+
+    pub fn with_attribute(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.attributes.push((name, value.into()));
+        self
+    }
+
+    pub fn with_child(mut self, child: HtmlNode) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    pub fn get_attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter().find(|(attribute_name, _)| *attribute_name == name).map(|(_, value)| value.as_str())
+    }
+
+    pub fn is_void(&self) -> bool {
+        VOID_TAGS.contains(&self.tag)
+    }
+}
+
+impl From<HtmlElementNode> for HtmlNode {
+    fn from(element: HtmlElementNode) -> Self {
+        HtmlNode::Element(element)
+    }
+}
+
+impl HtmlNode {
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        self.write_html(&mut html);
+        html
+    }
+
+    /// The text content only, as the browser's innerText would give it
+    pub fn get_text(&self) -> String {
+        match self {
+            HtmlNode::Element(element) => element.children.iter().map(|child| child.get_text()).collect(),
+            HtmlNode::Text(text) => text.clone(),
+            HtmlNode::Raw(_) => "".to_string()
+        }
+    }
+
+    fn write_html(&self, html: &mut String) {
+        match self {
+            HtmlNode::Element(element) => {
+                html.push('<');
+                html.push_str(element.tag);
+                for (name, value) in element.attributes.iter() {
+                    html.push_str(&format!(" {}=\"{}\"", name, escape_html(value)));
+                }
+                if element.is_void() {
+                    html.push_str(" />");
+                    return;
+                }
+                html.push('>');
+                for child in element.children.iter() {
+                    child.write_html(html);
+                }
+                html.push_str(&format!("</{}>", element.tag));
+            },
+            HtmlNode::Text(text) => html.push_str(&escape_html(text)),
+            HtmlNode::Raw(raw) => html.push_str(raw)
+        }
+    }
+}
+
+pub fn to_html(nodes: &[HtmlNode]) -> String {
+    nodes.iter().map(|node| node.to_html()).collect()
+}
+
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c)
+        }
+    }
+    escaped
+}
@@ -0,0 +1,6 @@
+//! Markdown rendering shared by the api, which renders posts when they are saved, and the front-ends
+pub mod heading;
+pub mod html_node;
+pub mod markdown_renderer;
+pub mod summary;
+pub mod syntax_highlight;
@@ -0,0 +1,837 @@
+use pulldown_cmark::{Alignment, CodeBlockKind, Event, HeadingLevel, LinkType, Options, Parser, Tag, TagEnd};
+use crate::{
+    heading::{Heading, HeadingIds},
+    html_node::{escape_html, to_html, HtmlElementNode, HtmlNode},
+    syntax_highlight::{highlight_code, HighlightLanguage}
+};
+
+/// Renders CommonMark markdown, parsed by pulldown-cmark, into html
+#[derive(Clone, Copy)]
+pub struct MarkdownRenderer {
+    pub(crate) options: Options,
+    pub(crate) autolink_bare_urls: bool,
+    pub(crate) heading_anchors: bool
+}
+
+pub struct RenderedMarkdown {
+    /// One node per top level block, e.g. a paragraph, heading, list or code block
+    pub nodes: Vec<HtmlNode>,
+    /// Every heading in the order written, their ids are only written to the html with MarkdownExtension::HeadingAnchors
+    pub headings: Vec<Heading>
+}
+
+/// GitHub flavored markdown additions to CommonMark, each is off unless enabled
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MarkdownExtension {
+    Tables,
+    /// List items starting with [ ] or [x]
+    TaskLists,
+    /// ~~text~~
+    Strikethrough,
+    /// [^label] references, with the definitions gathered at the end and linking back
+    Footnotes,
+    /// Links urls starting with http://, https:// or www. without needing <>
+    Autolinks,
+    /// Gives each heading an id made from its text and a link to itself.
+    /// Not in the GFM spec, but it is how GitHub renders headings
+    HeadingAnchors
+}
+
+impl MarkdownExtension {
+    pub const ALL: [MarkdownExtension; 6] = [
+        MarkdownExtension::Tables,
+        MarkdownExtension::TaskLists,
+        MarkdownExtension::Strikethrough,
+        MarkdownExtension::Footnotes,
+        MarkdownExtension::Autolinks,
+        MarkdownExtension::HeadingAnchors
+    ];
+}
+
+impl MarkdownRenderer {
+    pub fn new() -> Self {
+        MarkdownRenderer {
+            options: Options::empty(),
+            autolink_bare_urls: false,
+            heading_anchors: false
+        }
+    }
+
+    /// All GitHub flavored markdown extensions, which is what posts are written in
+    pub fn new_with_gfm() -> Self {
+        MarkdownExtension::ALL
+            .into_iter()
+            .fold(MarkdownRenderer::new(), |renderer, extension| renderer.with_extension(extension))
+    }
+
+    pub fn with_extension(mut self, extension: MarkdownExtension) -> Self {
+        match extension {
+            MarkdownExtension::Tables => self.options.insert(Options::ENABLE_TABLES),
+            MarkdownExtension::TaskLists => self.options.insert(Options::ENABLE_TASKLISTS),
+            MarkdownExtension::Strikethrough => self.options.insert(Options::ENABLE_STRIKETHROUGH),
+            MarkdownExtension::Footnotes => self.options.insert(Options::ENABLE_FOOTNOTES),
+            MarkdownExtension::Autolinks => self.autolink_bare_urls = true,
+            MarkdownExtension::HeadingAnchors => self.heading_anchors = true
+        }
+        self
+    }
+
+    pub fn render(&self, md: &str) -> RenderedMarkdown {
+        let mut builder = NodeBuilder {
+            autolink_bare_urls: self.autolink_bare_urls,
+            heading_anchors: self.heading_anchors,
+            ..Default::default()
+        };
+
+        for event in Parser::new_ext(md, self.options) {
+            builder.push_event(event);
+        }
+
+        builder.finish()
+    }
+
+    /// Returns one node per top level block, e.g. a paragraph, heading, list or code block
+    pub fn render_nodes(&self, md: &str) -> Vec<HtmlNode> {
+        self.render(md).nodes
+    }
+
+    pub fn render_html(&self, md: &str) -> String {
+        to_html(&self.render_nodes(md))
+    }
+}
+
+impl Default for MarkdownRenderer {
+    fn default() -> Self {
+        MarkdownRenderer::new()
+    }
+}
+
+/// Builds the node tree from the parser's flat stream of start, content and end events
+#[derive(Default)]
+struct NodeBuilder {
+    autolink_bare_urls: bool,
+    heading_anchors: bool,
+    blocks: Vec<HtmlNode>,
+    /// Elements that have started but not ended yet, the last one is the innermost
+    open_elements: Vec<HtmlElementNode>,
+    /// The parser can split one run of text over several events, so text is joined before it is appended
+    pending_text: String,
+    /// Code and html blocks are only text, so they are gathered and built on their end event
+    code_block: Option<CodeBlock>,
+    html_block: Option<String>,
+    /// Images take their alt from the text between their start and end events
+    image: Option<Image>,
+    /// Bare urls are not linked inside a link
+    link_depth: usize,
+    table: Option<Table>,
+    footnotes: Footnotes,
+    headings: Vec<Heading>,
+    heading_ids: HeadingIds
+}
+
+struct CodeBlock {
+    language: Option<String>,
+    content: String
+}
+
+struct Image {
+    src: String,
+    title: String,
+    alt: String
+}
+
+struct Table {
+    alignments: Vec<Alignment>,
+    is_head: bool,
+    has_body: bool,
+    column: usize
+}
+
+/// Footnotes are numbered in the order they are first referenced
+#[derive(Default)]
+struct Footnotes {
+    referenced_labels: Vec<String>,
+    reference_counts: Vec<usize>,
+    /// Labels of the definitions being built, definitions can hold other definitions' references but not nest
+    open_definition_labels: Vec<String>,
+    definitions: Vec<(String, HtmlElementNode)>
+}
+
+impl Footnotes {
+    /// Returns the footnote number and how many times it has now been referenced
+    fn add_reference(&mut self, label: &str) -> (usize, usize) {
+        let label = label.to_lowercase();
+        match self.referenced_labels.iter().position(|referenced| *referenced == label) {
+            Some(index) => {
+                self.reference_counts[index] += 1;
+                (index + 1, self.reference_counts[index])
+            },
+            None => {
+                self.referenced_labels.push(label);
+                self.reference_counts.push(1);
+                (self.referenced_labels.len(), 1)
+            }
+        }
+    }
+
+    /// A list of the referenced definitions, each linking back to its first reference.
+    /// Definitions that are never referenced are left out
+    fn get_section(mut self) -> Option<HtmlNode> {
+        let items = self.referenced_labels.iter().enumerate().filter_map(|(index, label)| {
+            let position = self.definitions.iter().position(|(defined_label, _)| defined_label == label)?;
+            let (_, definition) = self.definitions.remove(position);
+            let number = index + 1;
+            let back_reference = HtmlElementNode::new("a")
+                .with_attribute("href", format!("#fnref-{}", number))
+                .with_attribute("class", "footnote-backref")
+                .with_attribute("aria-label", "Back to reference")
+                .with_child(HtmlNode::Text("↩".to_string()));
+            Some(definition.with_attribute("id", format!("fn-{}", number)).with_child(back_reference.into()).into())
+        }).collect::<Vec<HtmlNode>>();
+
+        if items.is_empty() {
+            return None;
+        }
+        let mut list = HtmlElementNode::new("ol");
+        list.children = items;
+        Some(HtmlElementNode::new("section")
+            .with_attribute("class", "footnotes")
+            .with_child(HtmlElementNode::new("hr").into())
+            .with_child(list.into())
+            .into())
+    }
+}
+
+impl NodeBuilder {
+    fn push_event(&mut self, event: Event) {
+        if let Some(image) = self.image.as_mut() {
+            match event {
+                Event::End(TagEnd::Image) => {
+                    let image = self.image.take().unwrap();
+                    self.append_node(get_image(image).into());
+                },
+                Event::Text(text) | Event::Code(text) => image.alt.push_str(&text),
+                Event::SoftBreak | Event::HardBreak => image.alt.push('\n'),
+                _ => ()
+            }
+            return;
+        }
+
+        if let Event::Text(text) = event {
+            match (self.code_block.as_mut(), self.html_block.as_mut()) {
+                (Some(code_block), _) => code_block.content.push_str(&text),
+                // the indentation of an html block's lines
+                (None, Some(html_block)) => html_block.push_str(&escape_html(&text)),
+                (None, None) => self.pending_text.push_str(&text)
+            }
+            return;
+        }
+        self.flush_pending_text();
+
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let language = match kind {
+                    // rustdoc style fences can list attributes too, e.g. ```rust,ignore
+                    CodeBlockKind::Fenced(info) => info
+                        .split(|c: char| c.is_whitespace() || c == ',')
+                        .find(|language| !language.is_empty())
+                        .map(|language| language.to_string()),
+                    CodeBlockKind::Indented => None
+                };
+                self.code_block = Some(CodeBlock { language, content: "".to_string() });
+            },
+            Event::End(TagEnd::CodeBlock) => {
+                if let Some(code_block) = self.code_block.take() {
+                    self.append_node(get_code_block(code_block).into());
+                }
+            },
+            Event::Start(Tag::HtmlBlock) => self.html_block = Some("".to_string()),
+            Event::End(TagEnd::HtmlBlock) => {
+                if let Some(html) = self.html_block.take() {
+                    self.append_node(HtmlNode::Raw(html));
+                }
+            },
+            Event::Start(Tag::Image { dest_url, title, .. }) => {
+                self.image = Some(Image { src: dest_url.to_string(), title: title.to_string(), alt: "".to_string() });
+            },
+            Event::Start(tag) => self.start_element(tag),
+            Event::End(tag_end) => self.end_element(tag_end),
+            Event::Text(_) => (),
+            Event::Html(html) => {
+                match self.html_block.as_mut() {
+                    Some(html_block) => html_block.push_str(&html),
+                    None => self.append_node(HtmlNode::Raw(html.to_string()))
+                }
+            },
+            Event::InlineHtml(html) => self.append_node(HtmlNode::Raw(html.to_string())),
+            Event::Code(text) => self.append_node(HtmlElementNode::new("code").with_child(HtmlNode::Text(text.to_string())).into()),
+            Event::SoftBreak => self.append_text("\n".to_string()),
+            Event::HardBreak => {
+                self.append_node(HtmlElementNode::new("br").into());
+                self.append_text("\n".to_string());
+            },
+            Event::Rule => self.append_node(HtmlElementNode::new("hr").into()),
+            Event::FootnoteReference(label) => {
+                let (number, reference_count) = self.footnotes.add_reference(&label);
+                let id = if reference_count == 1 { format!("fnref-{}", number) } else { format!("fnref-{}-{}", number, reference_count) };
+                let anchor = HtmlElementNode::new("a")
+                    .with_attribute("href", format!("#fn-{}", number))
+                    .with_attribute("id", id)
+                    .with_child(HtmlNode::Text(number.to_string()));
+                self.append_node(HtmlElementNode::new("sup").with_attribute("class", "footnote-ref").with_child(anchor.into()).into());
+            },
+            Event::TaskListMarker(is_checked) => {
+                let checkbox = HtmlElementNode::new("input").with_attribute("type", "checkbox").with_attribute("disabled", "");
+                let checkbox = if is_checked { checkbox.with_attribute("checked", "") } else { checkbox };
+                self.append_node(checkbox.into());
+                self.append_text(" ".to_string());
+            }
+        }
+    }
+
+    fn start_element(&mut self, tag: Tag) {
+        match tag {
+            Tag::Table(alignments) => {
+                self.table = Some(Table { alignments, is_head: false, has_body: false, column: 0 });
+                self.open_elements.push(HtmlElementNode::new("table"));
+            },
+            Tag::TableHead => {
+                if let Some(table) = self.table.as_mut() {
+                    table.is_head = true;
+                    table.column = 0;
+                }
+                self.open_elements.push(HtmlElementNode::new("thead"));
+                self.open_elements.push(HtmlElementNode::new("tr"));
+            },
+            Tag::TableRow => {
+                if let Some(table) = self.table.as_mut() {
+                    table.column = 0;
+                    if !table.has_body {
+                        table.has_body = true;
+                        self.open_elements.push(HtmlElementNode::new("tbody"));
+                    }
+                }
+                self.open_elements.push(HtmlElementNode::new("tr"));
+            },
+            Tag::TableCell => {
+                let (is_head, alignment) = match self.table.as_mut() {
+                    Some(table) => {
+                        table.column += 1;
+                        (table.is_head, table.alignments.get(table.column - 1).copied().unwrap_or(Alignment::None))
+                    },
+                    None => (false, Alignment::None)
+                };
+                let cell = HtmlElementNode::new(if is_head { "th" } else { "td" });
+                let cell = match alignment {
+                    Alignment::None => cell,
+                    Alignment::Left => cell.with_attribute("align", "left"),
+                    Alignment::Center => cell.with_attribute("align", "center"),
+                    Alignment::Right => cell.with_attribute("align", "right")
+                };
+                self.open_elements.push(cell);
+            },
+            Tag::FootnoteDefinition(label) => {
+                self.footnotes.open_definition_labels.push(label.to_lowercase());
+                self.open_elements.push(HtmlElementNode::new("li"));
+            },
+            Tag::Link { .. } => {
+                self.link_depth += 1;
+                self.open_elements.push(get_element_for_tag(tag));
+            },
+            tag => self.open_elements.push(get_element_for_tag(tag))
+        }
+    }
+
+    fn end_element(&mut self, tag_end: TagEnd) {
+        match tag_end {
+            // the header row and its thead both end here
+            TagEnd::TableHead => {
+                self.close_element();
+                self.close_element();
+                if let Some(table) = self.table.as_mut() {
+                    table.is_head = false;
+                }
+            },
+            TagEnd::Table => {
+                if self.table.take().map(|table| table.has_body).unwrap_or_default() {
+                    self.close_element();
+                }
+                self.close_element();
+            },
+            TagEnd::Link => {
+                self.link_depth = self.link_depth.saturating_sub(1);
+                self.close_element();
+            },
+            TagEnd::Heading(level) => self.close_heading(level),
+            // definitions are moved to the end, see Footnotes::get_section
+            TagEnd::FootnoteDefinition => {
+                if let (Some(label), Some(definition)) = (self.footnotes.open_definition_labels.pop(), self.open_elements.pop()) {
+                    self.footnotes.definitions.push((label, definition));
+                }
+            },
+            _ => self.close_element()
+        }
+    }
+
+    fn close_element(&mut self) {
+        if let Some(element) = self.open_elements.pop() {
+            self.append_node(element.into());
+        }
+    }
+
+    fn close_heading(&mut self, level: HeadingLevel) {
+        let Some(element) = self.open_elements.pop() else {
+            return;
+        };
+        let text = element.children.iter().map(|child| child.get_text()).collect::<String>().trim().to_string();
+        let id = self.heading_ids.get_unique_id(&text);
+        self.headings.push(Heading { level: level as u8, id: id.clone(), text });
+
+        if !self.heading_anchors {
+            self.append_node(element.into());
+            return;
+        }
+        let anchor = HtmlElementNode::new("a")
+            .with_attribute("href", format!("#{}", id))
+            .with_attribute("class", "heading-anchor")
+            .with_attribute("aria-label", "Link to this heading")
+            .with_child(HtmlNode::Text("#".to_string()));
+        self.append_node(element.with_attribute("id", id).with_child(anchor.into()).into());
+    }
+
+    fn flush_pending_text(&mut self) {
+        if self.pending_text.is_empty() {
+            return;
+        }
+        let text = std::mem::take(&mut self.pending_text);
+        if !self.autolink_bare_urls || self.link_depth > 0 {
+            self.append_text(text);
+            return;
+        }
+
+        let mut last_end = 0;
+        for (start, end) in find_bare_urls(&text) {
+            self.append_text(text[last_end..start].to_string());
+            let url = &text[start..end];
+            let href = if url.starts_with("www.") { format!("http://{}", url) } else { url.to_string() };
+            self.append_node(HtmlElementNode::new("a").with_attribute("href", encode_url(&href)).with_child(HtmlNode::Text(url.to_string())).into());
+            last_end = end;
+        }
+        self.append_text(text[last_end..].to_string());
+    }
+
+    fn append_node(&mut self, node: HtmlNode) {
+        match self.open_elements.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => self.blocks.push(node)
+        }
+    }
+
+    fn append_text(&mut self, text: String) {
+        if text.is_empty() {
+            return;
+        }
+        match self.open_elements.last_mut() {
+            Some(parent) => parent.children.push(HtmlNode::Text(text)),
+            None => self.blocks.push(HtmlElementNode::new("span").with_child(HtmlNode::Text(text)).into())
+        }
+    }
+
+    fn finish(mut self) -> RenderedMarkdown {
+        self.flush_pending_text();
+        if let Some(section) = std::mem::take(&mut self.footnotes).get_section() {
+            self.blocks.push(section);
+        }
+        RenderedMarkdown { nodes: self.blocks, headings: self.headings }
+    }
+}
+
+fn get_element_for_tag(tag: Tag) -> HtmlElementNode {
+    match tag {
+        Tag::Paragraph => HtmlElementNode::new("p"),
+        Tag::Heading { level, .. } => HtmlElementNode::new(get_heading_tag(level)),
+        Tag::BlockQuote => HtmlElementNode::new("blockquote"),
+        // CommonMark lists may start from any number
+        Tag::List(Some(1)) => HtmlElementNode::new("ol"),
+        Tag::List(Some(start)) => HtmlElementNode::new("ol").with_attribute("start", start.to_string()),
+        Tag::List(None) => HtmlElementNode::new("ul"),
+        Tag::Item => HtmlElementNode::new("li"),
+        Tag::Emphasis => HtmlElementNode::new("em"),
+        Tag::Strong => HtmlElementNode::new("strong"),
+        Tag::Strikethrough => HtmlElementNode::new("del"),
+        Tag::Link { link_type, dest_url, title, .. } => {
+            let href = match link_type {
+                LinkType::Email => format!("mailto:{}", encode_url(&dest_url)),
+                _ => encode_url(&dest_url)
+            };
+            let anchor = HtmlElementNode::new("a").with_attribute("href", href);
+            if title.is_empty() {
+                anchor
+            } else {
+                anchor.with_attribute("title", title.to_string())
+            }
+        },
+        _ => HtmlElementNode::new("span")
+    }
+}
+
+fn get_heading_tag(level: HeadingLevel) -> &'static str {
+    match level {
+        HeadingLevel::H1 => "h1",
+        HeadingLevel::H2 => "h2",
+        HeadingLevel::H3 => "h3",
+        HeadingLevel::H4 => "h4",
+        HeadingLevel::H5 => "h5",
+        HeadingLevel::H6 => "h6"
+    }
+}
+
+/// The whole block becomes a single <pre><code>, highlighted when its language is one we know
+fn get_code_block(code_block: CodeBlock) -> HtmlElementNode {
+    let highlight_language = code_block.language.as_deref().and_then(HighlightLanguage::from_fence_language);
+    let mut code_element = match code_block.language {
+        Some(language) => HtmlElementNode::new("code").with_attribute("class", format!("language-{}", language)),
+        None => HtmlElementNode::new("code")
+    };
+
+    code_element.children = match highlight_language {
+        Some(highlight_language) => highlight_code(highlight_language, &code_block.content)
+            .into_iter()
+            .map(|token| match token.kind {
+                Some(kind) => HtmlElementNode::new("span").with_attribute("class", kind.class_name()).with_child(HtmlNode::Text(token.text)).into(),
+                None => HtmlNode::Text(token.text)
+            })
+            .collect(),
+        None => vec![HtmlNode::Text(code_block.content)]
+    };
+    HtmlElementNode::new("pre").with_child(code_element.into())
+}
+
+fn get_image(image: Image) -> HtmlElementNode {
+    let image_element = HtmlElementNode::new("img").with_attribute("src", encode_url(&image.src)).with_attribute("alt", image.alt);
+    if image.title.is_empty() {
+        image_element
+    } else {
+        image_element.with_attribute("title", image.title)
+    }
+}
+
+/// Percent encodes what is not allowed in a url, existing % escapes are kept as written
+fn encode_url(url: &str) -> String {
+    url.bytes().map(|byte| {
+        if byte <= b' ' || byte >= 0x7f || b"\"<>\\[]`{|}".contains(&byte) {
+            format!("%{:02X}", byte)
+        } else {
+            (byte as char).to_string()
+        }
+    }).collect()
+}
+
+/// Byte ranges of the urls in @text that are not written as links, following GitHub's extended autolink rules
+fn find_bare_urls(text: &str) -> Vec<(usize, usize)> {
+    let mut urls = vec![];
+    let mut previous: Option<char> = None;
+    let mut skip_until = 0;
+
+    for (start, c) in text.char_indices() {
+        let is_boundary = previous.map(|previous| previous.is_whitespace() || "*_~(".contains(previous)).unwrap_or(true);
+        previous = Some(c);
+        if start < skip_until || !is_boundary {
+            continue;
+        }
+        let Some(prefix_length) = ["https://", "http://", "www."].iter().find(|prefix| text[start..].starts_with(**prefix)).map(|prefix| prefix.len()) else {
+            continue;
+        };
+
+        let end = text[start..].find(|c: char| c.is_whitespace() || c == '<').map(|length| start + length).unwrap_or(text.len());
+        let url = trim_url_end(&text[start..end]);
+        // www. alone, or a scheme with no host, is left as text
+        if url.len() > prefix_length && url[prefix_length..].contains(char::is_alphanumeric) {
+            urls.push((start, start + url.len()));
+        }
+        skip_until = end;
+    }
+    urls
+}
+
+/// Trailing punctuation is taken to be the end of the sentence, and a closing parenthesis only stays when it has a match in the url
+fn trim_url_end(url: &str) -> &str {
+    let mut url = url;
+    loop {
+        match url.chars().last() {
+            Some(c) if "?!.,:*_~'\"".contains(c) => url = &url[..url.len() - 1],
+            Some(')') if url.matches(')').count() > url.matches('(').count() => url = &url[..url.len() - 1],
+            _ => return url
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Joins the top level blocks the way the CommonMark spec examples are written, one block per line
+    fn render(md: &str) -> String {
+        render_with(MarkdownRenderer::new(), md)
+    }
+
+    fn render_with(renderer: MarkdownRenderer, md: &str) -> String {
+        renderer.render_nodes(md)
+            .iter()
+            .map(|node| node.to_html())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// The spec writes a newline around every block tag, here blocks are only separated by render
+    fn normalize_spec_html(html: &str) -> String {
+        html.trim_end()
+            .replace("\n<", "<")
+            .replace(">\n<", "><")
+            .replace("&quot;", "\"")
+    }
+
+    fn assert_spec_example(md: &str, expected_html: &str) {
+        assert_eq!(normalize_spec_html(&render(md)), normalize_spec_html(expected_html));
+    }
+
+    mod tests_for_commonmark_spec_examples {
+        use super::*;
+
+        #[test]
+        fn test_thematic_breaks() {
+            assert_spec_example("***\n---\n___\n", "<hr />\n<hr />\n<hr />\n");
+            assert_spec_example("Foo\n***\nbar\n", "<p>Foo</p>\n<hr />\n<p>bar</p>\n");
+        }
+
+        #[test]
+        fn test_atx_and_setext_headings() {
+            assert_spec_example(
+                "# foo\n## foo\n### foo\n#### foo\n##### foo\n###### foo\n",
+                "<h1>foo</h1>\n<h2>foo</h2>\n<h3>foo</h3>\n<h4>foo</h4>\n<h5>foo</h5>\n<h6>foo</h6>\n"
+            );
+            assert_spec_example("####### foo\n", "<p>####### foo</p>\n");
+            assert_spec_example("#5 bolt\n\n#hashtag\n", "<p>#5 bolt</p>\n<p>#hashtag</p>\n");
+            assert_spec_example("Foo *bar*\n=========\n\nFoo *bar*\n---------\n", "<h1>Foo <em>bar</em></h1>\n<h2>Foo <em>bar</em></h2>\n");
+        }
+
+        #[test]
+        fn test_paragraphs_span_lines() {
+            assert_spec_example("aaa\nbbb\n\nccc\nddd\n", "<p>aaa\nbbb</p>\n<p>ccc\nddd</p>\n");
+            assert_spec_example("foo  \nbar\n", "<p>foo<br />\nbar</p>\n");
+        }
+
+        #[test]
+        fn test_indented_and_fenced_code() {
+            assert_spec_example("    a simple\n      indented code block\n", "<pre><code>a simple\n  indented code block\n</code></pre>\n");
+            assert_spec_example("```\n<\n >\n```\n", "<pre><code>&lt;\n &gt;\n</code></pre>\n");
+            assert_spec_example("```ruby\ndef foo(x)\n  return 3\nend\n```\n", "<pre><code class=\"language-ruby\">def foo(x)\n  return 3\nend\n</code></pre>\n");
+            assert_spec_example("````\naaa\n```\n``````\n", "<pre><code>aaa\n```\n</code></pre>\n");
+        }
+
+        #[test]
+        fn test_block_quotes() {
+            assert_spec_example("> # Foo\n> bar\n> baz\n", "<blockquote>\n<h1>Foo</h1>\n<p>bar\nbaz</p>\n</blockquote>\n");
+            assert_spec_example("> bar\nbaz\n> foo\n", "<blockquote>\n<p>bar\nbaz\nfoo</p>\n</blockquote>\n");
+        }
+
+        #[test]
+        fn test_nested_lists() {
+            assert_spec_example(
+                "- foo\n  - bar\n    - baz\n      - boo\n",
+                "<ul>\n<li>foo\n<ul>\n<li>bar\n<ul>\n<li>baz\n<ul>\n<li>boo</li>\n</ul>\n</li>\n</ul>\n</li>\n</ul>\n</li>\n</ul>\n"
+            );
+            assert_spec_example("123456789. ok\n", "<ol start=\"123456789\">\n<li>ok</li>\n</ol>\n");
+            assert_spec_example("- a\n- b\n\n- c\n", "<ul>\n<li>\n<p>a</p>\n</li>\n<li>\n<p>b</p>\n</li>\n<li>\n<p>c</p>\n</li>\n</ul>\n");
+        }
+
+        #[test]
+        fn test_emphasis_with_punctuation() {
+            assert_spec_example("*foo bar*\n", "<p><em>foo bar</em></p>\n");
+            assert_spec_example("*(*foo*)*\n", "<p><em>(<em>foo</em>)</em></p>\n");
+            assert_spec_example("**foo, *bar*!**\n", "<p><strong>foo, <em>bar</em>!</strong></p>\n");
+            assert_spec_example("***strong emph***\n", "<p><em><strong>strong emph</strong></em></p>\n");
+            assert_spec_example("foo*bar*\n", "<p>foo<em>bar</em></p>\n");
+            assert_spec_example("a * foo bar*\n", "<p>a * foo bar*</p>\n");
+        }
+
+        #[test]
+        fn test_code_spans_and_escapes() {
+            assert_spec_example("`` foo ` bar ``\n", "<p><code>foo ` bar</code></p>\n");
+            assert_spec_example("`*foo*`\n", "<p><code>*foo*</code></p>\n");
+            assert_spec_example("\\*not emphasized*\n", "<p>*not emphasized*</p>\n");
+        }
+
+        #[test]
+        fn test_links_and_images() {
+            assert_spec_example("[link](/uri \"title\")\n", "<p><a href=\"/uri\" title=\"title\">link</a></p>\n");
+            assert_spec_example("[foo]: /url \"title\"\n\n[foo]\n", "<p><a href=\"/url\" title=\"title\">foo</a></p>\n");
+            assert_spec_example("[link](foo%20b&auml;)\n", "<p><a href=\"foo%20b%C3%A4\">link</a></p>\n");
+            assert_spec_example("<http://foo.bar.baz>\n", "<p><a href=\"http://foo.bar.baz\">http://foo.bar.baz</a></p>\n");
+            assert_spec_example("![foo *bar*](train.jpg \"train & tracks\")\n", "<p><img src=\"train.jpg\" alt=\"foo bar\" title=\"train &amp; tracks\" /></p>\n");
+        }
+
+        #[test]
+        fn test_raw_html_is_passed_through() {
+            assert_spec_example("<div>\n*hello*\n</div>\n", "<div>\n*hello*\n</div>\n");
+            assert_spec_example("<a><bab><c2c>\n", "<p><a><bab><c2c></p>\n");
+        }
+    }
+
+    mod tests_for_code_blocks {
+        use super::*;
+
+        #[test]
+        fn test_fenced_code_block_is_one_highlighted_element() {
+            let html = render("```rust,ignore\nfn main() {\n    // hi\n}\n```\n");
+
+            assert!(html.starts_with("<pre><code class=\"language-rust\"><span class=\"hl-keyword\">fn</span> <span class=\"hl-function\">main</span>"));
+            assert!(html.contains("<span class=\"hl-comment\">// hi</span>"));
+            assert!(html.matches("<pre>").count() == 1);
+        }
+
+        #[test]
+        fn test_fenced_code_block_keeps_backticks_and_html() {
+            let html = render("```json\n{\"md\": \"`<b>`\"}\n```\n");
+
+            assert!(html.contains("<span class=\"hl-string\">&quot;`&lt;b&gt;`&quot;</span>"));
+        }
+
+        #[test]
+        fn test_fenced_code_block_with_unknown_language_is_plain() {
+            assert!(render("```ruby\nputs 1\n```\n") == "<pre><code class=\"language-ruby\">puts 1\n</code></pre>");
+        }
+    }
+
+    mod tests_for_gfm_extensions {
+        use super::*;
+
+        fn render_with_extension(extension: MarkdownExtension, md: &str) -> String {
+            render_with(MarkdownRenderer::new().with_extension(extension), md)
+        }
+
+        #[test]
+        fn test_tables_have_head_body_and_alignment() {
+            let md = "| abc | defghi |\n:-: | -----------:\nbar | baz\n";
+
+            assert!(render_with_extension(MarkdownExtension::Tables, md) == concat!(
+                "<table><thead><tr><th align=\"center\">abc</th><th align=\"right\">defghi</th></tr></thead>",
+                "<tbody><tr><td align=\"center\">bar</td><td align=\"right\">baz</td></tr></tbody></table>"
+            ));
+            assert!(render(md).starts_with("<p>| abc |"));
+        }
+
+        #[test]
+        fn test_tables_without_body_rows() {
+            assert!(render_with_extension(MarkdownExtension::Tables, "| a | b |\n| - | - |\n") == "<table><thead><tr><th>a</th><th>b</th></tr></thead></table>");
+        }
+
+        #[test]
+        fn test_task_lists_render_disabled_checkboxes() {
+            let html = render_with_extension(MarkdownExtension::TaskLists, "- [ ] foo\n- [x] bar\n");
+
+            assert!(html == concat!(
+                "<ul><li><input type=\"checkbox\" disabled=\"\" /> foo</li>",
+                "<li><input type=\"checkbox\" disabled=\"\" checked=\"\" /> bar</li></ul>"
+            ));
+            assert!(render("- [ ] foo\n") == "<ul><li>[ ] foo</li></ul>");
+        }
+
+        #[test]
+        fn test_strikethrough() {
+            assert!(render_with_extension(MarkdownExtension::Strikethrough, "~~Hi~~ Hello, world!") == "<p><del>Hi</del> Hello, world!</p>");
+            assert!(render("~~Hi~~") == "<p>~~Hi~~</p>");
+        }
+
+        #[test]
+        fn test_footnotes_are_numbered_by_reference_and_link_back() {
+            let md = "[^note] then [^1] and [^note] again\n\n[^1]: First *one*\n\n[^note]: The note\n\n[^unused]: Never referenced\n";
+
+            let html = render_with_extension(MarkdownExtension::Footnotes, md);
+
+            assert!(html == concat!(
+                "<p><sup class=\"footnote-ref\"><a href=\"#fn-1\" id=\"fnref-1\">1</a></sup> then ",
+                "<sup class=\"footnote-ref\"><a href=\"#fn-2\" id=\"fnref-2\">2</a></sup> and ",
+                "<sup class=\"footnote-ref\"><a href=\"#fn-1\" id=\"fnref-1-2\">1</a></sup> again</p>\n",
+                "<section class=\"footnotes\"><hr /><ol>",
+                "<li id=\"fn-1\"><p>The note</p><a href=\"#fnref-1\" class=\"footnote-backref\" aria-label=\"Back to reference\">↩</a></li>",
+                "<li id=\"fn-2\"><p>First <em>one</em></p><a href=\"#fnref-2\" class=\"footnote-backref\" aria-label=\"Back to reference\">↩</a></li>",
+                "</ol></section>"
+            ));
+        }
+
+        #[test]
+        fn test_autolinks_bare_urls() {
+            let get_html = |md| render_with_extension(MarkdownExtension::Autolinks, md);
+
+            assert!(get_html("Visit www.commonmark.org/help for more information.") == "<p>Visit <a href=\"http://www.commonmark.org/help\">www.commonmark.org/help</a> for more information.</p>");
+            assert!(get_html("See https://example.com/a_b_c.") == "<p>See <a href=\"https://example.com/a_b_c\">https://example.com/a_b_c</a>.</p>");
+            assert!(get_html("(www.google.com/search?q=Markup+(business))") == "<p>(<a href=\"http://www.google.com/search?q=Markup+(business)\">www.google.com/search?q=Markup+(business)</a>)</p>");
+            assert!(get_html("[https://a.com](https://b.com) `https://c.com` www.") == "<p><a href=\"https://b.com\">https://a.com</a> <code>https://c.com</code> www.</p>");
+            assert!(render("https://example.com") == "<p>https://example.com</p>");
+        }
+
+        #[test]
+        fn test_new_with_gfm_enables_every_extension() {
+            let html = MarkdownRenderer::new_with_gfm().render_html("- [x] ~~done~~ see https://a.com[^1]\n\n[^1]: note\n");
+
+            assert!(html.contains("<input") && html.contains("<del>done</del>") && html.contains("<a href=\"https://a.com\">") && html.contains("class=\"footnotes\""));
+        }
+    }
+
+    mod tests_for_heading_anchors {
+        use super::*;
+
+        #[test]
+        fn test_headings_of_every_level_get_unique_ids_and_anchors() {
+            let md = "# Intro\n## Intro\n### Getting *started*\n#### Four\n##### Five\n###### Six!\n";
+
+            let html = render_with(MarkdownRenderer::new().with_extension(MarkdownExtension::HeadingAnchors), md);
+
+            assert!(html.starts_with(concat!(
+                "<h1 id=\"intro\">Intro<a href=\"#intro\" class=\"heading-anchor\" aria-label=\"Link to this heading\">#</a></h1>\n",
+                "<h2 id=\"intro-1\">Intro<a href=\"#intro-1\" class=\"heading-anchor\" aria-label=\"Link to this heading\">#</a></h2>\n",
+                "<h3 id=\"getting-started\">Getting <em>started</em><a href=\"#getting-started\""
+            )));
+            assert!(html.contains("<h4 id=\"four\">") && html.contains("<h5 id=\"five\">") && html.contains("<h6 id=\"six\">"));
+            assert!(render("# Intro\n") == "<h1>Intro</h1>");
+        }
+
+        #[test]
+        fn test_render_returns_headings_in_order() {
+            let rendered = MarkdownRenderer::new_with_gfm().render("# Title\n\ntext\n\n## `code` and [link](/a)\n\n> ### Quoted\n");
+
+            assert!(rendered.headings == vec![
+                Heading { level: 1, id: "title".to_string(), text: "Title".to_string() },
+                Heading { level: 2, id: "code-and-link".to_string(), text: "code and link".to_string() },
+                Heading { level: 3, id: "quoted".to_string(), text: "Quoted".to_string() }
+            ]);
+        }
+    }
+
+    mod tests_for_render_nodes {
+        use super::*;
+
+        #[test]
+        fn test_render_nodes_returns_one_node_per_block() {
+            let nodes = MarkdownRenderer::new().render_nodes("# Title\n\nSome *text*\nover two lines\n\n1. first\n2. second\n");
+
+            assert!(nodes.len() == 3);
+            assert!(nodes[0].to_html() == "<h1>Title</h1>");
+            assert!(nodes[1].get_text() == "Some text\nover two lines");
+            assert!(nodes[2].to_html().starts_with("<ol>"));
+        }
+
+        #[test]
+        fn test_render_html_keeps_text_around_links() {
+            let html = MarkdownRenderer::new().render_html(r#"[First Link](https://first.com "first")You can find more info here! [Second Link](https://second.com) click that link"#);
+
+            assert!(html == r#"<p><a href="https://first.com" title="first">First Link</a>You can find more info here! <a href="https://second.com">Second Link</a> click that link</p>"#);
+        }
+
+        #[test]
+        fn test_render_html_escapes_text() {
+            assert!(MarkdownRenderer::new().render_html("a < b & \"c\"") == "<p>a &lt; b &amp; &quot;c&quot;</p>");
+        }
+    }
+}
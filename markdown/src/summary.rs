@@ -0,0 +1,279 @@
+use std::ops::Range;
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use unicode_segmentation::UnicodeSegmentation;
+use crate::{html_node::HtmlNode, markdown_renderer::MarkdownRenderer};
+
+/// Written on a line of its own, ends the summary there instead of after a set length
+pub const MORE_MARKER: &str = "<!-- more -->";
+
+/// A top level block of the markdown, e.g. a paragraph, heading, list or code block
+struct Block {
+    range: Range<usize>,
+    /// Visible text, without the markdown syntax
+    text: String,
+    /// Places the block can be cut without breaking a link, emphasis or code span. Only paragraphs and headings have them
+    cut_points: Vec<CutPoint>
+}
+
+struct CutPoint {
+    offset: usize,
+    /// Graphemes of visible text before @offset
+    text_length: usize
+}
+
+impl MarkdownRenderer {
+    /// Returns markdown for the start of @md, ending before MORE_MARKER when @md has one.
+    /// Otherwise whole blocks are kept while their text fits in @max_length graphemes,
+    /// and a first block that does not fit is cut at the last word that does
+    pub fn get_summary(&self, md: &str, max_length: usize) -> String {
+        let parser = Parser::new_ext(md, self.options);
+        // reference definitions are usually at the end, so any the summary leaves out are added back for its links
+        let mut reference_definitions = parser.reference_definitions()
+            .iter()
+            .map(|(_, definition)| definition.span.clone())
+            .collect::<Vec<Range<usize>>>();
+        reference_definitions.sort_by_key(|span| span.start);
+
+        let (summary, summary_end) = match get_blocks(parser) {
+            (_, Some(more_marker_offset)) => (md[..more_marker_offset].trim_end().to_string(), more_marker_offset),
+            (blocks, None) => get_blocks_summary(md, &blocks, max_length)
+        };
+
+        reference_definitions
+            .into_iter()
+            .filter(|span| span.start >= summary_end)
+            .fold(summary, |summary, span| format!("{}\n\n{}", summary, md[span].trim()))
+    }
+
+    /// The text of @md without markup, code blocks or footnotes, with whitespace collapsed.
+    /// Cut at the last word that fits in @max_length graphemes, for a meta description
+    pub fn get_plain_text(&self, md: &str, max_length: usize) -> String {
+        let renderer = MarkdownRenderer { heading_anchors: false, ..*self };
+        let mut text = String::new();
+        let mut is_in_raw_script = false;
+        for node in renderer.render_nodes(md).iter() {
+            push_plain_text(node, &mut text, &mut is_in_raw_script);
+        }
+        let words = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+
+        truncate_text(&words, max_length)
+    }
+}
+
+/// Returns the top level blocks and where MORE_MARKER starts, if it is written
+fn get_blocks(parser: Parser) -> (Vec<Block>, Option<usize>) {
+    let mut blocks: Vec<Block> = vec![];
+    let mut depth = 0;
+    let mut is_cuttable = false;
+    let mut is_footnote_definition = false;
+
+    for (event, range) in parser.into_offset_iter() {
+        match event {
+            Event::Start(tag) => {
+                if depth == 0 {
+                    is_cuttable = matches!(tag, Tag::Paragraph | Tag::Heading { .. });
+                    is_footnote_definition = matches!(tag, Tag::FootnoteDefinition(_));
+                    blocks.push(Block { range: range.clone(), text: "".to_string(), cut_points: vec![] });
+                }
+                depth += 1;
+            },
+            Event::End(tag_end) => {
+                depth -= 1;
+                // definitions are gathered at the end of the html, so they are not part of a summary
+                if depth == 0 && is_footnote_definition {
+                    blocks.pop();
+                } else if depth > 0 && is_block_end(tag_end) {
+                    if let Some(block) = blocks.last_mut() {
+                        block.text.push(' ');
+                    }
+                }
+            },
+            Event::Html(html) | Event::InlineHtml(html) if html.trim() == MORE_MARKER => return (blocks, Some(range.start)),
+            Event::Rule if depth == 0 => blocks.push(Block { range, text: "".to_string(), cut_points: vec![] }),
+            Event::Text(text) | Event::Code(text) => {
+                let Some(block) = blocks.last_mut() else {
+                    continue;
+                };
+                // text only maps onto its source when nothing in it was escaped, e.g. \* or &amp;
+                if is_cuttable && depth == 1 && text.len() == range.len() {
+                    let text_length = block.text.graphemes(true).count();
+                    block.cut_points.extend(text.char_indices().filter(|(_, c)| c.is_whitespace()).map(|(index, _)| CutPoint {
+                        offset: range.start + index,
+                        text_length: text_length + text[..index].graphemes(true).count()
+                    }));
+                }
+                block.text.push_str(&text);
+            },
+            Event::SoftBreak | Event::HardBreak => {
+                if let Some(block) = blocks.last_mut() {
+                    if is_cuttable && depth == 1 {
+                        block.cut_points.push(CutPoint { offset: range.start, text_length: block.text.graphemes(true).count() });
+                    }
+                    block.text.push(' ');
+                }
+            },
+            _ => ()
+        }
+    }
+    (blocks, None)
+}
+
+fn is_block_end(tag_end: TagEnd) -> bool {
+    matches!(tag_end, TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::BlockQuote | TagEnd::CodeBlock | TagEnd::Item | TagEnd::TableCell)
+}
+
+/// Returns the summary and where it ends in @md
+fn get_blocks_summary(md: &str, blocks: &[Block], max_length: usize) -> (String, usize) {
+    let mut text_length = 0;
+    let mut end = None;
+    for block in blocks {
+        let block_length = block.text.trim_end().graphemes(true).count();
+        if text_length + block_length > max_length {
+            break;
+        }
+        text_length += block_length;
+        end = Some(block.range.end);
+    }
+
+    match (end, blocks.first()) {
+        (Some(end), _) => (md[..end].trim_end().to_string(), end),
+        (None, Some(first_block)) => get_cut_block(md, first_block, max_length),
+        (None, None) => ("".to_string(), 0)
+    }
+}
+
+fn get_cut_block(md: &str, block: &Block, max_length: usize) -> (String, usize) {
+    let cut_point = block.cut_points
+        .iter()
+        .rev()
+        .find(|cut_point| cut_point.text_length > 0 && cut_point.text_length <= max_length);
+
+    match cut_point {
+        Some(cut_point) => (md[..cut_point.offset].trim_end().to_string(), cut_point.offset),
+        // one long word, or a block such as a list or code block, so only its text is kept
+        None => (escape_markdown(&truncate_text(&block.text, max_length)), block.range.end)
+    }
+}
+
+/// Block level elements are followed by a space, so their text does not run together.
+/// Inline html is raw nodes around text nodes, so @is_in_raw_script tracks text inside <script> or <style>
+fn push_plain_text(node: &HtmlNode, text: &mut String, is_in_raw_script: &mut bool) {
+    match node {
+        HtmlNode::Element(element) => {
+            if ["pre", "section", "sup", "input"].contains(&element.tag) {
+                return;
+            }
+            for child in element.children.iter() {
+                push_plain_text(child, text, is_in_raw_script);
+            }
+            if ["p", "h1", "h2", "h3", "h4", "h5", "h6", "blockquote", "li", "th", "td", "br"].contains(&element.tag) {
+                text.push(' ');
+            }
+        },
+        HtmlNode::Text(node_text) if !*is_in_raw_script => text.push_str(node_text),
+        HtmlNode::Text(_) => (),
+        HtmlNode::Raw(raw) => {
+            let raw = raw.to_lowercase();
+            if raw.starts_with("<script") || raw.starts_with("<style") {
+                *is_in_raw_script = true;
+            }
+            if raw.contains("</script") || raw.contains("</style") {
+                *is_in_raw_script = false;
+            }
+        }
+    }
+}
+
+/// Cuts @text at the last whitespace within @max_length graphemes, or mid word when it is one long word
+fn truncate_text(text: &str, max_length: usize) -> String {
+    let Some((end, _)) = text.grapheme_indices(true).nth(max_length) else {
+        return text.trim_end().to_string();
+    };
+    let cut_text = &text[..end];
+    let cut_text = match cut_text.rfind(char::is_whitespace) {
+        Some(last_space) if last_space > 0 => &cut_text[..last_space],
+        _ => cut_text
+    };
+    cut_text.trim_end().to_string()
+}
+
+fn escape_markdown(text: &str) -> String {
+    text.chars().fold(String::with_capacity(text.len()), |mut escaped, c| {
+        if c.is_ascii_punctuation() {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+        escaped
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_summary(md: &str, max_length: usize) -> String {
+        MarkdownRenderer::new_with_gfm().get_summary(md, max_length)
+    }
+
+    #[test]
+    fn test_get_summary_keeps_whole_blocks_that_fit() {
+        let md = "# Title\n\nFirst paragraph.\n\n```rust\nfn main() {}\n```\n\nLast paragraph that does not fit.";
+
+        assert!(get_summary(md, 40) == "# Title\n\nFirst paragraph.\n\n```rust\nfn main() {}\n```");
+        assert!(get_summary(md, 1000) == md);
+    }
+
+    #[test]
+    fn test_get_summary_does_not_cut_through_code_fences_or_links() {
+        let md = "Intro\n\n```\nlet a = 1;\nlet b = 2;\n```\n\nMore";
+
+        assert!(get_summary(md, 10) == "Intro");
+        assert!(get_summary("See [the docs](https://docs.rs/a-long-path) for more", 12) == "See [the docs](https://docs.rs/a-long-path)");
+        assert!(get_summary("See [the docs](https://docs.rs/a-long-path) for more", 11) == "See");
+        assert!(get_summary("Some **bold words** and more words", 20) == "Some **bold words** and");
+    }
+
+    #[test]
+    fn test_get_summary_cuts_on_grapheme_boundaries() {
+        let md = "Héllo wörld 👩‍👩‍👧 family emoji and more text";
+
+        assert!(get_summary(md, 13) == "Héllo wörld 👩‍👩‍👧");
+        assert!(get_summary("👩‍👩‍👧👩‍👩‍👧👩‍👩‍👧", 2) == "👩‍👩‍👧👩‍👩‍👧");
+        assert!(get_summary("- a *list* item that is long", 8) == "a list");
+    }
+
+    #[test]
+    fn test_get_summary_ends_at_more_marker() {
+        let md = "Start of the post.\n\n- one\n- two\n\n<!-- more -->\n\nThe rest of the post.";
+
+        assert!(get_summary(md, 5) == "Start of the post.\n\n- one\n- two");
+        assert!(get_summary("```\n<!-- more -->\n```\n\nText", 100) == "```\n<!-- more -->\n```\n\nText");
+    }
+
+    #[test]
+    fn test_get_summary_keeps_reference_definitions_it_uses() {
+        let md = "Read [the book][book].\n\nMore text that is cut.\n\n[book]: https://doc.rust-lang.org/book \"The Book\"";
+
+        let summary = get_summary(md, 20);
+
+        assert!(summary == "Read [the book][book].\n\n[book]: https://doc.rust-lang.org/book \"The Book\"");
+        assert!(MarkdownRenderer::new().render_html(&summary) == "<p>Read <a href=\"https://doc.rust-lang.org/book\" title=\"The Book\">the book</a>.</p>");
+    }
+
+    #[test]
+    fn test_get_summary_leaves_out_footnote_definitions() {
+        let md = "[^1]: A note\n\nText with a note[^1].\n\nMore text.";
+
+        assert!(get_summary(md, 20) == "[^1]: A note\n\nText with a note[^1].");
+    }
+
+    #[test]
+    fn test_get_plain_text_drops_markup_and_code() {
+        let md = "# Title\n\nSome *emphasis* and a [link](https://a.com).\n\n```rust\nfn main() {}\n```\n\n- one\n- two";
+
+        assert!(MarkdownRenderer::new_with_gfm().get_plain_text(md, 100) == "Title Some emphasis and a link. one two");
+        assert!(MarkdownRenderer::new_with_gfm().get_plain_text(md, 20) == "Title Some emphasis");
+        assert!(MarkdownRenderer::new_with_gfm().get_plain_text("Text with a note[^1].\n\n[^1]: The note", 100) == "Text with a note.");
+        assert!(MarkdownRenderer::new().get_plain_text("Some <b>html</b><script>alert(1)</script> text", 100) == "Some html text");
+    }
+}
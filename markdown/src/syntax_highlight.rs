@@ -0,0 +1,597 @@
+//! Token level highlighting for fenced code blocks.
+//! Written as small hand rolled scanners, rather than grammar files, so it stays light in the wasm build
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HighlightLanguage {
+    Rust,
+    Toml,
+    Shell,
+    Json
+}
+
+impl HighlightLanguage {
+    /// Matches the language given on a code fence, e.g. ```rust
+    pub fn from_fence_language(language: &str) -> Option<Self> {
+        match language.to_lowercase().as_str() {
+            "rust" | "rs" => Some(HighlightLanguage::Rust),
+            "toml" => Some(HighlightLanguage::Toml),
+            "sh" | "shell" | "bash" | "zsh" | "console" => Some(HighlightLanguage::Shell),
+            "json" => Some(HighlightLanguage::Json),
+            _ => None
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TokenKind {
+    Keyword,
+    Type,
+    Function,
+    Macro,
+    Attribute,
+    Lifetime,
+    Variable,
+    Property,
+    String,
+    Number,
+    /// true, false, null and the like
+    Literal,
+    Comment
+}
+
+impl TokenKind {
+    /// Css class of the span wrapping the token
+    pub fn class_name(&self) -> &'static str {
+        match self {
+            TokenKind::Keyword => "hl-keyword",
+            TokenKind::Type => "hl-type",
+            TokenKind::Function => "hl-function",
+            TokenKind::Macro => "hl-macro",
+            TokenKind::Attribute => "hl-attribute",
+            TokenKind::Lifetime => "hl-lifetime",
+            TokenKind::Variable => "hl-variable",
+            TokenKind::Property => "hl-property",
+            TokenKind::String => "hl-string",
+            TokenKind::Number => "hl-number",
+            TokenKind::Literal => "hl-literal",
+            TokenKind::Comment => "hl-comment"
+        }
+    }
+}
+
+/// A run of code text, @kind is None for text that is not highlighted such as whitespace and punctuation
+#[derive(Clone, Debug, PartialEq)]
+pub struct HighlightedToken {
+    pub kind: Option<TokenKind>,
+    pub text: String
+}
+
+/// Splits @code into tokens. Joining the token texts always gives back @code unchanged
+pub fn highlight_code(language: HighlightLanguage, code: &str) -> Vec<HighlightedToken> {
+    let mut scanner = Scanner::new(code);
+    match language {
+        HighlightLanguage::Rust => scan_rust(&mut scanner),
+        HighlightLanguage::Toml => scan_toml(&mut scanner),
+        HighlightLanguage::Shell => scan_shell(&mut scanner),
+        HighlightLanguage::Json => scan_json(&mut scanner)
+    }
+    scanner.tokens
+}
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "fn", "for", "if", "impl", "in",
+    "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait",
+    "type", "union", "unsafe", "use", "where", "while"
+];
+const RUST_PRIMITIVE_TYPES: &[&str] = &[
+    "bool", "char", "str", "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize", "f32", "f64"
+];
+const SHELL_KEYWORDS: &[&str] = &[
+    "if", "then", "else", "elif", "fi", "for", "while", "until", "do", "done", "case", "esac", "in", "function", "return",
+    "export", "local", "select"
+];
+/// Characters that end a shell word
+const SHELL_OPERATORS: &str = "|&;()<>";
+
+struct Scanner {
+    chars: Vec<char>,
+    position: usize,
+    tokens: Vec<HighlightedToken>
+}
+
+impl Scanner {
+    fn new(code: &str) -> Self {
+        Scanner { chars: code.chars().collect(), position: 0, tokens: vec![] }
+    }
+
+    fn is_done(&self) -> bool {
+        self.position >= self.chars.len()
+    }
+
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.position + offset).copied()
+    }
+
+    fn starts_with(&self, value: &str) -> bool {
+        value.chars().enumerate().all(|(offset, c)| self.peek(offset) == Some(c))
+    }
+
+    /// True when only spaces or tabs come before the current position on its line
+    fn is_line_start(&self) -> bool {
+        self.chars[..self.position].iter().rev().take_while(|c| **c != '\n').all(|c| *c == ' ' || *c == '\t')
+    }
+
+    /// The next character after any spaces, tabs or line breaks
+    fn peek_past_whitespace(&self) -> Option<char> {
+        self.chars[self.position..].iter().find(|c| !c.is_whitespace()).copied()
+    }
+
+    fn take(&mut self, count: usize) -> String {
+        let end = (self.position + count).min(self.chars.len());
+        let text = self.chars[self.position..end].iter().collect();
+        self.position = end;
+        text
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> String {
+        let start = self.position;
+        while self.peek(0).map(&predicate).unwrap_or_default() {
+            self.position += 1;
+        }
+        self.chars[start..self.position].iter().collect()
+    }
+
+    fn take_line(&mut self) -> String {
+        self.take_while(|c| c != '\n')
+    }
+
+    /// Takes up to and including @terminator, or the rest of the code when it never comes
+    fn take_until(&mut self, terminator: &str) -> String {
+        let start = self.position;
+        while !self.is_done() && !self.starts_with(terminator) {
+            self.position += 1;
+        }
+        self.position = (self.position + terminator.chars().count()).min(self.chars.len());
+        self.chars[start..self.position].iter().collect()
+    }
+
+    /// Takes a string opened by @quote, a backslash escapes the next character when @has_escapes
+    fn take_quoted(&mut self, quote: char, has_escapes: bool) -> String {
+        let start = self.position;
+        self.position += 1;
+        while let Some(c) = self.peek(0) {
+            self.position += 1;
+            if c == quote {
+                break;
+            }
+            if c == '\\' && has_escapes {
+                self.position = (self.position + 1).min(self.chars.len());
+            }
+        }
+        self.chars[start..self.position].iter().collect()
+    }
+
+    fn push(&mut self, kind: Option<TokenKind>, text: String) {
+        if text.is_empty() {
+            return;
+        }
+        match self.tokens.last_mut() {
+            Some(last) if kind.is_none() && last.kind.is_none() => last.text.push_str(&text),
+            _ => self.tokens.push(HighlightedToken { kind, text })
+        }
+    }
+
+    fn push_plain_char(&mut self) {
+        let text = self.take(1);
+        self.push(None, text);
+    }
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn scan_rust(scanner: &mut Scanner) {
+    let mut previous_keyword = String::new();
+
+    while let Some(c) = scanner.peek(0) {
+        if scanner.starts_with("//") {
+            let text = scanner.take_line();
+            scanner.push(Some(TokenKind::Comment), text);
+        } else if scanner.starts_with("/*") {
+            let text = take_rust_block_comment(scanner);
+            scanner.push(Some(TokenKind::Comment), text);
+        } else if let Some(prefix_length) = get_rust_raw_string_prefix_length(scanner) {
+            let hashes = "#".repeat(prefix_length - if c == 'b' { 3 } else { 2 });
+            let prefix = scanner.take(prefix_length);
+            let rest = scanner.take_until(&format!("\"{}", hashes));
+            scanner.push(Some(TokenKind::String), prefix + &rest);
+        } else if c == '"' || (c == 'b' && scanner.peek(1) == Some('"')) {
+            let prefix = if c == 'b' { scanner.take(1) } else { "".to_string() };
+            let text = scanner.take_quoted('"', true);
+            scanner.push(Some(TokenKind::String), prefix + &text);
+        } else if c == '\'' {
+            scan_rust_char_or_lifetime(scanner);
+        } else if scanner.starts_with("#[") || scanner.starts_with("#![") {
+            let text = take_rust_attribute(scanner);
+            scanner.push(Some(TokenKind::Attribute), text);
+        } else if c.is_ascii_digit() {
+            let text = take_number(scanner);
+            scanner.push(Some(TokenKind::Number), text);
+        } else if is_identifier_start(c) {
+            let mut identifier = scanner.take_while(is_identifier_char);
+            let next = scanner.peek(0);
+            let kind = if next == Some('!') && scanner.peek(1) != Some('=') {
+                identifier.push_str(&scanner.take(1));
+                Some(TokenKind::Macro)
+            } else if identifier == "true" || identifier == "false" {
+                Some(TokenKind::Literal)
+            } else if RUST_KEYWORDS.contains(&identifier.as_str()) {
+                Some(TokenKind::Keyword)
+            } else if RUST_PRIMITIVE_TYPES.contains(&identifier.as_str()) || identifier.starts_with(char::is_uppercase) {
+                Some(TokenKind::Type)
+            } else if previous_keyword == "fn" || next == Some('(') || scanner.starts_with("::<") {
+                Some(TokenKind::Function)
+            } else {
+                None
+            };
+            // the name after fn is a function even when generics come before its arguments
+            previous_keyword = if kind == Some(TokenKind::Keyword) { identifier.clone() } else { String::new() };
+            scanner.push(kind, identifier);
+        } else {
+            scanner.push_plain_char();
+        }
+    }
+}
+
+/// Length of r", r#", br#" and so on, when a raw string starts here
+fn get_rust_raw_string_prefix_length(scanner: &Scanner) -> Option<usize> {
+    let r_offset = match (scanner.peek(0), scanner.peek(1)) {
+        (Some('r'), _) => 0,
+        (Some('b'), Some('r')) => 1,
+        _ => return None
+    };
+    // must not be the end of an identifier such as `bar"`
+    if scanner.position > 0 && is_identifier_char(scanner.chars[scanner.position - 1]) {
+        return None;
+    }
+
+    let mut offset = r_offset + 1;
+    while scanner.peek(offset) == Some('#') {
+        offset += 1;
+    }
+    if scanner.peek(offset) == Some('"') {
+        return Some(offset + 1);
+    }
+    None
+}
+
+/// Rust block comments nest
+fn take_rust_block_comment(scanner: &mut Scanner) -> String {
+    let mut text = scanner.take(2);
+    let mut depth = 1;
+    while depth > 0 && !scanner.is_done() {
+        if scanner.starts_with("/*") {
+            depth += 1;
+            text.push_str(&scanner.take(2));
+        } else if scanner.starts_with("*/") {
+            depth -= 1;
+            text.push_str(&scanner.take(2));
+        } else {
+            text.push_str(&scanner.take(1));
+        }
+    }
+    text
+}
+
+fn take_rust_attribute(scanner: &mut Scanner) -> String {
+    let mut text = String::new();
+    let mut depth = 0;
+    while let Some(c) = scanner.peek(0) {
+        if c == '"' {
+            text.push_str(&scanner.take_quoted('"', true));
+            continue;
+        }
+        text.push_str(&scanner.take(1));
+        match c {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            },
+            _ => ()
+        }
+    }
+    text
+}
+
+/// A quote starts either a char literal, 'a' or '\n', or a lifetime, 'a
+fn scan_rust_char_or_lifetime(scanner: &mut Scanner) {
+    let is_char = matches!((scanner.peek(1), scanner.peek(2)), (Some('\\'), _) | (Some(_), Some('\'')));
+
+    if is_char {
+        let text = scanner.take_quoted('\'', true);
+        scanner.push(Some(TokenKind::String), text);
+    } else if scanner.peek(1).map(is_identifier_start).unwrap_or_default() {
+        let quote = scanner.take(1);
+        let name = scanner.take_while(is_identifier_char);
+        scanner.push(Some(TokenKind::Lifetime), quote + &name);
+    } else {
+        scanner.push_plain_char();
+    }
+}
+
+/// Covers integers, floats, hex and suffixes like 1_000u32. A dot is only taken when a digit follows, so ranges stay intact
+fn take_number(scanner: &mut Scanner) -> String {
+    let mut text = scanner.take_while(is_identifier_char);
+    if scanner.peek(0) == Some('.') && scanner.peek(1).map(|c| c.is_ascii_digit()).unwrap_or_default() {
+        text.push_str(&scanner.take(1));
+        text.push_str(&scanner.take_while(is_identifier_char));
+    }
+    text
+}
+
+fn scan_toml(scanner: &mut Scanner) {
+    let mut inline_table_depth = 0;
+    let mut array_depth = 0;
+    let mut expects_key = true;
+
+    while let Some(c) = scanner.peek(0) {
+        if c == '\n' {
+            expects_key = inline_table_depth == 0 && array_depth == 0;
+            scanner.push_plain_char();
+        } else if c == '#' {
+            let text = scanner.take_line();
+            scanner.push(Some(TokenKind::Comment), text);
+        } else if c == '[' && scanner.is_line_start() && inline_table_depth == 0 {
+            let text = scanner.take_while(|c| c != '\n' && c != '#');
+            let header = text.trim_end();
+            scanner.push(Some(TokenKind::Type), header.to_string());
+            scanner.push(None, text[header.len()..].to_string());
+        } else if scanner.starts_with("\"\"\"") || scanner.starts_with("'''") {
+            let delimiter = scanner.take(3);
+            let rest = scanner.take_until(&delimiter);
+            scanner.push(Some(TokenKind::String), delimiter + &rest);
+        } else if c == '"' || c == '\'' {
+            let text = scanner.take_quoted(c, c == '"');
+            let kind = if expects_key { TokenKind::Property } else { TokenKind::String };
+            scanner.push(Some(kind), text);
+        } else if c == '=' {
+            expects_key = false;
+            scanner.push_plain_char();
+        } else if c == '[' || c == ']' {
+            array_depth += if c == '[' { 1 } else { -1 };
+            scanner.push_plain_char();
+        } else if c == '{' || (c == ',' && inline_table_depth > 0 && array_depth == 0) {
+            if c == '{' {
+                inline_table_depth += 1;
+            }
+            expects_key = true;
+            scanner.push_plain_char();
+        } else if c == '}' {
+            inline_table_depth -= 1;
+            scanner.push_plain_char();
+        } else if expects_key && (c.is_alphanumeric() || c == '_' || c == '-') {
+            let text = scanner.take_while(|c| c.is_alphanumeric() || c == '_' || c == '-');
+            scanner.push(Some(TokenKind::Property), text);
+        } else if c.is_ascii_digit() || ((c == '+' || c == '-') && scanner.peek(1).map(|c| c.is_ascii_digit()).unwrap_or_default()) {
+            // also covers dates and times such as 1979-05-27T07:32:00Z
+            let sign = if c.is_ascii_digit() { "".to_string() } else { scanner.take(1) };
+            let text = scanner.take_while(|c| c.is_alphanumeric() || "_:.+-".contains(c));
+            scanner.push(Some(TokenKind::Number), sign + &text);
+        } else if is_identifier_start(c) {
+            let text = scanner.take_while(is_identifier_char);
+            let kind = if ["true", "false", "inf", "nan"].contains(&text.as_str()) { Some(TokenKind::Literal) } else { None };
+            scanner.push(kind, text);
+        } else {
+            scanner.push_plain_char();
+        }
+    }
+}
+
+fn scan_shell(scanner: &mut Scanner) {
+    let mut is_command_position = true;
+
+    while let Some(c) = scanner.peek(0) {
+        let is_word_start = scanner.position == 0 || scanner.chars[scanner.position - 1].is_whitespace();
+
+        if c == '\n' {
+            is_command_position = true;
+            scanner.push_plain_char();
+        } else if c.is_whitespace() {
+            scanner.push_plain_char();
+        } else if c == '#' && is_word_start {
+            let text = scanner.take_line();
+            scanner.push(Some(TokenKind::Comment), text);
+        } else if c == '$' && scanner.peek(1) == Some(' ') && scanner.is_line_start() {
+            // console prompt
+            scanner.push_plain_char();
+        } else if c == '\'' || c == '"' {
+            let text = scanner.take_quoted(c, c == '"');
+            scanner.push(Some(TokenKind::String), text);
+            is_command_position = false;
+        } else if c == '$' && scanner.peek(1) == Some('{') {
+            let text = scanner.take_until("}");
+            scanner.push(Some(TokenKind::Variable), text);
+        } else if c == '$' && scanner.peek(1).map(|c| is_identifier_char(c) || "?#@*!$-".contains(c)).unwrap_or_default() {
+            let dollar = scanner.take(1);
+            let name = if scanner.peek(0).map(is_identifier_char).unwrap_or_default() {
+                scanner.take_while(is_identifier_char)
+            } else {
+                scanner.take(1)
+            };
+            scanner.push(Some(TokenKind::Variable), dollar + &name);
+        } else if SHELL_OPERATORS.contains(c) {
+            is_command_position = c != ')' && c != '>' && c != '<';
+            scanner.push_plain_char();
+        } else {
+            let word = scanner.take_while(|c| !c.is_whitespace() && !SHELL_OPERATORS.contains(c) && c != '"' && c != '\'' && c != '$');
+            if word.is_empty() {
+                scanner.push_plain_char();
+                continue;
+            }
+            let kind = if SHELL_KEYWORDS.contains(&word.as_str()) {
+                // the word after these is a command again
+                is_command_position = ["then", "else", "do", "elif", "if", "while", "until"].contains(&word.as_str());
+                Some(TokenKind::Keyword)
+            } else if is_command_position && !word.contains('=') {
+                is_command_position = false;
+                Some(TokenKind::Function)
+            } else if word.chars().all(|c| c.is_ascii_digit()) {
+                Some(TokenKind::Number)
+            } else {
+                None
+            };
+            scanner.push(kind, word);
+        }
+    }
+}
+
+fn scan_json(scanner: &mut Scanner) {
+    while let Some(c) = scanner.peek(0) {
+        if c == '"' {
+            let text = scanner.take_quoted('"', true);
+            let kind = if scanner.peek_past_whitespace() == Some(':') { TokenKind::Property } else { TokenKind::String };
+            scanner.push(Some(kind), text);
+        } else if c.is_ascii_digit() || (c == '-' && scanner.peek(1).map(|c| c.is_ascii_digit()).unwrap_or_default()) {
+            let text = scanner.take_while(|c| c.is_ascii_digit() || "-+.eE".contains(c));
+            scanner.push(Some(TokenKind::Number), text);
+        } else if c.is_alphabetic() {
+            let text = scanner.take_while(char::is_alphabetic);
+            let kind = if ["true", "false", "null"].contains(&text.as_str()) { Some(TokenKind::Literal) } else { None };
+            scanner.push(kind, text);
+        } else {
+            scanner.push_plain_char();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_tokens_of_kind(language: HighlightLanguage, code: &str, kind: TokenKind) -> Vec<String> {
+        highlight_code(language, code)
+            .into_iter()
+            .filter(|token| token.kind == Some(kind))
+            .map(|token| token.text)
+            .collect()
+    }
+
+    fn assert_round_trips(language: HighlightLanguage, code: &str) {
+        let joined = highlight_code(language, code).into_iter().map(|token| token.text).collect::<String>();
+        assert_eq!(joined, code);
+    }
+
+    const RUST_CODE: &str = r##"#[derive(Debug)]
+pub struct Post<'a> {
+    title: &'a str, // the title
+}
+
+/* outer /* nested */ still comment */
+fn get_title(post: &Post) -> String {
+    let raw = r#"say "hi""#;
+    let c = '\n';
+    println!("{} {}", post.title, 1_000u32);
+    for i in 0..10 { _ = i != 2; }
+    String::from("done")
+}
+"##;
+
+    #[test]
+    fn test_highlight_code_rust_tokens() {
+        let get_tokens = |kind| get_tokens_of_kind(HighlightLanguage::Rust, RUST_CODE, kind);
+
+        assert_round_trips(HighlightLanguage::Rust, RUST_CODE);
+        assert!(get_tokens(TokenKind::Attribute) == vec!["#[derive(Debug)]"]);
+        assert!(get_tokens(TokenKind::Lifetime) == vec!["'a", "'a"]);
+        assert!(get_tokens(TokenKind::Comment) == vec!["// the title", "/* outer /* nested */ still comment */"]);
+        assert!(get_tokens(TokenKind::String) == vec![r##"r#"say "hi""#"##, r"'\n'", r#""{} {}""#, r#""done""#]);
+        assert!(get_tokens(TokenKind::Macro) == vec!["println!"]);
+        assert!(get_tokens(TokenKind::Number) == vec!["1_000u32", "0", "10", "2"]);
+        assert!(get_tokens(TokenKind::Function) == vec!["get_title", "from"]);
+        assert!(get_tokens(TokenKind::Type).contains(&"Post".to_string()) && get_tokens(TokenKind::Type).contains(&"str".to_string()));
+        assert!(get_tokens(TokenKind::Keyword).starts_with(&["pub".to_string(), "struct".to_string()]));
+    }
+
+    const TOML_CODE: &str = r#"[package]
+name = "rustyindie-common" # crate name
+edition = 2021
+
+[[bin]]
+serde = { version = "1.0", features = ["derive"] }
+published = 1979-05-27T07:32:00Z
+enabled = true
+"#;
+
+    #[test]
+    fn test_highlight_code_toml_tokens() {
+        let get_tokens = |kind| get_tokens_of_kind(HighlightLanguage::Toml, TOML_CODE, kind);
+
+        assert_round_trips(HighlightLanguage::Toml, TOML_CODE);
+        assert!(get_tokens(TokenKind::Type) == vec!["[package]", "[[bin]]"]);
+        assert!(get_tokens(TokenKind::Property) == vec!["name", "edition", "serde", "version", "features", "published", "enabled"]);
+        assert!(get_tokens(TokenKind::String) == vec![r#""rustyindie-common""#, r#""1.0""#, r#""derive""#]);
+        assert!(get_tokens(TokenKind::Number) == vec!["2021", "1979-05-27T07:32:00Z"]);
+        assert!(get_tokens(TokenKind::Comment) == vec!["# crate name"]);
+        assert!(get_tokens(TokenKind::Literal) == vec!["true"]);
+    }
+
+    const SHELL_CODE: &str = r#"$ cargo build --release
+# install the cli
+export PATH="$HOME/.cargo/bin:$PATH"
+if [ -f "$FILE" ]; then echo ${FILE} | grep -v 42; fi
+"#;
+
+    #[test]
+    fn test_highlight_code_shell_tokens() {
+        let get_tokens = |kind| get_tokens_of_kind(HighlightLanguage::Shell, SHELL_CODE, kind);
+
+        assert_round_trips(HighlightLanguage::Shell, SHELL_CODE);
+        assert!(get_tokens(TokenKind::Function) == vec!["cargo", "[", "echo", "grep"]);
+        assert!(get_tokens(TokenKind::Comment) == vec!["# install the cli"]);
+        assert!(get_tokens(TokenKind::Keyword) == vec!["export", "if", "then", "fi"]);
+        assert!(get_tokens(TokenKind::String) == vec![r#""$HOME/.cargo/bin:$PATH""#, r#""$FILE""#]);
+        assert!(get_tokens(TokenKind::Variable) == vec!["${FILE}"]);
+        assert!(get_tokens(TokenKind::Number) == vec!["42"]);
+    }
+
+    const JSON_CODE: &str = r#"{
+  "id": 12,
+  "title": "Hello",
+  "tags": ["rust", -1.5e3, null],
+  "is_draft": false
+}"#;
+
+    #[test]
+    fn test_highlight_code_json_tokens() {
+        let get_tokens = |kind| get_tokens_of_kind(HighlightLanguage::Json, JSON_CODE, kind);
+
+        assert_round_trips(HighlightLanguage::Json, JSON_CODE);
+        assert!(get_tokens(TokenKind::Property) == vec![r#""id""#, r#""title""#, r#""tags""#, r#""is_draft""#]);
+        assert!(get_tokens(TokenKind::String) == vec![r#""Hello""#, r#""rust""#]);
+        assert!(get_tokens(TokenKind::Number) == vec!["12", "-1.5e3"]);
+        assert!(get_tokens(TokenKind::Literal) == vec!["null", "false"]);
+    }
+
+    #[test]
+    fn test_highlight_code_keeps_unterminated_input() {
+        assert_round_trips(HighlightLanguage::Rust, "let s = \"never closed");
+        assert_round_trips(HighlightLanguage::Rust, "/* never closed");
+        assert_round_trips(HighlightLanguage::Shell, "echo ${never");
+        assert_round_trips(HighlightLanguage::Toml, "key = '''never");
+    }
+
+    #[test]
+    fn test_from_fence_language() {
+        assert!(HighlightLanguage::from_fence_language("Rust") == Some(HighlightLanguage::Rust));
+        assert!(HighlightLanguage::from_fence_language("bash") == Some(HighlightLanguage::Shell));
+        assert!(HighlightLanguage::from_fence_language("ruby").is_none());
+    }
+}
@@ -0,0 +1,51 @@
+use rustyindie_markdown::markdown_renderer::MarkdownRenderer;
+use serde::Deserialize;
+
+/// An example of the CommonMark spec. spec/commonmark_spec.json has every example of the spec pulldown-cmark 0.10.3
+/// is tested against, numbered as in the spec
+#[derive(Deserialize)]
+struct SpecExample {
+    example: u32,
+    markdown: String,
+    html: String
+}
+
+/// Examples whose html differs from the spec's, and why. Blocks are written without the newline the spec puts after them,
+/// which normalize_spec_html can only allow for when a tag follows
+const EXCLUDED_EXAMPLES: [(u32, &str); 2] = [
+    (191, "no newline between a code block and the indentation of the raw html line after it"),
+    (300, "no newline between a heading and the text after it in a tight list item")
+];
+
+/// The spec writes a newline around every block tag, here blocks are only separated by render
+fn normalize_spec_html(html: &str) -> String {
+    html.trim_end()
+        .replace("\n<", "<")
+        .replace(">\n<", "><")
+        .replace("&quot;", "\"")
+}
+
+#[test]
+fn test_render_html_matches_every_commonmark_spec_example() {
+    let examples = serde_json::from_str::<Vec<SpecExample>>(include_str!("spec/commonmark_spec.json")).unwrap();
+
+    let failed_examples = examples
+        .iter()
+        .filter(|example| !EXCLUDED_EXAMPLES.iter().any(|(excluded, _)| *excluded == example.example))
+        .filter(|example| normalize_spec_html(&MarkdownRenderer::new().render_html(&example.markdown)) != normalize_spec_html(&example.html))
+        .map(|example| example.example)
+        .collect::<Vec<u32>>();
+
+    assert!(examples.len() == 652);
+    assert!(failed_examples.is_empty(), "spec examples failed: {:?}", failed_examples);
+}
+
+#[test]
+fn test_excluded_commonmark_spec_examples_still_differ() {
+    let examples = serde_json::from_str::<Vec<SpecExample>>(include_str!("spec/commonmark_spec.json")).unwrap();
+
+    for (excluded, _) in EXCLUDED_EXAMPLES {
+        let example = examples.iter().find(|example| example.example == excluded).unwrap();
+        assert!(normalize_spec_html(&MarkdownRenderer::new().render_html(&example.markdown)) != normalize_spec_html(&example.html), "example {} now passes", excluded);
+    }
+}
@@ -6,6 +6,7 @@ use rustyindie_common::api::models::LoginResponse;
 use rustyindie_common::components::page_not_found::PageNotFound;
 use crate::pages::home::home::Home;
 use crate::pages::home::individual_post::IndividualPost;
+use crate::pages::home::tag_posts::TagPosts;
 
 
 #[component]
@@ -22,7 +23,8 @@ pub fn App() -> impl IntoView {
             <main>
                 <Routes>
                     <Route path="/" view=Home />
-                    <Route path="/post/:post_id" view=IndividualPost />  
+                    <Route path="/post/:post_slug" view=IndividualPost />  
+                    <Route path="/tag/:slug" view=TagPosts />
                     <Route path="/*" view=PageNotFound />               
                 </Routes>
             </main>
@@ -2,6 +2,7 @@ pub mod pages {
     pub mod home {
         pub mod home;
         pub mod individual_post;
+        pub mod tag_posts;
     }
 }
 pub mod app;
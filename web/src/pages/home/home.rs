@@ -3,25 +3,47 @@ use leptos::*;
 use leptos_router::A;
 use leptos_meta::*;
 use rustyindie_common::components::post::post_preview::PostPreviewParams;
-use rustyindie_common::components::{layout::Layout, post::post_preview::PostPreview, post::list_post_previews::ListPostPreviews};
+use rustyindie_common::components::{layout::Layout, paged_list::use_paged_list, post::post_preview::PostPreview, post::list_post_previews::ListPostPreviews};
 use rustyindie_common::api::api_service::ApiService;
+use rustyindie_common::api::models::{Paged, Post, PostSearchResult};
 use rustyindie_common::utils::date_time::convert_datetime_long_readable;
 
 
 #[component]
 pub fn Home() -> impl IntoView {
-    let (last_offset, _set_last_offset) = create_signal(0);    
     let api_service = expect_context::<ReadSignal<ApiService>>();
   
-    let posts = create_resource(last_offset, move |offset| async move {
-        let result = api_service.get_untracked().get_latest_posts(offset).await;
+    let posts = use_paged_list(|| (), move |_, cursor| async move {
+        let result = api_service.get_untracked().get_latest_posts(cursor).await;
         match result {
             Ok(data) => {
                 data
             },
             Err(e) => {
                 log!("Failed to get post data: {}", e);
-                vec![]
+                Paged::default()
+            }
+        }
+    });
+
+    // the search runs when the form is submitted, not on every key
+    let (search_text, set_search_text) = create_signal("".to_string());
+    let (submitted_search_text, set_submitted_search_text) = create_signal("".to_string());
+    let is_searching = move || !submitted_search_text().trim().is_empty();
+    let search_results = use_paged_list(submitted_search_text, move |text, cursor| async move {
+        if text.trim().is_empty() {
+            return Paged::default();
+        }
+        let result = api_service.get_untracked().search_posts(&text, cursor).await;
+        match result {
+            Ok(data) => Paged {
+                items: data.items.into_iter().map(get_search_result_preview).collect::<Vec<Post>>(),
+                next_cursor: data.next_cursor,
+                has_more: data.has_more
+            },
+            Err(e) => {
+                log!("Failed to search posts: {}", e);
+                Paged::default()
             }
         }
     });
@@ -39,8 +61,38 @@ pub fn Home() -> impl IntoView {
                         <small>"This app is built entirely with Rust: Leptos, Actix Web"</small>
                     </A>
                 </div>                
+                <form class="search-header post-search" on:submit=move |ev| {
+                    ev.prevent_default();
+                    set_submitted_search_text(search_text());
+                }>
+                    <input 
+                        type="search" 
+                        class="search-input" 
+                        placeholder="Search posts" 
+                        aria-label="Search posts" 
+                        on:input=move |ev| {
+                            let text = event_target_value(&ev);
+                            // clearing the box goes back to the latest posts
+                            if text.trim().is_empty() {
+                                set_submitted_search_text("".to_string());
+                            }
+                            set_search_text(text);
+                        } 
+                        prop:value=search_text
+                    />
+                </form>
             </div>
-            <ListPostPreviews posts=posts editable=false />
+            <Show 
+                when=is_searching 
+                fallback=move || view! { <ListPostPreviews posts=posts editable=false /> }
+            >
+                <ListPostPreviews posts=search_results editable=false empty_message="No posts match your search" />
+            </Show>
         </Layout>
     }
+}
+
+/// Search previews show the matching parts of the post, rather than its start
+fn get_search_result_preview(result: PostSearchResult) -> Post {
+    Post { summary_html: result.snippet_html, ..result.post }
 }
\ No newline at end of file
@@ -8,34 +8,51 @@ use rustyindie_common::components::layout::Layout;
 
 #[derive(Params, PartialEq)]
 struct GetPostParams {
-    post_id: i64
+    /// the post's slug, or its id for links from before posts had slugs
+    post_slug: String
 }
 
 #[component]
 pub fn IndividualPost() -> impl IntoView {
     let post_params = use_params::<GetPostParams>();
-    let post_id = move || {
+    let post_slug = move || {
         post_params.with(|params| {
             params
             .as_ref()
-            .map(|param| param.post_id)
+            .map(|param| param.post_slug.clone())
             .unwrap_or_default()
         })
     };
     let api_service = expect_context::<ReadSignal<ApiService>>();
-    let post_resource = create_resource(post_id, move |id| async move {
-        let result = api_service.get_untracked().get_post(id).await;
+    // slugs are never all digits, so a number is an id
+    let post_resource = create_resource(post_slug, move |slug| async move {
+        let result = match slug.parse::<i64>() {
+            Ok(id) => api_service.get_untracked().get_post(id).await,
+            Err(_) => api_service.get_untracked().get_post_by_slug(&slug).await
+        };
         match result {
             Ok(post) => post,
-            Err(_) => panic!("A post with id {} does not exist", id)
+            Err(e) => {
+                log!("Failed to get post {}: {}", slug, e);
+                None
+            }
+        }
+    });
+    // the web server permanently redirects ids and old slugs, this swaps them for the post's current slug when in-app links or dev servers bypass it
+    let navigate = use_navigate();
+    create_effect(move |_| {
+        if let Some(Some(post)) = post_resource() {
+            if post.slug != post_slug() {
+                navigate(&format!("/post/{}", post.slug), NavigateOptions { replace: true, ..Default::default() });
+            }
         }
     });
 
     view! {
         <Layout single_column=true>
             <div class="home-content">
-                <Title text=move || match post_resource() {
-                    Some(p) => format!("- {}", p.unwrap().title),
+                <Title text=move || match post_resource().flatten() {
+                    Some(p) => format!("- {}", p.title),
                     None => "- Post".to_string()
                 } />
                 <PostDetail post=post_resource />
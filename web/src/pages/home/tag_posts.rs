@@ -0,0 +1,65 @@
+use leptos::logging::log;
+use leptos::*;
+use leptos_router::*;
+use leptos_meta::Title;
+use rustyindie_common::components::{layout::Layout, paged_list::use_paged_list, post::list_post_previews::ListPostPreviews};
+use rustyindie_common::api::api_service::ApiService;
+use rustyindie_common::api::models::Paged;
+
+#[derive(Params, PartialEq)]
+struct TagPostsParams {
+    slug: String
+}
+
+/// Previews of the public posts with a tag
+#[component]
+pub fn TagPosts() -> impl IntoView {
+    let tag_params = use_params::<TagPostsParams>();
+    let tag_slug = move || {
+        tag_params.with(|params| {
+            params
+            .as_ref()
+            .map(|param| param.slug.clone())
+            .unwrap_or_default()
+        })
+    };
+    let api_service = expect_context::<ReadSignal<ApiService>>();
+
+    let posts = use_paged_list(tag_slug, move |slug, cursor| async move {
+        let result = api_service.get_untracked().get_tag_post_previews(&slug, cursor).await;
+        match result {
+            Ok(data) => data,
+            Err(e) => {
+                log!("Failed to get tag post data: {}", e);
+                Paged::default()
+            }
+        }
+    });
+    let tags = create_resource(|| (), move |_| async move {
+        match api_service.get_untracked().get_tags().await {
+            Ok(data) => data,
+            Err(e) => {
+                log!("Failed to get tag data: {}", e);
+                vec![]
+            }
+        }
+    });
+    // the name as it was written, the slug until the tags have loaded
+    let tag_name = move || {
+        tags()
+            .and_then(|data| data.into_iter().find(|tag| tag.slug == tag_slug()))
+            .map(|tag| tag.name)
+            .unwrap_or_else(tag_slug)
+    };
+
+    view! {
+        <Layout single_column=true>
+            <Title text=move || format!("- {}", tag_name()) />
+            <div class="home-content">
+                <h2>{move || format!("Posts tagged \"{}\"", tag_name())}</h2>
+                <A href="/"><small>"All posts"</small></A>
+            </div>
+            <ListPostPreviews posts=posts editable=false />
+        </Layout>
+    }
+}